@@ -73,7 +73,10 @@ impl DataflowHandle {
                             let config =
                                 Configuration::new(i, data_addresses, control_addresses, 4, None);
                             let mut node = Node::new(config);
-                            node.run();
+                            // The node logs the reason of the failure.
+                            if node.run().is_err() {
+                                std::process::exit(1);
+                            }
                             std::process::exit(0);
                         }
                     }
@@ -42,7 +42,7 @@ use crate::{{
     dataflow::{{
         state::{{AccessContext, ManagedState}},
         stream::{{InternalStatefulReadStream, StreamId}},
-        Data, State, StatefulReadStream, Timestamp, WriteStream,
+        Data, State, StatefulReadStream, Timestamp, TimestampT, WriteStream,
     }},
     node::operator_event::OperatorEvent,
     Uuid,
@@ -286,7 +286,6 @@ def make_add_write_stream(num_rs, num_ws):
 
 make_receive_watermark_template = """
     fn receive_watermark(&mut self, stream_id: StreamId, t: Timestamp) -> Vec<OperatorEvent> {{
-        let some_t = Some(t.clone());
         let mut previous_low_watermark_opt = Some(Timestamp::top());
         let mut current_low_watermark_opt = Some(Timestamp::top());
 
@@ -333,14 +332,16 @@ make_receive_watermark_template = """
 def make_receive_watermark(num_rs, num_ws, has_state):
     previous_low_watermark = "\n".join(
         map(
-            lambda x: """if previous_low_watermark_opt > self.rs{}_watermark {{
-                             previous_low_watermark_opt = self.rs{}_watermark.clone()
-                         }}""".format(x, x), range(num_rs)))
+            lambda x: """previous_low_watermark_opt = match (previous_low_watermark_opt, &self.rs{}_watermark) {{
+                             (Some(low_watermark), Some(watermark)) => Some(low_watermark.meet(watermark)),
+                             _ => None,
+                         }};""".format(x), range(num_rs)))
     current_low_watermark = "\n".join(
         map(
-            lambda x: """if current_low_watermark_opt > self.rs{}_watermark {{
-                             current_low_watermark_opt = self.rs{}_watermark.clone()
-                         }}""".format(x, x), range(num_rs)))
+            lambda x: """current_low_watermark_opt = match (current_low_watermark_opt, &self.rs{}_watermark) {{
+                             (Some(low_watermark), Some(watermark)) => Some(low_watermark.meet(watermark)),
+                             _ => None,
+                         }};""".format(x), range(num_rs)))
     reset_watermarks = "\n".join(
         map(lambda x: "self.rs{}_watermark = false;".format(x), range(num_rs)))
     get_states = "\n".join(
@@ -368,7 +369,7 @@ def make_receive_watermark(num_rs, num_ws, has_state):
     set_watermark = " else ".join(
         map(
             lambda x: """if stream_id == self.rs{x}_id {{
-            if some_t > self.rs{x}_watermark {{
+            if self.rs{x}_watermark.as_ref().map_or(true, |watermark| watermark < &t) {{
                 self.rs{x}_watermark = Some(t.clone());
             }} else {{
                 // The watermark is outdated
@@ -0,0 +1,253 @@
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::Notify;
+
+use crate::{
+    communication::CommunicationError,
+    dataflow::{
+        stream::{OverflowPolicy, StreamCapacity},
+        Data, Message,
+    },
+};
+
+/// Trait implemented by messages sent on tracked channels.
+///
+/// Only droppable messages count towards the queue depth and the capacity of a channel, and are
+/// subject to its [`OverflowPolicy`].
+pub trait Droppable {
+    fn is_droppable(&self) -> bool;
+}
+
+/// Data messages may be dropped, whereas watermarks are always delivered.
+impl<D: Data> Droppable for Message<D> {
+    fn is_droppable(&self) -> bool {
+        self.data().is_some()
+    }
+}
+
+impl<T: Droppable> Droppable for Arc<T> {
+    fn is_droppable(&self) -> bool {
+        (**self).is_droppable()
+    }
+}
+
+struct QueueState {
+    /// Number of droppable messages buffered on the channel.
+    len: usize,
+    /// Number of buffered messages the receiver must drop because newer messages overflowed.
+    num_to_drop: usize,
+    /// Whether the receiver was dropped.
+    closed: bool,
+}
+
+/// Tracks the number of droppable messages buffered on a channel. If the stream has a capacity,
+/// limits the number of buffered messages and applies the stream's [`OverflowPolicy`] to
+/// messages sent while the channel is full.
+///
+/// Senders [`reserve`](ChannelQueue::reserve) space before sending a droppable message, and
+/// the receiver [`release`](ChannelQueue::release)s the space once it reads the message.
+pub struct ChannelQueue {
+    /// The channel is unbounded if `None`.
+    capacity: Option<StreamCapacity>,
+    state: Mutex<QueueState>,
+    /// Notifies blocked senders that the receiver read a message or was dropped.
+    not_full: Notify,
+}
+
+impl ChannelQueue {
+    pub(crate) fn new(capacity: Option<StreamCapacity>) -> Arc<Self> {
+        Arc::new(Self {
+            capacity,
+            state: Mutex::new(QueueState {
+                len: 0,
+                num_to_drop: 0,
+                closed: false,
+            }),
+            not_full: Notify::new(),
+        })
+    }
+
+    /// Reserves space for a droppable message.
+    ///
+    /// Returns `false` if the message must be dropped instead of sent, and
+    /// [`CommunicationError::NoCapacity`] if the policy is to return an error.
+    ///
+    /// If the policy is to block, the worker thread of the runtime hands its other tasks over to
+    /// another thread while the sender waits for space.
+    pub(crate) fn reserve(&self) -> Result<bool, CommunicationError> {
+        match self.try_reserve() {
+            Some(result) => result,
+            None => {
+                tokio::task::block_in_place(|| futures::executor::block_on(self.reserve_async()))
+            }
+        }
+    }
+
+    /// Reserves space for a droppable message, and waits asynchronously for space if the policy
+    /// is to block.
+    pub(crate) async fn reserve_async(&self) -> Result<bool, CommunicationError> {
+        let mut waited = false;
+        loop {
+            if let Some(result) = self.try_reserve() {
+                if waited {
+                    // A notification may stand for several released messages, so pass it on.
+                    self.not_full.notify();
+                }
+                return result;
+            }
+            self.not_full.notified().await;
+            waited = true;
+        }
+    }
+
+    /// Reserves space for a droppable message, or returns `None` if the sender must wait for
+    /// space.
+    fn try_reserve(&self) -> Option<Result<bool, CommunicationError>> {
+        let mut state = self.state.lock().unwrap();
+        let capacity = match self.capacity {
+            Some(capacity) => capacity,
+            None => {
+                state.len += 1;
+                return Some(Ok(true));
+            }
+        };
+        if state.len - state.num_to_drop >= capacity.capacity {
+            match capacity.overflow_policy {
+                OverflowPolicy::Block if state.closed => {
+                    return Some(Err(CommunicationError::Disconnected))
+                }
+                OverflowPolicy::Block => return None,
+                OverflowPolicy::DropOldest => state.num_to_drop += 1,
+                OverflowPolicy::DropNewest => return Some(Ok(false)),
+                OverflowPolicy::Error => return Some(Err(CommunicationError::NoCapacity)),
+            }
+        }
+        state.len += 1;
+        Some(Ok(true))
+    }
+
+    /// Releases the space of a droppable message read by the receiver.
+    ///
+    /// Returns `true` if the receiver must drop the message because newer messages overflowed.
+    pub(crate) fn release(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        state.len -= 1;
+        self.not_full.notify();
+        if state.num_to_drop > 0 {
+            state.num_to_drop -= 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the number of droppable messages buffered on the channel.
+    pub(crate) fn len(&self) -> usize {
+        self.state.lock().unwrap().len
+    }
+
+    /// Unblocks senders once the receiver is dropped.
+    pub(crate) fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        // Each unblocked sender wakes the next one.
+        self.not_full.notify();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unbounded() {
+        let queue = ChannelQueue::new(None);
+        for _ in 0..3 {
+            assert!(queue.reserve().unwrap());
+        }
+        assert!(!queue.release());
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn test_drop_newest() {
+        let queue = ChannelQueue::new(Some(StreamCapacity::new(2, OverflowPolicy::DropNewest)));
+        assert!(queue.reserve().unwrap());
+        assert!(queue.reserve().unwrap());
+        assert!(!queue.reserve().unwrap());
+        assert!(!queue.release());
+        assert!(queue.reserve().unwrap());
+    }
+
+    #[test]
+    fn test_drop_oldest() {
+        let queue = ChannelQueue::new(Some(StreamCapacity::new(1, OverflowPolicy::DropOldest)));
+        assert!(queue.reserve().unwrap());
+        assert!(queue.reserve().unwrap());
+        // The first message is dropped, and the second one is delivered.
+        assert!(queue.release());
+        assert!(!queue.release());
+    }
+
+    #[test]
+    fn test_error() {
+        let queue = ChannelQueue::new(Some(StreamCapacity::new(1, OverflowPolicy::Error)));
+        assert!(queue.reserve().unwrap());
+        match queue.reserve() {
+            Err(CommunicationError::NoCapacity) => (),
+            _ => panic!("Expected NoCapacity error"),
+        }
+    }
+
+    #[test]
+    fn test_block() {
+        let queue = ChannelQueue::new(Some(StreamCapacity::new(1, OverflowPolicy::Block)));
+        assert!(queue.reserve().unwrap());
+        let queue_copy = Arc::clone(&queue);
+        let handle = std::thread::spawn(move || queue_copy.reserve().unwrap());
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        assert_eq!(queue.len(), 1);
+        queue.release();
+        assert!(handle.join().unwrap());
+    }
+
+    #[test]
+    fn test_block_async() {
+        let queue = ChannelQueue::new(Some(StreamCapacity::new(1, OverflowPolicy::Block)));
+        let mut runtime = tokio::runtime::Builder::new()
+            .basic_scheduler()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            assert!(queue.reserve_async().await.unwrap());
+            let queue_copy = Arc::clone(&queue);
+            // The blocked senders only run on the runtime's thread.
+            let senders: Vec<_> = (0..2)
+                .map(|_| {
+                    let queue = Arc::clone(&queue_copy);
+                    tokio::spawn(async move { queue.reserve_async().await })
+                })
+                .collect();
+            tokio::time::delay_for(std::time::Duration::from_millis(10)).await;
+            assert!(!queue.release());
+            queue.close();
+            let results = futures::future::join_all(senders).await;
+            // One sender reserves the released space, and the other one is unblocked by the
+            // closed receiver.
+            assert_eq!(
+                results
+                    .iter()
+                    .filter(|r| r.as_ref().unwrap().is_ok())
+                    .count(),
+                1
+            );
+            assert_eq!(
+                results
+                    .iter()
+                    .filter(|r| r.as_ref().unwrap().is_err())
+                    .count(),
+                1
+            );
+        });
+    }
+}
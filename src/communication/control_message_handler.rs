@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use slog::{self, Logger};
 use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
@@ -7,7 +7,6 @@ use crate::node::NodeId;
 
 use super::{CommunicationError, ControlMessage};
 
-// TODO: update `channels_to_nodes` for fault tolerance in case nodes to go down.
 pub struct ControlMessageHandler {
     /// Logger for error messages.
     logger: Logger,
@@ -20,6 +19,8 @@ pub struct ControlMessageHandler {
     channels_to_data_senders: HashMap<NodeId, UnboundedSender<ControlMessage>>,
     channels_to_data_receivers: HashMap<NodeId, UnboundedSender<ControlMessage>>,
     channels_to_nodes: HashMap<NodeId, UnboundedSender<ControlMessage>>,
+    /// Nodes which are considered down.
+    down_nodes: HashSet<NodeId>,
 }
 
 #[allow(dead_code)]
@@ -35,6 +36,7 @@ impl ControlMessageHandler {
             channels_to_data_senders: HashMap::new(),
             channels_to_data_receivers: HashMap::new(),
             channels_to_nodes: HashMap::new(),
+            down_nodes: HashSet::new(),
         }
     }
 
@@ -200,6 +202,45 @@ impl ControlMessageHandler {
         Ok(())
     }
 
+    /// Returns whether a node is considered down.
+    pub fn is_node_down(&self, node_id: NodeId) -> bool {
+        self.down_nodes.contains(&node_id)
+    }
+
+    /// Marks a node as down.
+    ///
+    /// Notifies the senders and receivers connected to the node so that they drop their
+    /// connections, and informs all other nodes that are up.
+    /// Returns `false` if the node was already considered down.
+    pub fn handle_node_down(&mut self, node_id: NodeId) -> Result<bool, CommunicationError> {
+        if !self.down_nodes.insert(node_id) {
+            return Ok(false);
+        }
+        let msg = ControlMessage::NodeDown(node_id);
+        // The channels may be missing if the node failed during setup.
+        self.send_to_control_sender(node_id, msg.clone()).ok();
+        self.send_to_control_receiver(node_id, msg.clone()).ok();
+        self.send_to_data_sender(node_id, msg.clone()).ok();
+        self.send_to_data_receiver(node_id, msg.clone()).ok();
+        for (other_node_id, tx) in self.channels_to_nodes.iter_mut() {
+            if !self.down_nodes.contains(other_node_id) {
+                tx.send(msg.clone()).map_err(CommunicationError::from)?;
+            }
+        }
+        Ok(true)
+    }
+
+    /// Marks a node which was considered down as up.
+    ///
+    /// Returns `false` if the node was not considered down.
+    pub fn handle_node_reconnected(&mut self, node_id: NodeId) -> bool {
+        self.down_nodes.remove(&node_id)
+    }
+
+    pub fn get_logger(&self) -> Logger {
+        self.logger.clone()
+    }
+
     pub fn get_channel_to_handler(&self) -> UnboundedSender<ControlMessage> {
         self.tx.clone()
     }
@@ -209,19 +250,18 @@ impl ControlMessageHandler {
     }
 
     // TODO: try to implement this via a generic
-    /// Reads messages until a `ControlMessage::AllOperatorsInitializedOnNode` is received
-    /// without consuming any other messages types.
+    /// Reads messages until a `ControlMessage::AllOperatorsInitializedOnNode` or a
+    /// `ControlMessage::NodeFailed` is received without consuming any other messages types.
     /// Note: this may affect message order.
     pub async fn read_all_operators_initialized_on_node_msg(
         &mut self,
-    ) -> Result<NodeId, CommunicationError> {
+    ) -> Result<ControlMessage, CommunicationError> {
         let mut read_msgs = Vec::new();
         let mut result = None;
         while result.is_none() {
             match self.read().await {
-                Ok(ControlMessage::AllOperatorsInitializedOnNode(node_id)) => {
-                    result = Some(Ok(node_id))
-                }
+                Ok(msg @ ControlMessage::AllOperatorsInitializedOnNode(_))
+                | Ok(msg @ ControlMessage::NodeFailed(_, _)) => result = Some(Ok(msg)),
                 Ok(msg) => read_msgs.push(msg),
                 Err(e) => result = Some(Err(e)),
             };
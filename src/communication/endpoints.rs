@@ -1,31 +1,108 @@
-use std::{fmt::Debug, sync::Arc};
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
 use tokio::sync::mpsc;
 
 use crate::{
-    communication::{CommunicationError, InterProcessMessage, Serializable, TryRecvError},
+    communication::{
+        ChannelQueue, CommunicationError, Droppable, InterProcessMessage, MessageRecorder,
+        Recordable, SharedMemorySender, TryRecvError,
+    },
     dataflow::stream::StreamId,
+    node::metrics::StreamMetrics,
 };
 
+/// The endpoints of the channels of a stream, each paired with a description of its sink.
+pub type ChannelEndpoints<D> = Arc<Mutex<Vec<(String, SendEndpoint<D>)>>>;
+
 /// Endpoint to be used to send messages between operators.
 #[derive(Clone)]
 pub enum SendEndpoint<D: Clone + Send + Debug> {
     /// Send messages to an operator running in the same process.
     InterThread(mpsc::UnboundedSender<D>),
+    /// Send messages to an operator running in the same process, tracking the queued messages and
+    /// applying the stream's capacity.
+    TrackedInterThread(mpsc::UnboundedSender<D>, Arc<ChannelQueue>),
     /// Send messages to operators running on a different node.
     /// Data is first sended to [`DataSender`](crate::communication::senders::DataSender)
     /// which encodes and sends the message on a TCP stream.
     InterProcess(StreamId, mpsc::UnboundedSender<InterProcessMessage>),
+    /// Send messages to operators running on a different node, tracking the stream's messages
+    /// queued at the [`DataSender`](crate::communication::senders::DataSender) and applying the
+    /// stream's capacity.
+    TrackedInterProcess(
+        StreamId,
+        mpsc::UnboundedSender<InterProcessMessage>,
+        Arc<ChannelQueue>,
+    ),
+    /// Send messages to operators running on a different node on the same host by writing them
+    /// to a shared-memory ring, which the other node reads.
+    SharedMemory(Arc<SharedMemorySender>),
+    /// Appends messages to the log of a [`MessageRecorder`].
+    Record(StreamId, Arc<MessageRecorder>),
+    /// Updates the metrics of the stream.
+    Metrics(Arc<StreamMetrics>),
+    /// Sends messages on the channels of a stream, which are added and removed when the dataflow
+    /// is reconfigured while the node runs.
+    Channels(ChannelEndpoints<D>),
 }
 
 /// Zero-copy implementation of the endpoint.
 /// Because we [`Arc`], the message isn't copied when sent between endpoints within the node.
-impl<D: 'static + Serializable + Send + Sync + Debug> SendEndpoint<Arc<D>> {
+impl<D: 'static + Recordable + Droppable + Send + Sync + Debug> SendEndpoint<Arc<D>> {
     pub fn send(&mut self, msg: Arc<D>) -> Result<(), CommunicationError> {
         match self {
             Self::InterThread(sender) => sender.send(msg).map_err(CommunicationError::from),
+            Self::TrackedInterThread(sender, queue) => {
+                if msg.is_droppable() && !queue.reserve()? {
+                    return Ok(());
+                }
+                sender.send(msg).map_err(CommunicationError::from)
+            }
             Self::InterProcess(stream_id, sender) => sender
                 .send(InterProcessMessage::new_deserialized(msg, *stream_id))
                 .map_err(CommunicationError::from),
+            Self::TrackedInterProcess(stream_id, sender, queue) => {
+                let droppable = msg.is_droppable();
+                let mut ipm = InterProcessMessage::new_deserialized(msg, *stream_id);
+                if droppable {
+                    if !queue.reserve()? {
+                        return Ok(());
+                    }
+                    ipm = ipm.with_queue(Arc::clone(queue));
+                }
+                sender.send(ipm).map_err(CommunicationError::from)
+            }
+            Self::SharedMemory(sender) => sender.send(&msg.encode()?),
+            Self::Record(stream_id, recorder) => {
+                recorder.record(*stream_id, msg.as_ref());
+                Ok(())
+            }
+            Self::Metrics(metrics) => {
+                metrics.observe(msg.as_ref());
+                Ok(())
+            }
+            Self::Channels(endpoints) => {
+                // Sending may block on a full channel, so send without holding the lock.
+                let mut endpoints: Vec<SendEndpoint<Arc<D>>> = endpoints
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .map(|(_, endpoint)| endpoint.clone())
+                    .collect();
+                let mut result = Ok(());
+                for endpoint in endpoints.iter_mut() {
+                    // A full bounded channel must not prevent sending on the other channels.
+                    if let Err(e) = endpoint.send(Arc::clone(&msg)) {
+                        if result.is_ok() {
+                            result = Err(e);
+                        }
+                    }
+                }
+                result
+            }
         }
     }
 }
@@ -33,9 +110,12 @@ impl<D: 'static + Serializable + Send + Sync + Debug> SendEndpoint<Arc<D>> {
 /// Endpoint to be used to receive messages.
 pub enum RecvEndpoint<D: Clone + Send + Debug> {
     InterThread(mpsc::UnboundedReceiver<D>),
+    /// Receives messages on a channel which tracks the queued messages and may have a limited
+    /// capacity.
+    TrackedInterThread(mpsc::UnboundedReceiver<D>, Arc<ChannelQueue>),
 }
 
-impl<D: Clone + Send + Debug> RecvEndpoint<D> {
+impl<D: Clone + Send + Debug + Droppable> RecvEndpoint<D> {
     /// Aync read of a new message.
     pub async fn read(&mut self) -> Result<D, CommunicationError> {
         match self {
@@ -43,6 +123,15 @@ impl<D: Clone + Send + Debug> RecvEndpoint<D> {
                 .recv()
                 .await
                 .ok_or(CommunicationError::Disconnected),
+            Self::TrackedInterThread(receiver, queue) => loop {
+                let msg = receiver
+                    .recv()
+                    .await
+                    .ok_or(CommunicationError::Disconnected)?;
+                if !Self::must_drop(&msg, queue) {
+                    break Ok(msg);
+                }
+            },
         }
     }
 
@@ -50,6 +139,40 @@ impl<D: Clone + Send + Debug> RecvEndpoint<D> {
     pub fn try_read(&mut self) -> Result<D, TryRecvError> {
         match self {
             Self::InterThread(receiver) => receiver.try_recv().map_err(TryRecvError::from),
+            Self::TrackedInterThread(receiver, queue) => loop {
+                let msg = receiver.try_recv().map_err(TryRecvError::from)?;
+                if !Self::must_drop(&msg, queue) {
+                    break Ok(msg);
+                }
+            },
+        }
+    }
+
+    /// Polls for a new message. Returns `None` if the channel is closed.
+    pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<D>> {
+        match self {
+            Self::InterThread(receiver) => receiver.poll_recv(cx),
+            Self::TrackedInterThread(receiver, queue) => loop {
+                match receiver.poll_recv(cx) {
+                    Poll::Ready(Some(msg)) if Self::must_drop(&msg, queue) => (),
+                    poll => break poll,
+                }
+            },
+        }
+    }
+
+    /// Releases the space of a received message, and returns whether the message must be
+    /// dropped because newer messages overflowed the channel.
+    fn must_drop(msg: &D, queue: &ChannelQueue) -> bool {
+        msg.is_droppable() && queue.release()
+    }
+}
+
+impl<D: Clone + Send + Debug> Drop for RecvEndpoint<D> {
+    fn drop(&mut self) {
+        if let Self::TrackedInterThread(_, queue) = self {
+            // Unblock senders waiting for capacity.
+            queue.close();
         }
     }
 }
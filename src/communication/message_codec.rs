@@ -111,7 +111,7 @@ impl Encoder<InterProcessMessage> for MessageCodec {
     fn encode(&mut self, msg: InterProcessMessage, buf: &mut BytesMut) -> Result<(), CodecError> {
         // Serialize and write the header.
         let (metadata, data) = match msg {
-            InterProcessMessage::Deserialized { metadata, data } => (metadata, data),
+            InterProcessMessage::Deserialized { metadata, data, .. } => (metadata, data),
             InterProcessMessage::Serialized {
                 metadata: _,
                 bytes: _,
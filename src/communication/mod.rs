@@ -17,43 +17,74 @@ use tokio::{
     time::delay_for,
 };
 
-use crate::{dataflow::stream::StreamId, node::NodeId, OperatorId};
+use crate::{
+    dataflow::{stream::StreamId, PanicPolicy},
+    node::NodeId,
+    OperatorId,
+};
 
 // Private submodules
+mod channel_queue;
 mod control_message_codec;
 mod control_message_handler;
 mod endpoints;
 mod errors;
 mod message_codec;
+mod reconnect;
+mod recorder;
 mod serializable;
+mod shared_memory;
 
 // Crate-wide visible submodules
 pub(crate) mod pusher;
 pub(crate) mod receivers;
 pub(crate) mod senders;
 
-// Private imports
-use serializable::Serializable;
-
 // Module-wide exports
+pub(crate) use channel_queue::{ChannelQueue, Droppable};
 pub(crate) use control_message_codec::ControlMessageCodec;
 pub(crate) use control_message_handler::ControlMessageHandler;
 pub(crate) use errors::{CodecError, CommunicationError, TryRecvError};
 pub(crate) use message_codec::MessageCodec;
-pub(crate) use pusher::{Pusher, PusherT};
+pub(crate) use pusher::{DecodeErrorHandler, DecodeErrorHandlers, Pusher, PusherT};
+pub(crate) use reconnect::{run_reconnectors, ReconnectEndpoint, Reconnector};
+pub(crate) use recorder::{MessageRecorder, Recordable};
+pub(crate) use serializable::{Deserializable, DeserializedMessage, Serializable};
+pub(crate) use shared_memory::{ring_path, SharedMemoryReceiver, SharedMemorySender};
 
 // Crate-wide exports
-pub(crate) use endpoints::{RecvEndpoint, SendEndpoint};
+pub(crate) use endpoints::{ChannelEndpoints, RecvEndpoint, SendEndpoint};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ControlMessage {
     AllOperatorsInitializedOnNode(NodeId),
     OperatorInitialized(OperatorId),
     RunOperator(OperatorId),
+    /// Stops an operator which was removed from the dataflow.
+    RemoveOperator(OperatorId),
     DataSenderInitialized(NodeId),
     DataReceiverInitialized(NodeId),
     ControlSenderInitialized(NodeId),
     ControlReceiverInitialized(NodeId),
+    /// Sent periodically by a node to show that it is alive.
+    Heartbeat(NodeId),
+    /// Notifies that a node stopped sending heartbeats and is considered down.
+    NodeDown(NodeId),
+    /// Notifies that a node which was considered down is reachable again.
+    NodeReconnected(NodeId),
+    /// Requests a graceful shutdown which drains the dataflow. Sent by the node which started the
+    /// shutdown to the other nodes, and by a node to its operators and data senders.
+    Shutdown(NodeId),
+    /// Notifies that a data sender sent the messages queued for the node before the shutdown.
+    DataSenderFlushed(NodeId),
+    /// Notifies the other nodes that a node failed, with the reason of the failure, so that they
+    /// stop as well.
+    NodeFailed(NodeId, String),
+    /// Reports to the node that a callback or [`Operator::run`](crate::dataflow::Operator::run)
+    /// of an operator panicked, with the panic message and the policy the operator applies.
+    OperatorPanicked(OperatorId, String, PanicPolicy),
+    /// Reports to the node that an operator could not be set up, with the reason of the failure.
+    OperatorFailed(OperatorId, String),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -70,6 +101,9 @@ pub enum InterProcessMessage {
     Deserialized {
         metadata: MessageMetadata,
         data: Arc<dyn Serializable + Send + Sync>,
+        /// Tracks the stream's messages queued at the `DataSender`, if the message counts
+        /// towards them.
+        queue: Option<Arc<ChannelQueue>>,
     },
 }
 
@@ -85,6 +119,32 @@ impl InterProcessMessage {
         Self::Deserialized {
             metadata: MessageMetadata { stream_id },
             data,
+            queue: None,
+        }
+    }
+
+    /// Counts the message towards the stream's queued messages.
+    pub(crate) fn with_queue(mut self, queue: Arc<ChannelQueue>) -> Self {
+        if let Self::Deserialized {
+            queue: ref mut message_queue,
+            ..
+        } = self
+        {
+            *message_queue = Some(queue);
+        }
+        self
+    }
+
+    /// Removes the message from the stream's queued messages.
+    ///
+    /// Returns `true` if the message must be dropped because newer messages overflowed the
+    /// stream's capacity.
+    pub(crate) fn release_queue(&self) -> bool {
+        match self {
+            Self::Deserialized {
+                queue: Some(queue), ..
+            } => queue.release(),
+            _ => false,
         }
     }
 }
@@ -93,11 +153,13 @@ impl InterProcessMessage {
 ///
 /// The function creates a TCPStream to each node address. The node address vector stores
 /// the network address of each node, and is indexed by node id.
+/// The function also returns the listener on the node's address, which accepts connections
+/// re-established by nodes that have a higher id than the node.
 pub async fn create_tcp_streams(
     node_addrs: Vec<SocketAddr>,
     node_id: NodeId,
     logger: &slog::Logger,
-) -> Vec<(NodeId, TcpStream)> {
+) -> (Vec<(NodeId, TcpStream)>, TcpListener) {
     let node_addr = node_addrs[node_id].clone();
     // Connect to the nodes that have a lower id than the node.
     let connect_streams_fut = connect_to_nodes(node_addrs[..node_id].to_vec(), node_id, logger);
@@ -105,10 +167,10 @@ pub async fn create_tcp_streams(
     let stream_fut = await_node_connections(node_addr, node_addrs.len() - node_id - 1, logger);
     // Wait until all connections are established.
     match future::try_join(connect_streams_fut, stream_fut).await {
-        Ok((mut streams, await_streams)) => {
+        Ok((mut streams, (await_streams, listener))) => {
             // Streams contains a TCP stream for each other node.
             streams.extend(await_streams);
-            streams
+            (streams, listener)
         }
         Err(e) => {
             slog::error!(
@@ -200,12 +262,12 @@ async fn connect_to_node(
 /// Awaiting for connections from `expected_conns` other nodes.
 ///
 /// Upon a new connection, the function reads from the stream the id of the node that initiated
-/// the connection.
+/// the connection. The listener is returned to accept future reconnections.
 async fn await_node_connections(
     addr: SocketAddr,
     expected_conns: usize,
     logger: &slog::Logger,
-) -> Result<Vec<(NodeId, TcpStream)>, std::io::Error> {
+) -> Result<(Vec<(NodeId, TcpStream)>, TcpListener), std::io::Error> {
     let mut await_futures = Vec::new();
     let mut listener = TcpListener::bind(&addr).await?;
     // Awaiting for `expected_conns` conections.
@@ -216,7 +278,8 @@ async fn await_node_connections(
         await_futures.push(read_node_id(stream, logger));
     }
     // Await until we've received `expected_conns` node ids.
-    Ok(future::try_join_all(await_futures).await?)
+    let streams = future::try_join_all(await_futures).await?;
+    Ok((streams, listener))
 }
 
 /// Reads a node id from a TCP stream.
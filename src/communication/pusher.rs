@@ -1,7 +1,7 @@
 use std::{
     any::Any,
     fmt::{self, Debug},
-    sync::Arc,
+    sync::{Arc, Mutex},
 };
 
 use bytes::BytesMut;
@@ -9,8 +9,8 @@ use serde::Deserialize;
 
 use crate::{
     communication::{
-        serializable::{Deserializable, DeserializedMessage, Serializable},
-        CommunicationError, SendEndpoint,
+        serializable::{Deserializable, DeserializedMessage},
+        CommunicationError, Droppable, Recordable, SendEndpoint,
     },
     dataflow::Data,
 };
@@ -25,29 +25,81 @@ pub trait PusherT: Send {
     fn send_from_bytes(&mut self, buf: BytesMut) -> Result<(), CommunicationError>;
 }
 
+/// Handles a message received from another node which could not be deserialized, given its bytes
+/// and the error.
+pub(crate) type DecodeErrorHandler = Box<dyn Fn(&[u8], &CommunicationError) + Send>;
+
+/// The handlers of messages which fail to deserialize, by the name of the channel to the reader
+/// which registered them.
+pub(crate) type DecodeErrorHandlers = Arc<Mutex<Vec<(String, DecodeErrorHandler)>>>;
+
 /// Internal structure used to send data on a collection of [`SendEndpoint`]s.
 #[derive(Clone)]
 pub struct Pusher<D: Debug + Clone + Send> {
     endpoints: Vec<SendEndpoint<D>>,
+    /// Handlers of messages which fail to deserialize, shared with the stream's other pushers.
+    /// The error is returned unless every reader of the stream has a handler.
+    decode_error_handlers: DecodeErrorHandlers,
 }
 
 /// Zero-copy implementation of the pusher.
-impl<D: 'static + Serializable + Send + Sync + Debug> Pusher<Arc<D>> {
+impl<D: 'static + Recordable + Droppable + Send + Sync + Debug> Pusher<Arc<D>> {
     pub fn new() -> Self {
         Self {
             endpoints: Vec::new(),
+            decode_error_handlers: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
+    /// Sets the handlers of messages which fail to deserialize.
+    pub fn set_decode_error_handlers(&mut self, handlers: DecodeErrorHandlers) {
+        self.decode_error_handlers = handlers;
+    }
+
     pub fn add_endpoint(&mut self, endpoint: SendEndpoint<Arc<D>>) {
         self.endpoints.push(endpoint);
     }
 
+    /// Sends the message on all endpoints, and returns the first error encountered.
     pub fn send(&mut self, msg: Arc<D>) -> Result<(), CommunicationError> {
+        let mut result = Ok(());
         for endpoint in self.endpoints.iter_mut() {
-            endpoint.send(Arc::clone(&msg))?;
+            // A full bounded channel must not prevent sending on the other endpoints.
+            if let Err(e) = endpoint.send(Arc::clone(&msg)) {
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+        result
+    }
+
+    /// Passes the bytes of a message which failed to deserialize to the handlers, and returns the
+    /// error unless every reader of the stream has a handler. The bytes may have been modified by
+    /// decoding them in place.
+    fn handle_decode_error(
+        &self,
+        bytes: &[u8],
+        error: CommunicationError,
+    ) -> Result<(), CommunicationError> {
+        let handlers = self.decode_error_handlers.lock().unwrap();
+        for (_, handler) in handlers.iter() {
+            (handler)(bytes, &error);
+        }
+        let has_handler = |sink: &str| handlers.iter().any(|(name, _)| name == sink);
+        let is_handled = self.endpoints.iter().all(|endpoint| match endpoint {
+            SendEndpoint::Channels(channels) => channels
+                .lock()
+                .unwrap()
+                .iter()
+                .all(|(sink, _)| has_handler(sink)),
+            _ => false,
+        });
+        if is_handled {
+            Ok(())
+        } else {
+            Err(error)
         }
-        Ok(())
     }
 }
 
@@ -61,7 +113,7 @@ impl Clone for Box<dyn PusherT> {
 /// The [`PusherT`] trait is implemented only for the [`Data`] pushers.
 impl<D> PusherT for Pusher<Arc<D>>
 where
-    for<'de> D: Data + Deserialize<'de>,
+    for<'de> D: Data + Deserialize<'de> + Droppable + Recordable,
 {
     fn as_any(&mut self) -> &mut dyn Any {
         self
@@ -73,9 +125,10 @@ where
 
     fn send_from_bytes(&mut self, mut buf: BytesMut) -> Result<(), CommunicationError> {
         if !self.endpoints.is_empty() {
-            let msg = match Deserializable::decode(&mut buf)? {
-                DeserializedMessage::<D>::Owned(msg) => msg,
-                DeserializedMessage::<D>::Ref(msg) => msg.clone(),
+            let msg = match Deserializable::decode(&mut buf) {
+                Ok(DeserializedMessage::<D>::Owned(msg)) => msg,
+                Ok(DeserializedMessage::<D>::Ref(msg)) => msg.clone(),
+                Err(e) => return self.handle_decode_error(&buf, e),
             };
             let msg_arc = Arc::new(msg);
             self.send(msg_arc)?;
@@ -89,3 +142,43 @@ impl fmt::Debug for Box<dyn PusherT> {
         write!(f, "Box<dyn PusheT> {{ }}")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataflow::Message;
+
+    #[test]
+    fn test_decode_error_handlers() {
+        let mut pusher = Pusher::<Arc<Message<String>>>::new();
+        let channels = ["a", "b"]
+            .iter()
+            .map(|sink| {
+                let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+                (sink.to_string(), SendEndpoint::InterThread(tx))
+            })
+            .collect();
+        pusher.add_endpoint(SendEndpoint::Channels(Arc::new(Mutex::new(channels))));
+        // Bytes which do not deserialize to a message.
+        let bytes = BytesMut::from(&[255u8; 4][..]);
+        assert!(pusher.send_from_bytes(bytes.clone()).is_err());
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let make_handler = |sink: &str| -> (String, DecodeErrorHandler) {
+            let received = Arc::clone(&received);
+            (
+                sink.to_string(),
+                Box::new(move |bytes, _error| {
+                    received.lock().unwrap().push(bytes.to_vec());
+                }),
+            )
+        };
+        let handlers = Arc::new(Mutex::new(vec![make_handler("a")]));
+        pusher.set_decode_error_handlers(Arc::clone(&handlers));
+        // The reader without a handler fails.
+        assert!(pusher.send_from_bytes(bytes.clone()).is_err());
+        handlers.lock().unwrap().push(make_handler("b"));
+        assert!(pusher.send_from_bytes(bytes).is_ok());
+        assert_eq!(received.lock().unwrap().len(), 3);
+    }
+}
@@ -1,38 +1,54 @@
+use bytes::BytesMut;
 use futures::{future, stream::SplitStream};
-use futures_util::stream::StreamExt;
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
 use tokio::{
     net::TcpStream,
     sync::{
         mpsc::{self, UnboundedReceiver, UnboundedSender},
         Mutex,
     },
+    time::{self, Instant},
 };
 use tokio_util::codec::Framed;
 
 use crate::{
     communication::{
         CommunicationError, ControlMessage, ControlMessageCodec, ControlMessageHandler,
-        InterProcessMessage, MessageCodec, PusherT,
+        InterProcessMessage, MessageCodec, PusherT, ReconnectEndpoint,
     },
     dataflow::stream::StreamId,
     node::NodeId,
-    scheduler::endpoints_manager::ChannelsToReceivers,
+    scheduler::endpoints_manager::{ChannelsToReceivers, PusherUpdate},
 };
 
+/// Maximum number of messages kept for a stream whose pusher has not arrived yet. Further
+/// messages on the stream are dropped.
+const MAX_PENDING_MESSAGES: usize = 1024;
+
 /// Listens on a TCP stream, and pushes messages it receives to operator executors.
 #[allow(dead_code)]
 pub(crate) struct DataReceiver {
     /// The id of the node the stream is receiving data from.
     node_id: NodeId,
-    /// Framed TCP read stream.
-    stream: SplitStream<Framed<TcpStream, MessageCodec>>,
+    /// Framed TCP read stream, which is replaced if the connection is re-established.
+    stream: ReconnectEndpoint<SplitStream<Framed<TcpStream, MessageCodec>>>,
     /// Channel receiver on which new pusher updates are received.
-    rx: UnboundedReceiver<(StreamId, Box<dyn PusherT>)>,
+    rx: UnboundedReceiver<PusherUpdate>,
     /// Mapping between stream id to [`PusherT`] trait objects.
     /// [`PusherT`] trait objects are used to deserialize and send
     /// messages to operators.
     stream_id_to_pusher: HashMap<StreamId, Box<dyn PusherT>>,
+    /// Messages received on streams whose pushers have not arrived yet, which happens if the
+    /// other node starts sending on a stream before this node reconfigured its dataflow.
+    pending_messages: HashMap<StreamId, Vec<BytesMut>>,
+    /// Streams removed from the dataflow, whose messages are dropped.
+    removed_streams: HashSet<StreamId>,
+    /// Logger for dropped messages.
+    logger: slog::Logger,
     /// Tokio channel sender to `ControlMessageHandler`.
     control_tx: UnboundedSender<ControlMessage>,
     /// Tokio channel receiver from `ControlMessageHandler`.
@@ -42,7 +58,7 @@ pub(crate) struct DataReceiver {
 impl DataReceiver {
     pub(crate) async fn new(
         node_id: NodeId,
-        stream: SplitStream<Framed<TcpStream, MessageCodec>>,
+        stream: ReconnectEndpoint<SplitStream<Framed<TcpStream, MessageCodec>>>,
         channels_to_receivers: Arc<Mutex<ChannelsToReceivers>>,
         control_handler: &mut ControlMessageHandler,
     ) -> Self {
@@ -58,6 +74,9 @@ impl DataReceiver {
             stream,
             rx,
             stream_id_to_pusher: HashMap::new(),
+            pending_messages: HashMap::new(),
+            removed_streams: HashSet::new(),
+            logger: control_handler.get_logger(),
             control_tx: control_handler.get_channel_to_handler(),
             control_rx,
         }
@@ -68,45 +87,68 @@ impl DataReceiver {
         self.control_tx
             .send(ControlMessage::DataReceiverInitialized(self.node_id))
             .map_err(CommunicationError::from)?;
-        while let Some(res) = self.stream.next().await {
+        loop {
+            let res = tokio::select! {
+                res = self.stream.recv() => res?,
+                // Drop the connection to a node that is down so that it is re-established.
+                Some(ControlMessage::NodeDown(_)) = self.control_rx.recv() => None,
+            };
             match res {
                 // Push the message to the listening operator executors.
-                Ok(msg) => {
+                Some(Ok(msg)) => {
                     // Update pushers before we send the message.
                     // Note: we may want to update the pushers less frequently.
-                    self.update_pushers();
+                    self.update_pushers()?;
                     // Send the message.
                     let (metadata, bytes) = match msg {
                         InterProcessMessage::Serialized { metadata, bytes } => (metadata, bytes),
-                        InterProcessMessage::Deserialized {
-                            metadata: _,
-                            data: _,
-                        } => unreachable!(),
+                        InterProcessMessage::Deserialized { .. } => unreachable!(),
                     };
-                    match self.stream_id_to_pusher.get_mut(&metadata.stream_id) {
-                        Some(pusher) => {
-                            if let Err(e) = pusher.send_from_bytes(bytes) {
-                                return Err(e);
-                            }
+                    let stream_id = metadata.stream_id;
+                    if let Some(pusher) = self.stream_id_to_pusher.get_mut(&stream_id) {
+                        pusher.send_from_bytes(bytes)?;
+                    } else if !self.removed_streams.contains(&stream_id) {
+                        // Keep the message until this node sets up the stream.
+                        let pending = self.pending_messages.entry(stream_id).or_default();
+                        if pending.len() < MAX_PENDING_MESSAGES {
+                            pending.push(bytes);
+                        } else {
+                            slog::warn!(
+                                self.logger,
+                                "Dropping message from node {} on stream {} which is not set up",
+                                self.node_id,
+                                stream_id
+                            );
                         }
-                        None => panic!(
-                            "Receiver does not have any pushers. \
-                             Race condition during data-flow reconfiguration."
-                        ),
                     }
                 }
-                Err(e) => return Err(CommunicationError::from(e)),
+                // The TCP stream failed or was closed.
+                Some(Err(_)) | None => self.stream.report_failure(),
             }
         }
-        Ok(())
     }
 
-    // TODO: update this method.
-    fn update_pushers(&mut self) {
+    /// Adds the pushers of streams set up on the node, and sends the messages received on these
+    /// streams before their pushers arrived. Removes the pushers of streams removed from the
+    /// dataflow.
+    fn update_pushers(&mut self) -> Result<(), CommunicationError> {
         // Execute while we still have pusher updates.
         while let Ok((stream_id, pusher)) = self.rx.try_recv() {
-            self.stream_id_to_pusher.insert(stream_id, pusher);
+            let messages = self.pending_messages.remove(&stream_id);
+            match pusher {
+                Some(mut pusher) => {
+                    for bytes in messages.into_iter().flatten() {
+                        pusher.send_from_bytes(bytes)?;
+                    }
+                    self.stream_id_to_pusher.insert(stream_id, pusher);
+                }
+                None => {
+                    self.stream_id_to_pusher.remove(&stream_id);
+                    self.removed_streams.insert(stream_id);
+                }
+            }
         }
+        Ok(())
     }
 }
 
@@ -122,12 +164,17 @@ pub(crate) async fn run_receivers(
 }
 
 /// Listens on a TCP stream, and pushes control messages it receives to the node.
+///
+/// The [`ControlReceiver`] considers the node down if it does not receive any messages for longer
+/// than the heartbeat timeout, and notifies the [`ControlMessageHandler`] accordingly.
 #[allow(dead_code)]
 pub(crate) struct ControlReceiver {
     /// The id of the node the stream is receiving data from.
     node_id: NodeId,
-    /// Framed TCP read stream.
-    stream: SplitStream<Framed<TcpStream, ControlMessageCodec>>,
+    /// Time without messages after which the node is considered down.
+    heartbeat_timeout: Duration,
+    /// Framed TCP read stream, which is replaced if the connection is re-established.
+    stream: ReconnectEndpoint<SplitStream<Framed<TcpStream, ControlMessageCodec>>>,
     /// Tokio channel sender to `ControlMessageHandler`.
     control_tx: UnboundedSender<ControlMessage>,
     /// Tokio channel receiver from `ControlMessageHandler`.
@@ -137,7 +184,8 @@ pub(crate) struct ControlReceiver {
 impl ControlReceiver {
     pub(crate) fn new(
         node_id: NodeId,
-        stream: SplitStream<Framed<TcpStream, ControlMessageCodec>>,
+        heartbeat_timeout: Duration,
+        stream: ReconnectEndpoint<SplitStream<Framed<TcpStream, ControlMessageCodec>>>,
         control_handler: &mut ControlMessageHandler,
     ) -> Self {
         // Set up control channel.
@@ -145,6 +193,7 @@ impl ControlReceiver {
         control_handler.add_channel_to_control_receiver(node_id, tx);
         Self {
             node_id,
+            heartbeat_timeout,
             stream,
             control_tx: control_handler.get_channel_to_handler(),
             control_rx,
@@ -152,23 +201,54 @@ impl ControlReceiver {
     }
 
     pub(crate) async fn run(&mut self) -> Result<(), CommunicationError> {
-        // TODO: update `self.channel_to_handler` for up-to-date mappings.
-        // between channels and handlers (e.g. for fault-tolerance).
         // Notify `ControlMessageHandler` that sender is initialized.
         self.control_tx
             .send(ControlMessage::ControlReceiverInitialized(self.node_id))
             .map_err(CommunicationError::from)?;
-        while let Some(res) = self.stream.next().await {
+        let mut last_msg_time = Instant::now();
+        let mut node_down = false;
+        loop {
+            let heartbeat_deadline = last_msg_time + self.heartbeat_timeout;
+            let res = tokio::select! {
+                res = self.stream.recv() => Some(res?),
+                _ = time::delay_until(heartbeat_deadline), if !node_down => {
+                    // Notify the handler, which informs the rest of the cluster.
+                    self.control_tx
+                        .send(ControlMessage::NodeDown(self.node_id))
+                        .map_err(CommunicationError::from)?;
+                    None
+                }
+                // The handler learned that the node is down.
+                Some(ControlMessage::NodeDown(_)) = self.control_rx.recv() => None,
+            };
             match res {
-                Ok(msg) => {
+                Some(Some(Ok(msg))) => {
+                    last_msg_time = Instant::now();
+                    if node_down {
+                        node_down = false;
+                        self.control_tx
+                            .send(ControlMessage::NodeReconnected(self.node_id))
+                            .map_err(CommunicationError::from)?;
+                    }
+                    // Heartbeats only reset the timeout.
+                    if let ControlMessage::Heartbeat(_) = msg {
+                        continue;
+                    }
                     self.control_tx
                         .send(msg)
                         .map_err(CommunicationError::from)?;
                 }
-                Err(e) => return Err(CommunicationError::from(e)),
+                // The TCP stream failed or was closed.
+                Some(_) => self.stream.report_failure(),
+                // Drop the connection to a node that is down so that it is re-established.
+                None => {
+                    if !node_down {
+                        node_down = true;
+                        self.stream.report_failure();
+                    }
+                }
             }
         }
-        Ok(())
     }
 }
 
@@ -0,0 +1,277 @@
+use std::{collections::HashMap, io, net::SocketAddr};
+
+use futures::{
+    future,
+    stream::{SplitSink, SplitStream},
+    Sink,
+};
+use futures_util::stream::StreamExt;
+use slog;
+use tokio::{
+    net::{TcpListener, TcpStream},
+    stream::Stream,
+    sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+};
+use tokio_util::codec::{Decoder, Framed};
+
+use crate::{communication::CommunicationError, node::NodeId};
+
+/// Write half of a TCP connection framed with codec `C`.
+type FramedSink<C, I> = SplitSink<Framed<TcpStream, C>, I>;
+/// Read half of a TCP connection framed with codec `C`.
+type FramedStream<C> = SplitStream<Framed<TcpStream, C>>;
+
+/// Half of a TCP connection to another node which is replaced upon reconnection.
+///
+/// Senders and receivers use the endpoint to report failures of the current connection to the
+/// [`Reconnector`], and to obtain the half of the connection which replaces it.
+pub(crate) struct ReconnectEndpoint<T> {
+    /// Half of the current connection, or `None` if the connection failed.
+    half: Option<T>,
+    /// Number of times the connection was re-established.
+    generation: usize,
+    /// Channel on which failures of the current connection are reported.
+    failed_tx: UnboundedSender<usize>,
+    /// Channel on which halves of re-established connections are received.
+    rx: UnboundedReceiver<(usize, T)>,
+}
+
+impl<T> ReconnectEndpoint<T> {
+    fn new(half: T, failed_tx: UnboundedSender<usize>, rx: UnboundedReceiver<(usize, T)>) -> Self {
+        Self {
+            half: Some(half),
+            generation: 0,
+            failed_tx,
+            rx,
+        }
+    }
+
+    /// Returns the half of the most recent connection.
+    ///
+    /// If the current connection failed, the method waits until the connection is re-established.
+    pub(crate) async fn get(&mut self) -> Result<&mut T, CommunicationError> {
+        // The connection may have been re-established because the other half failed.
+        while let Ok((generation, half)) = self.rx.try_recv() {
+            self.generation = generation;
+            self.half = Some(half);
+        }
+        if self.half.is_none() {
+            let (generation, half) = self
+                .rx
+                .recv()
+                .await
+                .ok_or(CommunicationError::Disconnected)?;
+            self.generation = generation;
+            self.half = Some(half);
+        }
+        Ok(self.half.as_mut().unwrap())
+    }
+
+    /// Returns the number of times the connection was re-established.
+    pub(crate) fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Drops the current connection and requests a new one.
+    pub(crate) fn report_failure(&mut self) {
+        self.half = None;
+        // The reconnector only shuts down once both halves are dropped.
+        self.failed_tx.send(self.generation).ok();
+    }
+}
+
+impl<T: Stream + Unpin> ReconnectEndpoint<T> {
+    /// Receives the next item from the most recent connection.
+    pub(crate) async fn recv(&mut self) -> Result<Option<T::Item>, CommunicationError> {
+        let stream = self.get().await?;
+        Ok(stream.next().await)
+    }
+}
+
+/// Re-establishes the TCP connection to another node after it fails.
+///
+/// The node with the higher id re-connects, while the node with the lower id waits for the
+/// connection to be accepted by [`run_reconnectors`].
+pub(crate) struct Reconnector<C, I> {
+    /// The id of the node the reconnector runs on.
+    node_id: NodeId,
+    /// The id of the node to which the connection is established.
+    peer_id: NodeId,
+    /// The address of the node to which the connection is established.
+    peer_addr: SocketAddr,
+    /// Number of times the connection was re-established.
+    generation: usize,
+    /// Channel on which the endpoints report failed connections.
+    failed_rx: UnboundedReceiver<usize>,
+    /// Channel on which connections initiated by the other node are received.
+    accepted_tx: UnboundedSender<TcpStream>,
+    accepted_rx: UnboundedReceiver<TcpStream>,
+    /// Channels on which re-established connections are sent to the endpoints.
+    sink_tx: UnboundedSender<(usize, FramedSink<C, I>)>,
+    stream_tx: UnboundedSender<(usize, FramedStream<C>)>,
+    /// Logger for reconnection messages.
+    logger: slog::Logger,
+}
+
+impl<C, I> Reconnector<C, I>
+where
+    C: Decoder + Default + Send + 'static,
+    I: Send + 'static,
+    Framed<TcpStream, C>: Sink<I>,
+{
+    /// Creates a reconnector for a TCP connection, and returns the endpoints for the connection's
+    /// sink and stream halves.
+    pub(crate) fn new(
+        node_id: NodeId,
+        peer_id: NodeId,
+        peer_addr: SocketAddr,
+        stream: TcpStream,
+        logger: slog::Logger,
+    ) -> (
+        Self,
+        ReconnectEndpoint<FramedSink<C, I>>,
+        ReconnectEndpoint<FramedStream<C>>,
+    ) {
+        let (failed_tx, failed_rx) = mpsc::unbounded_channel();
+        let (accepted_tx, accepted_rx) = mpsc::unbounded_channel();
+        let (sink_tx, sink_rx) = mpsc::unbounded_channel();
+        let (stream_tx, stream_rx) = mpsc::unbounded_channel();
+        // Use the codec to divide the TCP stream data into messages.
+        let (sink, stream) = Framed::new(stream, C::default()).split();
+        let reconnector = Self {
+            node_id,
+            peer_id,
+            peer_addr,
+            generation: 0,
+            failed_rx,
+            accepted_tx,
+            accepted_rx,
+            sink_tx,
+            stream_tx,
+            logger,
+        };
+        (
+            reconnector,
+            ReconnectEndpoint::new(sink, failed_tx.clone(), sink_rx),
+            ReconnectEndpoint::new(stream, failed_tx, stream_rx),
+        )
+    }
+
+    /// Re-establishes the connection whenever an endpoint reports a failure.
+    ///
+    /// Returns once both endpoints are dropped.
+    async fn run(mut self) -> Result<(), CommunicationError> {
+        while let Some(generation) = self.failed_rx.recv().await {
+            // Ignore failures of connections which were already replaced.
+            if generation < self.generation {
+                continue;
+            }
+            slog::warn!(
+                self.logger,
+                "Node {}: connection to node {} failed; reconnecting",
+                self.node_id,
+                self.peer_id
+            );
+            let stream = if self.peer_id < self.node_id {
+                super::connect_to_node(&self.peer_addr, self.node_id, &self.logger).await?
+            } else {
+                self.accepted_rx
+                    .recv()
+                    .await
+                    .ok_or(CommunicationError::Disconnected)?
+            };
+            self.generation += 1;
+            let (sink, stream) = Framed::new(stream, C::default()).split();
+            self.sink_tx.send((self.generation, sink))?;
+            self.stream_tx.send((self.generation, stream))?;
+            slog::info!(
+                self.logger,
+                "Node {}: reconnected to node {}",
+                self.node_id,
+                self.peer_id
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Re-establishes failed TCP connections to other nodes.
+///
+/// The function launches a task for each [`Reconnector`], and forwards the connections other
+/// nodes re-establish on `listener` to the corresponding reconnector.
+pub(crate) async fn run_reconnectors<C, I>(
+    reconnectors: Vec<Reconnector<C, I>>,
+    listener: TcpListener,
+    logger: slog::Logger,
+) -> Result<(), CommunicationError>
+where
+    C: Decoder + Default + Send + 'static,
+    I: Send + 'static,
+    Framed<TcpStream, C>: Sink<I>,
+{
+    let accepted_txs: HashMap<NodeId, UnboundedSender<TcpStream>> = reconnectors
+        .iter()
+        .map(|reconnector| (reconnector.peer_id, reconnector.accepted_tx.clone()))
+        .collect();
+    let reconnectors_fut = future::try_join_all(
+        reconnectors
+            .into_iter()
+            .map(|reconnector| tokio::spawn(reconnector.run())),
+    );
+    let accept_fut = accept_reconnections(listener, accepted_txs, logger);
+    tokio::select! {
+        results = reconnectors_fut => {
+            let results = results.map_err(|e| {
+                io::Error::new(io::ErrorKind::Other, format!("Reconnector task failed: {}", e))
+            })?;
+            for result in results {
+                result?;
+            }
+            Ok(())
+        }
+        result = accept_fut => result,
+    }
+}
+
+/// Accepts connections re-established by other nodes, and forwards each connection to the
+/// reconnector of the node which initiated it.
+async fn accept_reconnections(
+    mut listener: TcpListener,
+    accepted_txs: HashMap<NodeId, UnboundedSender<TcpStream>>,
+    logger: slog::Logger,
+) -> Result<(), CommunicationError> {
+    loop {
+        // Errors accepting a connection only affect that connection, so keep accepting.
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                slog::warn!(
+                    logger,
+                    "Failed to accept a re-established connection: {}",
+                    e
+                );
+                continue;
+            }
+        };
+        if let Err(e) = stream.set_nodelay(true) {
+            slog::warn!(
+                logger,
+                "Couldn't disable Nagle on a re-established connection: {}",
+                e
+            );
+        }
+        // Read the id of the node that re-established the connection.
+        let (node_id, stream) = match super::read_node_id(stream, &logger).await {
+            Ok(result) => result,
+            Err(_) => continue,
+        };
+        match accepted_txs.get(&node_id) {
+            Some(tx) => tx.send(stream)?,
+            None => slog::error!(
+                logger,
+                "Accepted connection from unknown node {}; dropping it",
+                node_id
+            ),
+        }
+    }
+}
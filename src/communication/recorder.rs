@@ -0,0 +1,117 @@
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    communication::Serializable,
+    dataflow::{
+        record::{RecordError, RecordedMessage},
+        stream::StreamId,
+        Data, Message, Timestamp,
+    },
+};
+
+/// Trait implemented by messages which can be recorded.
+pub trait Recordable: Serializable {
+    fn get_timestamp(&self) -> &Timestamp;
+    fn is_watermark(&self) -> bool;
+}
+
+impl<D: Data> Recordable for Message<D> {
+    fn get_timestamp(&self) -> &Timestamp {
+        self.timestamp()
+    }
+
+    fn is_watermark(&self) -> bool {
+        self.data().is_none()
+    }
+}
+
+/// Appends the messages sent on recorded streams to a log file.
+///
+/// Messages are buffered and written to the log in batches. The buffer is flushed upon each
+/// watermark and once the recorder is dropped, so the log only misses the messages sent after the
+/// last watermark if the process crashes.
+pub struct MessageRecorder {
+    writer: Mutex<BufWriter<File>>,
+    /// The recorded streams. All streams are recorded if `None`.
+    streams: Option<HashSet<StreamId>>,
+}
+
+impl MessageRecorder {
+    /// Creates a recorder which overwrites the log file at `path`.
+    pub(crate) fn new<P: AsRef<Path>>(
+        path: P,
+        streams: Option<HashSet<StreamId>>,
+    ) -> Result<Arc<Self>, RecordError> {
+        let file = File::create(path)?;
+        Ok(Arc::new(Self {
+            writer: Mutex::new(BufWriter::new(file)),
+            streams,
+        }))
+    }
+
+    /// Returns whether the messages sent on the stream are recorded.
+    pub(crate) fn is_recorded(&self, stream_id: StreamId) -> bool {
+        match &self.streams {
+            Some(streams) => streams.contains(&stream_id),
+            None => true,
+        }
+    }
+
+    /// Appends a message sent on a stream to the log.
+    ///
+    /// Failures are logged instead of returned so that recording does not affect the dataflow.
+    pub(crate) fn record<D: Recordable>(&self, stream_id: StreamId, msg: &D) {
+        if let Err(e) = self.try_record(stream_id, msg) {
+            slog::error!(
+                crate::TERMINAL_LOGGER,
+                "Failed to record message on stream {}: {:?}",
+                stream_id,
+                e
+            );
+        }
+    }
+
+    fn try_record<D: Recordable>(&self, stream_id: StreamId, msg: &D) -> Result<(), RecordError> {
+        let recorded_msg = RecordedMessage {
+            stream_id,
+            timestamp: msg.get_timestamp().clone(),
+            is_watermark: msg.is_watermark(),
+            send_time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default(),
+            bytes: msg.encode()?.to_vec(),
+        };
+        // Serialize before locking so that senders on other streams only wait for the copy.
+        let entry = bincode::serialize(&recorded_msg)?;
+        let mut writer = self.writer.lock().unwrap();
+        writer.write_all(&entry)?;
+        if recorded_msg.is_watermark {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the buffered messages to the log.
+    pub(crate) fn flush(&self) {
+        if let Err(e) = self.writer.lock().unwrap().flush() {
+            slog::error!(
+                crate::TERMINAL_LOGGER,
+                "Failed to flush the message log: {}",
+                e
+            );
+        }
+    }
+}
+
+impl Drop for MessageRecorder {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
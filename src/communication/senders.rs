@@ -1,6 +1,6 @@
 use futures::{future, stream::SplitSink};
 use futures_util::sink::SinkExt;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 use tokio::{
     self,
     net::TcpStream,
@@ -8,12 +8,13 @@ use tokio::{
         mpsc::{self, UnboundedReceiver, UnboundedSender},
         Mutex,
     },
+    time,
 };
 use tokio_util::codec::Framed;
 
 use crate::communication::{
     CommunicationError, ControlMessage, ControlMessageCodec, ControlMessageHandler,
-    InterProcessMessage, MessageCodec,
+    InterProcessMessage, MessageCodec, ReconnectEndpoint,
 };
 use crate::node::NodeId;
 use crate::scheduler::endpoints_manager::ChannelsToSenders;
@@ -22,11 +23,12 @@ use crate::scheduler::endpoints_manager::ChannelsToSenders;
 /// The [`DataSender`] pulls messages from a FIFO inter-thread channel.
 /// The [`DataSender`] services all operators sending messages to a particular
 /// node which may result in congestion.
+/// Messages which are being sent when the TCP connection fails may be lost.
 pub(crate) struct DataSender {
     /// The id of the node the sink is sending data to.
     node_id: NodeId,
-    /// Framed TCP write sink.
-    sink: SplitSink<Framed<TcpStream, MessageCodec>, InterProcessMessage>,
+    /// Framed TCP write sink, which is replaced if the connection is re-established.
+    sink: ReconnectEndpoint<SplitSink<Framed<TcpStream, MessageCodec>, InterProcessMessage>>,
     /// Tokio channel receiver on which to receive data from worker threads.
     rx: UnboundedReceiver<InterProcessMessage>,
     /// Tokio channel sender to `ControlMessageHandler`.
@@ -38,7 +40,7 @@ pub(crate) struct DataSender {
 impl DataSender {
     pub(crate) async fn new(
         node_id: NodeId,
-        sink: SplitSink<Framed<TcpStream, MessageCodec>, InterProcessMessage>,
+        sink: ReconnectEndpoint<SplitSink<Framed<TcpStream, MessageCodec>, InterProcessMessage>>,
         channels_to_senders: Arc<Mutex<ChannelsToSenders>>,
         control_handler: &mut ControlMessageHandler,
     ) -> Self {
@@ -63,18 +65,47 @@ impl DataSender {
         self.control_tx
             .send(ControlMessage::DataSenderInitialized(self.node_id))
             .map_err(CommunicationError::from)?;
-        // TODO: listen on control_rx?
         loop {
-            match self.rx.recv().await {
-                Some(msg) => {
-                    if let Err(e) = self.sink.send(msg).await.map_err(CommunicationError::from) {
-                        return Err(e);
-                    }
+            tokio::select! {
+                msg = self.rx.recv() => {
+                    self.send(msg.ok_or(CommunicationError::Disconnected)?).await?;
                 }
-                None => return Err(CommunicationError::Disconnected),
+                Some(control_msg) = self.control_rx.recv() => match control_msg {
+                    // Drop the connection to a node that is down so that it is re-established.
+                    ControlMessage::NodeDown(_) => self.sink.report_failure(),
+                    // Send the queued messages before the local node shuts down.
+                    ControlMessage::Shutdown(_) => {
+                        while let Ok(msg) = self.rx.try_recv() {
+                            self.send(msg).await?;
+                        }
+                        self.control_tx
+                            .send(ControlMessage::DataSenderFlushed(self.node_id))
+                            .map_err(CommunicationError::from)?;
+                    }
+                    _ => (),
+                },
             }
         }
     }
+
+    /// Sends a message to the node, and re-establishes the connection if it fails.
+    async fn send(&mut self, msg: InterProcessMessage) -> Result<(), CommunicationError> {
+        // Newer messages on a bounded stream may have overflowed the queue.
+        if msg.release_queue() {
+            return Ok(());
+        }
+        let generation = self.sink.generation();
+        let sink = self.sink.get().await?;
+        let result = sink.send(msg).await;
+        if self.sink.generation() > generation {
+            // Discard notifications that the node is down which predate the new connection.
+            while self.control_rx.try_recv().is_ok() {}
+        }
+        if result.is_err() {
+            self.sink.report_failure();
+        }
+        Ok(())
+    }
 }
 
 /// Sends messages received from operator executors to other nodes.
@@ -95,11 +126,16 @@ pub(crate) async fn run_senders(senders: Vec<DataSender>) -> Result<(), Communic
 
 #[allow(dead_code)]
 /// Listens for control messages on a `tokio::sync::mpsc` channel, and sends received messages on the network.
+/// The [`ControlSender`] also periodically sends heartbeats to the node.
 pub(crate) struct ControlSender {
     /// The id of the node the sink is sending data to.
     node_id: NodeId,
-    /// Framed TCP write sink.
-    sink: SplitSink<Framed<TcpStream, ControlMessageCodec>, ControlMessage>,
+    /// The id of the node from which heartbeats are sent.
+    local_node_id: NodeId,
+    /// Interval at which heartbeats are sent.
+    heartbeat_interval: Duration,
+    /// Framed TCP write sink, which is replaced if the connection is re-established.
+    sink: ReconnectEndpoint<SplitSink<Framed<TcpStream, ControlMessageCodec>, ControlMessage>>,
     /// Tokio channel receiver on which to receive data from worker threads.
     rx: UnboundedReceiver<ControlMessage>,
     /// Tokio channel sender to `ControlMessageHandler`.
@@ -111,7 +147,9 @@ pub(crate) struct ControlSender {
 impl ControlSender {
     pub(crate) fn new(
         node_id: NodeId,
-        sink: SplitSink<Framed<TcpStream, ControlMessageCodec>, ControlMessage>,
+        local_node_id: NodeId,
+        heartbeat_interval: Duration,
+        sink: ReconnectEndpoint<SplitSink<Framed<TcpStream, ControlMessageCodec>, ControlMessage>>,
         control_handler: &mut ControlMessageHandler,
     ) -> Self {
         // Set up channel to other node.
@@ -122,6 +160,8 @@ impl ControlSender {
         control_handler.add_channel_to_control_sender(node_id, control_tx);
         Self {
             node_id,
+            local_node_id,
+            heartbeat_interval,
             sink,
             rx,
             control_tx: control_handler.get_channel_to_handler(),
@@ -134,17 +174,33 @@ impl ControlSender {
         self.control_tx
             .send(ControlMessage::ControlSenderInitialized(self.node_id))
             .map_err(CommunicationError::from)?;
-        // TODO: listen on control_rx
+        let mut heartbeat_interval = time::interval(self.heartbeat_interval);
         loop {
-            match self.rx.recv().await {
-                Some(msg) => {
-                    if let Err(e) = self.sink.send(msg).await.map_err(CommunicationError::from) {
-                        return Err(e);
-                    }
-                }
-                None => {
-                    return Err(CommunicationError::Disconnected);
+            let msg = tokio::select! {
+                msg = self.rx.recv() => msg.ok_or(CommunicationError::Disconnected)?,
+                _ = heartbeat_interval.tick() => ControlMessage::Heartbeat(self.local_node_id),
+                // Drop the connection to a node that is down so that it is re-established.
+                Some(ControlMessage::NodeDown(_)) = self.control_rx.recv() => {
+                    self.sink.report_failure();
+                    continue;
                 }
+            };
+            // The local node stops once it notified the other nodes of its failure.
+            let is_last_msg = matches!(
+                &msg,
+                ControlMessage::NodeFailed(node_id, _) if *node_id == self.local_node_id
+            );
+            let generation = self.sink.generation();
+            let sink = self.sink.get().await?;
+            let result = sink.send(msg).await;
+            if self.sink.generation() > generation {
+                // Discard notifications that the node is down which predate the new connection.
+                while self.control_rx.try_recv().is_ok() {}
+            }
+            if result.is_err() {
+                self.sink.report_failure();
+            } else if is_last_msg {
+                return Ok(());
             }
         }
     }
@@ -0,0 +1,446 @@
+use std::{
+    fs::{self, OpenOptions},
+    io,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    ptr,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use bytes::BytesMut;
+use memmap::MmapMut;
+
+use crate::{
+    communication::{CommunicationError, PusherT},
+    dataflow::stream::StreamId,
+};
+
+/// Offset of the position up to which the ring was written, in bytes written since creation.
+const WRITE_POS_OFFSET: usize = 0;
+/// Offset of the counter which the writer increments after writing a message, and on which the
+/// reader waits for messages.
+const WRITE_SEQ_OFFSET: usize = 8;
+/// Offset of the flag which the reader sets while it waits for messages.
+const READER_WAITING_OFFSET: usize = 12;
+/// Offset of the position up to which the ring was read. The fields written by the reader are on
+/// a separate cache line from the ones written by the writer to avoid false sharing between the
+/// processes.
+const READ_POS_OFFSET: usize = 64;
+/// Offset of the counter which the reader increments after reading a message, and on which the
+/// writer waits for space.
+const READ_SEQ_OFFSET: usize = 72;
+/// Offset of the flag which the writer sets while it waits for space.
+const WRITER_WAITING_OFFSET: usize = 76;
+/// Size of the header which stores the positions.
+const HEADER_SIZE: usize = 128;
+/// Size of the length prefix of each message.
+const LEN_SIZE: usize = 4;
+/// Maximum time for which the reader waits for messages before checking whether to stop.
+const WAIT_TIMEOUT: Duration = Duration::from_millis(100);
+/// Maximum interval at which the sender checks whether the receiving node created the ring.
+const OPEN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// Time for which the sender waits for the receiving node to create the ring. The ring may be
+/// created after the sender starts sending if the dataflow is reconfigured.
+const OPEN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Returns the path of the ring which transports the messages of a stream to the node with the
+/// data address `sink_address`.
+///
+/// Rings are created in `/dev/shm` if available so that they are backed by memory.
+pub(crate) fn ring_path(sink_address: SocketAddr, stream_id: StreamId) -> PathBuf {
+    let dir = Path::new("/dev/shm");
+    let dir = if dir.is_dir() {
+        dir.to_path_buf()
+    } else {
+        std::env::temp_dir()
+    };
+    // The port distinguishes the rings of nodes which run on the same host.
+    dir.join(format!("erdos-{}-{}", sink_address.port(), stream_id))
+}
+
+/// A single-producer single-consumer ring buffer in a memory-mapped file, which is shared by the
+/// processes of the sending and the receiving node.
+///
+/// Messages are stored as a 4 byte length followed by the encoded message, and may wrap around
+/// the end of the ring. A reader waiting for messages and a writer waiting for space sleep on a
+/// futex in the header, which the other process wakes.
+struct Ring {
+    /// Keeps the file mapped.
+    _mmap: MmapMut,
+    /// Start of the mapping.
+    ptr: *mut u8,
+    /// Number of bytes available to store messages.
+    capacity: usize,
+}
+
+// The ring is only accessed through atomics and by a single writer and a single reader.
+unsafe impl Send for Ring {}
+unsafe impl Sync for Ring {}
+
+impl Ring {
+    /// Creates the ring at `path`, replacing any previous ring.
+    fn create(path: &Path, size: usize) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.set_len(size as u64)?;
+        Self::map(&file)
+    }
+
+    /// Opens the ring created at `path` by the receiving node.
+    fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        Self::map(&file)
+    }
+
+    fn map(file: &fs::File) -> io::Result<Self> {
+        let mut mmap = unsafe { MmapMut::map_mut(file)? };
+        if mmap.len() <= HEADER_SIZE + LEN_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "shared-memory ring is too small",
+            ));
+        }
+        Ok(Self {
+            ptr: mmap.as_mut_ptr(),
+            capacity: mmap.len() - HEADER_SIZE,
+            _mmap: mmap,
+        })
+    }
+
+    fn position(&self, offset: usize) -> &AtomicU64 {
+        // The mapping is page-aligned, so the positions are aligned.
+        unsafe { &*(self.ptr.add(offset) as *const AtomicU64) }
+    }
+
+    fn word(&self, offset: usize) -> &AtomicU32 {
+        unsafe { &*(self.ptr.add(offset) as *const AtomicU32) }
+    }
+
+    /// Returns the counter of written messages, which must be read before checking the ring for
+    /// messages and passed to [`Ring::wait_for_messages`].
+    fn write_seq(&self) -> u32 {
+        self.word(WRITE_SEQ_OFFSET).load(Ordering::SeqCst)
+    }
+
+    /// Returns the counter of read messages, which must be read before trying to write a message
+    /// and passed to [`Ring::wait_for_space`].
+    fn read_seq(&self) -> u32 {
+        self.word(READ_SEQ_OFFSET).load(Ordering::SeqCst)
+    }
+
+    /// Waits until the writer writes a message after `write_seq` was read, or the timeout expires.
+    fn wait_for_messages(&self, write_seq: u32, timeout: Duration) {
+        self.wait(WRITE_SEQ_OFFSET, READER_WAITING_OFFSET, write_seq, timeout);
+    }
+
+    /// Waits until the reader reads a message after `read_seq` was read, or the timeout expires.
+    fn wait_for_space(&self, read_seq: u32, timeout: Duration) {
+        self.wait(READ_SEQ_OFFSET, WRITER_WAITING_OFFSET, read_seq, timeout);
+    }
+
+    fn wait(&self, seq_offset: usize, waiting_offset: usize, seq: u32, timeout: Duration) {
+        self.word(waiting_offset).store(1, Ordering::SeqCst);
+        // Returns immediately if the counter changed since it was read.
+        futex_wait(self.word(seq_offset), seq, timeout);
+        self.word(waiting_offset).store(0, Ordering::SeqCst);
+    }
+
+    /// Increments the counter at `seq_offset`, and wakes the other process if it is waiting.
+    fn notify(&self, seq_offset: usize, waiting_offset: usize) {
+        self.word(seq_offset).fetch_add(1, Ordering::SeqCst);
+        if self.word(waiting_offset).load(Ordering::SeqCst) != 0 {
+            futex_wake(self.word(seq_offset));
+        }
+    }
+
+    /// Copies `buf` into the ring starting at `pos`, wrapping around the end of the ring.
+    fn copy_in(&self, pos: u64, buf: &[u8]) {
+        let start = (pos % self.capacity as u64) as usize;
+        let first = buf.len().min(self.capacity - start);
+        unsafe {
+            let data = self.ptr.add(HEADER_SIZE);
+            ptr::copy_nonoverlapping(buf.as_ptr(), data.add(start), first);
+            ptr::copy_nonoverlapping(buf[first..].as_ptr(), data, buf.len() - first);
+        }
+    }
+
+    /// Copies bytes from the ring starting at `pos` into `buf`, wrapping around the end of the
+    /// ring.
+    fn copy_out(&self, pos: u64, buf: &mut [u8]) {
+        let start = (pos % self.capacity as u64) as usize;
+        let first = buf.len().min(self.capacity - start);
+        unsafe {
+            let data = self.ptr.add(HEADER_SIZE);
+            ptr::copy_nonoverlapping(data.add(start), buf.as_mut_ptr(), first);
+            ptr::copy_nonoverlapping(data, buf[first..].as_mut_ptr(), buf.len() - first);
+        }
+    }
+
+    /// Appends a message to the ring. Returns `false` if the ring does not have enough space.
+    fn try_write(&self, msg: &[u8]) -> bool {
+        let len = (LEN_SIZE + msg.len()) as u64;
+        let write_pos = self.position(WRITE_POS_OFFSET).load(Ordering::Relaxed);
+        let read_pos = self.position(READ_POS_OFFSET).load(Ordering::Acquire);
+        if self.capacity as u64 - (write_pos - read_pos) < len {
+            return false;
+        }
+        self.copy_in(write_pos, &(msg.len() as u32).to_le_bytes());
+        self.copy_in(write_pos + LEN_SIZE as u64, msg);
+        // Publish the message to the reader.
+        self.position(WRITE_POS_OFFSET)
+            .store(write_pos + len, Ordering::Release);
+        self.notify(WRITE_SEQ_OFFSET, READER_WAITING_OFFSET);
+        true
+    }
+
+    /// Removes the oldest message from the ring, if any.
+    ///
+    /// Fails if the length prefix of the message exceeds the bytes written to the ring, e.g.
+    /// because another process corrupted the ring.
+    fn try_read(&self) -> Result<Option<BytesMut>, CommunicationError> {
+        let read_pos = self.position(READ_POS_OFFSET).load(Ordering::Relaxed);
+        let write_pos = self.position(WRITE_POS_OFFSET).load(Ordering::Acquire);
+        if read_pos == write_pos {
+            return Ok(None);
+        }
+        let mut len_bytes = [0u8; LEN_SIZE];
+        self.copy_out(read_pos, &mut len_bytes);
+        let len = u32::from_le_bytes(len_bytes) as usize;
+        let written = write_pos.saturating_sub(read_pos);
+        if len > self.capacity - LEN_SIZE || (LEN_SIZE + len) as u64 > written {
+            return Err(CommunicationError::IoError(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "shared-memory ring holds {} bytes, but the next message has {} bytes",
+                    written, len
+                ),
+            )));
+        }
+        let mut msg = BytesMut::with_capacity(len);
+        msg.resize(len, 0);
+        self.copy_out(read_pos + LEN_SIZE as u64, &mut msg);
+        // Release the space to the writer.
+        self.position(READ_POS_OFFSET)
+            .store(read_pos + (LEN_SIZE + len) as u64, Ordering::Release);
+        self.notify(READ_SEQ_OFFSET, WRITER_WAITING_OFFSET);
+        Ok(Some(msg))
+    }
+}
+
+/// Writes the encoded messages of a stream to the shared-memory ring of a node on the same host.
+pub struct SharedMemorySender {
+    path: PathBuf,
+    /// Opened upon sending the first message, once the receiving node created the ring.
+    ring: Mutex<Option<Ring>>,
+}
+
+impl SharedMemorySender {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            ring: Mutex::new(None),
+        }
+    }
+
+    /// Appends an encoded message to the ring. Blocks while the ring is full.
+    ///
+    /// While the sender blocks, the worker thread of the runtime hands its other tasks over to
+    /// another thread.
+    pub(crate) fn send(&self, msg: &[u8]) -> Result<(), CommunicationError> {
+        // Another thread may hold the lock while it waits for space.
+        let mut ring = match self.ring.try_lock() {
+            Ok(ring) => ring,
+            Err(_) => tokio::task::block_in_place(|| self.ring.lock().unwrap()),
+        };
+        if ring.is_none() {
+            *ring = Some(tokio::task::block_in_place(|| self.open())?);
+        }
+        let ring = ring.as_ref().unwrap();
+        if LEN_SIZE + msg.len() > ring.capacity {
+            return Err(CommunicationError::IoError(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "message of {} bytes does not fit in shared-memory ring {}",
+                    msg.len(),
+                    self.path.display()
+                ),
+            )));
+        }
+        let mut read_seq = ring.read_seq();
+        if !ring.try_write(msg) {
+            tokio::task::block_in_place(|| {
+                while !ring.try_write(msg) {
+                    ring.wait_for_space(read_seq, WAIT_TIMEOUT);
+                    read_seq = ring.read_seq();
+                }
+            });
+        }
+        Ok(())
+    }
+
+    /// Opens the ring, waiting until the receiving node created it.
+    fn open(&self) -> io::Result<Ring> {
+        let start = Instant::now();
+        let mut poll_interval = Duration::from_micros(100);
+        loop {
+            match Ring::open(&self.path) {
+                Err(e) if e.kind() == io::ErrorKind::NotFound && start.elapsed() < OPEN_TIMEOUT => {
+                    thread::sleep(poll_interval);
+                    poll_interval = (poll_interval * 2).min(OPEN_POLL_INTERVAL);
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Reads the messages of a stream from a shared-memory ring, and sends them on the pusher's
+/// endpoints.
+pub(crate) struct SharedMemoryReceiver {
+    path: PathBuf,
+    ring: Ring,
+    pusher: Box<dyn PusherT>,
+}
+
+impl SharedMemoryReceiver {
+    /// Creates the ring at `path`.
+    pub(crate) fn new(path: PathBuf, size: usize, pusher: Box<dyn PusherT>) -> io::Result<Self> {
+        let ring = Ring::create(&path, size)?;
+        Ok(Self { path, ring, pusher })
+    }
+
+    /// Forwards messages from the ring until an error occurs.
+    ///
+    /// Waiting for messages blocks, so the receiver runs on a thread of the runtime's blocking
+    /// pool. The thread stops once the returned future is dropped, e.g. when the node shuts down.
+    pub(crate) async fn run(self) -> Result<(), CommunicationError> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let _stop_on_drop = StopOnDrop(Arc::clone(&stop));
+        tokio::task::spawn_blocking(move || self.forward(&stop))
+            .await
+            .map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("Shared-memory receiver failed: {}", e),
+                )
+            })?
+    }
+
+    fn forward(mut self, stop: &AtomicBool) -> Result<(), CommunicationError> {
+        while !stop.load(Ordering::Relaxed) {
+            let write_seq = self.ring.write_seq();
+            match self.ring.try_read()? {
+                Some(msg) => self.pusher.send_from_bytes(msg)?,
+                None => self.ring.wait_for_messages(write_seq, WAIT_TIMEOUT),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sets the flag when dropped.
+struct StopOnDrop(Arc<AtomicBool>);
+
+impl Drop for StopOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for SharedMemoryReceiver {
+    fn drop(&mut self) {
+        fs::remove_file(&self.path).ok();
+    }
+}
+
+/// Sleeps until `word` is woken, unless it differs from `expected`, or the timeout expires.
+///
+/// The futex is not private to the process, so that processes which map the same ring wake
+/// each other.
+#[cfg(target_os = "linux")]
+fn futex_wait(word: &AtomicU32, expected: u32, timeout: Duration) {
+    let timeout = libc::timespec {
+        tv_sec: timeout.as_secs() as libc::time_t,
+        tv_nsec: timeout.subsec_nanos() as libc::c_long,
+    };
+    unsafe {
+        libc::syscall(
+            libc::SYS_futex,
+            word as *const AtomicU32 as *const u32,
+            libc::FUTEX_WAIT,
+            expected,
+            &timeout as *const libc::timespec,
+        );
+    }
+}
+
+/// Wakes the threads sleeping on `word`.
+#[cfg(target_os = "linux")]
+fn futex_wake(word: &AtomicU32) {
+    unsafe {
+        libc::syscall(
+            libc::SYS_futex,
+            word as *const AtomicU32 as *const u32,
+            libc::FUTEX_WAKE,
+            i32::MAX,
+        );
+    }
+}
+
+/// Polls `word` on platforms without futexes.
+#[cfg(not(target_os = "linux"))]
+fn futex_wait(word: &AtomicU32, expected: u32, timeout: Duration) {
+    let start = Instant::now();
+    while word.load(Ordering::SeqCst) == expected && start.elapsed() < timeout {
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn futex_wake(_word: &AtomicU32) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_wraps_around() {
+        let path = std::env::temp_dir().join(format!("erdos-ring-test-{}", std::process::id()));
+        let reader = Ring::create(&path, HEADER_SIZE + 16).unwrap();
+        let writer = Ring::open(&path).unwrap();
+        for i in 0..10u8 {
+            let msg = [i; 5];
+            assert!(writer.try_write(&msg));
+            // The ring only fits 1 message.
+            assert!(!writer.try_write(&msg));
+            assert_eq!(&reader.try_read().unwrap().unwrap()[..], &msg[..]);
+            assert!(reader.try_read().unwrap().is_none());
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_ring_rejects_invalid_length() {
+        let path =
+            std::env::temp_dir().join(format!("erdos-ring-invalid-test-{}", std::process::id()));
+        let reader = Ring::create(&path, HEADER_SIZE + 16).unwrap();
+        let writer = Ring::open(&path).unwrap();
+        assert!(writer.try_write(&[0; 5]));
+        // Overwrite the length prefix with a length which exceeds the capacity of the ring.
+        writer.copy_in(0, &u32::MAX.to_le_bytes());
+        assert!(reader.try_read().is_err());
+        // A length which fits in the ring, but exceeds the bytes written, is also rejected.
+        writer.copy_in(0, &8u32.to_le_bytes());
+        assert!(reader.try_read().is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
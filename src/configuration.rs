@@ -1,6 +1,15 @@
-use std::net::SocketAddr;
+use std::{
+    collections::HashSet, fmt::Display, net::SocketAddr, str::FromStr, sync::Arc, time::Duration,
+};
 
-use crate::node::NodeId;
+use crate::{
+    dataflow::{
+        checkpoint::{CheckpointStore, FileCheckpointStore},
+        stream::StreamId,
+    },
+    node::NodeId,
+    scheduler::{MinCommunicationScheduler, RoundRobinScheduler, Scheduler},
+};
 
 /// Stores the configuration parameters of a [`node`](crate::node::Node).
 #[derive(Clone)]
@@ -17,6 +26,39 @@ pub struct Configuration {
     pub logger: slog::Logger,
     /// DOT file to export dataflow graph.
     pub graph_filename: Option<String>,
+    /// Interval at which the node sends heartbeats to other nodes.
+    pub heartbeat_interval: Duration,
+    /// Time without messages from another node after which that node is considered down.
+    pub heartbeat_timeout: Duration,
+    /// Store to which operators checkpoint their states. Checkpointing is disabled if `None`.
+    pub checkpoint_store: Option<Arc<dyn CheckpointStore>>,
+    /// Whether to restore operators from the latest consistent checkpoint in the checkpoint
+    /// store.
+    pub recover_from_checkpoint: bool,
+    /// The number of latest checkpoints of each state which are kept in the checkpoint store.
+    /// Older checkpoints are removed once all states of the operator have a later checkpoint.
+    pub checkpoints_to_keep: usize,
+    /// File to which the node records the messages sent on streams originating on the node.
+    /// Recording is disabled if `None`.
+    pub record_filename: Option<String>,
+    /// Streams whose messages are recorded. All streams are recorded if `None`.
+    pub recorded_streams: Option<HashSet<StreamId>>,
+    /// Address of the HTTP endpoint which serves the node's metrics in the Prometheus text
+    /// format. The endpoint is disabled if `None`.
+    pub metrics_address: Option<SocketAddr>,
+    /// Whether to collect the number and size of the messages sent on the streams originating on
+    /// the node, and their watermark lag. Disabled by default because it serializes every message
+    /// a second time to compute its size.
+    pub stream_metrics: bool,
+    /// File to which the node writes traces of the operators' callbacks in the Chrome trace
+    /// event format. Tracing is disabled if `None`.
+    pub trace_filename: Option<String>,
+    /// Places the operators which are not pinned to a node. If `None`, operators run on the node
+    /// set in their [`OperatorConfig`](crate::dataflow::OperatorConfig).
+    pub scheduler: Option<Arc<dyn Scheduler>>,
+    /// Size in bytes of the shared-memory rings which transport messages between nodes that run
+    /// on the same host. Such nodes communicate over TCP if `None`, which is the default.
+    pub shared_memory_size: Option<usize>,
 }
 
 impl Configuration {
@@ -35,6 +77,18 @@ impl Configuration {
             control_addresses,
             logger: crate::get_terminal_logger(),
             graph_filename,
+            heartbeat_interval: Duration::from_millis(500),
+            heartbeat_timeout: Duration::from_secs(5),
+            checkpoint_store: None,
+            recover_from_checkpoint: false,
+            checkpoints_to_keep: 3,
+            record_filename: None,
+            recorded_streams: None,
+            metrics_address: None,
+            stream_metrics: false,
+            trace_filename: None,
+            scheduler: None,
+            shared_memory_size: None,
         }
     }
 
@@ -76,6 +130,72 @@ impl Configuration {
         } else {
             Some(graph_filename_arg.to_string())
         };
+        let checkpoint_dir = args.value_of("checkpoint-dir").unwrap();
+        let checkpoint_store: Option<Arc<dyn CheckpointStore>> = if checkpoint_dir.is_empty() {
+            None
+        } else {
+            Some(Arc::new(FileCheckpointStore::new(checkpoint_dir)))
+        };
+        let recover_from_checkpoint = args.is_present("recover");
+        if recover_from_checkpoint && checkpoint_store.is_none() {
+            exit_with_error("Recovering requires a checkpoint directory");
+        }
+        let checkpoints_to_keep = parse_arg(args, "checkpoints-to-keep");
+        if checkpoints_to_keep == 0 {
+            exit_with_error("At least one checkpoint must be kept");
+        }
+        let record_filename_arg = args.value_of("record-filename").unwrap();
+        let record_filename = if record_filename_arg.is_empty() {
+            None
+        } else {
+            Some(record_filename_arg.to_string())
+        };
+        let recorded_streams_arg = args.value_of("record-streams").unwrap();
+        let recorded_streams = if recorded_streams_arg.is_empty() {
+            None
+        } else {
+            Some(
+                recorded_streams_arg
+                    .split(',')
+                    .map(|id| {
+                        id.parse().unwrap_or_else(|e| {
+                            exit_with_error(&format!("Invalid stream ID '{}': {}", id, e))
+                        })
+                    })
+                    .collect(),
+            )
+        };
+        let metrics_address_arg = args.value_of("metrics-address").unwrap();
+        let metrics_address = if metrics_address_arg.is_empty() {
+            None
+        } else {
+            Some(metrics_address_arg.parse().unwrap_or_else(|e| {
+                exit_with_error(&format!(
+                    "Invalid metrics address '{}': {}",
+                    metrics_address_arg, e
+                ))
+            }))
+        };
+        let trace_filename_arg = args.value_of("trace-filename").unwrap();
+        let trace_filename = if trace_filename_arg.is_empty() {
+            None
+        } else {
+            Some(trace_filename_arg.to_string())
+        };
+        let scheduler: Option<Arc<dyn Scheduler>> = match args.value_of("scheduler").unwrap() {
+            "round-robin" => Some(Arc::new(RoundRobinScheduler::new())),
+            "min-communication" => Some(Arc::new(MinCommunicationScheduler::new())),
+            _ => None,
+        };
+        let heartbeat_interval = Duration::from_millis(parse_arg(args, "heartbeat-interval-ms"));
+        let heartbeat_timeout = Duration::from_millis(parse_arg(args, "heartbeat-timeout-ms"));
+        if heartbeat_timeout <= heartbeat_interval {
+            exit_with_error("The heartbeat timeout must be longer than the heartbeat interval");
+        }
+        let shared_memory_size = match parse_arg(args, "shared-memory-size") {
+            0 => None,
+            size => Some(size),
+        };
         Self {
             index: node_index,
             num_worker_threads: num_threads,
@@ -83,6 +203,35 @@ impl Configuration {
             control_addresses,
             logger: crate::get_terminal_logger(),
             graph_filename,
+            heartbeat_interval,
+            heartbeat_timeout,
+            checkpoint_store,
+            recover_from_checkpoint,
+            checkpoints_to_keep,
+            record_filename,
+            recorded_streams,
+            metrics_address,
+            stream_metrics: args.is_present("stream-metrics"),
+            trace_filename,
+            scheduler,
+            shared_memory_size,
         }
     }
 }
+
+/// Parses the value of the command line argument `name`, and exits if the value is invalid.
+fn parse_arg<T>(args: &clap::ArgMatches, name: &str) -> T
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let value = args.value_of(name).unwrap();
+    value.parse().unwrap_or_else(|e| {
+        exit_with_error(&format!("Invalid value '{}' for --{}: {}", value, name, e))
+    })
+}
+
+/// Prints a command line error and exits.
+fn exit_with_error(description: &str) -> ! {
+    clap::Error::with_description(description, clap::ErrorKind::InvalidValue).exit()
+}
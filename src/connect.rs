@@ -9,6 +9,10 @@ macro_rules! flow_watermarks {
     (($($rs:ident),+), ($($ws:ident),+)) => {
         let cb_builder = $crate::make_callback_builder!(($($rs.add_state(())),+), ($($ws),+));
         cb_builder.borrow_mut().add_watermark_callback_with_priority(|timestamp, $($rs),+, $($ws),+| {
+            // Only the write streams are used.
+            $(
+                let _ = $rs;
+            )+
             $(
                 match $ws.send(Message::new_watermark(timestamp.clone())) {
                     Ok(_) => (),
@@ -43,6 +47,39 @@ macro_rules! make_operator {
     };
 }
 
+/// Takes the endpoint on which the operator receives the read stream with the given ID, and
+/// returns the [`ReadStream`](crate::dataflow::ReadStream) the operator reads from.
+///
+/// Note: this is an internal macro called by [`make_operator_executor`] and
+/// [`make_vec_operator_executor`].
+#[doc(hidden)]
+#[macro_export]
+macro_rules! make_read_stream {
+    ($id:expr, $config:expr, $channel_manager:ident, $side_output:ident, $op_ex_streams:ident) => {{
+        let id = $id;
+        let recv_endpoint = $channel_manager
+            .lock()
+            .unwrap()
+            .take_recv_endpoint(id, &$crate::dataflow::graph::Vertex::Operator($config.id))
+            .unwrap();
+        let mut internal_stream = InternalReadStream::from_endpoint(recv_endpoint, id);
+        if let Some(late_data_policy) = $config.late_data_policy {
+            internal_stream.set_late_data_policy(late_data_policy, $config.allowed_lateness);
+        }
+        if let Some(side_output) = &$side_output {
+            $channel_manager.lock().unwrap().add_side_output(
+                id,
+                &$crate::dataflow::graph::Vertex::Operator($config.id),
+                side_output,
+            );
+            internal_stream.set_side_output(side_output.clone());
+        }
+        let read_stream = ReadStream::from(internal_stream);
+        $op_ex_streams.push(Box::new(OperatorExecutorStream::from(&read_stream)));
+        read_stream
+    }};
+}
+
 /// Makes a closure that initializes the operator and returns a corresponding
 /// [`OperatorExecutor`](crate::node::operator_executor::OperatorExecutor).
 ///
@@ -51,53 +88,134 @@ macro_rules! make_operator {
 #[macro_export]
 macro_rules! make_operator_executor {
     ($t:ty, $config:expr, ($($rs:ident),*), ($($ws:ident),*)) => {{
-        // Copy IDs to avoid moving streams into closure
+        // Copy IDs to avoid moving read streams into closure
         // Before: $rs is an identifier pointing to a read stream
         // $ws is an identifier pointing to a write stream
         $(
             let $rs = ($rs.get_id());
         )*
         $(
-            let $ws = ($ws.get_id());
+            let $ws = $ws.clone();
         )*
         // After: $rs is an identifier pointing to a read stream's StreamId
-        // $ws is an identifier pointing to a write stream's StreamId
-        move |channel_manager: Arc<Mutex<ChannelManager>>, control_sender: UnboundedSender<ControlMessage>, mut control_receiver: UnboundedReceiver<ControlMessage>| {
+        // $ws is an identifier pointing to a copy of the write stream returned by `connect`
+        move |channel_manager: Arc<Mutex<ChannelManager>>, control_sender: UnboundedSender<ControlMessage>, control_receiver: UnboundedReceiver<ControlMessage>| {
             let mut op_ex_streams: Vec<Box<dyn OperatorExecutorStreamT>> = Vec::new();
             // Before: $rs is an identifier pointing to a read stream's StreamId
-            // $ws is an identifier pointing to a write stream's StreamId
+            // $ws is an identifier pointing to a copy of the write stream returned by `connect`
+            let mut side_output: Option<$crate::dataflow::stream::SideOutput> = None;
             $(
-                let $rs = {
-                    let recv_endpoint = channel_manager.lock().unwrap().take_recv_endpoint($rs).unwrap();
-                    let read_stream = ReadStream::from(InternalReadStream::from_endpoint(recv_endpoint, $rs));
-                    op_ex_streams.push(
-                        Box::new(OperatorExecutorStream::from(&read_stream))
-                    );
-                    read_stream
+                let $ws = {
+                    let send_endpoints = channel_manager.lock().unwrap().get_send_endpoints($ws.get_id()).unwrap();
+                    let write_stream = WriteStream::from_template(&$ws, send_endpoints);
+                    if side_output.is_none() {
+                        side_output = write_stream.make_side_output();
+                    }
+                    write_stream
                 };
             )*
             $(
-                let $ws = {
-                    let send_endpoints = channel_manager.lock().unwrap().get_send_endpoints($ws).unwrap();
-                    WriteStream::from_endpoints(send_endpoints, $ws)
-                };
+                let $rs = $crate::make_read_stream!($rs, $config, channel_manager, side_output, op_ex_streams);
             )*
             // After: $rs is an identifier pointing to ReadStream
             // $ws is an identifier pointing to WriteStream
             let mut config = $config.clone();
             config.node_id = channel_manager.lock().unwrap().node_id();
+            config.reset_timers();
             let flow_watermarks = config.flow_watermarks;
-            // TODO: set operator name?
-            let mut op = $crate::make_operator!($t, config.clone(), ($($rs),*), ($($ws),*));
-            // Pass on watermarks
-            if flow_watermarks {
-                $crate::flow_watermarks!(($($rs),*), ($($ws),*));
-            }
-            // Notify node that operator is done setting up
-            if let Err(e) = control_sender.send(ControlMessage::OperatorInitialized(config.id)) {
-                panic!("Error sending OperatorInitialized message to control handler: {:?}", e);
+            // Creates the operator, and the instances which replace it upon a restart.
+            let new_operator = {
+                let config = config.clone();
+                move || {
+                    // TODO: set operator name?
+                    let op = $crate::make_operator!($t, config.clone(), ($($rs),*), ($($ws),*));
+                    // Pass on watermarks
+                    if flow_watermarks {
+                        $crate::flow_watermarks!(($($rs),*), ($($ws),*));
+                    }
+                    // Generate watermarks on idle write streams
+                    $(
+                        $ws.add_watermark_timer(&config);
+                    )*
+                    op
+                }
+            };
+            let op = new_operator();
+            let mut op_executor = OperatorExecutor::new(op, config, op_ex_streams, control_sender, control_receiver);
+            op_executor.set_operator_factory(Box::new(move || Box::new(new_operator())));
+            op_executor
+        }
+    }};
+}
+
+/// Makes a closure that initializes an operator which reads a vector of streams and writes on 1
+/// stream, and returns a corresponding
+/// [`OperatorExecutor`](crate::node::operator_executor::OperatorExecutor).
+///
+/// Note: this is intended as an internal macro called by [`connect_vec_1_write`].
+#[doc(hidden)]
+#[macro_export]
+macro_rules! make_vec_operator_executor {
+    ($t:ty, $config:expr, $read_stream_ids:expr, $ws:ident) => {{
+        let read_stream_ids: Vec<$crate::dataflow::stream::StreamId> = $read_stream_ids;
+        let ws = $ws.clone();
+        move |channel_manager: Arc<Mutex<ChannelManager>>,
+              control_sender: UnboundedSender<ControlMessage>,
+              control_receiver: UnboundedReceiver<ControlMessage>| {
+            let mut op_ex_streams: Vec<Box<dyn OperatorExecutorStreamT>> = Vec::new();
+            let send_endpoints = channel_manager
+                .lock()
+                .unwrap()
+                .get_send_endpoints(ws.get_id())
+                .unwrap();
+            let ws = WriteStream::from_template(&ws, send_endpoints);
+            let side_output = ws.make_side_output();
+            let mut read_streams = Vec::with_capacity(read_stream_ids.len());
+            for &id in read_stream_ids.iter() {
+                read_streams.push($crate::make_read_stream!(
+                    id,
+                    $config,
+                    channel_manager,
+                    side_output,
+                    op_ex_streams
+                ));
             }
-            let mut op_executor = OperatorExecutor::new(op, config, op_ex_streams, control_receiver);
+            let mut config = $config.clone();
+            config.node_id = channel_manager.lock().unwrap().node_id();
+            config.reset_timers();
+            let flow_watermarks = config.flow_watermarks;
+            // Creates the operator, and the instances which replace it upon a restart.
+            let new_operator = {
+                let config = config.clone();
+                move || {
+                    let op = <$t>::new(config.clone(), read_streams.clone(), ws.clone());
+                    // Pass on watermarks
+                    if flow_watermarks {
+                        $crate::dataflow::add_watermark_callback_vec(
+                            read_streams.iter().collect(),
+                            vec![&ws],
+                            |timestamp, write_streams| {
+                                for write_stream in write_streams.iter_mut() {
+                                    match write_stream
+                                        .send(Message::new_watermark(timestamp.clone()))
+                                    {
+                                        Ok(_) => (),
+                                        Err(_) => eprintln!("Error flowing watermark"),
+                                    }
+                                }
+                            },
+                            127,
+                        );
+                    }
+                    // Generate watermarks on idle write streams
+                    ws.add_watermark_timer(&config);
+                    op
+                }
+            };
+            let op = new_operator();
+            let mut op_executor =
+                OperatorExecutor::new(op, config, op_ex_streams, control_sender, control_receiver);
+            op_executor.set_operator_factory(Box::new(move || Box::new(new_operator())));
             op_executor
         }
     }};
@@ -110,17 +228,9 @@ macro_rules! make_operator_executor {
 #[macro_export]
 macro_rules! imports {
     () => {
-        use std::{
-            cell::RefCell,
-            rc::Rc,
-            sync::{Arc, Mutex},
-            thread,
-            time::Duration,
-        };
-        use $crate::slog;
+        use std::sync::{Arc, Mutex};
         use $crate::tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
         use $crate::{
-            self,
             communication::ControlMessage,
             dataflow::graph::default_graph,
             dataflow::stream::{InternalReadStream, WriteStreamT},
@@ -159,15 +269,55 @@ macro_rules! register {
         let read_stream_ids = vec![$($rs.get_id()),*];
         let write_stream_ids = vec![$($ws.get_id()),*];
         let op_runner = $crate::make_operator_executor!($t, config_copy, ($($rs),*), ($($ws),*));
-        default_graph::add_operator(config.id, config.name.clone(), config.node_id, read_stream_ids, write_stream_ids, op_runner);
+        default_graph::add_operator(config.id, config.name.clone(), config.node_id, config.pinned, read_stream_ids, write_stream_ids, op_runner);
         $(
-            default_graph::add_operator_stream(config.id, &$ws);
+            default_graph::add_operator_stream(config.id, &$ws, config.write_stream_capacity);
         )*
         // Register streams with stream manager.
         ($(ReadStream::from(&$ws)),*)
     }};
 }
 
+/// Registers an operator which reads a vector of streams and writes on 1 stream to the dataflow
+/// graph and the stream manager.
+///
+/// Note: this is intended as an internal macro called by [`connect_vec_1_write`].
+#[doc(hidden)]
+#[macro_export]
+macro_rules! register_vec {
+    ($t:ty, $config:expr, $rs:ident, $ws:ident) => {{
+        // Import necesary structs, modules, and functions.
+        $crate::imports!();
+
+        let mut config = $config.clone();
+        config.id = OperatorId::new_deterministic();
+        let config_copy = config.clone();
+
+        // No-op that throws compile-time error if types in `new` and `connect` don't match.
+        if false {
+            let mut op = <$t>::new(config.clone(), $rs.clone(), $ws.clone());
+            Operator::run(&mut op)
+        }
+
+        // Add operator to dataflow graph.
+        let read_stream_ids: Vec<_> = $rs.iter().map(|rs| rs.get_id()).collect();
+        let write_stream_ids = vec![$ws.get_id()];
+        let op_runner =
+            $crate::make_vec_operator_executor!($t, config_copy, read_stream_ids.clone(), $ws);
+        default_graph::add_operator(
+            config.id,
+            config.name.clone(),
+            config.node_id,
+            config.pinned,
+            read_stream_ids,
+            write_stream_ids,
+            op_runner,
+        );
+        default_graph::add_operator_stream(config.id, &$ws, config.write_stream_capacity);
+        ReadStream::from(&$ws)
+    }};
+}
+
 /// Connects read streams to an operator that writes on 0 streams.
 ///
 /// Use:
@@ -204,6 +354,24 @@ macro_rules! connect_1_write {
     }};
 }
 
+/// Connects a vector of read streams to an operator that writes on 1 stream.
+///
+/// The operator's `new` and `connect` functions receive the read streams as a `Vec` and a slice,
+/// respectively.
+///
+/// Use:
+/// ```ignore
+/// let read_stream_3 = connect_vec_1_write!(MyOp, arg, vec![read_stream_1, read_stream_2, ...]);
+/// ```
+#[macro_export]
+macro_rules! connect_vec_1_write {
+    ($t:ty, $config:expr, $read_streams:expr) => {{
+        let read_streams: Vec<$crate::dataflow::ReadStream<_>> = $read_streams;
+        let ws = <$t>::connect(&read_streams);
+        $crate::register_vec!($t, $config, read_streams, ws)
+    }};
+}
+
 /// Connects read streams to an operator that writes on 2 streams.
 ///
 /// Use:
@@ -255,8 +423,6 @@ macro_rules! make_callback_builder {
     // Base case: 1 read stream
     (($rs_head:expr), ($($ws:expr),*)) => {{
         use std::{cell::RefCell, rc::Rc};
-        use $crate::dataflow::callback_builder::MultiStreamEventMaker;
-
 
         let cb_builder = Rc::new(RefCell::new($rs_head));
         $(
@@ -0,0 +1,413 @@
+//! Structures and traits for checkpointing the state of operators.
+//!
+//! Checkpointing is enabled per [`TimeVersionedState`](crate::dataflow::state::TimeVersionedState)
+//! using
+//! [`StatefulReadStream::enable_checkpointing`](crate::dataflow::stream::StatefulReadStream::enable_checkpointing).
+//! Once a watermark callback closes a timestamp, the state is serialized and written to the
+//! [`CheckpointStore`] set in the node's [`Configuration`](crate::Configuration).
+//! Once a new checkpoint is written, a state removes its checkpoints which precede both the latest
+//! timestamp for which all states of the operator have a checkpoint, and the
+//! [`checkpoints_to_keep`](crate::Configuration::checkpoints_to_keep) latest checkpoints of the
+//! state.
+//! When recovering, a node restores states from the latest timestamp for which all states of the
+//! dataflow's operators have a checkpoint, and skips replayed messages with timestamps up to and
+//! including that timestamp.
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use crate::{dataflow::Timestamp, OperatorId};
+
+/// Error raised when writing or reading checkpoints.
+#[derive(Debug)]
+pub enum CheckpointError {
+    /// Failed to serialize/deserialize the state with Bincode.
+    BincodeError(bincode::Error),
+    /// Failed to access the checkpoint store.
+    IoError(io::Error),
+}
+
+impl From<bincode::Error> for CheckpointError {
+    fn from(e: bincode::Error) -> Self {
+        CheckpointError::BincodeError(e)
+    }
+}
+
+impl From<io::Error> for CheckpointError {
+    fn from(e: io::Error) -> Self {
+        CheckpointError::IoError(e)
+    }
+}
+
+/// Trait that must be implemented by stores of checkpoints.
+///
+/// Checkpoints are identified by a key, which is unique for each checkpointed state, and the
+/// timestamp for which the checkpoint was taken.
+pub trait CheckpointStore: Send + Sync {
+    /// Writes the checkpoint of a state for a timestamp.
+    fn write(&self, key: &str, t: &Timestamp, bytes: &[u8]) -> Result<(), CheckpointError>;
+    /// Reads the checkpoint of a state for a timestamp.
+    fn read(&self, key: &str, t: &Timestamp) -> Result<Option<Vec<u8>>, CheckpointError>;
+    /// Removes the checkpoint of a state for a timestamp, if it exists.
+    fn remove(&self, key: &str, t: &Timestamp) -> Result<(), CheckpointError>;
+    /// Returns the timestamps of the checkpoints of each state.
+    fn list(&self) -> Result<HashMap<String, BTreeSet<Timestamp>>, CheckpointError>;
+    /// Returns the timestamps of the checkpoints of a state.
+    fn list_key(&self, key: &str) -> Result<BTreeSet<Timestamp>, CheckpointError>;
+}
+
+/// Stores checkpoints as files in a local directory.
+///
+/// Each state has a sub-directory which contains one file per checkpointed timestamp.
+pub struct FileCheckpointStore {
+    dir: PathBuf,
+}
+
+impl FileCheckpointStore {
+    const EXTENSION: &'static str = "ckpt";
+
+    /// Creates a store which writes checkpoints to `dir`.
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    fn file_name(t: &Timestamp) -> String {
+        let mut name = String::from("t");
+        for coordinate in t.time().iter() {
+            name.push_str(&format!("_{}", coordinate));
+        }
+        format!("{}.{}", name, Self::EXTENSION)
+    }
+
+    fn parse_file_name(path: &Path) -> Option<Timestamp> {
+        if path.extension()? != Self::EXTENSION {
+            return None;
+        }
+        let stem = path.file_stem()?.to_str()?;
+        let mut parts = stem.split('_');
+        if parts.next()? != "t" {
+            return None;
+        }
+        let time: Result<Vec<u64>, _> = parts.map(|part| part.parse()).collect();
+        time.ok().map(Timestamp::new)
+    }
+
+    /// Recursively adds the checkpoints in `dir` to `checkpoints`.
+    fn list_dir(
+        &self,
+        dir: &Path,
+        checkpoints: &mut HashMap<String, BTreeSet<Timestamp>>,
+    ) -> Result<(), CheckpointError> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                self.list_dir(&path, checkpoints)?;
+            } else if let Some(t) = Self::parse_file_name(&path) {
+                let key = dir
+                    .strip_prefix(&self.dir)
+                    .expect("Checkpoint is outside of the store directory")
+                    .to_string_lossy()
+                    .to_string();
+                checkpoints.entry(key).or_default().insert(t);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CheckpointStore for FileCheckpointStore {
+    fn write(&self, key: &str, t: &Timestamp, bytes: &[u8]) -> Result<(), CheckpointError> {
+        let dir = self.dir.join(key);
+        fs::create_dir_all(&dir)?;
+        // Write to a temporary file first so that partially written checkpoints are not read.
+        let tmp_path = dir.join(format!("{}.tmp", Self::file_name(t)));
+        fs::write(&tmp_path, bytes)?;
+        fs::rename(&tmp_path, dir.join(Self::file_name(t)))?;
+        Ok(())
+    }
+
+    fn read(&self, key: &str, t: &Timestamp) -> Result<Option<Vec<u8>>, CheckpointError> {
+        match fs::read(self.dir.join(key).join(Self::file_name(t))) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(CheckpointError::from(e)),
+        }
+    }
+
+    fn remove(&self, key: &str, t: &Timestamp) -> Result<(), CheckpointError> {
+        match fs::remove_file(self.dir.join(key).join(Self::file_name(t))) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(CheckpointError::from(e)),
+        }
+    }
+
+    fn list(&self) -> Result<HashMap<String, BTreeSet<Timestamp>>, CheckpointError> {
+        let mut checkpoints = HashMap::new();
+        if self.dir.is_dir() {
+            self.list_dir(&self.dir, &mut checkpoints)?;
+        }
+        Ok(checkpoints)
+    }
+
+    fn list_key(&self, key: &str) -> Result<BTreeSet<Timestamp>, CheckpointError> {
+        let dir = self.dir.join(key);
+        let mut timestamps = BTreeSet::new();
+        if dir.is_dir() {
+            for entry in fs::read_dir(dir)? {
+                if let Some(t) = Self::parse_file_name(&entry?.path()) {
+                    timestamps.insert(t);
+                }
+            }
+        }
+        Ok(timestamps)
+    }
+}
+
+/// Returns the latest timestamp for which all states of the `operators` have a checkpoint.
+///
+/// The checkpoints of other operators, such as operators which were removed from the dataflow, are
+/// ignored so that their stale checkpoints do not prevent recovery.
+pub fn latest_consistent_checkpoint(
+    store: &dyn CheckpointStore,
+    operators: &[OperatorId],
+) -> Result<Option<Timestamp>, CheckpointError> {
+    let operators: HashSet<String> = operators.iter().map(OperatorId::to_string).collect();
+    let checkpoints = store.list()?;
+    // The keys of an operator's states start with the operator's id.
+    let mut timestamps = checkpoints
+        .iter()
+        .filter(|(key, _)| operators.contains(key.split('/').next().unwrap()))
+        .map(|(_, timestamps)| timestamps);
+    let first = match timestamps.next() {
+        Some(first) => first.clone(),
+        None => return Ok(None),
+    };
+    let common = timestamps.fold(first, |common, other| {
+        common.intersection(other).cloned().collect()
+    });
+    Ok(common.into_iter().next_back())
+}
+
+/// Writes the checkpoints of a state to a store, and reads the checkpoint from which the state
+/// recovers.
+#[doc(hidden)]
+#[derive(Clone)]
+pub struct Checkpointer {
+    store: Arc<dyn CheckpointStore>,
+    /// Uniquely identifies the checkpointed state.
+    key: String,
+    /// Timestamp of the checkpoint from which to recover.
+    recovery_time: Option<Timestamp>,
+    /// The number of latest checkpoints of the state which are kept in the store.
+    checkpoints_to_keep: usize,
+    /// The keys of the checkpointed states of the operator, which are shared with the nested
+    /// checkpointers.
+    state_keys: Arc<Mutex<BTreeSet<String>>>,
+}
+
+impl Checkpointer {
+    pub(crate) fn new(
+        store: Arc<dyn CheckpointStore>,
+        key: String,
+        recovery_time: Option<Timestamp>,
+        checkpoints_to_keep: usize,
+    ) -> Self {
+        Self {
+            store,
+            key,
+            recovery_time,
+            checkpoints_to_keep,
+            state_keys: Arc::new(Mutex::new(BTreeSet::new())),
+        }
+    }
+
+    /// Returns a checkpointer for a state nested under the current key.
+    pub(crate) fn nested(&self, key: &str) -> Self {
+        Self {
+            store: Arc::clone(&self.store),
+            key: format!("{}/{}", self.key, key),
+            recovery_time: self.recovery_time.clone(),
+            checkpoints_to_keep: self.checkpoints_to_keep,
+            state_keys: Arc::clone(&self.state_keys),
+        }
+    }
+
+    /// Registers the state as one of the operator's checkpointed states, whose checkpoints other
+    /// states keep until they are superseded by a consistent checkpoint.
+    pub(crate) fn register(&self) {
+        self.state_keys.lock().unwrap().insert(self.key.clone());
+    }
+
+    /// Returns the timestamp of the checkpoint from which the state recovers.
+    pub(crate) fn recovery_time(&self) -> Option<&Timestamp> {
+        self.recovery_time.as_ref()
+    }
+
+    /// Writes the checkpoint of the state for a timestamp, and removes the checkpoints which
+    /// precede both the latest checkpoint common to all states of the operator and the
+    /// `checkpoints_to_keep` latest checkpoints of the state.
+    pub(crate) fn write(&self, t: &Timestamp, bytes: &[u8]) -> Result<(), CheckpointError> {
+        self.register();
+        self.store.write(&self.key, t, bytes)?;
+        let state_keys: Vec<String> = self.state_keys.lock().unwrap().iter().cloned().collect();
+        let mut timestamps = BTreeSet::new();
+        let mut common: Option<BTreeSet<Timestamp>> = None;
+        for key in state_keys.iter() {
+            let key_timestamps = self.store.list_key(key)?;
+            common = Some(match common {
+                Some(common) => common.intersection(&key_timestamps).cloned().collect(),
+                None => key_timestamps.clone(),
+            });
+            if key == &self.key {
+                timestamps = key_timestamps;
+            }
+        }
+        // Recovery needs the latest consistent checkpoint, so keep it and all later checkpoints.
+        let consistent_time = match common.and_then(|common| common.into_iter().next_back()) {
+            Some(consistent_time) => consistent_time,
+            None => return Ok(()),
+        };
+        let num_removable = timestamps.len().saturating_sub(self.checkpoints_to_keep);
+        for old_t in timestamps.iter().take(num_removable) {
+            if old_t >= &consistent_time {
+                break;
+            }
+            self.store.remove(&self.key, old_t)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the checkpoint from which the state recovers, if there is one.
+    pub(crate) fn read_recovery_checkpoint(
+        &self,
+    ) -> Result<Option<(Timestamp, Vec<u8>)>, CheckpointError> {
+        match &self.recovery_time {
+            Some(t) => Ok(self
+                .store
+                .read(&self.key, t)?
+                .map(|bytes| (t.clone(), bytes))),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latest_consistent_checkpoint() {
+        let dir =
+            std::env::temp_dir().join(format!("erdos-checkpoint-test-{}", std::process::id()));
+        let store = FileCheckpointStore::new(&dir);
+        let op = OperatorId::new_v4();
+        let (key_a, key_b) = (format!("{}/a", op), format!("{}/b", op));
+        assert_eq!(latest_consistent_checkpoint(&store, &[op]).unwrap(), None);
+
+        for t in 1..4 {
+            store
+                .write(&key_a, &Timestamp::new(vec![t]), &[t as u8])
+                .unwrap();
+        }
+        for t in 1..3 {
+            store
+                .write(&key_b, &Timestamp::new(vec![t]), &[t as u8])
+                .unwrap();
+        }
+        // Checkpoints of operators which are not in the dataflow are ignored.
+        store
+            .write(
+                &format!("{}/a", OperatorId::new_v4()),
+                &Timestamp::new(vec![1]),
+                &[1],
+            )
+            .unwrap();
+        // Timestamp 3 is not checkpointed for state b.
+        assert_eq!(
+            latest_consistent_checkpoint(&store, &[op]).unwrap(),
+            Some(Timestamp::new(vec![2]))
+        );
+        assert_eq!(
+            store.read(&key_a, &Timestamp::new(vec![3])).unwrap(),
+            Some(vec![3])
+        );
+        assert_eq!(store.read(&key_b, &Timestamp::new(vec![3])).unwrap(), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_remove_old_checkpoints() {
+        let dir = std::env::temp_dir().join(format!("erdos-prune-test-{}", std::process::id()));
+        let store: Arc<dyn CheckpointStore> = Arc::new(FileCheckpointStore::new(&dir));
+        let checkpointer = Checkpointer::new(Arc::clone(&store), String::from("op"), None, 2);
+        for t in 1..5 {
+            checkpointer
+                .write(&Timestamp::new(vec![t]), &[t as u8])
+                .unwrap();
+        }
+        let expected: BTreeSet<Timestamp> = (3..5).map(|t| Timestamp::new(vec![t])).collect();
+        assert_eq!(store.list().unwrap().get("op"), Some(&expected));
+        assert_eq!(fs::read_dir(dir.join("op")).unwrap().count(), 2);
+        assert_eq!(store.read("op", &Timestamp::new(vec![1])).unwrap(), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_keep_consistent_checkpoint() {
+        let dir =
+            std::env::temp_dir().join(format!("erdos-consistent-test-{}", std::process::id()));
+        let store: Arc<dyn CheckpointStore> = Arc::new(FileCheckpointStore::new(&dir));
+        let op = OperatorId::new_v4();
+        let checkpointer = Checkpointer::new(Arc::clone(&store), op.to_string(), None, 1);
+        let (fast, slow) = (checkpointer.nested("fast"), checkpointer.nested("slow"));
+        fast.register();
+        slow.register();
+        let write = |checkpointer: &Checkpointer, t: u64| {
+            checkpointer
+                .write(&Timestamp::new(vec![t]), &[t as u8])
+                .unwrap()
+        };
+        let timestamps = |checkpointer: &Checkpointer| -> Vec<u64> {
+            store
+                .list_key(&checkpointer.key)
+                .unwrap()
+                .iter()
+                .map(|t| t.time()[0])
+                .collect()
+        };
+
+        // The fast state keeps its checkpoints until the slow state checkpoints the same time.
+        for t in 1..5 {
+            write(&fast, t);
+        }
+        assert_eq!(timestamps(&fast), vec![1, 2, 3, 4]);
+        write(&slow, 2);
+        assert_eq!(
+            latest_consistent_checkpoint(store.as_ref(), &[op]).unwrap(),
+            Some(Timestamp::new(vec![2]))
+        );
+        write(&fast, 5);
+        assert_eq!(timestamps(&fast), vec![2, 3, 4, 5]);
+        assert_eq!(timestamps(&slow), vec![2]);
+
+        // Once the slow state catches up, the older checkpoints are removed.
+        write(&slow, 4);
+        assert_eq!(timestamps(&slow), vec![4]);
+        write(&fast, 6);
+        assert_eq!(timestamps(&fast), vec![4, 5, 6]);
+        assert_eq!(
+            latest_consistent_checkpoint(store.as_ref(), &[op]).unwrap(),
+            Some(Timestamp::new(vec![4]))
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
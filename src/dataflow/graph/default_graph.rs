@@ -4,50 +4,66 @@ use serde::Deserialize;
 
 use crate::{
     dataflow::{
-        stream::{ExtractStream, IngestStream, LoopStream, StreamId, WriteStream},
+        stream::{ExtractStream, IngestStream, LoopStream, StreamCapacity, StreamId, WriteStream},
         Data,
     },
     node::NodeId,
     OperatorId,
 };
 
-use super::{Graph, OperatorRunner, StreamSetupHook};
+use super::{Graph, OperatorRunner, StreamCloseHook, StreamSetupHook};
 
 thread_local!(static DEFAULT_GRAPH: RefCell<Graph> = RefCell::new(Graph::new()));
 
 /// Adds an operator to the default graph.
 ///
-/// The operator is pinned on a given node.
+/// The operator runs on the given node, unless it is not pinned and the scheduler places it on
+/// another node.
 pub fn add_operator<F: OperatorRunner>(
     id: OperatorId,
     name: Option<String>,
     node_id: NodeId,
+    pinned: bool,
     read_stream_ids: Vec<StreamId>,
     write_stream_ids: Vec<StreamId>,
     runner: F,
 ) {
     DEFAULT_GRAPH.with(|g| {
-        g.borrow_mut()
-            .add_operator(id, name, node_id, read_stream_ids, write_stream_ids, runner);
+        g.borrow_mut().add_operator(
+            id,
+            name,
+            node_id,
+            pinned,
+            read_stream_ids,
+            write_stream_ids,
+            runner,
+        );
     });
 }
 
-pub fn add_operator_stream<D>(operator_id: OperatorId, write_stream: &WriteStream<D>)
-where
+pub fn add_operator_stream<D>(
+    operator_id: OperatorId,
+    write_stream: &WriteStream<D>,
+    default_capacity: Option<StreamCapacity>,
+) where
     for<'a> D: Data + Deserialize<'a>,
 {
     DEFAULT_GRAPH.with(|g| {
         g.borrow_mut()
-            .add_operator_stream(operator_id, write_stream);
+            .add_operator_stream(operator_id, write_stream, default_capacity);
     });
 }
 
-pub fn add_ingest_stream<D, F: StreamSetupHook>(ingest_stream: &IngestStream<D>, setup_hook: F)
-where
+pub fn add_ingest_stream<D, F: StreamSetupHook, G: StreamCloseHook>(
+    ingest_stream: &IngestStream<D>,
+    setup_hook: F,
+    close_hook: G,
+) where
     for<'a> D: Data + Deserialize<'a>,
 {
     DEFAULT_GRAPH.with(|g| {
-        g.borrow_mut().add_ingest_stream(ingest_stream, setup_hook);
+        g.borrow_mut()
+            .add_ingest_stream(ingest_stream, setup_hook, close_hook);
     });
 }
 
@@ -70,11 +86,48 @@ where
     });
 }
 
+/// Removes an operator from the default graph.
+pub fn remove_operator(id: OperatorId) -> Result<(), String> {
+    DEFAULT_GRAPH.with(|g| g.borrow_mut().remove_operator(id))
+}
+
+/// Removes an ingest stream from the default graph.
+pub fn remove_ingest_stream<D>(ingest_stream: &IngestStream<D>) -> Result<(), String>
+where
+    for<'a> D: Data + Deserialize<'a>,
+{
+    DEFAULT_GRAPH.with(|g| {
+        g.borrow_mut()
+            .remove_ingest_stream(ingest_stream.get_id(), ingest_stream.get_node_id())
+    })
+}
+
+/// Removes an extract stream from the default graph.
+pub fn remove_extract_stream<D>(extract_stream: &ExtractStream<D>) -> Result<(), String>
+where
+    for<'a> D: Data + Deserialize<'a>,
+{
+    DEFAULT_GRAPH.with(|g| {
+        g.borrow_mut()
+            .remove_extract_stream(extract_stream.get_id(), extract_stream.get_node_id())
+    })
+}
+
 /// Adds an alias from from_id to to_id on the default graph.
 pub fn add_stream_alias(from_id: StreamId, to_id: StreamId) -> Result<(), String> {
     DEFAULT_GRAPH.with(|g| g.borrow_mut().add_stream_alias(from_id, to_id))
 }
 
+/// Sets the number of iteration scopes of a stream on the default graph.
+pub(crate) fn set_iteration_depth(stream_id: StreamId, depth: usize) {
+    DEFAULT_GRAPH.with(|g| g.borrow_mut().set_iteration_depth(stream_id, depth))
+}
+
+/// Returns the number of iteration scopes of a stream on the default graph.
+pub(crate) fn get_iteration_depth(stream_id: StreamId) -> usize {
+    DEFAULT_GRAPH.with(|g| g.borrow().get_iteration_depth(stream_id))
+}
+
 pub fn clone() -> Graph {
     DEFAULT_GRAPH.with(|g| g.borrow().clone())
 }
@@ -3,7 +3,10 @@ use std::marker::PhantomData;
 use serde::Deserialize;
 
 use crate::{
-    dataflow::{stream::StreamId, Data},
+    dataflow::{
+        stream::{StreamCapacity, StreamId},
+        Data,
+    },
     scheduler::channel_manager::{StreamEndpoints, StreamEndpointsT},
 };
 
@@ -13,9 +16,23 @@ use super::Vertex;
 pub enum Channel {
     InterThread(ChannelMetadata),
     InterNode(ChannelMetadata),
+    /// A channel between nodes which run on the same host, over a shared-memory ring.
+    SharedMemory(ChannelMetadata),
     Unscheduled(ChannelMetadata),
 }
 
+impl Channel {
+    /// Returns the metadata of the channel, regardless of how it is scheduled.
+    pub fn metadata(&self) -> &ChannelMetadata {
+        match self {
+            Self::InterThread(cm)
+            | Self::InterNode(cm)
+            | Self::SharedMemory(cm)
+            | Self::Unscheduled(cm) => cm,
+        }
+    }
+}
+
 /// Stores metadata about a data-flow channel.
 ///
 /// A data-flow channel is an edge in the data-flow graph.
@@ -44,6 +61,8 @@ where
     id: StreamId,
     source: Vertex,
     channels: Vec<Channel>,
+    /// Bounds the channels of the stream, which are unbounded if `None`.
+    capacity: Option<StreamCapacity>,
     phantom: PhantomData<D>,
 }
 
@@ -51,11 +70,12 @@ impl<D> TypedStreamMetadata<D>
 where
     for<'a> D: Data + Deserialize<'a>,
 {
-    pub fn new(id: StreamId, source: Vertex) -> Self {
+    pub fn new(id: StreamId, source: Vertex, capacity: Option<StreamCapacity>) -> Self {
         Self {
             id,
             source,
             channels: Vec::new(),
+            capacity,
             phantom: PhantomData,
         }
     }
@@ -64,6 +84,7 @@ where
 pub trait StreamMetadataT: Send {
     fn get_id(&self) -> StreamId;
     fn get_source(&self) -> Vertex;
+    fn get_capacity(&self) -> Option<StreamCapacity>;
     fn box_clone(&self) -> Box<dyn StreamMetadataT>;
     fn to_stream_endpoints_t(&self) -> Box<dyn StreamEndpointsT>;
     fn add_channel(&mut self, channel: Channel);
@@ -83,12 +104,16 @@ where
         self.source.clone()
     }
 
+    fn get_capacity(&self) -> Option<StreamCapacity> {
+        self.capacity
+    }
+
     fn box_clone(&self) -> Box<dyn StreamMetadataT> {
         Box::new(self.clone())
     }
 
     fn to_stream_endpoints_t(&self) -> Box<dyn StreamEndpointsT> {
-        Box::new(StreamEndpoints::<D>::new(self.id))
+        Box::new(StreamEndpoints::<D>::new(self.id, self.capacity))
     }
 
     fn add_channel(&mut self, channel: Channel) {
@@ -109,12 +134,12 @@ pub struct StreamMetadata {
 }
 
 impl StreamMetadata {
-    pub fn new<D>(id: StreamId, source: Vertex) -> Self
+    pub fn new<D>(id: StreamId, source: Vertex, capacity: Option<StreamCapacity>) -> Self
     where
         for<'a> D: Data + Deserialize<'a>,
     {
         Self {
-            stream_metadata_t: Box::new(TypedStreamMetadata::<D>::new(id, source)),
+            stream_metadata_t: Box::new(TypedStreamMetadata::<D>::new(id, source, capacity)),
         }
     }
 
@@ -126,6 +151,10 @@ impl StreamMetadata {
         self.stream_metadata_t.get_source()
     }
 
+    pub fn get_capacity(&self) -> Option<StreamCapacity> {
+        self.stream_metadata_t.get_capacity()
+    }
+
     pub fn to_stream_endpoints_t(&self) -> Box<dyn StreamEndpointsT> {
         self.stream_metadata_t.to_stream_endpoints_t()
     }
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::prelude::*;
 
@@ -6,7 +6,7 @@ use serde::Deserialize;
 
 use crate::{
     dataflow::{
-        stream::{ExtractStream, IngestStream, LoopStream, StreamId, WriteStream},
+        stream::{ExtractStream, IngestStream, LoopStream, StreamCapacity, StreamId, WriteStream},
         Data,
     },
     node::NodeId,
@@ -14,8 +14,8 @@ use crate::{
 };
 
 use super::{
-    Channel, ChannelMetadata, DriverMetadata, OperatorMetadata, OperatorRunner, StreamMetadata,
-    StreamSetupHook, Vertex,
+    Channel, ChannelMetadata, DriverMetadata, OperatorMetadata, OperatorRunner, StreamCloseHook,
+    StreamMetadata, StreamSetupHook, Vertex,
 };
 
 /// Represents a data-flow computation.
@@ -33,6 +33,8 @@ pub struct Graph {
     streams: HashMap<StreamId, StreamMetadata>,
     /// ID mappings for streams aliasing other streams, e.g. LoopStreams
     stream_aliases: HashMap<StreamId, StreamId>,
+    /// The number of iteration scopes of the streams which enter or leave the scope of a loop.
+    iteration_depths: HashMap<StreamId, usize>,
 }
 
 impl Graph {
@@ -42,14 +44,17 @@ impl Graph {
             drivers: HashMap::new(),
             streams: HashMap::new(),
             stream_aliases: HashMap::new(),
+            iteration_depths: HashMap::new(),
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn add_operator<F: OperatorRunner>(
         &mut self,
         id: OperatorId,
         name: Option<String>,
         node_id: NodeId,
+        pinned: bool,
         read_stream_ids: Vec<StreamId>,
         write_stream_ids: Vec<StreamId>,
         runner: F,
@@ -76,25 +81,43 @@ impl Graph {
 
         self.operators.insert(
             id,
-            OperatorMetadata::new(id, name, node_id, read_stream_ids, write_stream_ids, runner),
+            OperatorMetadata::new(
+                id,
+                name,
+                node_id,
+                pinned,
+                read_stream_ids,
+                write_stream_ids,
+                runner,
+            ),
         );
     }
 
-    pub fn add_operator_stream<D>(&mut self, operator_id: OperatorId, write_stream: &WriteStream<D>)
-    where
+    /// Adds a stream written by an operator.
+    ///
+    /// The stream's channels are bounded by the capacity set on the write stream, or otherwise by
+    /// `default_capacity`.
+    pub fn add_operator_stream<D>(
+        &mut self,
+        operator_id: OperatorId,
+        write_stream: &WriteStream<D>,
+        default_capacity: Option<StreamCapacity>,
+    ) where
         for<'a> D: Data + Deserialize<'a>,
     {
         let stream_id = write_stream.get_id();
+        let capacity = write_stream.get_capacity().or(default_capacity);
         let mut stream_metadata =
-            StreamMetadata::new::<D>(stream_id, Vertex::Operator(operator_id));
+            StreamMetadata::new::<D>(stream_id, Vertex::Operator(operator_id), capacity);
         self.add_channels(&mut stream_metadata);
         self.streams.insert(stream_id, stream_metadata);
     }
 
-    pub fn add_ingest_stream<D, F: StreamSetupHook>(
+    pub fn add_ingest_stream<D, F: StreamSetupHook, G: StreamCloseHook>(
         &mut self,
         ingest_stream: &IngestStream<D>,
         setup_hook: F,
+        close_hook: G,
     ) where
         for<'a> D: Data + Deserialize<'a>,
     {
@@ -105,9 +128,10 @@ impl Graph {
             .drivers
             .entry(ingest_stream.get_node_id())
             .or_insert_with(|| DriverMetadata::new(node_id));
-        driver.add_ingest_stream(stream_id, setup_hook);
+        driver.add_ingest_stream(stream_id, setup_hook, close_hook);
         // Add stream to graph
-        let mut stream_metadata = StreamMetadata::new::<D>(stream_id, Vertex::Driver(node_id));
+        let mut stream_metadata =
+            StreamMetadata::new::<D>(stream_id, Vertex::Driver(node_id), None);
         self.add_channels(&mut stream_metadata);
         self.streams.insert(stream_id, stream_metadata);
     }
@@ -146,7 +170,48 @@ impl Graph {
     {
         let write_stream = WriteStream::<D>::new_with_id(loop_stream.get_id());
         // TODO: clean up this hack
-        self.add_operator_stream(OperatorId::nil(), &write_stream);
+        self.add_operator_stream(OperatorId::nil(), &write_stream, None);
+    }
+
+    /// Sets the number of iteration scopes of a stream which enters or leaves the scope of a loop.
+    pub(crate) fn set_iteration_depth(&mut self, stream_id: StreamId, depth: usize) {
+        self.iteration_depths
+            .insert(self.resolve_stream_id(stream_id), depth);
+    }
+
+    /// Returns the number of iteration scopes the stream is in, which is the number of iteration
+    /// coordinates of the timestamps of its messages.
+    ///
+    /// Streams written by operators are in the deepest scope of the operators' read streams.
+    pub(crate) fn get_iteration_depth(&self, stream_id: StreamId) -> usize {
+        self.get_iteration_depth_from(self.resolve_stream_id(stream_id), &mut HashSet::new())
+    }
+
+    fn get_iteration_depth_from(
+        &self,
+        stream_id: StreamId,
+        visited: &mut HashSet<StreamId>,
+    ) -> usize {
+        if let Some(&depth) = self.iteration_depths.get(&stream_id) {
+            return depth;
+        }
+        // Loops without iteration scopes do not change the depth.
+        if !visited.insert(stream_id) {
+            return 0;
+        }
+        let operator_id = match self.streams.get(&stream_id).map(|s| s.get_source()) {
+            Some(Vertex::Operator(operator_id)) => operator_id,
+            _ => return 0,
+        };
+        match self.operators.get(&operator_id) {
+            Some(operator) => operator
+                .read_stream_ids
+                .iter()
+                .map(|&id| self.get_iteration_depth_from(self.resolve_stream_id(id), visited))
+                .max()
+                .unwrap_or(0),
+            None => 0,
+        }
     }
 
     pub fn resolve_stream_id(&self, stream_id: StreamId) -> StreamId {
@@ -174,6 +239,7 @@ impl Graph {
                     for channel in from_stream.get_channels() {
                         let mut channel_metadata = match channel {
                             Channel::InterNode(cm) => cm,
+                            Channel::SharedMemory(cm) => cm,
                             Channel::InterThread(cm) => cm,
                             Channel::Unscheduled(cm) => cm,
                         };
@@ -245,6 +311,130 @@ impl Graph {
     pub fn get_streams_ref_mut(&mut self) -> Vec<&mut StreamMetadata> {
         self.streams.values_mut().collect()
     }
+
+    /// Places an operator on a node.
+    pub(crate) fn set_operator_node(&mut self, operator_id: OperatorId, node_id: NodeId) {
+        if let Some(operator) = self.operators.get_mut(&operator_id) {
+            operator.node_id = node_id;
+        }
+    }
+
+    /// Pins an operator to a node so that the scheduler does not move it.
+    pub(crate) fn pin_operator(&mut self, operator_id: OperatorId, node_id: NodeId) {
+        if let Some(operator) = self.operators.get_mut(&operator_id) {
+            operator.node_id = node_id;
+            operator.pinned = true;
+        }
+    }
+
+    /// Removes an operator, the channels on which it reads, and the streams it writes.
+    ///
+    /// Fails if another operator or driver reads from a stream the operator writes.
+    pub fn remove_operator(&mut self, operator_id: OperatorId) -> Result<(), String> {
+        let operator = match self.operators.get(&operator_id) {
+            Some(operator) => operator,
+            None => {
+                return Err(format!(
+                    "Dataflow graph does not contain operator with ID {}",
+                    operator_id
+                ))
+            }
+        };
+        for stream_id in operator.write_stream_ids.iter() {
+            if let Some(stream) = self.streams.get(stream_id) {
+                if let Some(channel) = stream
+                    .get_channels()
+                    .iter()
+                    .find(|channel| channel.metadata().sink != Vertex::Operator(operator_id))
+                {
+                    return Err(format!(
+                        "Unable to remove operator {}: {:?} reads from its stream {}",
+                        operator_id,
+                        channel.metadata().sink,
+                        stream_id
+                    ));
+                }
+            }
+        }
+        let operator = self.operators.remove(&operator_id).unwrap();
+        for stream_id in operator.write_stream_ids.iter() {
+            self.streams.remove(stream_id);
+        }
+        // Remove the channels on which the operator reads.
+        for stream in self.streams.values_mut() {
+            let channels = stream
+                .get_channels()
+                .into_iter()
+                .filter(|channel| channel.metadata().sink != Vertex::Operator(operator_id))
+                .collect();
+            stream.set_channels(channels);
+        }
+        Ok(())
+    }
+
+    /// Removes an ingest stream of the driver on `node_id`.
+    ///
+    /// Fails if an operator or driver reads from the stream.
+    pub fn remove_ingest_stream(
+        &mut self,
+        stream_id: StreamId,
+        node_id: NodeId,
+    ) -> Result<(), String> {
+        if let Some(stream) = self.streams.get(&stream_id) {
+            if let Some(channel) = stream.get_channels().first() {
+                return Err(format!(
+                    "Unable to remove ingest stream {}: {:?} reads from it",
+                    stream_id,
+                    channel.metadata().sink
+                ));
+            }
+        }
+        let removed = match self.drivers.get_mut(&node_id) {
+            Some(driver) => driver.remove_ingest_stream(stream_id),
+            None => false,
+        };
+        if removed {
+            self.streams.remove(&stream_id);
+            Ok(())
+        } else {
+            Err(format!(
+                "Driver on node {} does not have ingest stream {}",
+                node_id, stream_id
+            ))
+        }
+    }
+
+    /// Removes an extract stream of the driver on `node_id`.
+    pub fn remove_extract_stream(
+        &mut self,
+        stream_id: StreamId,
+        node_id: NodeId,
+    ) -> Result<(), String> {
+        let removed = match self.drivers.get_mut(&node_id) {
+            Some(driver) => driver.remove_extract_stream(stream_id),
+            None => false,
+        };
+        if removed {
+            // Remove a single channel in case the driver extracts the stream several times.
+            if let Some(stream) = self.streams.get_mut(&stream_id) {
+                let mut channels = stream.get_channels();
+                if let Some(index) = channels
+                    .iter()
+                    .position(|channel| channel.metadata().sink == Vertex::Driver(node_id))
+                {
+                    channels.remove(index);
+                }
+                stream.set_channels(channels);
+            }
+            Ok(())
+        } else {
+            Err(format!(
+                "Driver on node {} does not have extract stream {}",
+                node_id, stream_id
+            ))
+        }
+    }
+
     pub fn get_vertices_on(&self, node_id: NodeId) -> Vec<Vertex> {
         let mut result = Vec::new();
         result.extend(
@@ -267,30 +457,46 @@ impl Graph {
         let mut file = File::create(filename)?;
         writeln!(file, "digraph erdos_dataflow {{")?;
 
-        // Drivers
-        writeln!(file, "   // Declare driver")?;
-        for driver_metadata in self.drivers.values() {
-            writeln!(
-                file,
-                "   \"{node_id}\" [label=\"Driver ({node_id})\"];",
-                node_id = driver_metadata.id
-            )?;
-        }
+        // Group the drivers and operators by the node on which they run.
+        let mut node_ids: Vec<NodeId> = self
+            .drivers
+            .keys()
+            .cloned()
+            .chain(self.operators.values().map(|op| op.node_id))
+            .collect();
+        node_ids.sort_unstable();
+        node_ids.dedup();
+        for node_id in node_ids {
+            writeln!(file, "   subgraph cluster_node_{} {{", node_id)?;
+            writeln!(file, "      label=\"Node {}\";", node_id)?;
 
-        // Operators
-        writeln!(file, "   // Declare operators")?;
-        for operator in self.operators.values() {
-            let op_name = match &operator.name {
-                Some(name) => name.clone(),
-                None => format!("{}", operator.id),
-            };
-            writeln!(
-                file,
-                "   \"{op_id}\" [label=\"{op_name}\\n(Node {node_id})\"];",
-                op_name = op_name,
-                op_id = operator.id,
-                node_id = operator.node_id
-            )?;
+            // Drivers
+            writeln!(file, "      // Declare driver")?;
+            if let Some(driver_metadata) = self.drivers.get(&node_id) {
+                writeln!(
+                    file,
+                    "      \"{node_id}\" [label=\"Driver ({node_id})\"];",
+                    node_id = driver_metadata.id
+                )?;
+            }
+
+            // Operators
+            writeln!(file, "      // Declare operators")?;
+            for operator in self.operators.values().filter(|op| op.node_id == node_id) {
+                let op_name = match &operator.name {
+                    Some(name) => name.clone(),
+                    None => format!("{}", operator.id),
+                };
+                writeln!(
+                    file,
+                    "      \"{op_id}\" [label=\"{op_name}\\n(Node {node_id}{pinned})\"];",
+                    op_name = op_name,
+                    op_id = operator.id,
+                    node_id = operator.node_id,
+                    pinned = if operator.pinned { ", pinned" } else { "" }
+                )?;
+            }
+            writeln!(file, "   }}")?;
         }
 
         // Channels
@@ -301,8 +507,10 @@ impl Graph {
                 Vertex::Operator(op_id) => format!("{}", op_id),
             };
             for channel in stream.get_channels() {
-                let channel_metadata = match channel {
-                    Channel::InterNode(x) | Channel::InterThread(x) | Channel::Unscheduled(x) => x,
+                // Channels between nodes are dashed.
+                let (channel_metadata, style) = match channel {
+                    Channel::InterNode(x) | Channel::SharedMemory(x) => (x, ", style=dashed"),
+                    Channel::InterThread(x) | Channel::Unscheduled(x) => (x, ""),
                 };
                 let to = match channel_metadata.sink {
                     Vertex::Driver(node_id) => format!("{}", node_id),
@@ -310,10 +518,11 @@ impl Graph {
                 };
                 writeln!(
                     file,
-                    "   \"{from}\" -> \"{to}\" [label=\"{stream_id}\"];",
+                    "   \"{from}\" -> \"{to}\" [label=\"{stream_id}\"{style}];",
                     from = from,
                     to = to,
-                    stream_id = stream.get_id()
+                    stream_id = stream.get_id(),
+                    style = style
                 )?;
             }
         }
@@ -17,10 +17,11 @@ pub mod default_graph;
 
 // Crate-wide exports
 pub(crate) use edge::{Channel, ChannelMetadata, StreamMetadata};
-pub(crate) use vertex::{DriverMetadata, OperatorMetadata, Vertex};
+pub(crate) use vertex::{DriverMetadata, OperatorMetadata};
 
 // Public exports
 pub use graph::Graph;
+pub use vertex::Vertex;
 
 pub trait OperatorRunner:
     'static
@@ -61,3 +62,14 @@ impl<T: 'static + Fn(Arc<Mutex<ChannelManager>>) + Sync + Send + Clone> StreamSe
         Box::new(self.clone())
     }
 }
+
+/// Closes a stream of the driver when the node shuts down gracefully.
+pub trait StreamCloseHook: 'static + Fn() + Sync + Send {
+    fn box_clone(&self) -> Box<dyn StreamCloseHook>;
+}
+
+impl<T: 'static + Fn() + Sync + Send + Clone> StreamCloseHook for T {
+    fn box_clone(&self) -> Box<dyn StreamCloseHook> {
+        Box::new(self.clone())
+    }
+}
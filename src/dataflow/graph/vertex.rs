@@ -1,10 +1,13 @@
 use crate::{dataflow::stream::StreamId, node::NodeId, OperatorId};
 
-use super::{OperatorRunner, StreamSetupHook};
+use super::{OperatorRunner, StreamCloseHook, StreamSetupHook};
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+/// A vertex of the dataflow graph.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Vertex {
+    /// The driver running on a node.
     Driver(NodeId),
+    /// An operator.
     Operator(OperatorId),
 }
 
@@ -13,7 +16,12 @@ pub struct DriverMetadata {
     pub id: NodeId,
     pub ingest_stream_ids: Vec<StreamId>,
     pub extract_stream_ids: Vec<StreamId>,
-    pub setup_hooks: Vec<Box<dyn StreamSetupHook>>,
+    /// Set up the ingest streams, in the order of `ingest_stream_ids`.
+    pub ingest_setup_hooks: Vec<Box<dyn StreamSetupHook>>,
+    /// Close the ingest streams, in the order of `ingest_stream_ids`.
+    pub ingest_close_hooks: Vec<Box<dyn StreamCloseHook>>,
+    /// Set up the extract streams, in the order of `extract_stream_ids`.
+    pub extract_setup_hooks: Vec<Box<dyn StreamSetupHook>>,
 }
 
 impl DriverMetadata {
@@ -22,33 +30,90 @@ impl DriverMetadata {
             id,
             ingest_stream_ids: Vec::new(),
             extract_stream_ids: Vec::new(),
-            setup_hooks: Vec::new(),
+            ingest_setup_hooks: Vec::new(),
+            ingest_close_hooks: Vec::new(),
+            extract_setup_hooks: Vec::new(),
         }
     }
 
-    pub fn add_ingest_stream<F: StreamSetupHook>(&mut self, stream_id: StreamId, setup_hook: F) {
+    pub fn add_ingest_stream<F: StreamSetupHook, G: StreamCloseHook>(
+        &mut self,
+        stream_id: StreamId,
+        setup_hook: F,
+        close_hook: G,
+    ) {
         self.ingest_stream_ids.push(stream_id);
-        self.setup_hooks.push(Box::new(setup_hook));
+        self.ingest_setup_hooks.push(Box::new(setup_hook));
+        self.ingest_close_hooks.push(Box::new(close_hook));
     }
 
     pub fn add_extract_stream<F: StreamSetupHook>(&mut self, stream_id: StreamId, setup_hook: F) {
         self.extract_stream_ids.push(stream_id);
-        self.setup_hooks.push(Box::new(setup_hook));
+        self.extract_setup_hooks.push(Box::new(setup_hook));
+    }
+
+    /// Removes an ingest stream. Returns `false` if the driver has no such ingest stream.
+    pub fn remove_ingest_stream(&mut self, stream_id: StreamId) -> bool {
+        match self
+            .ingest_stream_ids
+            .iter()
+            .position(|&id| id == stream_id)
+        {
+            Some(index) => {
+                self.ingest_stream_ids.remove(index);
+                self.ingest_setup_hooks.remove(index);
+                self.ingest_close_hooks.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes an extract stream. Returns `false` if the driver has no such extract stream.
+    pub fn remove_extract_stream(&mut self, stream_id: StreamId) -> bool {
+        match self
+            .extract_stream_ids
+            .iter()
+            .position(|&id| id == stream_id)
+        {
+            Some(index) => {
+                self.extract_stream_ids.remove(index);
+                self.extract_setup_hooks.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the hooks which set up the ingest and extract streams of the driver.
+    pub fn setup_hooks(&self) -> impl Iterator<Item = &Box<dyn StreamSetupHook>> {
+        self.ingest_setup_hooks
+            .iter()
+            .chain(self.extract_setup_hooks.iter())
     }
 }
 
 impl Clone for DriverMetadata {
     fn clone(&self) -> Self {
-        let mut setup_hooks = Vec::new();
-        for i in 0..self.setup_hooks.len() {
-            setup_hooks.push(self.setup_hooks[i].box_clone())
-        }
-
         Self {
             id: self.id,
             ingest_stream_ids: self.ingest_stream_ids.clone(),
             extract_stream_ids: self.extract_stream_ids.clone(),
-            setup_hooks,
+            ingest_setup_hooks: self
+                .ingest_setup_hooks
+                .iter()
+                .map(|hook| (**hook).box_clone())
+                .collect(),
+            ingest_close_hooks: self
+                .ingest_close_hooks
+                .iter()
+                .map(|hook| (**hook).box_clone())
+                .collect(),
+            extract_setup_hooks: self
+                .extract_setup_hooks
+                .iter()
+                .map(|hook| (**hook).box_clone())
+                .collect(),
         }
     }
 }
@@ -59,9 +124,10 @@ pub struct OperatorMetadata {
     /// The name of the operator.
     pub name: Option<String>,
     /// The id of the node on which the operator executes.
-    /// TODO: change this to a scheduling restriction which is an
-    /// enum that may point to a node id.
     pub node_id: NodeId,
+    /// Whether the operator must execute on `node_id`. Otherwise, the operator may be placed on
+    /// another node by the scheduler.
+    pub pinned: bool,
     /// The ids of the read streams the operator uses.
     pub read_stream_ids: Vec<StreamId>,
     /// The ids of the write streams the operators uses.
@@ -75,6 +141,7 @@ impl OperatorMetadata {
         id: OperatorId,
         name: Option<String>,
         node_id: NodeId,
+        pinned: bool,
         read_stream_ids: Vec<StreamId>,
         write_stream_ids: Vec<StreamId>,
         runner: F,
@@ -83,6 +150,7 @@ impl OperatorMetadata {
             id,
             name,
             node_id,
+            pinned,
             read_stream_ids,
             write_stream_ids,
             runner: Box::new(runner),
@@ -96,6 +164,7 @@ impl Clone for OperatorMetadata {
             id: self.id,
             name: self.name.clone(),
             node_id: self.node_id,
+            pinned: self.pinned,
             read_stream_ids: self.read_stream_ids.clone(),
             write_stream_ids: self.write_stream_ids.clone(),
             runner: self.runner.box_clone(),
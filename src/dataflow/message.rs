@@ -1,5 +1,12 @@
-use std::{cmp::Ordering, fmt::Debug};
+use std::{
+    cmp::Ordering,
+    convert::TryFrom,
+    fmt::{self, Debug},
+    hash::Hash,
+    ops::{Deref, DerefMut},
+};
 
+use abomonation::Abomonation;
 use abomonation_derive::Abomonation;
 use serde::{Deserialize, Serialize};
 
@@ -87,34 +94,176 @@ impl<D: Data + PartialEq> PartialEq for TimestampedData<D> {
 // Alias to [`IntTimestamp`] in case more timestamp variants are added.
 pub type Timestamp = IntTimestamp;
 
+/// Trait for timestamps which are partially ordered, and form a lattice with a bottom and a top
+/// element.
+///
+/// The partial order determines which events of an operator must precede each other, and how the
+/// watermarks of several streams combine. The `Ord` implementation must be a linear extension of
+/// the partial order (i.e. `a.less_equal(&b)` implies `a <= b`), and is used to store timestamps in
+/// ordered collections.
+pub trait TimestampT: Clone + Debug + Eq + Hash + Ord {
+    /// Returns the timestamp which precedes all other timestamps.
+    fn bottom() -> Self;
+
+    /// Returns the timestamp which follows all other timestamps, and is used to close streams.
+    fn top() -> Self;
+
+    fn is_top(&self) -> bool;
+
+    /// Returns `true` if `self` precedes or equals `other` in the partial order.
+    fn less_equal(&self, other: &Self) -> bool;
+
+    /// Returns `true` if `self` strictly precedes `other` in the partial order.
+    fn less_than(&self, other: &Self) -> bool {
+        self != other && self.less_equal(other)
+    }
+
+    /// Returns the least timestamp which follows both `self` and `other`.
+    fn join(&self, other: &Self) -> Self;
+
+    /// Returns the greatest timestamp which precedes both `self` and `other`.
+    fn meet(&self, other: &Self) -> Self;
+}
+
+/// The maximum number of dimensions of an [`IntTimestamp`].
+pub const MAX_DIMENSIONS: usize = 4;
+
+/// The values of the dimensions of an [`IntTimestamp`], which are stored inline.
+///
+/// Dereferences to a slice with one value per dimension, and is ordered lexicographically.
+/// Serialized as the values of the dimensions, which are validated when deserialized.
+#[derive(Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(try_from = "Vec<u64>", into = "Vec<u64>")]
+struct Coordinates {
+    /// The number of dimensions.
+    len: u8,
+    /// The values of the dimensions, followed by zeros.
+    values: [u64; MAX_DIMENSIONS],
+}
+
+impl Coordinates {
+    /// Creates new coordinates from the values of the dimensions.
+    ///
+    /// Fails if there are more than [`MAX_DIMENSIONS`] values.
+    fn try_new(time: &[u64]) -> Result<Self, String> {
+        if time.len() > MAX_DIMENSIONS {
+            return Err(format!(
+                "Timestamps have at most {} dimensions, got {:?}",
+                MAX_DIMENSIONS, time
+            ));
+        }
+        let mut values = [0; MAX_DIMENSIONS];
+        values[..time.len()].copy_from_slice(time);
+        Ok(Self {
+            len: time.len() as u8,
+            values,
+        })
+    }
+}
+
+impl TryFrom<Vec<u64>> for Coordinates {
+    type Error = String;
+
+    fn try_from(time: Vec<u64>) -> Result<Self, String> {
+        Self::try_new(&time)
+    }
+}
+
+impl From<Coordinates> for Vec<u64> {
+    fn from(coordinates: Coordinates) -> Self {
+        coordinates.to_vec()
+    }
+}
+
+// The coordinates are stored inline and own no memory, so nothing needs to be encoded. Decoding
+// only checks the number of dimensions, which would otherwise panic upon dereferencing.
+impl Abomonation for Coordinates {
+    unsafe fn exhume<'a, 'b>(&'a mut self, bytes: &'b mut [u8]) -> Option<&'b mut [u8]> {
+        if self.len as usize > MAX_DIMENSIONS {
+            return None;
+        }
+        Some(bytes)
+    }
+}
+
+impl Deref for Coordinates {
+    type Target = [u64];
+
+    fn deref(&self) -> &[u64] {
+        &self.values[..self.len as usize]
+    }
+}
+
+impl DerefMut for Coordinates {
+    fn deref_mut(&mut self) -> &mut [u64] {
+        &mut self.values[..self.len as usize]
+    }
+}
+
+impl Debug for Coordinates {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+impl Ord for Coordinates {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl PartialOrd for Coordinates {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 /// Information about when an operator released a message.
+///
+/// Timestamps are partially ordered by the product order: a timestamp precedes another timestamp
+/// with the same number of dimensions if it precedes it in every dimension, and timestamps with
+/// different numbers of dimensions are incomparable. The bottom timestamp precedes and the top
+/// timestamp follows all other timestamps. The `Ord` implementation compares the dimensions
+/// lexicographically, which extends the product order to a total order, and is used to store
+/// timestamps in ordered collections.
 #[derive(Debug, Clone, Serialize, Deserialize, Abomonation, PartialEq, Eq, Hash)]
 pub struct IntTimestamp {
-    // TODO: Storing the dimensions in a vector is inefficient. Fix.
     /// Stores the timestamp values for each dimension.
-    pub time: Vec<u64>,
+    time: Coordinates,
     /// Whether this is a top timestamp used to close streams.
     is_top: bool,
 }
 
 impl IntTimestamp {
+    /// Creates a new timestamp from the values of its dimensions.
+    ///
+    /// Panics if there are more than [`MAX_DIMENSIONS`] values.
     pub fn new(time: Vec<u64>) -> Self {
-        Self {
-            time,
+        Self::try_new(time).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Creates a new timestamp from the values of its dimensions.
+    ///
+    /// Fails if there are more than [`MAX_DIMENSIONS`] values.
+    pub fn try_new(time: Vec<u64>) -> Result<Self, String> {
+        Ok(Self {
+            time: Coordinates::try_new(&time)?,
             is_top: false,
-        }
+        })
     }
 
+    /// Returns the timestamp which follows all other timestamps, and is used to close streams.
     pub fn top() -> Self {
         Self {
-            time: Vec::new(),
+            time: Coordinates::default(),
             is_top: true,
         }
     }
 
+    /// Returns the timestamp which precedes all other timestamps.
     pub fn bottom() -> Self {
         Self {
-            time: Vec::new(),
+            time: Coordinates::default(),
             is_top: false,
         }
     }
@@ -122,6 +271,71 @@ impl IntTimestamp {
     pub fn is_top(&self) -> bool {
         self.is_top
     }
+
+    fn is_bottom(&self) -> bool {
+        !self.is_top && self.time.is_empty()
+    }
+
+    /// Returns the values of the dimensions of the timestamp.
+    pub fn time(&self) -> &[u64] {
+        &self.time
+    }
+
+    /// Returns the values of the dimensions of the timestamp, which can be modified.
+    pub fn time_mut(&mut self) -> &mut [u64] {
+        &mut self.time
+    }
+}
+
+impl TimestampT for IntTimestamp {
+    fn bottom() -> Self {
+        IntTimestamp::bottom()
+    }
+
+    fn top() -> Self {
+        IntTimestamp::top()
+    }
+
+    fn is_top(&self) -> bool {
+        self.is_top
+    }
+
+    fn less_equal(&self, other: &Self) -> bool {
+        if self.is_bottom() || other.is_top {
+            true
+        } else if self.is_top || other.is_bottom() {
+            self == other
+        } else {
+            self.time.len() == other.time.len()
+                && self.time.iter().zip(other.time.iter()).all(|(x, y)| x <= y)
+        }
+    }
+
+    fn join(&self, other: &Self) -> Self {
+        if self.less_equal(other) {
+            other.clone()
+        } else if other.less_equal(self) {
+            self.clone()
+        } else if self.time.len() == other.time.len() {
+            let time = self.time.iter().zip(other.time.iter());
+            Self::new(time.map(|(x, y)| *x.max(y)).collect())
+        } else {
+            Self::top()
+        }
+    }
+
+    fn meet(&self, other: &Self) -> Self {
+        if self.less_equal(other) {
+            self.clone()
+        } else if other.less_equal(self) {
+            other.clone()
+        } else if self.time.len() == other.time.len() {
+            let time = self.time.iter().zip(other.time.iter());
+            Self::new(time.map(|(x, y)| *x.min(y)).collect())
+        } else {
+            Self::bottom()
+        }
+    }
 }
 
 impl Ord for IntTimestamp {
@@ -140,3 +354,86 @@ impl PartialOrd for IntTimestamp {
         Some(self.cmp(other))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_product_order() {
+        let t = |time: Vec<u64>| Timestamp::new(time);
+        assert!(t(vec![1, 2]).less_equal(&t(vec![1, 2])));
+        assert!(t(vec![1, 2]).less_than(&t(vec![1, 3])));
+        assert!(t(vec![1, 2]).less_than(&t(vec![2, 3])));
+        // Incomparable timestamps are still ordered lexicographically.
+        assert!(!t(vec![1, 3]).less_equal(&t(vec![2, 2])));
+        assert!(!t(vec![2, 2]).less_equal(&t(vec![1, 3])));
+        assert!(t(vec![1, 3]) < t(vec![2, 2]));
+        // Timestamps with different numbers of dimensions are incomparable.
+        assert!(!t(vec![1]).less_equal(&t(vec![1, 2])));
+        assert!(!t(vec![1, 2]).less_equal(&t(vec![1])));
+        // Bottom and top precede and follow all timestamps.
+        for time in &[
+            Timestamp::bottom(),
+            t(vec![0]),
+            t(vec![3, 1]),
+            Timestamp::top(),
+        ] {
+            assert!(Timestamp::bottom().less_equal(time));
+            assert!(time.less_equal(&Timestamp::top()));
+        }
+        assert!(!Timestamp::top().less_equal(&t(vec![3, 1])));
+        assert!(!t(vec![3, 1]).less_equal(&Timestamp::bottom()));
+    }
+
+    #[test]
+    fn test_join_meet() {
+        let t = |time: Vec<u64>| Timestamp::new(time);
+        assert_eq!(t(vec![1, 3]).join(&t(vec![2, 2])), t(vec![2, 3]));
+        assert_eq!(t(vec![1, 3]).meet(&t(vec![2, 2])), t(vec![1, 2]));
+        assert_eq!(t(vec![1, 2]).join(&t(vec![2, 3])), t(vec![2, 3]));
+        assert_eq!(t(vec![1, 2]).meet(&t(vec![2, 3])), t(vec![1, 2]));
+        assert_eq!(t(vec![1]).join(&t(vec![1, 2])), Timestamp::top());
+        assert_eq!(t(vec![1]).meet(&t(vec![1, 2])), Timestamp::bottom());
+        assert_eq!(t(vec![1]).meet(&Timestamp::top()), t(vec![1]));
+        assert_eq!(t(vec![1]).join(&Timestamp::bottom()), t(vec![1]));
+    }
+
+    #[test]
+    fn test_coordinates() {
+        let mut t = Timestamp::new(vec![1, 2, 3]);
+        t.time_mut()[2] += 1;
+        assert_eq!(t.time(), &[1, 2, 4]);
+        assert_eq!(
+            format!("{:?}", t),
+            "IntTimestamp { time: [1, 2, 4], is_top: false }"
+        );
+        assert_eq!(t, Timestamp::new(vec![1, 2, 4]));
+        assert!(Timestamp::new(vec![1, 2]) < Timestamp::new(vec![1, 2, 0]));
+    }
+
+    #[test]
+    fn test_too_many_dimensions() {
+        assert!(Timestamp::try_new(vec![0; MAX_DIMENSIONS]).is_ok());
+        assert!(Timestamp::try_new(vec![0; MAX_DIMENSIONS + 1]).is_err());
+    }
+
+    #[test]
+    fn test_decode_too_many_dimensions() {
+        let bytes = bincode::serialize(&(vec![0u64; MAX_DIMENSIONS + 1], false)).unwrap();
+        assert!(bincode::deserialize::<Timestamp>(&bytes).is_err());
+        let bytes = bincode::serialize(&(vec![1u64, 2], false)).unwrap();
+        assert_eq!(
+            bincode::deserialize::<Timestamp>(&bytes).unwrap(),
+            Timestamp::new(vec![1, 2])
+        );
+
+        let mut t = Timestamp::new(vec![1]);
+        t.time.len = MAX_DIMENSIONS as u8 + 1;
+        let mut bytes = Vec::new();
+        unsafe {
+            abomonation::encode(&t, &mut bytes).unwrap();
+            assert!(abomonation::decode::<Timestamp>(&mut bytes).is_none());
+        }
+    }
+}
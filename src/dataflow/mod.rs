@@ -2,22 +2,28 @@
 
 // Public submodules
 pub mod callback_builder;
+pub mod checkpoint;
 #[doc(hidden)]
 pub mod graph;
 pub mod message;
 pub mod operator;
 pub mod operators;
+pub mod record;
 pub mod state;
 pub mod stream;
 
+// Crate-wide submodules
+pub(crate) mod timer;
+
 // Crate-wide exports
 pub(crate) use stream::EventMakerT;
 
 // Public exports
-pub use message::{Data, Message, Timestamp, TimestampedData};
-pub use operator::{Operator, OperatorConfig};
+pub use message::{Data, Message, Timestamp, TimestampT, TimestampedData};
+pub use operator::{Operator, OperatorConfig, PanicPolicy};
 pub use state::State;
 pub use stream::{LoopStream, ReadStream, StatefulReadStream, WriteStream};
+pub use timer::add_timer_callback;
 
 /// Adds a watermark callback over a vector a [`ReadStream`]s and
 /// [`WriteStream`]s.
@@ -1,10 +1,22 @@
-use crate::{node::NodeId, OperatorId};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dataflow::{
+        stream::{LateDataPolicy, OverflowPolicy, StreamCapacity},
+        timer::TimerRegistry,
+    },
+    node::NodeId,
+    OperatorId,
+};
 
 /// Trait that must be implemented by any operator.
 pub trait Operator {
     /// Implement this method if you want to take control of the execution loop of an
     /// operator (e.g., pull messages from streams).
-    /// Note: No callbacks are invoked before the completion of this method.
+    /// Note: No callbacks are invoked before the completion of this method, unless
+    /// [`OperatorConfig::run_concurrently`] is set.
     fn run(&mut self) {}
 
     /// Implement this method if you need to do clean-up before the operator completes.
@@ -12,6 +24,24 @@ pub trait Operator {
     fn destroy(&mut self) {}
 }
 
+/// Determines how an [`Operator`] handles a panic in one of its callbacks or in
+/// [`Operator::run`]. In all cases, the panic is logged and reported to the node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PanicPolicy {
+    /// Skips the callback which panicked, and keeps invoking the other callbacks. A panic in
+    /// [`Operator::run`] ends it, after which callbacks are invoked as usual.
+    Skip,
+    /// Replaces the [`Operator`] with a new instance created from the same configuration, which
+    /// starts with fresh state and runs [`Operator::run`] again. The events the previous instance
+    /// did not process are discarded, and [`Operator::destroy`] is never called on the previous
+    /// instance, so it should not hold resources which must be released.
+    /// If [`OperatorConfig::run_concurrently`] is set, the restart waits for [`Operator::run`] of
+    /// the previous instance to return.
+    Restart,
+    /// Stops the [`Operator`] and fails the node, which stops the dataflow.
+    FailNode,
+}
+
 #[derive(Clone)]
 pub struct OperatorConfig<T: Clone> {
     /// A human-readable name for the [`Operator`] used in logging.
@@ -34,11 +64,47 @@ pub struct OperatorConfig<T: Clone> {
     /// running. Defaults to `true`.
     pub flow_watermarks: bool,
     /// The ID of the node on which the operator should run. Defaults to `0`.
+    /// Unless the operator is pinned, the [`Scheduler`](crate::scheduler::Scheduler) set in the
+    /// [`Configuration`](crate::Configuration) may place the operator on another node.
     pub node_id: NodeId,
+    /// Whether the operator must run on `node_id`. Set by [`OperatorConfig::node`].
+    /// Defaults to `false`.
+    pub pinned: bool,
     /// Number of parallel tasks which process callbacks.
     /// A higher number may result in more parallelism; however this may be limited
     /// by dependencies on [`State`](crate::dataflow::State) and timestamps.
     pub num_event_runners: usize,
+    /// Relative deadline for processing each [`Timestamp`](crate::dataflow::Timestamp).
+    /// The deadline starts when the first event for a timestamp reaches the operator, and
+    /// is met once all events of the operator for that timestamp and the timestamps preceding
+    /// it, including the watermark callbacks, complete. Upon a miss, deadline
+    /// miss handlers registered on the operator's read streams are invoked. Defaults to `None`.
+    pub deadline: Option<Duration>,
+    /// Capacity of the [`WriteStream`](crate::dataflow::WriteStream)s of the [`Operator`]
+    /// which do not set their own capacity using
+    /// [`WriteStream::with_capacity`](crate::dataflow::WriteStream::with_capacity).
+    /// Defaults to `None`, in which case the streams are unbounded.
+    pub write_stream_capacity: Option<StreamCapacity>,
+    /// Whether [`Operator::run`] executes on an additional event runner while callbacks are
+    /// invoked. [`Operator::run`] is scheduled as an event of the lattice of the operator which
+    /// precedes the callbacks of the top watermark, so watermarks flow while it executes. The
+    /// operator is destroyed once [`Operator::run`] completes. Defaults to `false`.
+    pub run_concurrently: bool,
+    /// Policy applied to the messages which the [`Operator`] receives after a watermark with an
+    /// equal or greater [`Timestamp`](crate::dataflow::Timestamp), e.g. after a
+    /// [processing-time watermark](crate::dataflow::stream::ProcessingTimeWatermarks).
+    /// Defaults to `None`, in which case the callbacks of late messages are invoked as usual.
+    pub late_data_policy: Option<LateDataPolicy>,
+    /// How long after receiving a watermark the [`Operator`] still processes the messages it
+    /// covers as usual, before applying the `late_data_policy`. Defaults to zero.
+    pub allowed_lateness: Duration,
+    /// How the [`Operator`] handles a panic in one of its callbacks or in [`Operator::run`].
+    /// Defaults to [`PanicPolicy::FailNode`]; [`PanicPolicy::Skip`] and [`PanicPolicy::Restart`]
+    /// must be set explicitly.
+    pub panic_policy: PanicPolicy,
+    /// The timers registered by the [`Operator`] using
+    /// [`add_timer_callback`](crate::dataflow::add_timer_callback).
+    pub(crate) timers: TimerRegistry,
 }
 
 impl<T: Clone> OperatorConfig<T> {
@@ -49,7 +115,15 @@ impl<T: Clone> OperatorConfig<T> {
             arg: None,
             flow_watermarks: true,
             node_id: 0,
+            pinned: false,
             num_event_runners: 1,
+            deadline: None,
+            write_stream_capacity: None,
+            run_concurrently: false,
+            late_data_policy: None,
+            allowed_lateness: Duration::from_secs(0),
+            panic_policy: PanicPolicy::FailNode,
+            timers: TimerRegistry::default(),
         }
     }
 
@@ -71,9 +145,10 @@ impl<T: Clone> OperatorConfig<T> {
         self
     }
 
-    /// Set the node on which the [`Operator`] runs.
+    /// Pins the [`Operator`] to the node on which it runs.
     pub fn node(mut self, node_id: NodeId) -> Self {
         self.node_id = node_id;
+        self.pinned = true;
         self
     }
 
@@ -88,6 +163,51 @@ impl<T: Clone> OperatorConfig<T> {
         self
     }
 
+    /// Sets a relative deadline within which the [`Operator`] must process each timestamp.
+    /// Handlers registered with `add_deadline_miss_handler` on the read streams are invoked
+    /// if the deadline is missed.
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Bounds the number of data messages buffered for each receiver of the [`Operator`]'s
+    /// write streams, and sets the policy applied when a receiver is full.
+    pub fn write_stream_capacity(
+        mut self,
+        capacity: usize,
+        overflow_policy: OverflowPolicy,
+    ) -> Self {
+        self.write_stream_capacity = Some(StreamCapacity::new(capacity, overflow_policy));
+        self
+    }
+
+    /// Sets whether [`Operator::run`] executes on an additional event runner while callbacks are
+    /// invoked, instead of before callbacks are invoked.
+    pub fn run_concurrently(mut self, run_concurrently: bool) -> Self {
+        self.run_concurrently = run_concurrently;
+        self
+    }
+
+    /// Sets the policy applied to messages received after a watermark which covers them, once
+    /// `allowed_lateness` elapsed since the watermark was received.
+    pub fn late_data_policy(
+        mut self,
+        late_data_policy: LateDataPolicy,
+        allowed_lateness: Duration,
+    ) -> Self {
+        self.late_data_policy = Some(late_data_policy);
+        self.allowed_lateness = allowed_lateness;
+        self
+    }
+
+    /// Sets how the [`Operator`] handles a panic in one of its callbacks or in
+    /// [`Operator::run`].
+    pub fn panic_policy(mut self, panic_policy: PanicPolicy) -> Self {
+        self.panic_policy = panic_policy;
+        self
+    }
+
     /// Removes the argument to lose type information. Used in
     /// [`OperatorExecutor`](crate::node::operator_executor::OperatorExecutor).
     pub(crate) fn drop_arg(self) -> OperatorConfig<()> {
@@ -97,7 +217,25 @@ impl<T: Clone> OperatorConfig<T> {
             arg: None,
             flow_watermarks: self.flow_watermarks,
             node_id: self.node_id,
+            pinned: self.pinned,
             num_event_runners: self.num_event_runners,
+            deadline: self.deadline,
+            write_stream_capacity: self.write_stream_capacity,
+            run_concurrently: self.run_concurrently,
+            late_data_policy: self.late_data_policy,
+            allowed_lateness: self.allowed_lateness,
+            panic_policy: self.panic_policy,
+            timers: self.timers,
         }
     }
+
+    /// Gives the config a new registry of timers, so that the operator created from the config
+    /// does not share its timers with other operators created from copies of the config.
+    ///
+    /// Note: this is an internal method called by the `connect_x_write` macros before the
+    /// operator is created.
+    #[doc(hidden)]
+    pub fn reset_timers(&mut self) {
+        self.timers = TimerRegistry::default();
+    }
 }
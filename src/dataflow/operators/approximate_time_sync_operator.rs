@@ -0,0 +1,468 @@
+use std::{collections::VecDeque, marker::PhantomData};
+
+use serde::Deserialize;
+
+use crate::dataflow::{
+    message::Message, operators::join_operator::StreamState, stream::WriteStreamT, Data, Operator,
+    OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+
+/// Describes how an [`ApproximateTimeSyncOperator`] matches the messages of its incoming
+/// streams, similar to the approximate time policy of ROS `message_filters`.
+///
+/// The time of a message is the first coordinate of its timestamp.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ApproximateTime {
+    /// The maximum difference between the times of the messages which are matched.
+    pub slop: u64,
+    /// The maximum number of unmatched messages buffered for each stream. The oldest messages are
+    /// dropped once a stream has more unmatched messages.
+    pub queue_size: usize,
+}
+
+/// The next action to take on the queued messages.
+#[derive(Debug, PartialEq, Eq)]
+enum Step {
+    /// More messages must be received before the next set can be found.
+    Wait,
+    /// The oldest message of the stream can not be part of any set, and is dropped.
+    Drop(usize),
+    /// The messages at the indices of the queues form a set.
+    Match(Vec<usize>),
+}
+
+impl ApproximateTime {
+    /// Returns a new policy which matches messages whose times differ by at most `slop`, and
+    /// buffers up to `queue_size` unmatched messages for each stream.
+    pub fn new(slop: u64, queue_size: usize) -> Self {
+        Self { slop, queue_size }
+    }
+
+    /// Returns the next step given the times of the queued messages of each stream, and the time
+    /// up to which all messages were received.
+    ///
+    /// The set is built around the pivot, which is the oldest message of the stream whose oldest
+    /// message is the most recent. Each stream contributes the message closest to the pivot, or
+    /// the most recent message before the pivot if the closest messages are more than `slop`
+    /// apart.
+    fn next_step(&self, queues: &[Vec<u64>], received_time: u64) -> Step {
+        if queues.iter().any(|queue| queue.is_empty()) {
+            return Step::Wait;
+        }
+        let pivot = queues.iter().map(|queue| queue[0]).max().unwrap();
+        // Messages older than the slop before the pivot match no message of the pivot's stream.
+        if let Some(stream) = queues
+            .iter()
+            .position(|queue| queue[0] < pivot.saturating_sub(self.slop))
+        {
+            return Step::Drop(stream);
+        }
+        // Wait until all messages which may be closer to the pivot were received.
+        if received_time < pivot.saturating_add(self.slop) {
+            return Step::Wait;
+        }
+
+        let closest: Vec<usize> = queues
+            .iter()
+            .map(|queue| {
+                (0..queue.len())
+                    .take_while(|&i| queue[i] <= pivot.saturating_add(self.slop))
+                    .min_by_key(|&i| queue[i].max(pivot) - queue[i].min(pivot))
+                    .unwrap()
+            })
+            .collect();
+        let times = || closest.iter().zip(queues).map(|(&i, queue)| queue[i]);
+        if times().max().unwrap() - times().min().unwrap() <= self.slop {
+            return Step::Match(closest);
+        }
+        // All oldest messages are within the slop before the pivot, so the most recent messages
+        // before the pivot match.
+        Step::Match(
+            queues
+                .iter()
+                .map(|queue| queue.iter().rposition(|&time| time <= pivot).unwrap())
+                .collect(),
+        )
+    }
+}
+
+/// Returns the time of a message.
+fn time(t: &Timestamp) -> u64 {
+    t.time().first().cloned().unwrap_or(0)
+}
+
+/// Returns the times of the queued messages.
+fn times<D>(queue: &VecDeque<(Timestamp, D)>) -> Vec<u64> {
+    queue.iter().map(|(t, _)| time(t)).collect()
+}
+
+/// Appends the received messages to the queue, dropping the oldest messages if the queue is
+/// full.
+fn enqueue<D: Data>(
+    queue: &mut VecDeque<(Timestamp, D)>,
+    state: &StreamState<D>,
+    t: &Timestamp,
+    policy: &ApproximateTime,
+) {
+    for (timestamp, msgs) in state.take_state(t) {
+        queue.extend(msgs.into_iter().map(|msg| (timestamp.clone(), msg)));
+    }
+    while queue.len() > policy.queue_size {
+        queue.pop_front();
+    }
+}
+
+/// Removes the message at the index from the queue, along with all older messages.
+fn dequeue<D>(queue: &mut VecDeque<(Timestamp, D)>, index: usize) -> (Timestamp, D) {
+    queue.drain(..index);
+    queue.pop_front().unwrap()
+}
+
+/// An operator that synchronizes several incoming streams of different types whose messages are
+/// not sent with the same timestamps, such as the streams of sensors which tick at different
+/// rates.
+///
+/// The operator buffers the messages of each stream, and matches one message of each stream
+/// according to the [`ApproximateTime`] policy. Each set is sent as a tuple of the messages and
+/// their timestamps, in the order of the streams. Sets are found once the watermarks of all
+/// streams pass the time of their messages by the slop, and are sent with the timestamp of the
+/// watermark. Messages older than a set are dropped.
+///
+/// The type parameter is the tuple of the types of the incoming streams, of which there are
+/// either 2 or 3.
+///
+/// # Example
+/// The below example shows how to use an ApproximateTimeSyncOperator to match the messages of a
+/// camera stream and of a lidar stream which are at most 10 units of time apart.
+///
+/// ```
+/// # use erdos::dataflow::{
+/// #     stream::IngestStream,
+/// #     operators::{ApproximateTime, ApproximateTimeSyncOperator},
+/// #     OperatorConfig
+/// # };
+/// # use erdos::*;
+/// #
+/// # let mut camera_stream: IngestStream<Vec<u8>> = IngestStream::new(0);
+/// # let mut lidar_stream: IngestStream<Vec<f32>> = IngestStream::new(0);
+/// #
+/// // Add the policy as an argument to the operator via the OperatorConfig.
+/// let sync_config = OperatorConfig::new()
+///     .name("ApproximateTimeSyncOperator")
+///     .arg(ApproximateTime::new(10, 100));
+/// let synced_stream = connect_1_write!(
+///     ApproximateTimeSyncOperator<(Vec<u8>, Vec<f32>)>,
+///     sync_config,
+///     camera_stream,
+///     lidar_stream
+/// );
+/// ```
+pub struct ApproximateTimeSyncOperator<T> {
+    phantom_data: PhantomData<T>,
+}
+
+/// The synchronized messages of 2 streams.
+type Synced2<D1, D2> = ((Timestamp, D1), (Timestamp, D2));
+
+/// The synchronized messages of 3 streams.
+type Synced3<D1, D2, D3> = ((Timestamp, D1), (Timestamp, D2), (Timestamp, D3));
+
+/// The queues of unmatched messages of 2 streams.
+type Queues2<D1, D2> = (VecDeque<(Timestamp, D1)>, VecDeque<(Timestamp, D2)>);
+
+/// The queues of unmatched messages of 3 streams.
+type Queues3<D1, D2, D3> = (
+    VecDeque<(Timestamp, D1)>,
+    VecDeque<(Timestamp, D2)>,
+    VecDeque<(Timestamp, D3)>,
+);
+
+/// Returns the policy provided by the config.
+fn get_policy(config: &OperatorConfig<ApproximateTime>) -> ApproximateTime {
+    let name = config
+        .name
+        .clone()
+        .unwrap_or_else(|| format!("ApproximateTimeSyncOperator {}", config.id));
+    let policy = config
+        .arg
+        .unwrap_or_else(|| panic!("{}: no policy provided", name));
+    if policy.queue_size == 0 {
+        panic!("{}: the queue size must be positive", name);
+    }
+    policy
+}
+
+impl<D1, D2> ApproximateTimeSyncOperator<(D1, D2)>
+where
+    for<'a> D1: Data + Deserialize<'a>,
+    for<'a> D2: Data + Deserialize<'a>,
+{
+    /// Returns a new instance of the ApproximateTimeSyncOperator over 2 incoming streams.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the [`ApproximateTime`] policy.
+    /// * `input_stream_1` - Represents the first incoming stream of messages of type D1.
+    /// * `input_stream_2` - Represents the second incoming stream of messages of type D2.
+    /// * `output_stream` - Represents an outgoing stream of synchronized messages.
+    pub fn new(
+        config: OperatorConfig<ApproximateTime>,
+        input_stream_1: ReadStream<D1>,
+        input_stream_2: ReadStream<D2>,
+        output_stream: WriteStream<Synced2<D1, D2>>,
+    ) -> Self {
+        let policy = get_policy(&config);
+
+        let stateful_stream_1 = input_stream_1.add_state(StreamState::<D1>::new());
+        stateful_stream_1.add_callback(|t: &Timestamp, msg: &D1, state: &mut StreamState<D1>| {
+            state.add_msg(t, msg.clone());
+        });
+        let stateful_stream_2 = input_stream_2.add_state(StreamState::<D2>::new());
+        stateful_stream_2.add_callback(|t: &Timestamp, msg: &D2, state: &mut StreamState<D2>| {
+            state.add_msg(t, msg.clone());
+        });
+
+        let queues: Queues2<D1, D2> = (VecDeque::new(), VecDeque::new());
+        stateful_stream_1
+            .add_read_stream(&stateful_stream_2)
+            .borrow_mut()
+            .add_write_stream(&output_stream)
+            .borrow_mut()
+            .add_state(queues)
+            .borrow_mut()
+            .add_watermark_callback(
+                move |t: &Timestamp,
+                      queues: &mut Queues2<D1, D2>,
+                      state_1: &StreamState<D1>,
+                      state_2: &StreamState<D2>,
+                      write_stream: &mut WriteStream<Synced2<D1, D2>>| {
+                    Self::on_watermark_callback(t, queues, state_1, state_2, write_stream, &policy)
+                },
+            );
+
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send its outgoing messages on.
+    ///
+    /// # Arguments
+    /// * `input_stream_1` - Represents the first incoming stream of messages of type D1.
+    /// * `input_stream_2` - Represents the second incoming stream of messages of type D2.
+    pub fn connect(
+        _input_stream_1: &ReadStream<D1>,
+        _input_stream_2: &ReadStream<D2>,
+    ) -> WriteStream<Synced2<D1, D2>> {
+        WriteStream::new()
+    }
+
+    /// The function to be called when a watermark is received on all the input streams.
+    /// This callback adds the messages received up to the watermark to the queues, and sends the
+    /// sets of messages which are found.
+    fn on_watermark_callback(
+        t: &Timestamp,
+        queues: &mut Queues2<D1, D2>,
+        state_1: &StreamState<D1>,
+        state_2: &StreamState<D2>,
+        write_stream: &mut WriteStream<Synced2<D1, D2>>,
+        policy: &ApproximateTime,
+    ) {
+        if t.is_top() {
+            return;
+        }
+        enqueue(&mut queues.0, state_1, t, policy);
+        enqueue(&mut queues.1, state_2, t, policy);
+        loop {
+            let times = [times(&queues.0), times(&queues.1)];
+            match policy.next_step(&times, time(t)) {
+                Step::Wait => break,
+                Step::Drop(0) => {
+                    queues.0.pop_front();
+                }
+                Step::Drop(_) => {
+                    queues.1.pop_front();
+                }
+                Step::Match(indices) => {
+                    let set = (
+                        dequeue(&mut queues.0, indices[0]),
+                        dequeue(&mut queues.1, indices[1]),
+                    );
+                    write_stream
+                        .send(Message::new_message(t.clone(), set))
+                        .expect("ApproximateTimeSyncOperator: error sending on write stream");
+                }
+            }
+        }
+    }
+}
+
+impl<D1, D2> Operator for ApproximateTimeSyncOperator<(D1, D2)>
+where
+    for<'a> D1: Data + Deserialize<'a>,
+    for<'a> D2: Data + Deserialize<'a>,
+{
+}
+
+impl<D1, D2, D3> ApproximateTimeSyncOperator<(D1, D2, D3)>
+where
+    for<'a> D1: Data + Deserialize<'a>,
+    for<'a> D2: Data + Deserialize<'a>,
+    for<'a> D3: Data + Deserialize<'a>,
+{
+    /// Returns a new instance of the ApproximateTimeSyncOperator over 3 incoming streams.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the [`ApproximateTime`] policy.
+    /// * `input_stream_1` - Represents the first incoming stream of messages of type D1.
+    /// * `input_stream_2` - Represents the second incoming stream of messages of type D2.
+    /// * `input_stream_3` - Represents the third incoming stream of messages of type D3.
+    /// * `output_stream` - Represents an outgoing stream of synchronized messages.
+    pub fn new(
+        config: OperatorConfig<ApproximateTime>,
+        input_stream_1: ReadStream<D1>,
+        input_stream_2: ReadStream<D2>,
+        input_stream_3: ReadStream<D3>,
+        output_stream: WriteStream<Synced3<D1, D2, D3>>,
+    ) -> Self {
+        let policy = get_policy(&config);
+
+        let stateful_stream_1 = input_stream_1.add_state(StreamState::<D1>::new());
+        stateful_stream_1.add_callback(|t: &Timestamp, msg: &D1, state: &mut StreamState<D1>| {
+            state.add_msg(t, msg.clone());
+        });
+        let stateful_stream_2 = input_stream_2.add_state(StreamState::<D2>::new());
+        stateful_stream_2.add_callback(|t: &Timestamp, msg: &D2, state: &mut StreamState<D2>| {
+            state.add_msg(t, msg.clone());
+        });
+        let stateful_stream_3 = input_stream_3.add_state(StreamState::<D3>::new());
+        stateful_stream_3.add_callback(|t: &Timestamp, msg: &D3, state: &mut StreamState<D3>| {
+            state.add_msg(t, msg.clone());
+        });
+
+        let queues: Queues3<D1, D2, D3> = (VecDeque::new(), VecDeque::new(), VecDeque::new());
+        stateful_stream_1
+            .add_read_stream(&stateful_stream_2)
+            .borrow_mut()
+            .add_read_stream(&stateful_stream_3)
+            .borrow_mut()
+            .add_write_stream(&output_stream)
+            .borrow_mut()
+            .add_state(queues)
+            .borrow_mut()
+            .add_watermark_callback(
+                move |t: &Timestamp,
+                      queues: &mut Queues3<D1, D2, D3>,
+                      state_1: &StreamState<D1>,
+                      state_2: &StreamState<D2>,
+                      state_3: &StreamState<D3>,
+                      write_stream: &mut WriteStream<Synced3<D1, D2, D3>>| {
+                    Self::on_watermark_callback(
+                        t,
+                        queues,
+                        state_1,
+                        state_2,
+                        state_3,
+                        write_stream,
+                        &policy,
+                    )
+                },
+            );
+
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send its outgoing messages on.
+    ///
+    /// # Arguments
+    /// * `input_stream_1` - Represents the first incoming stream of messages of type D1.
+    /// * `input_stream_2` - Represents the second incoming stream of messages of type D2.
+    /// * `input_stream_3` - Represents the third incoming stream of messages of type D3.
+    pub fn connect(
+        _input_stream_1: &ReadStream<D1>,
+        _input_stream_2: &ReadStream<D2>,
+        _input_stream_3: &ReadStream<D3>,
+    ) -> WriteStream<Synced3<D1, D2, D3>> {
+        WriteStream::new()
+    }
+
+    /// The function to be called when a watermark is received on all the input streams.
+    /// This callback adds the messages received up to the watermark to the queues, and sends the
+    /// sets of messages which are found.
+    fn on_watermark_callback(
+        t: &Timestamp,
+        queues: &mut Queues3<D1, D2, D3>,
+        state_1: &StreamState<D1>,
+        state_2: &StreamState<D2>,
+        state_3: &StreamState<D3>,
+        write_stream: &mut WriteStream<Synced3<D1, D2, D3>>,
+        policy: &ApproximateTime,
+    ) {
+        if t.is_top() {
+            return;
+        }
+        enqueue(&mut queues.0, state_1, t, policy);
+        enqueue(&mut queues.1, state_2, t, policy);
+        enqueue(&mut queues.2, state_3, t, policy);
+        loop {
+            let times = [times(&queues.0), times(&queues.1), times(&queues.2)];
+            match policy.next_step(&times, time(t)) {
+                Step::Wait => break,
+                Step::Drop(0) => {
+                    queues.0.pop_front();
+                }
+                Step::Drop(1) => {
+                    queues.1.pop_front();
+                }
+                Step::Drop(_) => {
+                    queues.2.pop_front();
+                }
+                Step::Match(indices) => {
+                    let set = (
+                        dequeue(&mut queues.0, indices[0]),
+                        dequeue(&mut queues.1, indices[1]),
+                        dequeue(&mut queues.2, indices[2]),
+                    );
+                    write_stream
+                        .send(Message::new_message(t.clone(), set))
+                        .expect("ApproximateTimeSyncOperator: error sending on write stream");
+                }
+            }
+        }
+    }
+}
+
+impl<D1, D2, D3> Operator for ApproximateTimeSyncOperator<(D1, D2, D3)>
+where
+    for<'a> D1: Data + Deserialize<'a>,
+    for<'a> D2: Data + Deserialize<'a>,
+    for<'a> D3: Data + Deserialize<'a>,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_step() {
+        let policy = ApproximateTime::new(2, 10);
+        // Waits for messages on all streams.
+        assert_eq!(policy.next_step(&[vec![0], vec![]], 10), Step::Wait);
+        // Drops the messages which are too old to match the pivot.
+        assert_eq!(policy.next_step(&[vec![0, 5], vec![5]], 10), Step::Drop(0));
+        // Waits until all messages within the slop after the pivot were received.
+        assert_eq!(policy.next_step(&[vec![4], vec![5]], 6), Step::Wait);
+        // Matches the messages closest to the pivot.
+        assert_eq!(
+            policy.next_step(&[vec![3, 5, 6], vec![5]], 7),
+            Step::Match(vec![1, 0])
+        );
+        // Falls back to the messages before the pivot if the closest messages are too far apart.
+        assert_eq!(
+            policy.next_step(&[vec![3], vec![5], vec![3, 6]], 10),
+            Step::Match(vec![0, 0, 0])
+        );
+    }
+}
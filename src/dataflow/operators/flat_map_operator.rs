@@ -0,0 +1,111 @@
+use crate::dataflow::message::Message;
+use crate::dataflow::{
+    stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+use serde::Deserialize;
+use std::marker::PhantomData;
+
+/// An operator that maps each message of an incoming stream of type D1 to any number of messages
+/// of type D2 using the provided function. The messages are sent with the timestamp of the
+/// incoming message.
+///
+/// # Example
+/// The below example shows how to use a FlatMapOperator to split an incoming stream of String
+/// messages into a stream of words.
+///
+/// ```
+/// # use erdos::dataflow::{stream::IngestStream, operators::FlatMapOperator, OperatorConfig};
+/// # use erdos::*;
+/// #
+/// # let mut string_stream = IngestStream::new(0);
+/// #
+/// // Add the mapping function as an argument to the operator via the OperatorConfig.
+/// let flat_map_config = OperatorConfig::new()
+///     .name("FlatMapOperator")
+///     .arg(|data: &String| -> Vec<String> {
+///         data.split_whitespace().map(String::from).collect()
+///     });
+/// let word_stream =
+///     connect_1_write!(FlatMapOperator<String, String>, flat_map_config, string_stream);
+/// ```
+pub struct FlatMapOperator<D1: Data, D2: Data> {
+    phantom_data: PhantomData<(D1, D2)>,
+}
+
+impl<'a, D1: Data, D2: Data + Deserialize<'a>> FlatMapOperator<D1, D2> {
+    /// Returns a new instance of the FlatMapOperator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the closure used to map items of
+    ///   type D1 to collections of items of type D2.
+    /// * `input_stream` - Represents the incoming stream of messages of type D1.
+    /// * `output_stream` - Represents an outgoing stream of messages of type D2.
+    pub fn new<F, I>(
+        config: OperatorConfig<F>,
+        input_stream: ReadStream<D1>,
+        output_stream: WriteStream<D2>,
+    ) -> Self
+    where
+        F: 'static + Clone + Fn(&D1) -> I,
+        I: IntoIterator<Item = D2>,
+    {
+        let stateful_stream = input_stream.add_state(output_stream);
+
+        // Clone the name so that we can move the passed function into the callback.
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("FlatMapOperator {}", config.id));
+        let callback = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no flat map function supplied", name));
+
+        stateful_stream.add_callback(
+            move |t: &Timestamp, msg: &D1, output_stream: &mut WriteStream<_>| {
+                Self::on_data_callback(t, msg, output_stream, &callback)
+            },
+        );
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send its outgoing messages on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type D1.
+    pub fn connect(_input_stream: &ReadStream<D1>) -> WriteStream<D2> {
+        WriteStream::new()
+    }
+
+    /// The callback function to be invoked upon receipt of a message on the input stream.
+    ///
+    /// # Arguments
+    /// * `t` - The timestamp of the message.
+    /// * `msg` - The incoming message on the input stream.
+    /// * `output_stream` - A handle to the output stream to write the output to.
+    /// * `flat_map_function` - A reference to the function to invoke for the message.
+    fn on_data_callback<F, I>(
+        t: &Timestamp,
+        msg: &D1,
+        output_stream: &mut WriteStream<D2>,
+        flat_map_function: &F,
+    ) where
+        F: 'static + Clone + Fn(&D1) -> I,
+        I: IntoIterator<Item = D2>,
+    {
+        for result in flat_map_function(msg) {
+            output_stream
+                .send(Message::new_message(t.clone(), result))
+                .unwrap_or_else(|e| {
+                    panic!(
+                        "FlatMap operator unable to send message on stream {}: {:?}",
+                        output_stream.get_id(),
+                        e
+                    )
+                });
+        }
+    }
+}
+
+impl<'a, D1: Data, D2: Data + Deserialize<'a>> Operator for FlatMapOperator<D1, D2> {}
@@ -0,0 +1,262 @@
+use std::{collections::BTreeMap, marker::PhantomData};
+
+use serde::Deserialize;
+
+use crate::dataflow::{
+    message::Message, state::TimeVersionedState, stream::WriteStreamT, Data, Operator,
+    OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+
+/// An operator that folds the messages of type D1 received for each timestamp into a value of
+/// type D2, and sends the value once the watermark for the timestamp is received.
+///
+/// The fold starts from a clone of the provided initial value for each timestamp, and adds the
+/// messages in the order in which they were processed. No value is sent for timestamps without
+/// messages.
+///
+/// # Example
+/// The below example shows how to use a FoldOperator to sum the u32 messages of each timestamp
+/// into a u64 message.
+///
+/// ```
+/// # use erdos::dataflow::{stream::IngestStream, operators::FoldOperator, OperatorConfig};
+/// # use erdos::*;
+/// #
+/// # let mut u32_stream = IngestStream::new(0);
+/// #
+/// // Add the initial value and the folding function as an argument to the operator via the
+/// // OperatorConfig.
+/// let fold_config = OperatorConfig::new()
+///     .name("FoldOperator")
+///     .arg((0, |sum: u64, data: &u32| -> u64 { sum + *data as u64 }));
+/// let sum_stream = connect_1_write!(FoldOperator<u32, u64>, fold_config, u32_stream);
+/// ```
+pub struct FoldOperator<D1: Data, D2: Data> {
+    phantom_data: PhantomData<(D1, D2)>,
+}
+
+impl<'a, D1: Data, D2: Data + Deserialize<'a>> FoldOperator<D1, D2> {
+    /// Returns a new instance of the FoldOperator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the initial value and the closure
+    ///   used to fold items of type D1 into a value of type D2.
+    /// * `input_stream` - Represents the incoming stream of messages of type D1.
+    /// * `output_stream` - Represents an outgoing stream of messages of type D2.
+    pub fn new<F: 'static + Clone + Fn(D2, &D1) -> D2>(
+        config: OperatorConfig<(D2, F)>,
+        input_stream: ReadStream<D1>,
+        output_stream: WriteStream<D2>,
+    ) -> Self {
+        let name = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("FoldOperator {}", config.id));
+        let (init, fold) = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no fold function supplied", name));
+        add_fold_callbacks(name, input_stream, &output_stream, init, fold, |result| {
+            result
+        });
+
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send its outgoing messages on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type D1.
+    pub fn connect(_input_stream: &ReadStream<D1>) -> WriteStream<D2> {
+        WriteStream::new()
+    }
+}
+
+impl<'a, D1: Data, D2: Data + Deserialize<'a>> Operator for FoldOperator<D1, D2> {}
+
+/// An operator that folds the values of type D1 received for each key and timestamp on a stream
+/// of `(K, D1)` pairs into a value of type D2, and sends a `(K, D2)` pair for each key once the
+/// watermark for the timestamp is received.
+///
+/// The pairs of a timestamp are sent in the order of their keys.
+///
+/// # Example
+/// The below example shows how to use a KeyedFoldOperator to count the messages received for
+/// each key and timestamp.
+///
+/// ```
+/// # use erdos::dataflow::{stream::IngestStream, operators::KeyedFoldOperator, OperatorConfig};
+/// # use erdos::*;
+/// #
+/// # let mut keyed_stream: IngestStream<(String, u32)> = IngestStream::new(0);
+/// #
+/// let fold_config = OperatorConfig::new()
+///     .name("KeyedFoldOperator")
+///     .arg((0, |count: usize, _data: &u32| -> usize { count + 1 }));
+/// let count_stream = connect_1_write!(
+///     KeyedFoldOperator<String, u32, usize>, fold_config, keyed_stream);
+/// ```
+pub struct KeyedFoldOperator<K: Data, D1: Data, D2: Data> {
+    phantom_data: PhantomData<(K, D1, D2)>,
+}
+
+impl<K, D1, D2> KeyedFoldOperator<K, D1, D2>
+where
+    for<'a> K: Data + Deserialize<'a> + Ord,
+    for<'a> D1: Data + Deserialize<'a>,
+    for<'a> D2: Data + Deserialize<'a>,
+{
+    /// Returns a new instance of the KeyedFoldOperator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the initial value and the closure
+    ///   used to fold items of type D1 into a value of type D2.
+    /// * `input_stream` - Represents the incoming stream of keyed messages of type D1.
+    /// * `output_stream` - Represents an outgoing stream of keyed messages of type D2.
+    pub fn new<F: 'static + Clone + Fn(D2, &D1) -> D2>(
+        config: OperatorConfig<(D2, F)>,
+        input_stream: ReadStream<(K, D1)>,
+        output_stream: WriteStream<(K, D2)>,
+    ) -> Self {
+        let name = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("KeyedFoldOperator {}", config.id));
+        let (init, fold) = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no fold function supplied", name));
+        add_keyed_fold_callbacks(name, input_stream, &output_stream, init, fold, |result| {
+            result
+        });
+
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send its outgoing messages on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of keyed messages of type D1.
+    pub fn connect(_input_stream: &ReadStream<(K, D1)>) -> WriteStream<(K, D2)> {
+        WriteStream::new()
+    }
+}
+
+impl<K, D1, D2> Operator for KeyedFoldOperator<K, D1, D2>
+where
+    for<'a> K: Data + Deserialize<'a> + Ord,
+    for<'a> D1: Data + Deserialize<'a>,
+    for<'a> D2: Data + Deserialize<'a>,
+{
+}
+
+/// Adds the callbacks which fold the messages received on `input_stream` for each timestamp into
+/// an accumulator of type A, and send the value which `finish` computes from the accumulator on
+/// `output_stream` once the watermark for the timestamp is received. No value is sent for
+/// timestamps without messages.
+pub(crate) fn add_fold_callbacks<'a, D1, A, D2, F, G>(
+    name: String,
+    input_stream: ReadStream<D1>,
+    output_stream: &WriteStream<D2>,
+    init: A,
+    fold: F,
+    finish: G,
+) where
+    D1: Data,
+    A: 'static + Clone,
+    D2: Data + Deserialize<'a>,
+    F: 'static + Fn(A, &D1) -> A,
+    G: 'static + Fn(A) -> D2,
+{
+    // Stores the messages for each timestamp until the watermark for the timestamp.
+    let stateful_stream = input_stream.add_state(TimeVersionedState::<(), D1>::new());
+    stateful_stream.add_callback(
+        |_t: &Timestamp, msg: &D1, message_state: &mut TimeVersionedState<(), D1>| {
+            message_state.append(msg.clone()).unwrap();
+        },
+    );
+    stateful_stream
+        .add_write_stream(output_stream)
+        .borrow_mut()
+        .add_watermark_callback(
+            move |t: &Timestamp,
+                  message_state: &TimeVersionedState<(), D1>,
+                  output_stream: &mut WriteStream<D2>| {
+                let messages = message_state.get_current_messages().unwrap();
+                if messages.is_empty() {
+                    return;
+                }
+                let result = finish(messages.iter().fold(init.clone(), &fold));
+                output_stream
+                    .send(Message::new_message(t.clone(), result))
+                    .unwrap_or_else(|e| panic!("{}: error sending on write stream: {:?}", name, e));
+            },
+        );
+    // Garbage collect the messages once they were folded.
+    stateful_stream.add_watermark_callback_with_priority(
+        |t: &Timestamp, message_state: &mut TimeVersionedState<(), D1>| {
+            message_state.close_time(t).unwrap();
+        },
+        127,
+    );
+}
+
+/// Adds the callbacks which fold the values received on `input_stream` for each key and timestamp
+/// into an accumulator of type A, and send a pair of the key and the value which `finish` computes
+/// from the accumulator on `output_stream` once the watermark for the timestamp is received.
+pub(crate) fn add_keyed_fold_callbacks<K, D1, A, D2, F, G>(
+    name: String,
+    input_stream: ReadStream<(K, D1)>,
+    output_stream: &WriteStream<(K, D2)>,
+    init: A,
+    fold: F,
+    finish: G,
+) where
+    for<'a> K: Data + Deserialize<'a> + Ord,
+    for<'a> D1: Data + Deserialize<'a>,
+    A: 'static + Clone,
+    for<'a> D2: Data + Deserialize<'a>,
+    F: 'static + Fn(A, &D1) -> A,
+    G: 'static + Fn(A) -> D2,
+{
+    let stateful_stream = input_stream.add_state(TimeVersionedState::<(), (K, D1)>::new());
+    stateful_stream.add_callback(
+        |_t: &Timestamp, msg: &(K, D1), message_state: &mut TimeVersionedState<(), (K, D1)>| {
+            message_state.append(msg.clone()).unwrap();
+        },
+    );
+    stateful_stream
+        .add_write_stream(output_stream)
+        .borrow_mut()
+        .add_watermark_callback(
+            move |t: &Timestamp,
+                  message_state: &TimeVersionedState<(), (K, D1)>,
+                  output_stream: &mut WriteStream<(K, D2)>| {
+                for (key, values) in group_by_key(message_state.get_current_messages().unwrap()) {
+                    let result = finish(values.into_iter().fold(init.clone(), &fold));
+                    output_stream
+                        .send(Message::new_message(t.clone(), (key.clone(), result)))
+                        .unwrap_or_else(|e| {
+                            panic!("{}: error sending on write stream: {:?}", name, e)
+                        });
+                }
+            },
+        );
+    stateful_stream.add_watermark_callback_with_priority(
+        |t: &Timestamp, message_state: &mut TimeVersionedState<(), (K, D1)>| {
+            message_state.close_time(t).unwrap();
+        },
+        127,
+    );
+}
+
+/// Groups the values of keyed messages by key, in the order of the keys.
+pub(crate) fn group_by_key<K: Ord, D>(messages: &[(K, D)]) -> BTreeMap<&K, Vec<&D>> {
+    let mut groups: BTreeMap<&K, Vec<&D>> = BTreeMap::new();
+    for (key, data) in messages {
+        groups.entry(key).or_default().push(data);
+    }
+    groups
+}
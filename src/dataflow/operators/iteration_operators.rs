@@ -0,0 +1,367 @@
+//! Operators which implement the iteration scope of a [`LoopStream`](crate::dataflow::LoopStream).
+//!
+//! Messages in the scope have timestamps with an additional coordinate, which increases with the
+//! iterations of the loop. The [`EnterLoopOperator`] iterates over the data received up to each
+//! watermark of its input stream in a round, and sends the watermark `[t, i]` into the scope once
+//! all the messages of the iteration `i` of the round with timestamp `t` are received. Iterations
+//! have even coordinates, which continue to increase across rounds so that the watermarks in the
+//! scope advance in the partial order of timestamps. A round converges once an iteration receives
+//! no messages, which the operator signals with the watermark `[t, i + 1]` after the last
+//! iteration `i`, and the [`LeaveLoopOperator`] translates to the watermark `t`.
+//!
+//! These operators handle watermarks themselves, so they must be connected with
+//! [`flow_watermarks`](crate::dataflow::OperatorConfig::flow_watermarks) disabled.
+
+use std::{
+    collections::HashMap,
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
+
+use serde::Deserialize;
+
+use crate::dataflow::{
+    message::Message, stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream, Timestamp,
+    TimestampT, WriteStream,
+};
+
+/// The difference between the coordinates of consecutive iterations. The odd coordinate between
+/// them signals that a round converged.
+const ITERATION_STEP: u64 = 2;
+
+/// Returns the timestamp of the iteration with coordinate `iteration` of the loop over `t`.
+///
+/// Panics if `t` already has [`MAX_DIMENSIONS`](crate::dataflow::message::MAX_DIMENSIONS)
+/// dimensions, which [`LoopStream::enter`](crate::dataflow::LoopStream::enter) prevents for
+/// timestamps with one dimension outside of all loops.
+fn enter_time(t: &Timestamp, iteration: u64) -> Timestamp {
+    let mut time = t.time().to_vec();
+    time.push(iteration);
+    Timestamp::new(time)
+}
+
+/// Returns the timestamp outside the loop and the iteration of a timestamp in the loop.
+fn leave_time(t: &Timestamp) -> (Timestamp, u64) {
+    let (iteration, time) = t
+        .time()
+        .split_last()
+        .unwrap_or_else(|| panic!("Timestamp {:?} has no iteration coordinate", t));
+    (Timestamp::new(time.to_vec()), *iteration)
+}
+
+/// Returns whether the watermark with coordinate `iteration` signals that a round converged.
+fn is_converged(iteration: u64) -> bool {
+    iteration % ITERATION_STEP == 1
+}
+
+/// The state shared by the callbacks of an [`EnterLoopOperator`].
+struct EnterLoopState<D: Data> {
+    name: String,
+    output_stream: WriteStream<D>,
+    /// The watermark of the round of the loop, and the iteration whose watermark was sent last.
+    round: Option<(Timestamp, u64)>,
+    /// The watermark of the last round which converged.
+    last_round_watermark: Option<Timestamp>,
+    /// The coordinate of the first iteration of the next round.
+    next_iteration: u64,
+    /// The greatest iteration of the messages received on the feedback stream, by the timestamp of
+    /// the messages outside the loop.
+    feedback_iterations: HashMap<Timestamp, u64>,
+    /// The last watermark received on the input stream for which no round started.
+    pending_watermark: Option<Timestamp>,
+    /// The input messages which no round covers yet.
+    pending_messages: Vec<(Timestamp, D)>,
+}
+
+impl<D> EnterLoopState<D>
+where
+    for<'a> D: Data + Deserialize<'a>,
+{
+    fn send(&mut self, msg: Message<D>) {
+        if let Err(e) = self.output_stream.send(msg) {
+            slog::error!(
+                crate::TERMINAL_LOGGER,
+                "{}: error sending on write stream: {:?}",
+                self.name,
+                e
+            );
+        }
+    }
+
+    /// Starts a round over the pending watermark if no round is in progress.
+    fn start_round(&mut self) {
+        if self.round.is_some() {
+            return;
+        }
+        let watermark = match self.pending_watermark.take() {
+            Some(watermark) => watermark,
+            None => return,
+        };
+        let round_watermark = if watermark.is_top() {
+            if self.pending_messages.is_empty() {
+                self.send(Message::new_watermark(Timestamp::top()));
+                return;
+            }
+            // Iterate over the remaining messages before closing the loop.
+            self.pending_watermark = Some(watermark);
+            self.pending_messages
+                .iter()
+                .map(|(t, _)| t.clone())
+                .chain(self.last_round_watermark.clone())
+                .fold(Timestamp::bottom(), |time, t| time.join(&t))
+        } else {
+            watermark
+        };
+        let iteration = self.next_iteration;
+        let (covered, uncovered) = std::mem::take(&mut self.pending_messages)
+            .into_iter()
+            .partition(|(t, _)| t.less_equal(&round_watermark));
+        self.pending_messages = uncovered;
+        for (t, msg) in covered {
+            self.send(Message::new_message(enter_time(&t, iteration), msg));
+        }
+        self.send(Message::new_watermark(enter_time(
+            &round_watermark,
+            iteration,
+        )));
+        self.round = Some((round_watermark, iteration));
+    }
+
+    fn on_input_msg(&mut self, t: &Timestamp, msg: &D) {
+        // The messages enter the loop once their round starts, so that the coordinates of their
+        // iterations exceed those of the previous rounds.
+        self.pending_messages.push((t.clone(), msg.clone()));
+    }
+
+    fn on_input_watermark(&mut self, t: &Timestamp) {
+        self.pending_watermark = Some(t.clone());
+        self.start_round();
+    }
+
+    fn on_feedback_msg(&mut self, t: &Timestamp, msg: &D) {
+        let (time, iteration) = leave_time(t);
+        let max_iteration = self.feedback_iterations.entry(time).or_insert(iteration);
+        *max_iteration = iteration.max(*max_iteration);
+        self.send(Message::new_message(t.clone(), msg.clone()));
+    }
+
+    fn on_feedback_watermark(&mut self, t: &Timestamp) {
+        if t.is_top() {
+            return;
+        }
+        let (time, watermark_iteration) = leave_time(t);
+        let (round_watermark, iteration) = match &self.round {
+            Some((round_watermark, iteration)) => (round_watermark.clone(), *iteration),
+            None => return,
+        };
+        if time != round_watermark || watermark_iteration != iteration + ITERATION_STEP {
+            return;
+        }
+        let is_active = self.feedback_iterations.iter().any(|(time, iteration)| {
+            time.less_equal(&round_watermark) && *iteration >= watermark_iteration
+        });
+        if is_active {
+            // All the messages of the next iteration were received.
+            self.send(Message::new_watermark(t.clone()));
+            self.round = Some((round_watermark, watermark_iteration));
+        } else {
+            self.feedback_iterations
+                .retain(|time, _| !time.less_equal(&round_watermark));
+            self.send(Message::new_watermark(enter_time(
+                &round_watermark,
+                iteration + 1,
+            )));
+            self.next_iteration = watermark_iteration;
+            self.last_round_watermark = Some(round_watermark);
+            self.round = None;
+            self.start_round();
+        }
+    }
+}
+
+/// Merges the input stream of a loop with its feedback stream, and adds an iteration coordinate to
+/// the timestamps of the input messages.
+pub(crate) struct EnterLoopOperator<D: Data> {
+    phantom_data: PhantomData<D>,
+}
+
+impl<D> EnterLoopOperator<D>
+where
+    for<'a> D: Data + Deserialize<'a>,
+{
+    pub fn new(
+        config: OperatorConfig<()>,
+        input_stream: ReadStream<D>,
+        feedback_stream: ReadStream<D>,
+        output_stream: WriteStream<D>,
+    ) -> Self {
+        let name = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("EnterLoopOperator {}", config.id));
+        let state = Arc::new(Mutex::new(EnterLoopState {
+            name,
+            output_stream,
+            round: None,
+            last_round_watermark: None,
+            next_iteration: 0,
+            feedback_iterations: HashMap::new(),
+            pending_watermark: None,
+            pending_messages: Vec::new(),
+        }));
+
+        let stateful_input_stream = input_stream.add_state(Arc::clone(&state));
+        stateful_input_stream.add_callback(
+            |t: &Timestamp, msg: &D, state: &mut Arc<Mutex<EnterLoopState<D>>>| {
+                state.lock().unwrap().on_input_msg(t, msg)
+            },
+        );
+        stateful_input_stream.add_watermark_callback(
+            |t: &Timestamp, state: &mut Arc<Mutex<EnterLoopState<D>>>| {
+                state.lock().unwrap().on_input_watermark(t)
+            },
+        );
+        let stateful_feedback_stream = feedback_stream.add_state(state);
+        stateful_feedback_stream.add_callback(
+            |t: &Timestamp, msg: &D, state: &mut Arc<Mutex<EnterLoopState<D>>>| {
+                state.lock().unwrap().on_feedback_msg(t, msg)
+            },
+        );
+        stateful_feedback_stream.add_watermark_callback(
+            |t: &Timestamp, state: &mut Arc<Mutex<EnterLoopState<D>>>| {
+                state.lock().unwrap().on_feedback_watermark(t)
+            },
+        );
+
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    pub fn connect(
+        _input_stream: &ReadStream<D>,
+        _feedback_stream: &ReadStream<D>,
+    ) -> WriteStream<D> {
+        WriteStream::new()
+    }
+}
+
+impl<D> Operator for EnterLoopOperator<D> where for<'a> D: Data + Deserialize<'a> {}
+
+/// Increments the iteration coordinate of the timestamps of the messages and watermarks sent back
+/// to the start of a loop.
+pub(crate) struct FeedbackOperator<D: Data> {
+    phantom_data: PhantomData<D>,
+}
+
+impl<D> FeedbackOperator<D>
+where
+    for<'a> D: Data + Deserialize<'a>,
+{
+    pub fn new(
+        config: OperatorConfig<()>,
+        input_stream: ReadStream<D>,
+        output_stream: WriteStream<D>,
+    ) -> Self {
+        let name = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("FeedbackOperator {}", config.id));
+        let stateful_stream = input_stream.add_state(output_stream);
+        let name_copy = name.clone();
+        stateful_stream.add_callback(
+            move |t: &Timestamp, msg: &D, output_stream: &mut WriteStream<D>| {
+                let (time, iteration) = leave_time(t);
+                output_stream
+                    .send(Message::new_message(
+                        enter_time(&time, iteration + ITERATION_STEP),
+                        msg.clone(),
+                    ))
+                    .unwrap_or_else(|e| panic!("{}: error sending message: {:?}", name_copy, e));
+            },
+        );
+        stateful_stream.add_watermark_callback(
+            move |t: &Timestamp, output_stream: &mut WriteStream<D>| {
+                let watermark = if t.is_top() {
+                    t.clone()
+                } else {
+                    match leave_time(t) {
+                        // The start of the loop does not wait for converged rounds.
+                        (_, iteration) if is_converged(iteration) => return,
+                        (time, iteration) => enter_time(&time, iteration + ITERATION_STEP),
+                    }
+                };
+                output_stream
+                    .send(Message::new_watermark(watermark))
+                    .unwrap_or_else(|e| panic!("{}: error sending watermark: {:?}", name, e));
+            },
+        );
+
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    pub fn connect(_input_stream: &ReadStream<D>) -> WriteStream<D> {
+        WriteStream::new()
+    }
+}
+
+impl<D> Operator for FeedbackOperator<D> where for<'a> D: Data + Deserialize<'a> {}
+
+/// Removes the iteration coordinate from the timestamps of the messages leaving a loop, and sends
+/// the watermark of a round once it converges.
+pub(crate) struct LeaveLoopOperator<D: Data> {
+    phantom_data: PhantomData<D>,
+}
+
+impl<D> LeaveLoopOperator<D>
+where
+    for<'a> D: Data + Deserialize<'a>,
+{
+    pub fn new(
+        config: OperatorConfig<()>,
+        input_stream: ReadStream<D>,
+        output_stream: WriteStream<D>,
+    ) -> Self {
+        let name = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("LeaveLoopOperator {}", config.id));
+        let stateful_stream = input_stream.add_state(output_stream);
+        let name_copy = name.clone();
+        stateful_stream.add_callback(
+            move |t: &Timestamp, msg: &D, output_stream: &mut WriteStream<D>| {
+                output_stream
+                    .send(Message::new_message(leave_time(t).0, msg.clone()))
+                    .unwrap_or_else(|e| panic!("{}: error sending message: {:?}", name_copy, e));
+            },
+        );
+        stateful_stream.add_watermark_callback(
+            move |t: &Timestamp, output_stream: &mut WriteStream<D>| {
+                let watermark = if t.is_top() {
+                    t.clone()
+                } else {
+                    match leave_time(t) {
+                        (time, iteration) if is_converged(iteration) => time,
+                        // The round has not converged yet.
+                        _ => return,
+                    }
+                };
+                output_stream
+                    .send(Message::new_watermark(watermark))
+                    .unwrap_or_else(|e| panic!("{}: error sending watermark: {:?}", name, e));
+            },
+        );
+
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    pub fn connect(_input_stream: &ReadStream<D>) -> WriteStream<D> {
+        WriteStream::new()
+    }
+}
+
+impl<D> Operator for LeaveLoopOperator<D> where for<'a> D: Data + Deserialize<'a> {}
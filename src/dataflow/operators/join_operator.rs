@@ -9,7 +9,7 @@ use serde::Deserialize;
 
 use crate::dataflow::{
     message::Message, stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream, Timestamp,
-    WriteStream,
+    TimestampT, WriteStream,
 };
 
 /// A structure that stores the state associated with a stream for the JoinOperator, and provides
@@ -17,14 +17,14 @@ use crate::dataflow::{
 /// Uses a ConcurrentHashMap to store the messages and a min-heap to ensure easy retrieval of the
 /// timestamps for cleaning.
 #[derive(Clone)]
-struct StreamState<D: Data> {
+pub(crate) struct StreamState<D: Data> {
     msgs: Arc<RwLock<HashMap<Timestamp, Vec<D>>>>,
     // A min-heap tracking the keys of the hashmap.
     timestamps: Arc<RwLock<BinaryHeap<Reverse<Timestamp>>>>,
 }
 
 impl<D: Data> StreamState<D> {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Self {
             msgs: Arc::new(RwLock::new(HashMap::new())),
             timestamps: Arc::new(RwLock::new(BinaryHeap::new())),
@@ -32,7 +32,7 @@ impl<D: Data> StreamState<D> {
     }
 
     /// Adds a message to the ConcurrentHashMap.
-    fn add_msg(&mut self, timestamp: &Timestamp, msg: D) {
+    pub(crate) fn add_msg(&mut self, timestamp: &Timestamp, msg: D) {
         // Insert a new vector if the key does not exist, and add the key to the timestamps.
         let mut msgs = self.msgs.write().unwrap();
         match msgs.get_mut(timestamp) {
@@ -48,7 +48,7 @@ impl<D: Data> StreamState<D> {
     }
 
     /// Cleans the state corresponding to a given Timestamp (upto and including).
-    fn clean_state(&self, timestamp: &Timestamp) {
+    pub(crate) fn clean_state(&self, timestamp: &Timestamp) {
         let timestamps = &mut self.timestamps.write().unwrap();
         while timestamps.peek().map_or(false, |t| t.0 <= *timestamp) {
             let t = timestamps.pop().unwrap().0;
@@ -60,8 +60,32 @@ impl<D: Data> StreamState<D> {
         }
     }
 
+    /// Removes and returns the messages up to and including the given Timestamp, in the order of
+    /// their timestamps.
+    pub(crate) fn take_state(&self, timestamp: &Timestamp) -> Vec<(Timestamp, Vec<D>)> {
+        let timestamps = &mut self.timestamps.write().unwrap();
+        let mut msgs = self.msgs.write().unwrap();
+        // The heap is ordered lexicographically, so timestamps which are incomparable with the
+        // given Timestamp may precede timestamps which are less than or equal to it.
+        let (mut taken, kept): (Vec<Timestamp>, Vec<Timestamp>) = timestamps
+            .drain()
+            .map(|t| t.0)
+            .partition(|t| t.less_equal(timestamp));
+        **timestamps = kept.into_iter().map(Reverse).collect();
+        taken.sort();
+        taken
+            .into_iter()
+            .map(|t| {
+                let msg_vec = msgs
+                    .remove(&t)
+                    .expect("StreamState: expected Timestamp to be present");
+                (t, msg_vec)
+            })
+            .collect()
+    }
+
     /// Retrieve the state.
-    fn get_state(&self, timestamp: &Timestamp) -> Option<Vec<D>> {
+    pub(crate) fn get_state(&self, timestamp: &Timestamp) -> Option<Vec<D>> {
         match self.msgs.read().unwrap().get(timestamp) {
             Some(value) => Some(value.clone()),
             None => None,
@@ -196,3 +220,33 @@ impl<'a, D1: Data, D2: Data, D3: Data + Deserialize<'a>> JoinOperator<D1, D2, D3
 }
 
 impl<'a, D1: Data, D2: Data, D3: Data + Deserialize<'a>> Operator for JoinOperator<D1, D2, D3> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_state_product_order() {
+        let mut state = StreamState::new();
+        for time in &[vec![0, 5], vec![1, 0], vec![1, 2], vec![2, 1]] {
+            state.add_msg(&Timestamp::new(time.clone()), time[1]);
+        }
+        // [0, 5] is incomparable with [1, 2], so it is kept.
+        let taken = state.take_state(&Timestamp::new(vec![1, 2]));
+        assert_eq!(
+            taken,
+            vec![
+                (Timestamp::new(vec![1, 0]), vec![0]),
+                (Timestamp::new(vec![1, 2]), vec![2]),
+            ]
+        );
+        let taken = state.take_state(&Timestamp::top());
+        assert_eq!(
+            taken,
+            vec![
+                (Timestamp::new(vec![0, 5]), vec![5]),
+                (Timestamp::new(vec![2, 1]), vec![1]),
+            ]
+        );
+    }
+}
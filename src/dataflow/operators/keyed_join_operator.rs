@@ -0,0 +1,208 @@
+use std::{collections::BTreeMap, marker::PhantomData};
+
+use serde::Deserialize;
+
+use crate::dataflow::{
+    message::Message, operators::join_operator::StreamState, stream::WriteStreamT, Data, Operator,
+    OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+
+/// Describes which messages a [`KeyedJoinOperator`] sends for the keys which are not received on
+/// both streams.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JoinType {
+    /// Only sends the pairs of messages with the same key.
+    Inner,
+    /// Also sends the messages of the left stream whose key is not received on the right stream.
+    Left,
+    /// Also sends the messages of either stream whose key is not received on the other stream.
+    Outer,
+}
+
+/// An operator that joins the messages of type D1 and D2 which have the same timestamp and key,
+/// and sends each pair as a `(K, Option<D1>, Option<D2>)` message.
+///
+/// The keys are computed by the provided key-extraction closures once the watermark for a
+/// timestamp is received on both streams. The messages of a timestamp are sent in the order of
+/// their keys, and the messages received for the timestamp are then garbage collected.
+///
+/// # Example
+/// The below example shows how to use a KeyedJoinOperator to join two streams of `(u32, String)`
+/// pairs by their first element, keeping the pairs of the left stream which have no match.
+///
+/// ```
+/// # use erdos::dataflow::{
+/// #     stream::IngestStream,
+/// #     operators::{JoinType, KeyedJoinOperator},
+/// #     OperatorConfig
+/// # };
+/// # use erdos::*;
+/// #
+/// # let mut left_stream: IngestStream<(u32, String)> = IngestStream::new(0);
+/// # let mut right_stream: IngestStream<(u32, String)> = IngestStream::new(0);
+/// #
+/// // Add the join type and the key-extraction closures as an argument to the operator via the
+/// // OperatorConfig.
+/// let join_config = OperatorConfig::new().name("KeyedJoinOperator").arg((
+///     JoinType::Left,
+///     |left: &(u32, String)| -> u32 { left.0 },
+///     |right: &(u32, String)| -> u32 { right.0 },
+/// ));
+/// let joined_stream = connect_1_write!(
+///     KeyedJoinOperator<u32, (u32, String), (u32, String)>,
+///     join_config,
+///     left_stream,
+///     right_stream
+/// );
+/// ```
+pub struct KeyedJoinOperator<K: Data, D1: Data, D2: Data> {
+    phantom_data: PhantomData<(K, D1, D2)>,
+}
+
+impl<K, D1, D2> KeyedJoinOperator<K, D1, D2>
+where
+    for<'a> K: Data + Deserialize<'a> + Ord,
+    for<'a> D1: Data + Deserialize<'a>,
+    for<'a> D2: Data + Deserialize<'a>,
+{
+    /// Returns a new instance of the KeyedJoinOperator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the [`JoinType`] and the closures
+    ///   which return the keys of the items of type D1 and D2.
+    /// * `input_stream_left` - Represents the incoming stream of messages of type D1.
+    /// * `input_stream_right` - Represents the incoming stream of messages of type D2.
+    /// * `output_stream` - Represents an outgoing stream of joined messages.
+    pub fn new<F1, F2>(
+        config: OperatorConfig<(JoinType, F1, F2)>,
+        input_stream_left: ReadStream<D1>,
+        input_stream_right: ReadStream<D2>,
+        output_stream: WriteStream<(K, Option<D1>, Option<D2>)>,
+    ) -> Self
+    where
+        F1: 'static + Clone + Fn(&D1) -> K,
+        F2: 'static + Clone + Fn(&D2) -> K,
+    {
+        let name = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("KeyedJoinOperator {}", config.id));
+        let (join_type, left_key, right_key) = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no key functions provided", name));
+
+        let stateful_stream_left = input_stream_left.add_state(StreamState::<D1>::new());
+        stateful_stream_left.add_callback(
+            |t: &Timestamp, msg: &D1, state: &mut StreamState<D1>| {
+                state.add_msg(t, msg.clone());
+            },
+        );
+        let stateful_stream_right = input_stream_right.add_state(StreamState::<D2>::new());
+        stateful_stream_right.add_callback(
+            |t: &Timestamp, msg: &D2, state: &mut StreamState<D2>| {
+                state.add_msg(t, msg.clone());
+            },
+        );
+
+        stateful_stream_left
+            .add_read_stream(&stateful_stream_right)
+            .borrow_mut()
+            .add_write_stream(&output_stream)
+            .borrow_mut()
+            .add_watermark_callback(
+                move |t: &Timestamp,
+                      left_state: &StreamState<D1>,
+                      right_state: &StreamState<D2>,
+                      write_stream: &mut WriteStream<(K, Option<D1>, Option<D2>)>| {
+                    Self::on_watermark_callback(
+                        t,
+                        left_state,
+                        right_state,
+                        write_stream,
+                        join_type,
+                        &left_key,
+                        &right_key,
+                    )
+                },
+            );
+
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send its outgoing messages on.
+    ///
+    /// # Arguments
+    /// * `input_stream_left` - Represents the incoming stream of messages of type D1.
+    /// * `input_stream_right` - Represents the incoming stream of messages of type D2.
+    pub fn connect(
+        _input_stream_left: &ReadStream<D1>,
+        _input_stream_right: &ReadStream<D2>,
+    ) -> WriteStream<(K, Option<D1>, Option<D2>)> {
+        WriteStream::new()
+    }
+
+    /// The function to be called when a watermark is received on both the left and the right
+    /// streams.
+    /// This callback joins the messages received for the watermark's timestamp by key, and sends
+    /// the joined messages.
+    fn on_watermark_callback<F1, F2>(
+        t: &Timestamp,
+        left_state: &StreamState<D1>,
+        right_state: &StreamState<D2>,
+        write_stream: &mut WriteStream<(K, Option<D1>, Option<D2>)>,
+        join_type: JoinType,
+        left_key: &F1,
+        right_key: &F2,
+    ) where
+        F1: 'static + Clone + Fn(&D1) -> K,
+        F2: 'static + Clone + Fn(&D2) -> K,
+    {
+        // Group the messages of both streams by key.
+        let mut groups: BTreeMap<K, (Vec<D1>, Vec<D2>)> = BTreeMap::new();
+        for data in left_state.get_state(t).unwrap_or_default() {
+            groups.entry(left_key(&data)).or_default().0.push(data);
+        }
+        for data in right_state.get_state(t).unwrap_or_default() {
+            groups.entry(right_key(&data)).or_default().1.push(data);
+        }
+
+        for (key, (left, right)) in groups {
+            let joined: Vec<(Option<D1>, Option<D2>)> = match (left.is_empty(), right.is_empty()) {
+                (false, false) => left
+                    .iter()
+                    .flat_map(|l| {
+                        right
+                            .iter()
+                            .map(move |r| (Some(l.clone()), Some(r.clone())))
+                    })
+                    .collect(),
+                (false, true) if join_type != JoinType::Inner => {
+                    left.into_iter().map(|l| (Some(l), None)).collect()
+                }
+                (true, false) if join_type == JoinType::Outer => {
+                    right.into_iter().map(|r| (None, Some(r))).collect()
+                }
+                _ => Vec::new(),
+            };
+            for (l, r) in joined {
+                write_stream
+                    .send(Message::new_message(t.clone(), (key.clone(), l, r)))
+                    .expect("KeyedJoinOperator: error sending on write stream");
+            }
+        }
+
+        // Garbage collect all the data upto and including this timestamp.
+        left_state.clean_state(t);
+        right_state.clean_state(t);
+    }
+}
+
+impl<K, D1, D2> Operator for KeyedJoinOperator<K, D1, D2>
+where
+    for<'a> K: Data + Deserialize<'a> + Ord,
+    for<'a> D1: Data + Deserialize<'a>,
+    for<'a> D2: Data + Deserialize<'a>,
+{
+}
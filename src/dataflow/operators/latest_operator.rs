@@ -0,0 +1,73 @@
+use std::marker::PhantomData;
+
+use serde::Deserialize;
+
+use crate::dataflow::{
+    message::Message,
+    stream::{OverflowPolicy, WriteStreamT},
+    Data, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+
+/// An operator that forwards the messages of an incoming stream on an outgoing stream which
+/// buffers at most one message for each receiver.
+///
+/// If a receiver is busy when a message is forwarded, the message replaces the message buffered
+/// for the receiver, so that the receiver only processes the latest message once it is ready.
+/// Watermarks are never dropped.
+///
+/// # Example
+/// The below example shows how to use a LatestOperator to make an expensive operator skip the
+/// u32 messages which arrive while it is busy.
+///
+/// ```
+/// # use erdos::dataflow::{stream::IngestStream, operators::LatestOperator, OperatorConfig};
+/// # use erdos::*;
+/// #
+/// # let mut u32_stream = IngestStream::new(0);
+/// #
+/// let latest_config = OperatorConfig::new().name("LatestOperator");
+/// let latest_stream = connect_1_write!(LatestOperator<u32>, latest_config, u32_stream);
+/// ```
+pub struct LatestOperator<D: Data> {
+    phantom_data: PhantomData<D>,
+}
+
+impl<D> LatestOperator<D>
+where
+    for<'a> D: Data + Deserialize<'a>,
+{
+    /// Returns a new instance of the LatestOperator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig.
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    /// * `output_stream` - Represents an outgoing stream of messages of type D.
+    pub fn new(
+        _config: OperatorConfig<()>,
+        input_stream: ReadStream<D>,
+        output_stream: WriteStream<D>,
+    ) -> Self {
+        input_stream.add_state(output_stream).add_callback(
+            |t: &Timestamp, msg: &D, output_stream: &mut WriteStream<D>| {
+                output_stream
+                    .send(Message::new_message(t.clone(), msg.clone()))
+                    .expect("LatestOperator: error sending on write stream");
+            },
+        );
+
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream with a capacity of one message, which drops the
+    /// buffered message when a new message is sent.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    pub fn connect(_input_stream: &ReadStream<D>) -> WriteStream<D> {
+        WriteStream::new().with_capacity(1, OverflowPolicy::DropOldest)
+    }
+}
+
+impl<D> Operator for LatestOperator<D> where for<'a> D: Data + Deserialize<'a> {}
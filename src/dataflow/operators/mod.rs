@@ -1,11 +1,40 @@
 //! Library of generic operators for building ERDOS applications.
 
 // Private submodules
+mod approximate_time_sync_operator;
+mod filter_operator;
+mod flat_map_operator;
+mod fold_operator;
+pub(crate) mod iteration_operators;
 mod join_operator;
+mod keyed_join_operator;
+mod latest_operator;
 mod map_operator;
+mod n_way_join_operator;
+mod periodic_source_operator;
+mod reduce_operator;
+mod sample_operator;
 mod source_operator;
+mod split_operator;
+mod throttle_operator;
+mod window_operator;
 
 // Public exports
+pub use crate::dataflow::operators::approximate_time_sync_operator::{
+    ApproximateTime, ApproximateTimeSyncOperator,
+};
+pub use crate::dataflow::operators::filter_operator::FilterOperator;
+pub use crate::dataflow::operators::flat_map_operator::FlatMapOperator;
+pub use crate::dataflow::operators::fold_operator::{FoldOperator, KeyedFoldOperator};
 pub use crate::dataflow::operators::join_operator::JoinOperator;
+pub use crate::dataflow::operators::keyed_join_operator::{JoinType, KeyedJoinOperator};
+pub use crate::dataflow::operators::latest_operator::LatestOperator;
 pub use crate::dataflow::operators::map_operator::MapOperator;
+pub use crate::dataflow::operators::n_way_join_operator::NWayJoinOperator;
+pub use crate::dataflow::operators::periodic_source_operator::PeriodicSourceOperator;
+pub use crate::dataflow::operators::reduce_operator::{KeyedReduceOperator, ReduceOperator};
+pub use crate::dataflow::operators::sample_operator::SampleOperator;
 pub use crate::dataflow::operators::source_operator::SourceOperator;
+pub use crate::dataflow::operators::split_operator::SplitOperator;
+pub use crate::dataflow::operators::throttle_operator::ThrottleOperator;
+pub use crate::dataflow::operators::window_operator::{Window, WindowOperator};
@@ -0,0 +1,137 @@
+use std::marker::PhantomData;
+
+use serde::Deserialize;
+
+use crate::dataflow::{
+    add_watermark_callback_vec, message::Message, operators::join_operator::StreamState,
+    stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+
+/// An operator that joins any number of incoming streams of type D1 into a stream of type D2
+/// using the function provided.
+///
+/// Once the watermark for a timestamp is received on all incoming streams, the function is
+/// called with the messages received for the timestamp on each stream, in the order of the
+/// streams, and its result is sent with the timestamp. Nothing is sent for timestamps without
+/// messages. The messages received for the timestamp are then garbage collected.
+///
+/// The incoming streams are connected as a vector using
+/// [`connect_vec_1_write`](crate::connect_vec_1_write). At most 20 streams can be joined, which
+/// is the limit of [`add_watermark_callback_vec`].
+///
+/// # Example
+/// The below example shows how to use an NWayJoinOperator to sum three streams of incoming u32
+/// messages, and return them as u64 messages.
+///
+/// ```
+/// # use erdos::dataflow::{
+/// #     stream::IngestStream, operators::NWayJoinOperator, OperatorConfig, ReadStream
+/// # };
+/// # use erdos::*;
+/// #
+/// # let u32_stream_0: IngestStream<u32> = IngestStream::new(0);
+/// # let u32_stream_1: IngestStream<u32> = IngestStream::new(0);
+/// # let u32_stream_2: IngestStream<u32> = IngestStream::new(0);
+/// #
+/// // Add the joining function as an argument to the operator via the OperatorConfig.
+/// let join_config = OperatorConfig::new()
+///     .name("NWayJoinOperator")
+///     .arg(|data: Vec<Vec<u32>>| -> u64 {
+///         data.iter().flatten().map(|x| *x as u64).sum()
+///     });
+/// let input_streams = vec![
+///     ReadStream::from(&u32_stream_0),
+///     ReadStream::from(&u32_stream_1),
+///     ReadStream::from(&u32_stream_2),
+/// ];
+/// let output_stream =
+///     connect_vec_1_write!(NWayJoinOperator<u32, u64>, join_config, input_streams);
+/// ```
+pub struct NWayJoinOperator<D1: Data, D2: Data> {
+    phantom_data: PhantomData<(D1, D2)>,
+}
+
+impl<'a, D1: Data, D2: Data + Deserialize<'a>> NWayJoinOperator<D1, D2> {
+    /// Returns a new instance of the NWayJoinOperator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the closure used to join the
+    ///   items of type D1 received on each stream to a value of type D2.
+    /// * `input_streams` - Represents the incoming streams of messages of type D1.
+    /// * `output_stream` - Represents an outgoing stream of messages of type D2.
+    pub fn new<F: 'static + Clone + Fn(Vec<Vec<D1>>) -> D2>(
+        config: OperatorConfig<F>,
+        input_streams: Vec<ReadStream<D1>>,
+        output_stream: WriteStream<D2>,
+    ) -> Self {
+        let name = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("NWayJoinOperator {}", config.id));
+        let join_function = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no join function provided", name));
+
+        // The states share their messages with the copies moved into the watermark callback.
+        let states: Vec<StreamState<D1>> = input_streams
+            .iter()
+            .map(|input_stream| {
+                let state = StreamState::new();
+                input_stream.add_state(state.clone()).add_callback(
+                    |t: &Timestamp, msg: &D1, state: &mut StreamState<D1>| {
+                        state.add_msg(t, msg.clone());
+                    },
+                );
+                state
+            })
+            .collect();
+
+        add_watermark_callback_vec(
+            input_streams.iter().collect(),
+            vec![&output_stream],
+            move |t: &Timestamp, output_streams: &mut Vec<WriteStream<D2>>| {
+                Self::on_watermark_callback(t, &states, &mut output_streams[0], &join_function)
+            },
+            0,
+        );
+
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send its outgoing messages on.
+    ///
+    /// # Arguments
+    /// * `input_streams` - Represents the incoming streams of messages of type D1.
+    pub fn connect(_input_streams: &[ReadStream<D1>]) -> WriteStream<D2> {
+        WriteStream::new()
+    }
+
+    /// The function to be called when a watermark is received on all the input streams.
+    /// This callback uses the saved states of the streams and joins them using the provided
+    /// closure.
+    fn on_watermark_callback<F: 'static + Clone + Fn(Vec<Vec<D1>>) -> D2>(
+        t: &Timestamp,
+        states: &[StreamState<D1>],
+        write_stream: &mut WriteStream<D2>,
+        join_function: &F,
+    ) {
+        let states_t: Vec<Vec<D1>> = states
+            .iter()
+            .map(|state| state.get_state(t).unwrap_or_default())
+            .collect();
+        if states_t.iter().any(|state_t| !state_t.is_empty()) {
+            write_stream
+                .send(Message::new_message(t.clone(), join_function(states_t)))
+                .expect("NWayJoinOperator: error sending on write stream");
+        }
+
+        // Garbage collect all the data upto and including this timestamp.
+        for state in states {
+            state.clean_state(t);
+        }
+    }
+}
+
+impl<'a, D1: Data, D2: Data + Deserialize<'a>> Operator for NWayJoinOperator<D1, D2> {}
@@ -0,0 +1,108 @@
+use std::{marker::PhantomData, time::Duration};
+
+use serde::Deserialize;
+
+use crate::dataflow::{
+    add_timer_callback, message::Message, stream::WriteStreamT, Data, Operator, OperatorConfig,
+    Timestamp, WriteStream,
+};
+
+/// An operator that periodically generates messages of type D using the provided closure.
+///
+/// On its i-th tick, the operator calls the closure with the timestamp `[i]`, and sends the
+/// generated data followed by a watermark with the timestamp. Once the closure returns `None`,
+/// the operator sends a top watermark, which closes the stream, and stops generating messages.
+///
+/// # Example
+/// The below example shows how to use a PeriodicSourceOperator to send the timestamps of the
+/// first 100 ticks of a 10 Hz timer as u64 messages.
+///
+/// ```
+/// # use std::time::Duration;
+/// # use erdos::dataflow::{operators::PeriodicSourceOperator, OperatorConfig, Timestamp};
+/// # use erdos::*;
+/// #
+/// // Add the period and the generating function as an argument to the operator via the
+/// // OperatorConfig.
+/// let source_config = OperatorConfig::new().name("PeriodicSourceOperator").arg((
+///     Duration::from_millis(100),
+///     |t: &Timestamp| -> Option<u64> { Some(t.time()[0]).filter(|time| *time < 100) },
+/// ));
+/// let u64_stream = connect_1_write!(PeriodicSourceOperator<u64>, source_config);
+/// ```
+pub struct PeriodicSourceOperator<D: Data> {
+    phantom_data: PhantomData<D>,
+}
+
+impl<D> PeriodicSourceOperator<D>
+where
+    for<'a> D: Data + Deserialize<'a>,
+{
+    /// Returns a new instance of the PeriodicSourceOperator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the period of the ticks and the
+    ///   closure used to generate the item of type D for the timestamp of a tick.
+    /// * `output_stream` - Represents an outgoing stream of messages of type D.
+    pub fn new<F: 'static + Clone + Send + Sync + Fn(&Timestamp) -> Option<D>>(
+        config: OperatorConfig<(Duration, F)>,
+        output_stream: WriteStream<D>,
+    ) -> Self {
+        let name = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("PeriodicSourceOperator {}", config.id));
+        let (period, generator) = config
+            .arg
+            .clone()
+            .unwrap_or_else(|| panic!("{}: no period and generator provided", name));
+
+        add_timer_callback(
+            &config,
+            period,
+            output_stream,
+            move |tick: u64, output_stream: &mut WriteStream<D>| {
+                Self::on_timer_callback(tick, output_stream, &generator)
+            },
+        );
+
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send its outgoing messages on.
+    pub fn connect() -> WriteStream<D> {
+        WriteStream::new()
+    }
+
+    /// The function to be called on each tick of the timer.
+    /// This callback sends the generated message and a watermark for the tick, or closes the
+    /// stream and stops the timer once no message is generated.
+    fn on_timer_callback<F: 'static + Clone + Send + Sync + Fn(&Timestamp) -> Option<D>>(
+        tick: u64,
+        output_stream: &mut WriteStream<D>,
+        generator: &F,
+    ) -> bool {
+        let timestamp = Timestamp::new(vec![tick]);
+        match generator(&timestamp) {
+            Some(data) => {
+                output_stream
+                    .send(Message::new_message(timestamp.clone(), data))
+                    .expect("PeriodicSourceOperator: error sending on write stream");
+                output_stream
+                    .send(Message::new_watermark(timestamp))
+                    .expect("PeriodicSourceOperator: error sending watermark");
+                true
+            }
+            None => {
+                output_stream
+                    .send(Message::new_watermark(Timestamp::top()))
+                    .expect("PeriodicSourceOperator: error sending watermark");
+                false
+            }
+        }
+    }
+}
+
+impl<D> Operator for PeriodicSourceOperator<D> where for<'a> D: Data + Deserialize<'a> {}
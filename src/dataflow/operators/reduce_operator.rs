@@ -0,0 +1,170 @@
+use std::marker::PhantomData;
+
+use serde::Deserialize;
+
+use crate::dataflow::{
+    operators::fold_operator::{add_fold_callbacks, add_keyed_fold_callbacks},
+    Data, Operator, OperatorConfig, ReadStream, WriteStream,
+};
+
+/// An operator that reduces the messages of type D received for each timestamp into a single
+/// message of type D, and sends it once the watermark for the timestamp is received.
+///
+/// The reduction starts from the first message of the timestamp, and adds the other messages in
+/// the order in which they were processed. No message is sent for timestamps without messages.
+///
+/// # Example
+/// The below example shows how to use a ReduceOperator to find the largest u32 message of each
+/// timestamp.
+///
+/// ```
+/// # use erdos::dataflow::{stream::IngestStream, operators::ReduceOperator, OperatorConfig};
+/// # use erdos::*;
+/// #
+/// # let mut u32_stream = IngestStream::new(0);
+/// #
+/// // Add the reducing function as an argument to the operator via the OperatorConfig.
+/// let reduce_config = OperatorConfig::new()
+///     .name("ReduceOperator")
+///     .arg(|max: u32, data: &u32| -> u32 { max.max(*data) });
+/// let max_stream = connect_1_write!(ReduceOperator<u32>, reduce_config, u32_stream);
+/// ```
+pub struct ReduceOperator<D: Data> {
+    phantom_data: PhantomData<D>,
+}
+
+impl<'a, D: Data + Deserialize<'a>> ReduceOperator<D> {
+    /// Returns a new instance of the ReduceOperator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the closure used to combine two
+    ///   items of type D.
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    /// * `output_stream` - Represents an outgoing stream of messages of type D.
+    pub fn new<F: 'static + Clone + Fn(D, &D) -> D>(
+        config: OperatorConfig<F>,
+        input_stream: ReadStream<D>,
+        output_stream: WriteStream<D>,
+    ) -> Self {
+        let name = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("ReduceOperator {}", config.id));
+        let reduce = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no reduce function supplied", name));
+        add_fold_callbacks(
+            name,
+            input_stream,
+            &output_stream,
+            None,
+            reduce_into(reduce),
+            // Timestamps with messages are reduced to a value.
+            Option::unwrap,
+        );
+
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send its outgoing messages on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    pub fn connect(_input_stream: &ReadStream<D>) -> WriteStream<D> {
+        WriteStream::new()
+    }
+}
+
+impl<'a, D: Data + Deserialize<'a>> Operator for ReduceOperator<D> {}
+
+/// An operator that reduces the values of type D received for each key and timestamp on a
+/// stream of `(K, D)` pairs into a single value, and sends a `(K, D)` pair for each key once the
+/// watermark for the timestamp is received.
+///
+/// The pairs of a timestamp are sent in the order of their keys.
+///
+/// # Example
+/// The below example shows how to use a KeyedReduceOperator to sum the u32 values received for
+/// each key and timestamp.
+///
+/// ```
+/// # use erdos::dataflow::{stream::IngestStream, operators::KeyedReduceOperator, OperatorConfig};
+/// # use erdos::*;
+/// #
+/// # let mut keyed_stream: IngestStream<(String, u32)> = IngestStream::new(0);
+/// #
+/// let reduce_config = OperatorConfig::new()
+///     .name("KeyedReduceOperator")
+///     .arg(|sum: u32, data: &u32| -> u32 { sum + data });
+/// let sum_stream = connect_1_write!(
+///     KeyedReduceOperator<String, u32>, reduce_config, keyed_stream);
+/// ```
+pub struct KeyedReduceOperator<K: Data, D: Data> {
+    phantom_data: PhantomData<(K, D)>,
+}
+
+impl<K, D> KeyedReduceOperator<K, D>
+where
+    for<'a> K: Data + Deserialize<'a> + Ord,
+    for<'a> D: Data + Deserialize<'a>,
+{
+    /// Returns a new instance of the KeyedReduceOperator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the closure used to combine two
+    ///   items of type D.
+    /// * `input_stream` - Represents the incoming stream of keyed messages of type D.
+    /// * `output_stream` - Represents an outgoing stream of keyed messages of type D.
+    pub fn new<F: 'static + Clone + Fn(D, &D) -> D>(
+        config: OperatorConfig<F>,
+        input_stream: ReadStream<(K, D)>,
+        output_stream: WriteStream<(K, D)>,
+    ) -> Self {
+        let name = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("KeyedReduceOperator {}", config.id));
+        let reduce = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no reduce function supplied", name));
+        add_keyed_fold_callbacks(
+            name,
+            input_stream,
+            &output_stream,
+            None,
+            reduce_into(reduce),
+            // Each key has at least one value.
+            Option::unwrap,
+        );
+
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send its outgoing messages on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of keyed messages of type D.
+    pub fn connect(_input_stream: &ReadStream<(K, D)>) -> WriteStream<(K, D)> {
+        WriteStream::new()
+    }
+}
+
+impl<K, D> Operator for KeyedReduceOperator<K, D>
+where
+    for<'a> K: Data + Deserialize<'a> + Ord,
+    for<'a> D: Data + Deserialize<'a>,
+{
+}
+
+/// Turns a reducing function into a folding function whose accumulator is empty until it folds the
+/// first value.
+fn reduce_into<D: Clone, F: Fn(D, &D) -> D>(reduce: F) -> impl Fn(Option<D>, &D) -> Option<D> {
+    move |result: Option<D>, data: &D| match result {
+        Some(result) => Some(reduce(result, data)),
+        None => Some(data.clone()),
+    }
+}
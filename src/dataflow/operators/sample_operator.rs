@@ -0,0 +1,137 @@
+use std::marker::PhantomData;
+
+use serde::Deserialize;
+
+use crate::dataflow::{
+    message::Message, state::TimeVersionedState, stream::WriteStreamT, Data, Operator,
+    OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+
+/// An operator that forwards the messages of every N-th timestamp of an incoming stream, and
+/// drops the messages of the other timestamps.
+///
+/// Only the timestamps which have messages are counted, starting with the first one which is
+/// forwarded. The messages of a timestamp are forwarded once the watermark for the timestamp is
+/// received, and the watermarks of all timestamps flow to the outgoing stream.
+///
+/// # Example
+/// The below example shows how to use a SampleOperator to downsample a 30 Hz stream of u32
+/// messages to 10 Hz.
+///
+/// ```
+/// # use erdos::dataflow::{stream::IngestStream, operators::SampleOperator, OperatorConfig};
+/// # use erdos::*;
+/// #
+/// # let mut u32_stream = IngestStream::new(0);
+/// #
+/// // Add the sampling interval as an argument to the operator via the OperatorConfig.
+/// let sample_config = OperatorConfig::new().name("SampleOperator").arg(3);
+/// let sampled_stream = connect_1_write!(SampleOperator<u32>, sample_config, u32_stream);
+/// ```
+pub struct SampleOperator<D: Data> {
+    phantom_data: PhantomData<D>,
+}
+
+impl<D> SampleOperator<D>
+where
+    for<'a> D: Data + Deserialize<'a>,
+{
+    /// Returns a new instance of the SampleOperator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the number N, such that the
+    ///   messages of every N-th timestamp are forwarded.
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    /// * `output_stream` - Represents an outgoing stream of messages of type D.
+    pub fn new(
+        config: OperatorConfig<usize>,
+        input_stream: ReadStream<D>,
+        output_stream: WriteStream<D>,
+    ) -> Self {
+        let name = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("SampleOperator {}", config.id));
+        let interval = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no sampling interval provided", name));
+        if interval == 0 {
+            panic!("{}: the sampling interval must be positive", name);
+        }
+
+        // Stores the messages for each timestamp until the watermark for the timestamp.
+        let stateful_stream = input_stream.add_state(TimeVersionedState::<(), D>::new());
+        stateful_stream.add_callback(
+            |_t: &Timestamp, msg: &D, message_state: &mut TimeVersionedState<(), D>| {
+                message_state.append(msg.clone()).unwrap();
+            },
+        );
+        // Counts the timestamps with messages whose watermark was received since the last sampled
+        // timestamp.
+        let num_timestamps: usize = 0;
+        stateful_stream
+            .add_write_stream(&output_stream)
+            .borrow_mut()
+            .add_state(num_timestamps)
+            .borrow_mut()
+            .add_watermark_callback(
+                move |t: &Timestamp,
+                      num_timestamps: &mut usize,
+                      message_state: &TimeVersionedState<(), D>,
+                      output_stream: &mut WriteStream<D>| {
+                    Self::on_watermark_callback(
+                        t,
+                        num_timestamps,
+                        message_state,
+                        output_stream,
+                        interval,
+                    )
+                },
+            );
+        // Garbage collect the messages once they were sampled.
+        stateful_stream.add_watermark_callback_with_priority(
+            |t: &Timestamp, message_state: &mut TimeVersionedState<(), D>| {
+                message_state.close_time(t).unwrap();
+            },
+            127,
+        );
+
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send its outgoing messages on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    pub fn connect(_input_stream: &ReadStream<D>) -> WriteStream<D> {
+        WriteStream::new()
+    }
+
+    /// The function to be called when a watermark is received on the input stream.
+    /// This callback forwards the messages received for the watermark's timestamp if the
+    /// timestamp is sampled.
+    fn on_watermark_callback(
+        t: &Timestamp,
+        num_timestamps: &mut usize,
+        message_state: &TimeVersionedState<(), D>,
+        output_stream: &mut WriteStream<D>,
+        interval: usize,
+    ) {
+        let messages = message_state.get_current_messages().unwrap();
+        if messages.is_empty() {
+            return;
+        }
+        if *num_timestamps == 0 {
+            for data in messages {
+                output_stream
+                    .send(Message::new_message(t.clone(), data.clone()))
+                    .expect("SampleOperator: error sending on write stream");
+            }
+        }
+        *num_timestamps = (*num_timestamps + 1) % interval;
+    }
+}
+
+impl<D> Operator for SampleOperator<D> where for<'a> D: Data + Deserialize<'a> {}
@@ -0,0 +1,160 @@
+use crate::dataflow::message::Message;
+use crate::dataflow::{
+    stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+use serde::Deserialize;
+use std::marker::PhantomData;
+
+/// An operator that routes each message of an incoming stream of type D to one of `N` outgoing
+/// streams, chosen by the index the provided function returns for the message. The operator panics
+/// if the function returns an index of `N` or greater, so messages are never dropped silently.
+///
+/// `N` is either 2 or 3, and the outgoing streams are connected using
+/// [`connect_2_write`](crate::connect_2_write) and [`connect_3_write`](crate::connect_3_write),
+/// respectively.
+///
+/// # Example
+/// The below example shows how to use a SplitOperator to split an incoming stream of u32 messages
+/// into a stream of even messages and a stream of odd messages.
+///
+/// ```
+/// # use erdos::dataflow::{stream::IngestStream, operators::SplitOperator, OperatorConfig};
+/// # use erdos::*;
+/// #
+/// # let mut u32_stream = IngestStream::new(0);
+/// #
+/// // Add the routing function as an argument to the operator via the OperatorConfig.
+/// let split_config = OperatorConfig::new()
+///     .name("SplitOperator")
+///     .arg(|data: &u32| -> usize { (data % 2) as usize });
+/// let (even_stream, odd_stream) =
+///     connect_2_write!(SplitOperator<u32, 2>, split_config, u32_stream);
+/// ```
+pub struct SplitOperator<D: Data, const N: usize> {
+    phantom_data: PhantomData<D>,
+}
+
+impl<'a, D: Data + Deserialize<'a>, const N: usize> SplitOperator<D, N> {
+    /// Adds the callback which routes the messages of the input stream to the output streams.
+    fn add_callback<F: 'static + Clone + Fn(&D) -> usize>(
+        config: OperatorConfig<F>,
+        input_stream: ReadStream<D>,
+        output_streams: Vec<WriteStream<D>>,
+    ) -> Self {
+        let stateful_stream = input_stream.add_state(output_streams);
+
+        // Clone the name so that we can move the passed function into the callback.
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("SplitOperator {}", config.id));
+        let callback = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no split function supplied", name));
+
+        stateful_stream.add_callback(
+            move |t: &Timestamp, msg: &D, output_streams: &mut Vec<WriteStream<D>>| {
+                Self::on_data_callback(t, msg, output_streams, &callback)
+            },
+        );
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// The callback function to be invoked upon receipt of a message on the input stream.
+    ///
+    /// # Arguments
+    /// * `t` - The timestamp of the message.
+    /// * `msg` - The incoming message on the input stream.
+    /// * `output_streams` - Handles to the output streams to write the output to.
+    /// * `split_function` - A reference to the function which chooses the output stream.
+    fn on_data_callback<F: 'static + Clone + Fn(&D) -> usize>(
+        t: &Timestamp,
+        msg: &D,
+        output_streams: &mut [WriteStream<D>],
+        split_function: &F,
+    ) {
+        let index = split_function(msg);
+        let num_streams = output_streams.len();
+        let output_stream = output_streams.get_mut(index).unwrap_or_else(|| {
+            panic!(
+                "Split operator received index {} for a message, but only has {} output streams",
+                index, num_streams
+            )
+        });
+        output_stream
+            .send(Message::new_message(t.clone(), msg.clone()))
+            .unwrap_or_else(|e| {
+                panic!(
+                    "Split operator unable to send message on stream {}: {:?}",
+                    output_stream.get_id(),
+                    e
+                )
+            });
+    }
+}
+
+impl<'a, D: Data + Deserialize<'a>> SplitOperator<D, 2> {
+    /// Returns a new instance of the SplitOperator with 2 outgoing streams.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the closure which returns the
+    ///   index of the outgoing stream for each message.
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    /// * `output_stream_0` - Represents the outgoing stream of messages with index 0.
+    /// * `output_stream_1` - Represents the outgoing stream of messages with index 1.
+    pub fn new<F: 'static + Clone + Fn(&D) -> usize>(
+        config: OperatorConfig<F>,
+        input_stream: ReadStream<D>,
+        output_stream_0: WriteStream<D>,
+        output_stream_1: WriteStream<D>,
+    ) -> Self {
+        Self::add_callback(config, input_stream, vec![output_stream_0, output_stream_1])
+    }
+
+    /// Returns new instances of the WriteStreams to send its outgoing messages on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    pub fn connect(_input_stream: &ReadStream<D>) -> (WriteStream<D>, WriteStream<D>) {
+        (WriteStream::new(), WriteStream::new())
+    }
+}
+
+impl<'a, D: Data + Deserialize<'a>> SplitOperator<D, 3> {
+    /// Returns a new instance of the SplitOperator with 3 outgoing streams.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the closure which returns the
+    ///   index of the outgoing stream for each message.
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    /// * `output_stream_0` - Represents the outgoing stream of messages with index 0.
+    /// * `output_stream_1` - Represents the outgoing stream of messages with index 1.
+    /// * `output_stream_2` - Represents the outgoing stream of messages with index 2.
+    pub fn new<F: 'static + Clone + Fn(&D) -> usize>(
+        config: OperatorConfig<F>,
+        input_stream: ReadStream<D>,
+        output_stream_0: WriteStream<D>,
+        output_stream_1: WriteStream<D>,
+        output_stream_2: WriteStream<D>,
+    ) -> Self {
+        Self::add_callback(
+            config,
+            input_stream,
+            vec![output_stream_0, output_stream_1, output_stream_2],
+        )
+    }
+
+    /// Returns new instances of the WriteStreams to send its outgoing messages on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    pub fn connect(
+        _input_stream: &ReadStream<D>,
+    ) -> (WriteStream<D>, WriteStream<D>, WriteStream<D>) {
+        (WriteStream::new(), WriteStream::new(), WriteStream::new())
+    }
+}
+
+impl<'a, D: Data + Deserialize<'a>, const N: usize> Operator for SplitOperator<D, N> {}
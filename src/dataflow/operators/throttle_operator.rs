@@ -0,0 +1,143 @@
+use std::{
+    marker::PhantomData,
+    time::{Duration, Instant},
+};
+
+use serde::Deserialize;
+
+use crate::dataflow::{
+    message::Message, state::TimeVersionedState, stream::WriteStreamT, Data, Operator,
+    OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+
+/// An operator that caps the frequency at which the timestamps of an incoming stream are
+/// forwarded, measured in wall-clock time.
+///
+/// The messages of a timestamp are forwarded once the watermark for the timestamp is received if
+/// at least the provided period elapsed since the last timestamp was forwarded, and are dropped
+/// otherwise. Timestamps without messages are not forwarded, and the watermarks of all timestamps
+/// flow to the outgoing stream.
+///
+/// # Example
+/// The below example shows how to use a ThrottleOperator to forward a stream of u32 messages at
+/// no more than 5 Hz.
+///
+/// ```
+/// # use std::time::Duration;
+/// # use erdos::dataflow::{stream::IngestStream, operators::ThrottleOperator, OperatorConfig};
+/// # use erdos::*;
+/// #
+/// # let mut u32_stream = IngestStream::new(0);
+/// #
+/// // Add the minimum period between forwarded timestamps as an argument to the operator via the
+/// // OperatorConfig.
+/// let throttle_config = OperatorConfig::new()
+///     .name("ThrottleOperator")
+///     .arg(Duration::from_millis(200));
+/// let throttled_stream = connect_1_write!(ThrottleOperator<u32>, throttle_config, u32_stream);
+/// ```
+pub struct ThrottleOperator<D: Data> {
+    phantom_data: PhantomData<D>,
+}
+
+impl<D> ThrottleOperator<D>
+where
+    for<'a> D: Data + Deserialize<'a>,
+{
+    /// Returns a new instance of the ThrottleOperator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the minimum period between two
+    ///   forwarded timestamps.
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    /// * `output_stream` - Represents an outgoing stream of messages of type D.
+    pub fn new(
+        config: OperatorConfig<Duration>,
+        input_stream: ReadStream<D>,
+        output_stream: WriteStream<D>,
+    ) -> Self {
+        let name = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("ThrottleOperator {}", config.id));
+        let period = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no period provided", name));
+
+        // Stores the messages for each timestamp until the watermark for the timestamp.
+        let stateful_stream = input_stream.add_state(TimeVersionedState::<(), D>::new());
+        stateful_stream.add_callback(
+            |_t: &Timestamp, msg: &D, message_state: &mut TimeVersionedState<(), D>| {
+                message_state.append(msg.clone()).unwrap();
+            },
+        );
+        // The time at which the last timestamp was forwarded.
+        let last_forwarded: Option<Instant> = None;
+        stateful_stream
+            .add_write_stream(&output_stream)
+            .borrow_mut()
+            .add_state(last_forwarded)
+            .borrow_mut()
+            .add_watermark_callback(
+                move |t: &Timestamp,
+                      last_forwarded: &mut Option<Instant>,
+                      message_state: &TimeVersionedState<(), D>,
+                      output_stream: &mut WriteStream<D>| {
+                    Self::on_watermark_callback(
+                        t,
+                        last_forwarded,
+                        message_state,
+                        output_stream,
+                        period,
+                    )
+                },
+            );
+        // Garbage collect the messages once they were forwarded or dropped.
+        stateful_stream.add_watermark_callback_with_priority(
+            |t: &Timestamp, message_state: &mut TimeVersionedState<(), D>| {
+                message_state.close_time(t).unwrap();
+            },
+            127,
+        );
+
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send its outgoing messages on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    pub fn connect(_input_stream: &ReadStream<D>) -> WriteStream<D> {
+        WriteStream::new()
+    }
+
+    /// The function to be called when a watermark is received on the input stream.
+    /// This callback forwards the messages received for the watermark's timestamp if the period
+    /// elapsed since the last timestamp was forwarded.
+    fn on_watermark_callback(
+        t: &Timestamp,
+        last_forwarded: &mut Option<Instant>,
+        message_state: &TimeVersionedState<(), D>,
+        output_stream: &mut WriteStream<D>,
+        period: Duration,
+    ) {
+        let messages = message_state.get_current_messages().unwrap();
+        if messages.is_empty() {
+            return;
+        }
+        let now = Instant::now();
+        if matches!(*last_forwarded, Some(last) if now.duration_since(last) < period) {
+            return;
+        }
+        for data in messages {
+            output_stream
+                .send(Message::new_message(t.clone(), data.clone()))
+                .expect("ThrottleOperator: error sending on write stream");
+        }
+        *last_forwarded = Some(now);
+    }
+}
+
+impl<D> Operator for ThrottleOperator<D> where for<'a> D: Data + Deserialize<'a> {}
@@ -0,0 +1,326 @@
+use std::marker::PhantomData;
+
+use serde::Deserialize;
+
+use crate::dataflow::{
+    message::Message, state::TimeVersionedState, stream::WriteStreamT, Data, Operator,
+    OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+
+/// Describes how a [`WindowOperator`] groups messages into windows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Window {
+    /// Windows of `size` messages, one of which starts every `slide` messages.
+    ///
+    /// A window is sent with the timestamp of its last message.
+    Count { size: usize, slide: usize },
+    /// Windows over `size` units of time, one of which starts every `slide` units of time. The
+    /// time of a message is its timestamp, which must have exactly one dimension; the operator
+    /// panics upon a watermark with more or fewer dimensions, because the windows would not
+    /// order the other dimensions.
+    ///
+    /// A window is sent with the last time it covers as timestamp, once a watermark for that time
+    /// is received. Windows without messages are not sent.
+    Time { size: u64, slide: u64 },
+    /// Windows of the messages received for each watermark.
+    ///
+    /// A window is sent with the timestamp of the watermark, even if it has no messages.
+    Watermark,
+}
+
+impl Window {
+    /// Returns non-overlapping windows of `size` messages.
+    pub fn count_tumbling(size: usize) -> Self {
+        Self::count_sliding(size, size)
+    }
+
+    /// Returns windows of `size` messages, one of which starts every `slide` messages.
+    pub fn count_sliding(size: usize, slide: usize) -> Self {
+        Self::Count { size, slide }
+    }
+
+    /// Returns non-overlapping windows over `size` units of time.
+    pub fn time_tumbling(size: u64) -> Self {
+        Self::time_sliding(size, size)
+    }
+
+    /// Returns windows over `size` units of time, one of which starts every `slide` units of time.
+    pub fn time_sliding(size: u64, slide: u64) -> Self {
+        Self::Time { size, slide }
+    }
+
+    /// Returns windows of the messages received for each watermark.
+    pub fn watermark() -> Self {
+        Self::Watermark
+    }
+}
+
+/// The messages which were not sent in all their windows yet.
+#[derive(Clone)]
+struct WindowState<D> {
+    /// The messages in the order of their timestamps.
+    messages: Vec<(Timestamp, D)>,
+    /// The number of upcoming messages which are in no count window.
+    skip: usize,
+    /// The index of the next time window to send.
+    next_window: u64,
+}
+
+impl<D> Default for WindowState<D> {
+    fn default() -> Self {
+        Self {
+            messages: Vec::new(),
+            skip: 0,
+            next_window: 0,
+        }
+    }
+}
+
+/// An operator that groups the messages of an incoming stream of type D into windows, and sends
+/// each window as a message of type `Vec<D>`.
+///
+/// Messages are added to windows once the watermark for their timestamp is received, and are
+/// ordered by timestamp. Thus, a watermark must be sent for each timestamp of a message. The
+/// windows are computed from [`TimeVersionedState`] so that they do not depend on the order in
+/// which messages with different timestamps are received.
+///
+/// # Example
+/// The below example shows how to use a WindowOperator to group an incoming stream of u32
+/// messages into windows of 10 messages, a new one of which starts every 5 messages.
+///
+/// ```
+/// # use erdos::dataflow::{
+/// #     stream::IngestStream,
+/// #     operators::{Window, WindowOperator},
+/// #     OperatorConfig
+/// # };
+/// # use erdos::*;
+/// #
+/// # let mut u32_stream = IngestStream::new(0);
+/// #
+/// // Add the window as an argument to the operator via the OperatorConfig.
+/// let window_config = OperatorConfig::new()
+///     .name("WindowOperator")
+///     .arg(Window::count_sliding(10, 5));
+/// let window_stream = connect_1_write!(WindowOperator<u32>, window_config, u32_stream);
+/// ```
+pub struct WindowOperator<D: Data> {
+    phantom_data: PhantomData<D>,
+}
+
+impl<D> WindowOperator<D>
+where
+    for<'a> D: Data + Deserialize<'a>,
+{
+    /// Returns a new instance of the WindowOperator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the [`Window`] to use.
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    /// * `output_stream` - Represents an outgoing stream of windows of messages of type D.
+    pub fn new(
+        config: OperatorConfig<Window>,
+        input_stream: ReadStream<D>,
+        output_stream: WriteStream<Vec<D>>,
+    ) -> Self {
+        let name = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("WindowOperator {}", config.id));
+        let window = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no window provided", name));
+        match window {
+            Window::Count { size, slide } if size == 0 || slide == 0 => {
+                panic!(
+                    "{}: count windows must have a positive size and slide",
+                    name
+                )
+            }
+            Window::Time { size, slide } if size == 0 || slide == 0 => {
+                panic!("{}: time windows must have a positive size and slide", name)
+            }
+            _ => (),
+        }
+
+        // Stores the messages for each timestamp until the watermark for the timestamp.
+        let stateful_stream = input_stream.add_state(TimeVersionedState::<(), D>::new());
+        stateful_stream.add_callback(Self::on_data_callback);
+
+        // Carries the messages which are in windows that were not sent yet to the next watermark.
+        let mut window_state = TimeVersionedState::new_with_history_size(1);
+        window_state
+            .set_initial_state(WindowState::default())
+            .unwrap();
+        stateful_stream
+            .add_write_stream(&output_stream)
+            .borrow_mut()
+            .add_state(window_state)
+            .borrow_mut()
+            .add_watermark_callback(
+                move |t: &Timestamp,
+                      window_state: &mut TimeVersionedState<WindowState<D>, ()>,
+                      message_state: &TimeVersionedState<(), D>,
+                      output_stream: &mut WriteStream<Vec<D>>| {
+                    Self::on_watermark_callback(
+                        t,
+                        window_state,
+                        message_state,
+                        output_stream,
+                        &window,
+                    )
+                },
+            );
+        // Garbage collect the messages once they were added to the windows.
+        stateful_stream.add_watermark_callback_with_priority(
+            |t: &Timestamp, message_state: &mut TimeVersionedState<(), D>| {
+                message_state.close_time(t).unwrap();
+            },
+            127,
+        );
+
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send its outgoing windows on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    pub fn connect(_input_stream: &ReadStream<D>) -> WriteStream<Vec<D>> {
+        WriteStream::new()
+    }
+
+    /// The function to be called when a message is received on the input stream.
+    /// This callback adds the data received in the message to the state associated with the
+    /// stream.
+    fn on_data_callback(_t: &Timestamp, msg: &D, message_state: &mut TimeVersionedState<(), D>) {
+        message_state.append(msg.clone()).unwrap();
+    }
+
+    /// The function to be called when a watermark is received on the input stream.
+    /// This callback adds the messages for the watermark's timestamp to the windows, and sends
+    /// the windows which are complete.
+    fn on_watermark_callback(
+        t: &Timestamp,
+        window_state: &mut TimeVersionedState<WindowState<D>, ()>,
+        message_state: &TimeVersionedState<(), D>,
+        output_stream: &mut WriteStream<Vec<D>>,
+        window: &Window,
+    ) {
+        // Continue from the state after the previous watermark.
+        let mut state = window_state
+            .iter_states()
+            .unwrap()
+            .nth(1)
+            .map(|(_, state)| state.clone())
+            .unwrap_or_default();
+        state.messages.extend(
+            message_state
+                .get_current_messages()
+                .unwrap()
+                .iter()
+                .map(|data| (t.clone(), data.clone())),
+        );
+
+        let windows = match *window {
+            Window::Count { size, slide } => Self::count_windows(&mut state, size, slide),
+            Window::Time { size, slide } => Self::time_windows(t, &mut state, size, slide),
+            Window::Watermark if t.is_top() => Vec::new(),
+            Window::Watermark => {
+                let data = state.messages.drain(..).map(|(_, data)| data).collect();
+                vec![(t.clone(), data)]
+            }
+        };
+        for (timestamp, data) in windows {
+            output_stream
+                .send(Message::new_message(timestamp, data))
+                .expect("WindowOperator: error sending on write stream");
+        }
+
+        *window_state.get_current_state_mut().unwrap() = state;
+        window_state.close_time(t).unwrap();
+    }
+
+    /// Removes the count windows which are complete from the state, and returns them with the
+    /// timestamps of their last messages.
+    fn count_windows(
+        state: &mut WindowState<D>,
+        size: usize,
+        slide: usize,
+    ) -> Vec<(Timestamp, Vec<D>)> {
+        let mut windows = Vec::new();
+        loop {
+            let skipped = state.skip.min(state.messages.len());
+            state.messages.drain(..skipped);
+            state.skip -= skipped;
+            if state.messages.len() < size {
+                break;
+            }
+            let timestamp = state.messages[size - 1].0.clone();
+            let data = state.messages[..size]
+                .iter()
+                .map(|(_, data)| data.clone())
+                .collect();
+            windows.push((timestamp, data));
+            // The next window starts `slide` messages after the start of this window.
+            state.skip = slide;
+        }
+        windows
+    }
+
+    /// Removes the time windows which end at or before the watermark `t` from the state, and
+    /// returns them with the last times they cover. All windows are complete for the top
+    /// watermark.
+    fn time_windows(
+        t: &Timestamp,
+        state: &mut WindowState<D>,
+        size: u64,
+        slide: u64,
+    ) -> Vec<(Timestamp, Vec<D>)> {
+        // The messages have the timestamps of the watermarks which added them to the state.
+        if !t.is_top() && t.time().len() != 1 {
+            panic!(
+                "WindowOperator: time windows require timestamps with one dimension, got {:?}",
+                t
+            );
+        }
+        let time = |t: &Timestamp| t.time()[0];
+        let watermark_time = if t.is_top() { u64::MAX } else { time(t) };
+        let mut windows = Vec::new();
+        while let Some((first_t, _)) = state.messages.first() {
+            // Skip the windows which end before the earliest message.
+            let first_time = time(first_t);
+            let first_window = if first_time >= size {
+                (first_time - size) / slide + 1
+            } else {
+                0
+            };
+            let window = state.next_window.max(first_window);
+            let start = window.saturating_mul(slide);
+            let end = start.saturating_add(size - 1);
+            if end > watermark_time {
+                break;
+            }
+            let data: Vec<D> = state
+                .messages
+                .iter()
+                .filter(|(t, _)| start <= time(t) && time(t) <= end)
+                .map(|(_, data)| data.clone())
+                .collect();
+            if !data.is_empty() {
+                windows.push((Timestamp::new(vec![end]), data));
+            }
+            // Drop the messages which are in no later window.
+            state.next_window = window + 1;
+            match state.next_window.checked_mul(slide) {
+                Some(next_start) => state.messages.retain(|(t, _)| time(t) >= next_start),
+                None => state.messages.clear(),
+            }
+        }
+        windows
+    }
+}
+
+impl<D> Operator for WindowOperator<D> where for<'a> D: Data + Deserialize<'a> {}
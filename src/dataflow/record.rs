@@ -0,0 +1,207 @@
+//! Structures for recording the messages sent on streams and replaying them.
+//!
+//! Recording is enabled by setting
+//! [`Configuration::record_filename`](crate::Configuration::record_filename). Each node then
+//! appends every message and watermark sent on the recorded streams which originate on the node
+//! to its log, together with the stream's ID, the message's timestamp, and the wall-clock time at
+//! which the message was sent. The log is complete once the node stops.
+//!
+//! Stream IDs are deterministic, so a driver which reconstructs the same dataflow graph obtains
+//! [`IngestStream`]s with the IDs of the recorded streams. A [`Replayer`] sends the recorded
+//! messages on these streams in the order in which they were recorded.
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufReader},
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+
+use bytes::BytesMut;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    communication::{CommunicationError, Deserializable, DeserializedMessage},
+    dataflow::{
+        stream::{errors::WriteStreamError, IngestStream, StreamId},
+        Data, Message, Timestamp,
+    },
+};
+
+/// Error raised when recording or replaying messages.
+#[derive(Debug)]
+pub enum RecordError {
+    /// Failed to access the log.
+    IoError(io::Error),
+    /// Failed to serialize/deserialize a log entry with Bincode.
+    BincodeError(bincode::Error),
+    /// Failed to encode/decode a recorded message.
+    CommunicationError(CommunicationError),
+    /// Failed to send a replayed message.
+    WriteStreamError(WriteStreamError),
+}
+
+impl From<io::Error> for RecordError {
+    fn from(e: io::Error) -> Self {
+        RecordError::IoError(e)
+    }
+}
+
+impl From<bincode::Error> for RecordError {
+    fn from(e: bincode::Error) -> Self {
+        RecordError::BincodeError(e)
+    }
+}
+
+impl From<CommunicationError> for RecordError {
+    fn from(e: CommunicationError) -> Self {
+        RecordError::CommunicationError(e)
+    }
+}
+
+impl From<WriteStreamError> for RecordError {
+    fn from(e: WriteStreamError) -> Self {
+        RecordError::WriteStreamError(e)
+    }
+}
+
+/// An entry of the log, which stores a message sent on a recorded stream.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedMessage {
+    /// The ID of the stream on which the message was sent.
+    pub stream_id: StreamId,
+    /// The timestamp of the message.
+    pub timestamp: Timestamp,
+    /// Whether the message is a watermark.
+    pub is_watermark: bool,
+    /// Wall-clock time at which the message was sent, since the Unix epoch.
+    pub send_time: Duration,
+    /// The message, encoded in the same format used to send it to other nodes.
+    pub bytes: Vec<u8>,
+}
+
+impl RecordedMessage {
+    /// Decodes the recorded message.
+    pub fn decode<D>(&self) -> Result<Message<D>, RecordError>
+    where
+        for<'a> D: Data + Deserialize<'a>,
+    {
+        let mut buf = BytesMut::from(&self.bytes[..]);
+        let msg = match Deserializable::decode(&mut buf)? {
+            DeserializedMessage::<Message<D>>::Owned(msg) => msg,
+            DeserializedMessage::<Message<D>>::Ref(msg) => msg.clone(),
+        };
+        Ok(msg)
+    }
+}
+
+/// Reads the messages recorded in a log, in the order in which they were recorded.
+///
+/// An incomplete entry at the end of the log, e.g. due to a crash, is ignored.
+pub fn read_log<P: AsRef<Path>>(path: P) -> Result<Vec<RecordedMessage>, RecordError> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut log = Vec::new();
+    loop {
+        match bincode::deserialize_from(&mut reader) {
+            Ok(recorded_msg) => log.push(recorded_msg),
+            Err(e) => match *e {
+                bincode::ErrorKind::Io(ref io_error)
+                    if io_error.kind() == io::ErrorKind::UnexpectedEof =>
+                {
+                    return Ok(log)
+                }
+                _ => return Err(RecordError::from(e)),
+            },
+        }
+    }
+}
+
+type ReplayFn<'a> = Box<dyn FnMut(&RecordedMessage) -> Result<(), RecordError> + 'a>;
+
+/// Replays recorded messages on [`IngestStream`]s.
+///
+/// # Example
+/// ```ignore
+/// // Reconstruct the recorded graph so that the streams get the recorded IDs.
+/// let mut ingest_stream = IngestStream::new(0);
+/// let s = connect_1_write!(MyOp, OperatorConfig::new(), ingest_stream);
+/// node.run_async();
+///
+/// let recorded_id = ingest_stream.get_id();
+/// Replayer::from_file("messages.log")?
+///     .add_stream(recorded_id, &mut ingest_stream)
+///     .replay()?;
+/// ```
+pub struct Replayer<'a> {
+    log: Vec<RecordedMessage>,
+    /// Sends the messages of each replayed stream.
+    replay_fns: HashMap<StreamId, ReplayFn<'a>>,
+    /// Whether to reproduce the recorded delays between messages.
+    real_time: bool,
+}
+
+impl<'a> Replayer<'a> {
+    /// Creates a replayer for the recorded messages.
+    pub fn new(log: Vec<RecordedMessage>) -> Self {
+        Self {
+            log,
+            replay_fns: HashMap::new(),
+            real_time: false,
+        }
+    }
+
+    /// Creates a replayer for the messages recorded in a log.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, RecordError> {
+        Ok(Self::new(read_log(path)?))
+    }
+
+    /// Replays the messages recorded on the stream with ID `recorded_stream_id` on
+    /// `ingest_stream`.
+    pub fn add_stream<D>(
+        mut self,
+        recorded_stream_id: StreamId,
+        ingest_stream: &'a mut IngestStream<D>,
+    ) -> Self
+    where
+        for<'b> D: Data + Deserialize<'b>,
+    {
+        self.replay_fns.insert(
+            recorded_stream_id,
+            Box::new(move |recorded_msg: &RecordedMessage| {
+                ingest_stream.send(recorded_msg.decode()?)?;
+                Ok(())
+            }),
+        );
+        self
+    }
+
+    /// Sets whether to reproduce the wall-clock delays between the replayed messages.
+    /// Defaults to `false`, in which case messages are sent as fast as possible.
+    pub fn real_time(mut self, real_time: bool) -> Self {
+        self.real_time = real_time;
+        self
+    }
+
+    /// Sends the messages of the added streams in the order in which they were recorded.
+    pub fn replay(&mut self) -> Result<(), RecordError> {
+        let start = Instant::now();
+        let mut first_send_time = None;
+        for recorded_msg in self.log.iter() {
+            if let Some(replay_fn) = self.replay_fns.get_mut(&recorded_msg.stream_id) {
+                if self.real_time {
+                    let first_send_time = *first_send_time.get_or_insert(recorded_msg.send_time);
+                    let offset = recorded_msg
+                        .send_time
+                        .checked_sub(first_send_time)
+                        .unwrap_or_default();
+                    if let Some(delay) = offset.checked_sub(start.elapsed()) {
+                        thread::sleep(delay);
+                    }
+                }
+                (replay_fn)(recorded_msg)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -7,7 +7,9 @@ use std::{
     ops::Bound::{Excluded, Unbounded},
 };
 
-use crate::dataflow::Timestamp;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::dataflow::{checkpoint::CheckpointError, Timestamp, TimestampT};
 
 /// Trait that must be implemented by stream state.
 pub trait State: 'static + Clone {}
@@ -53,6 +55,19 @@ impl<S: State> ManagedState for S {
     }
 }
 
+/// Trait which must be implemented by checkpointed states.
+///
+/// Implemented for [`TimeVersionedState`] over serializable states and messages.
+/// Callbacks for later timestamps may run before or concurrently with the checkpoint for a
+/// timestamp, so a checkpoint may only include the parts of the state for timestamps less than or
+/// equal to the checkpointed timestamp.
+pub trait CheckpointableState {
+    /// Serializes the state once all callbacks for timestamp t completed.
+    fn checkpoint(&self, t: &Timestamp) -> Result<Vec<u8>, CheckpointError>;
+    /// Restores the state from a checkpoint taken at timestamp t.
+    fn restore(&mut self, t: &Timestamp, bytes: &[u8]) -> Result<(), CheckpointError>;
+}
+
 /// Ensures that an operator behaves deterministically while allowing as much
 /// parallelism as possible.
 ///
@@ -385,6 +400,41 @@ impl<S: State + Default, T: Clone> ManagedState for TimeVersionedState<S, T> {
     }
 }
 
+/// Only checkpoints the states and messages for timestamps less than or equal to the checkpointed
+/// timestamp, as the state for other timestamps may still change.
+impl<S, T> CheckpointableState for TimeVersionedState<S, T>
+where
+    S: State + Default + Serialize + DeserializeOwned,
+    T: Clone + Serialize + DeserializeOwned,
+{
+    fn checkpoint(&self, t: &Timestamp) -> Result<Vec<u8>, CheckpointError> {
+        let message_history: BTreeMap<&Timestamp, &Vec<T>> = self
+            .message_history
+            .iter()
+            .filter(|(message_t, _)| message_t.less_equal(t))
+            .collect();
+        let state_history: BTreeMap<&Timestamp, &S> = self
+            .state_history
+            .iter()
+            .filter(|(state_t, _)| state_t.less_equal(t))
+            .collect();
+        Ok(bincode::serialize(&(
+            self.history_size,
+            message_history,
+            state_history,
+        ))?)
+    }
+
+    fn restore(&mut self, t: &Timestamp, bytes: &[u8]) -> Result<(), CheckpointError> {
+        let (history_size, message_history, state_history) = bincode::deserialize(bytes)?;
+        self.history_size = history_size;
+        self.message_history = message_history;
+        self.state_history = state_history;
+        self.current_time = t.clone();
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -549,4 +599,69 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_checkpoint_restore() {
+        let mut state: TimeVersionedState<usize, usize> = TimeVersionedState::new();
+        state.set_initial_state(0).unwrap();
+        for i in 1..4 {
+            let t = Timestamp::new(vec![i]);
+            state.set_current_time(t.clone());
+            state.access_context = AccessContext::Callback;
+            state.append(i as usize).unwrap();
+            state.access_context = AccessContext::WatermarkCallback;
+            *state.get_current_state_mut().unwrap() = i as usize;
+        }
+        let checkpoint_time = Timestamp::new(vec![2]);
+        let bytes = state.checkpoint(&checkpoint_time).unwrap();
+
+        let mut restored: TimeVersionedState<usize, usize> = TimeVersionedState::new();
+        restored.restore(&checkpoint_time, &bytes).unwrap();
+        restored.access_context = AccessContext::WatermarkCallback;
+        assert_eq!(restored.current_time, checkpoint_time);
+        assert_eq!(restored.get_current_state().unwrap(), &2);
+        assert_eq!(restored.get_current_messages().unwrap(), &vec![2]);
+        // State for timestamps later than the checkpoint is not restored.
+        assert!(!restored
+            .state_history
+            .contains_key(&Timestamp::new(vec![3])));
+    }
+
+    #[test]
+    /// Messages for t + 1 are appended before the watermark callback for t runs, and must not be
+    /// included in the checkpoint for t.
+    fn test_checkpoint_excludes_later_messages() {
+        let mut state: TimeVersionedState<usize, usize> = TimeVersionedState::new();
+        state.set_initial_state(0).unwrap();
+        state.access_context = AccessContext::Callback;
+        let checkpoint_time = Timestamp::new(vec![1, 1]);
+        // The timestamp [0, 2] precedes the checkpointed timestamp lexicographically, but is
+        // incomparable to it.
+        let timestamps = [
+            Timestamp::new(vec![1, 0]),
+            checkpoint_time.clone(),
+            Timestamp::new(vec![2, 1]),
+            Timestamp::new(vec![0, 2]),
+        ];
+        for (i, t) in timestamps.iter().enumerate() {
+            state.set_current_time(t.clone());
+            state.append(i).unwrap();
+        }
+        state.set_current_time(checkpoint_time.clone());
+        state.access_context = AccessContext::WatermarkCallback;
+        *state.get_current_state_mut().unwrap() = 1;
+        let bytes = state.checkpoint(&checkpoint_time).unwrap();
+
+        let mut restored: TimeVersionedState<usize, usize> = TimeVersionedState::new();
+        restored.restore(&checkpoint_time, &bytes).unwrap();
+        assert_eq!(
+            restored.message_history.keys().collect::<Vec<_>>(),
+            vec![&Timestamp::bottom(), &timestamps[0], &checkpoint_time]
+        );
+        assert_eq!(
+            restored.state_history.keys().collect::<Vec<_>>(),
+            vec![&Timestamp::bottom(), &timestamps[0], &checkpoint_time]
+        );
+        assert_eq!(restored.state_history.get(&checkpoint_time), Some(&1));
+    }
 }
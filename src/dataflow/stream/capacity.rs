@@ -0,0 +1,35 @@
+/// Determines what happens to a data message sent on a stream whose channel is full.
+///
+/// Watermarks are never dropped or delayed to ensure that operators make progress.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Blocks the sender until the receiver reads a message.
+    Block,
+    /// Drops the oldest data message buffered on the channel.
+    DropOldest,
+    /// Drops the message being sent.
+    DropNewest,
+    /// Returns [`WriteStreamError::NoCapacity`](super::errors::WriteStreamError::NoCapacity)
+    /// to the sender.
+    Error,
+}
+
+/// Maximum number of data messages buffered on each channel of a stream, and the policy applied
+/// when a channel is full.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StreamCapacity {
+    /// Maximum number of buffered data messages.
+    pub capacity: usize,
+    /// Policy applied to data messages sent while the channel is full.
+    pub overflow_policy: OverflowPolicy,
+}
+
+impl StreamCapacity {
+    pub fn new(capacity: usize, overflow_policy: OverflowPolicy) -> Self {
+        assert!(capacity > 0, "Stream capacity must be at least 1");
+        Self {
+            capacity,
+            overflow_policy,
+        }
+    }
+}
@@ -48,14 +48,16 @@ pub enum WriteStreamError {
     TimestampError,
     /// Stream is closed and can no longer send messages.
     Closed,
+    /// A receiver of the bounded stream is full, and the stream's overflow policy is
+    /// [`OverflowPolicy::Error`](super::OverflowPolicy::Error).
+    NoCapacity,
 }
 
 impl From<CommunicationError> for WriteStreamError {
     fn from(e: CommunicationError) -> Self {
         match e {
-            CommunicationError::NoCapacity | CommunicationError::Disconnected => {
-                WriteStreamError::IOError
-            }
+            CommunicationError::NoCapacity => WriteStreamError::NoCapacity,
+            CommunicationError::Disconnected => WriteStreamError::IOError,
             CommunicationError::SerializeNotImplemented
             | CommunicationError::DeserializeNotImplemented => {
                 eprintln!("Serialize not implemented");
@@ -7,7 +7,10 @@ use std::{
 use serde::Deserialize;
 
 use crate::{
-    dataflow::{graph::default_graph, Data, Message},
+    dataflow::{
+        graph::{default_graph, Vertex},
+        Data, Message,
+    },
     node::NodeId,
     scheduler::channel_manager::ChannelManager,
 };
@@ -187,7 +190,11 @@ where
         } else {
             // Try to setup read stream
             if let Some(channel_manager) = &*self.channel_manager_option.lock().unwrap() {
-                match channel_manager.lock().unwrap().take_recv_endpoint(self.id) {
+                match channel_manager
+                    .lock()
+                    .unwrap()
+                    .take_recv_endpoint(self.id, &Vertex::Driver(self.node_id))
+                {
                     Ok(recv_endpoint) => {
                         let read_stream = ReadStream::from(InternalReadStream::from_endpoint(
                             recv_endpoint,
@@ -7,12 +7,14 @@ use std::{
 use serde::Deserialize;
 
 use crate::{
-    dataflow::{graph::default_graph, Data, Message},
+    dataflow::{graph::default_graph, Data, Message, Timestamp},
     node::NodeId,
     scheduler::channel_manager::ChannelManager,
 };
 
-use super::{errors::WriteStreamError, StreamId, WriteStream, WriteStreamT};
+use super::{
+    errors::WriteStreamError, ProcessingTimeWatermarks, StreamId, WriteStream, WriteStreamT,
+};
 
 /// An [`IngestStream`] enables drivers to inject data into a running ERDOS application.
 ///
@@ -59,6 +61,8 @@ where
     node_id: NodeId,
     // Use a std mutex because the driver doesn't run on the tokio runtime.
     write_stream_option: Arc<Mutex<Option<WriteStream<D>>>>,
+    /// Watermarks generated while the driver sends nothing, which are set up with the stream.
+    processing_time_watermarks: Arc<Mutex<Option<ProcessingTimeWatermarks>>>,
 }
 
 impl<D> IngestStream<D>
@@ -106,8 +110,10 @@ where
             name,
             node_id,
             write_stream_option: Arc::new(Mutex::new(None)),
+            processing_time_watermarks: Arc::new(Mutex::new(None)),
         };
         let write_stream_option_copy = Arc::clone(&ingest_stream.write_stream_option);
+        let processing_time_watermarks_copy = Arc::clone(&ingest_stream.processing_time_watermarks);
 
         // Sets up self.write_stream_option using channel_manager
         let setup_hook = move |channel_manager: Arc<Mutex<ChannelManager>>| match channel_manager
@@ -116,7 +122,15 @@ where
             .get_send_endpoints(id)
         {
             Ok(send_endpoints) => {
-                let write_stream = WriteStream::from_endpoints(send_endpoints, id);
+                let mut write_stream = WriteStream::from_endpoints(send_endpoints, id);
+                if let Some(watermarks) = processing_time_watermarks_copy.lock().unwrap().clone() {
+                    let idle_timeout = watermarks.idle_timeout;
+                    write_stream = write_stream.with_processing_time_watermarks(watermarks);
+                    Self::spawn_watermark_generator(
+                        Arc::clone(&write_stream_option_copy),
+                        idle_timeout,
+                    );
+                }
                 write_stream_option_copy
                     .lock()
                     .unwrap()
@@ -125,10 +139,64 @@ where
             Err(msg) => panic!("Unable to set up IngestStream {}: {}", id, msg),
         };
 
-        default_graph::add_ingest_stream(&ingest_stream, setup_hook);
+        // Closes the stream when the node shuts down gracefully.
+        let write_stream_option_copy = Arc::clone(&ingest_stream.write_stream_option);
+        let close_hook = move || {
+            if let Some(write_stream) = write_stream_option_copy.lock().unwrap().as_mut() {
+                if write_stream.is_closed() {
+                    return;
+                }
+                if let Err(e) = write_stream.send(Message::new_watermark(Timestamp::top())) {
+                    slog::error!(
+                        crate::TERMINAL_LOGGER,
+                        "Unable to close IngestStream {}: {:?}",
+                        id,
+                        e
+                    );
+                }
+            }
+        };
+
+        default_graph::add_ingest_stream(&ingest_stream, setup_hook, close_hook);
         ingest_stream
     }
 
+    /// Sends [`ProcessingTimeWatermarks`] on the stream once the driver sent nothing for their
+    /// idle timeout. Must be called before the node runs.
+    pub fn with_processing_time_watermarks(self, watermarks: ProcessingTimeWatermarks) -> Self {
+        self.processing_time_watermarks
+            .lock()
+            .unwrap()
+            .replace(watermarks);
+        self
+    }
+
+    /// Checks whether the stream is idle every `idle_timeout` in a task on the runtime of the node,
+    /// which stops once the stream closes or the node shuts down.
+    fn spawn_watermark_generator(
+        write_stream_option: Arc<Mutex<Option<WriteStream<D>>>>,
+        idle_timeout: Duration,
+    ) {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::delay_for(idle_timeout).await;
+                if let Some(write_stream) = write_stream_option.lock().unwrap().as_mut() {
+                    if write_stream.is_closed() {
+                        break;
+                    }
+                    if let Err(e) = write_stream.send_generated_watermark() {
+                        slog::error!(
+                            crate::TERMINAL_LOGGER,
+                            "Error generating watermark on IngestStream {}: {:?}",
+                            write_stream.get_id(),
+                            e
+                        );
+                    }
+                }
+            }
+        });
+    }
+
     /// Get the ID given to the stream by the constructor
     pub fn get_id(&self) -> StreamId {
         self.id
@@ -1,16 +1,30 @@
-use std::{cell::RefCell, collections::HashSet, rc::Rc, sync::Arc};
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use crate::{
     communication::{RecvEndpoint, TryRecvError},
-    dataflow::{Data, Message, State, Timestamp},
+    dataflow::{
+        checkpoint::{CheckpointError, Checkpointer},
+        Data, Message, State, Timestamp, TimestampT,
+    },
     node::operator_event::OperatorEvent,
 };
 
 use super::{
     errors::{ReadError, TryReadError},
-    EventMakerT, InternalStatefulReadStream, StreamId,
+    lateness::LatenessTracker,
+    DeadLetter, DeadLetterReason, EventMakerT, InternalStatefulReadStream, LateDataPolicy,
+    SideOutput, StreamId,
 };
 
+/// A callback invoked on a message.
+type MessageCallback<D> = Arc<dyn Fn(&Timestamp, &D)>;
+
 // TODO: split between system read streams and user accessible read streams to avoid Rc<RefCell<...>> in operator
 pub struct InternalReadStream<D: Data> {
     /// The id of the stream.
@@ -27,6 +41,20 @@ pub struct InternalReadStream<D: Data> {
     callbacks: Vec<Arc<dyn Fn(&Timestamp, &D)>>,
     /// A vector of watermark callbacks registered on the stream.
     watermark_cbs: Vec<Arc<dyn Fn(&Timestamp)>>,
+    /// A vector of handlers invoked when the operator misses its deadline.
+    deadline_miss_handlers: Vec<Arc<dyn Fn(&Timestamp)>>,
+    /// Timestamp of the checkpoint the operator recovered from. Replayed messages with timestamps
+    /// up to and including this timestamp were already processed, and are skipped.
+    recovered_time: Option<Timestamp>,
+    /// Detects late messages if the operator sets a
+    /// [`LateDataPolicy`](super::LateDataPolicy).
+    lateness_tracker: Option<RefCell<LatenessTracker>>,
+    /// A vector of callbacks invoked on late messages with the
+    /// [`SideOutput`](super::LateDataPolicy::SideOutput) policy.
+    late_data_cbs: Vec<MessageCallback<D>>,
+    /// The side output of the operator, which receives late messages and messages whose
+    /// callbacks panic.
+    side_output: Option<SideOutput>,
 }
 
 impl<D: Data> InternalReadStream<D> {
@@ -41,6 +69,11 @@ impl<D: Data> InternalReadStream<D> {
             children: Vec::new(),
             callbacks: Vec::new(),
             watermark_cbs: Vec::new(),
+            deadline_miss_handlers: Vec::new(),
+            recovered_time: None,
+            lateness_tracker: None,
+            late_data_cbs: Vec::new(),
+            side_output: None,
         }
     }
 
@@ -53,6 +86,11 @@ impl<D: Data> InternalReadStream<D> {
             children: Vec::new(),
             callbacks: Vec::new(),
             watermark_cbs: Vec::new(),
+            deadline_miss_handlers: Vec::new(),
+            recovered_time: None,
+            lateness_tracker: None,
+            late_data_cbs: Vec::new(),
+            side_output: None,
         }
     }
 
@@ -77,6 +115,11 @@ impl<D: Data> InternalReadStream<D> {
             children: Vec::new(),
             callbacks: Vec::new(),
             watermark_cbs: Vec::new(),
+            deadline_miss_handlers: Vec::new(),
+            recovered_time: None,
+            lateness_tracker: None,
+            late_data_cbs: Vec::new(),
+            side_output: None,
         }
     }
 
@@ -91,6 +134,122 @@ impl<D: Data> InternalReadStream<D> {
         self.watermark_cbs.push(Arc::new(callback));
     }
 
+    /// Add a handler to be invoked when the operator misses the deadline for a timestamp.
+    pub fn add_deadline_miss_handler<F: 'static + Fn(&Timestamp)>(&mut self, handler: F) {
+        self.deadline_miss_handlers.push(Arc::new(handler));
+    }
+
+    /// Add a callback to be invoked when the stream receives a late message with the
+    /// [`SideOutput`](super::LateDataPolicy::SideOutput) policy.
+    pub fn add_late_data_callback<F: 'static + Fn(&Timestamp, &D)>(&mut self, callback: F) {
+        self.late_data_cbs.push(Arc::new(callback));
+    }
+
+    /// Applies `policy` to the messages received after a watermark with an equal or greater
+    /// timestamp, once `allowed_lateness` elapsed since the watermark was received.
+    pub fn set_late_data_policy(&mut self, policy: LateDataPolicy, allowed_lateness: Duration) {
+        self.lateness_tracker = Some(RefCell::new(LatenessTracker::new(policy, allowed_lateness)));
+    }
+
+    /// Sends the late messages and the messages whose callbacks panic on `side_output`.
+    pub fn set_side_output(&mut self, side_output: SideOutput) {
+        self.side_output = Some(side_output);
+    }
+
+    /// Removes the callbacks and states registered on the stream, so that a new instance of the
+    /// operator can register its own.
+    pub(crate) fn clear_callbacks(&mut self) {
+        self.children.clear();
+        self.callbacks.clear();
+        self.watermark_cbs.clear();
+        self.deadline_miss_handlers.clear();
+        self.late_data_cbs.clear();
+    }
+
+    /// Makes the callbacks of the events of a message send the message on the side output if
+    /// they panic.
+    fn catch_panics(&self, events: &mut [OperatorEvent], msg: &Arc<Message<D>>) {
+        if let (Some(side_output), Message::TimestampedData(_)) = (&self.side_output, msg.as_ref())
+        {
+            for event in events.iter_mut() {
+                side_output.catch_panics(event, self.id, Arc::clone(msg));
+            }
+        }
+    }
+
+    /// Returns the events which handle a late message according to the late data policy.
+    fn make_late_data_events(
+        &self,
+        policy: LateDataPolicy,
+        msg: Arc<Message<D>>,
+    ) -> Vec<OperatorEvent> {
+        let make_event = |callback: Box<dyn FnOnce()>| {
+            OperatorEvent::new(
+                msg.timestamp().clone(),
+                false,
+                0,
+                HashSet::with_capacity(0),
+                HashSet::with_capacity(0),
+                callback,
+            )
+        };
+        match policy {
+            LateDataPolicy::Drop => {
+                slog::debug!(
+                    crate::TERMINAL_LOGGER,
+                    "Dropping late message with timestamp {:?} on ReadStream {} (ID: {})",
+                    msg.timestamp(),
+                    self.name,
+                    self.id
+                );
+                Vec::new()
+            }
+            LateDataPolicy::SideOutput => {
+                self.send_late_message(&msg);
+                self.late_data_cbs
+                    .iter()
+                    .map(|callback| {
+                        let callback = Arc::clone(callback);
+                        let msg = Arc::clone(&msg);
+                        make_event(Box::new(move || {
+                            (callback)(msg.timestamp(), msg.data().unwrap())
+                        }))
+                    })
+                    .collect()
+            }
+            LateDataPolicy::Error => {
+                if !self.send_late_message(&msg) {
+                    slog::error!(
+                        crate::TERMINAL_LOGGER,
+                        "Received late message with timestamp {:?} on ReadStream {} (ID: {})",
+                        msg.timestamp(),
+                        self.name,
+                        self.id
+                    );
+                }
+                Vec::new()
+            }
+        }
+    }
+
+    /// Sends a late message on the side output, and returns whether the operator has one.
+    fn send_late_message(&self, msg: &Message<D>) -> bool {
+        match &self.side_output {
+            Some(side_output) => {
+                side_output.send(
+                    msg.timestamp().clone(),
+                    DeadLetter {
+                        reason: DeadLetterReason::Late,
+                        stream_id: self.id,
+                        data: format!("{:?}", msg.data().unwrap()),
+                    },
+                );
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Returns a new instance of the stream with state associated to it.
     pub fn add_state<S: State>(
         &mut self,
@@ -184,6 +343,26 @@ impl<D: Data> EventMakerT for InternalReadStream<D> {
 
     fn make_events(&self, msg: Arc<Message<Self::EventDataType>>) -> Vec<OperatorEvent> {
         let mut events: Vec<OperatorEvent> = Vec::new();
+        if let Some(recovered_time) = &self.recovered_time {
+            if msg.timestamp().less_equal(recovered_time) {
+                return events;
+            }
+        }
+        if let Some(lateness_tracker) = &self.lateness_tracker {
+            let mut lateness_tracker = lateness_tracker.borrow_mut();
+            let now = Instant::now();
+            match msg.as_ref() {
+                Message::TimestampedData(data) => {
+                    if lateness_tracker.is_late(&data.timestamp, now) {
+                        let mut events =
+                            self.make_late_data_events(lateness_tracker.policy, Arc::clone(&msg));
+                        self.catch_panics(&mut events, &msg);
+                        return events;
+                    }
+                }
+                Message::Watermark(timestamp) => lateness_tracker.on_watermark(timestamp, now),
+            }
+        }
         match msg.as_ref() {
             Message::TimestampedData(_) => {
                 // Stateless callbacks may run in parallel, so create 1 event for each
@@ -222,6 +401,37 @@ impl<D: Data> EventMakerT for InternalReadStream<D> {
         for child in self.children.iter() {
             events.append(&mut child.borrow_mut().make_events(msg.clone()));
         }
+        self.catch_panics(&mut events, &msg);
         events
     }
+
+    fn make_deadline_events(&self, t: &Timestamp) -> Vec<OperatorEvent> {
+        let mut events: Vec<OperatorEvent> = Vec::new();
+        for handler in self.deadline_miss_handlers.iter() {
+            let handler = Arc::clone(handler);
+            let timestamp_copy = t.clone();
+            events.push(OperatorEvent::new(
+                t.clone(),
+                false,
+                0,
+                HashSet::with_capacity(0),
+                HashSet::with_capacity(0),
+                move || (handler)(&timestamp_copy),
+            ));
+        }
+        for child in self.children.iter() {
+            events.append(&mut child.borrow().make_deadline_events(t));
+        }
+        events
+    }
+
+    fn setup_checkpoints(&mut self, checkpointer: &Checkpointer) -> Result<(), CheckpointError> {
+        self.recovered_time = checkpointer.recovery_time().cloned();
+        for (i, child) in self.children.iter().enumerate() {
+            child
+                .borrow_mut()
+                .setup_checkpoints(&checkpointer.nested(&i.to_string()))?;
+        }
+        Ok(())
+    }
 }
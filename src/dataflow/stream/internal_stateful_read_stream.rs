@@ -3,7 +3,8 @@ use std::{cell::RefCell, collections::HashSet, rc::Rc, sync::Arc};
 use crate::{
     dataflow::{
         callback_builder::MultiStreamEventMaker,
-        state::{AccessContext, ManagedState},
+        checkpoint::{CheckpointError, Checkpointer},
+        state::{AccessContext, CheckpointableState, ManagedState},
         Data, Message, State, Timestamp,
     },
     node::operator_event::OperatorEvent,
@@ -12,6 +13,11 @@ use crate::{
 
 use super::{EventMakerT, InternalReadStream, StreamId};
 
+/// Serializes a state for a timestamp.
+type CheckpointFn<S> = fn(&S, &Timestamp) -> Result<Vec<u8>, CheckpointError>;
+/// Restores a state from a checkpoint for a timestamp.
+type RestoreFn<S> = fn(&mut S, &Timestamp, &[u8]) -> Result<(), CheckpointError>;
+
 /// Stream that has associated some state with it.
 pub struct InternalStatefulReadStream<D: Data, S: State> {
     /// StreamId of the stream.
@@ -25,8 +31,15 @@ pub struct InternalStatefulReadStream<D: Data, S: State> {
     callbacks: Vec<Arc<dyn Fn(&Timestamp, &D, &mut S)>>,
     /// Watermark callbacks registered on the stream.
     watermark_cbs: Vec<(Arc<dyn Fn(&Timestamp, &mut S)>, i8)>,
+    /// Handlers invoked when the operator misses its deadline.
+    deadline_miss_handlers: Vec<Arc<dyn Fn(&Timestamp, &mut S)>>,
     /// Vector of stream bundles that must be invoked when this stream receives a message.
     children: RefCell<Vec<Rc<RefCell<dyn MultiStreamEventMaker>>>>,
+    /// Serializes and restores the state if it is checkpointed.
+    checkpoint_fns: Option<(CheckpointFn<S>, RestoreFn<S>)>,
+    /// Writes checkpoints of the state. Set once the operator is created on a node which
+    /// checkpoints state.
+    checkpointer: Option<Checkpointer>,
 }
 
 impl<D: Data, S: State> InternalStatefulReadStream<D, S> {
@@ -37,7 +50,10 @@ impl<D: Data, S: State> InternalStatefulReadStream<D, S> {
             state_id: Uuid::new_deterministic(),
             callbacks: Vec::new(),
             watermark_cbs: Vec::new(),
+            deadline_miss_handlers: Vec::new(),
             children: RefCell::new(Vec::new()),
+            checkpoint_fns: None,
+            checkpointer: None,
         }
     }
 
@@ -68,6 +84,12 @@ impl<D: Data, S: State> InternalStatefulReadStream<D, S> {
         self.watermark_cbs.push((Arc::new(callback), priority));
     }
 
+    /// Add a handler to be invoked when the operator misses the deadline for a timestamp.
+    /// The handler receives the stream's state as an argument.
+    pub fn add_deadline_miss_handler<F: 'static + Fn(&Timestamp, &mut S)>(&mut self, handler: F) {
+        self.deadline_miss_handlers.push(Arc::new(handler));
+    }
+
     /// Gets a reference to the stream state.
     pub fn get_state(&self) -> Arc<S> {
         Arc::clone(&self.state)
@@ -77,6 +99,14 @@ impl<D: Data, S: State> InternalStatefulReadStream<D, S> {
         self.state_id
     }
 
+    /// Checkpoints the state after the watermark callbacks for a timestamp complete.
+    pub fn enable_checkpointing(&mut self)
+    where
+        S: CheckpointableState,
+    {
+        self.checkpoint_fns = Some((S::checkpoint, S::restore));
+    }
+
     pub fn add_child<T: 'static + MultiStreamEventMaker>(&self, child: Rc<RefCell<T>>) {
         self.children.borrow_mut().push(child);
     }
@@ -139,6 +169,36 @@ impl<D: Data, S: State> EventMakerT for InternalStatefulReadStream<D, S> {
                         },
                     ));
                 }
+                // Checkpoint the state after the watermark callbacks which write to it ran.
+                if let (Some(checkpointer), Some((checkpoint_fn, _))) =
+                    (&self.checkpointer, &self.checkpoint_fns)
+                {
+                    if !timestamp.is_top() {
+                        let checkpoint_fn = *checkpoint_fn;
+                        let checkpointer = checkpointer.clone();
+                        let timestamp_copy = timestamp.clone();
+                        let state_arc = Arc::clone(&self.state);
+                        events.push(OperatorEvent::new(
+                            timestamp.clone(),
+                            true,
+                            i8::MAX,
+                            write_ids.clone(),
+                            HashSet::with_capacity(0),
+                            move || {
+                                let result = (checkpoint_fn)(&state_arc, &timestamp_copy)
+                                    .and_then(|bytes| checkpointer.write(&timestamp_copy, &bytes));
+                                if let Err(e) = result {
+                                    slog::error!(
+                                        crate::TERMINAL_LOGGER,
+                                        "Failed to checkpoint state for {:?}: {:?}",
+                                        timestamp_copy,
+                                        e
+                                    );
+                                }
+                            },
+                        ));
+                    }
+                }
                 // Notify children of watermark and get events
                 for child in self.children.borrow().iter() {
                     let mut child = child.borrow_mut();
@@ -148,4 +208,44 @@ impl<D: Data, S: State> EventMakerT for InternalStatefulReadStream<D, S> {
         }
         events
     }
+
+    fn make_deadline_events(&self, t: &Timestamp) -> Vec<OperatorEvent> {
+        let mut write_ids = HashSet::with_capacity(1);
+        write_ids.insert(self.state_id);
+        self.deadline_miss_handlers
+            .iter()
+            .map(|handler| {
+                let handler = Arc::clone(handler);
+                let timestamp_copy = t.clone();
+                let mut state_arc = Arc::clone(&self.state);
+                OperatorEvent::new(
+                    t.clone(),
+                    false,
+                    0,
+                    HashSet::with_capacity(0),
+                    write_ids.clone(),
+                    move || {
+                        let state_ref_mut = unsafe { Arc::get_mut_unchecked(&mut state_arc) };
+                        state_ref_mut.set_access_context(AccessContext::Callback);
+                        state_ref_mut.set_current_time(timestamp_copy.clone());
+                        (handler)(&timestamp_copy, state_ref_mut)
+                    },
+                )
+            })
+            .collect()
+    }
+
+    fn setup_checkpoints(&mut self, checkpointer: &Checkpointer) -> Result<(), CheckpointError> {
+        let restore_fn = match &self.checkpoint_fns {
+            Some((_, restore_fn)) => *restore_fn,
+            None => return Ok(()),
+        };
+        if let Some((t, bytes)) = checkpointer.read_recovery_checkpoint()? {
+            let state_ref_mut = unsafe { Arc::get_mut_unchecked(&mut self.state) };
+            (restore_fn)(state_ref_mut, &t, &bytes)?;
+        }
+        checkpointer.register();
+        self.checkpointer = Some(checkpointer.clone());
+        Ok(())
+    }
 }
@@ -0,0 +1,102 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::dataflow::{Timestamp, TimestampT};
+
+/// Determines what happens to a message received after a watermark with an equal or greater
+/// timestamp, once the allowed lateness of the watermark elapsed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LateDataPolicy {
+    /// Drops the message.
+    Drop,
+    /// Invokes the callbacks registered with
+    /// [`ReadStream::add_late_data_callback`](super::ReadStream::add_late_data_callback)
+    /// instead of the callbacks of the message, and sends the message on the
+    /// [side output](super::WriteStream::new_side_output) of the operator if it has one.
+    SideOutput,
+    /// Reports the message as an error without invoking any callbacks: sends it on the
+    /// [side output](super::WriteStream::new_side_output) of the operator if it has one, and logs
+    /// an error otherwise.
+    Error,
+}
+
+/// Detects the late messages received on a stream.
+pub(crate) struct LatenessTracker {
+    pub policy: LateDataPolicy,
+    /// How long messages are accepted after a watermark covering them was received.
+    allowed_lateness: Duration,
+    /// The watermarks whose allowed lateness has not yet elapsed, and when they were received.
+    pending_watermarks: VecDeque<(Timestamp, Instant)>,
+    /// The last watermark whose allowed lateness elapsed.
+    expired_watermark: Option<Timestamp>,
+}
+
+impl LatenessTracker {
+    pub fn new(policy: LateDataPolicy, allowed_lateness: Duration) -> Self {
+        Self {
+            policy,
+            allowed_lateness,
+            pending_watermarks: VecDeque::new(),
+            expired_watermark: None,
+        }
+    }
+
+    pub fn on_watermark(&mut self, t: &Timestamp, now: Instant) {
+        self.expire_watermarks(now);
+        self.pending_watermarks.push_back((t.clone(), now));
+    }
+
+    /// Whether a message with timestamp `t` received at `now` is late.
+    pub fn is_late(&mut self, t: &Timestamp, now: Instant) -> bool {
+        self.expire_watermarks(now);
+        match &self.expired_watermark {
+            Some(watermark) => t.less_equal(watermark),
+            None => false,
+        }
+    }
+
+    /// Removes the watermarks whose allowed lateness elapsed at `now`.
+    fn expire_watermarks(&mut self, now: Instant) {
+        while let Some((_, received)) = self.pending_watermarks.front() {
+            if now.duration_since(*received) < self.allowed_lateness {
+                break;
+            }
+            self.expired_watermark = self.pending_watermarks.pop_front().map(|(t, _)| t);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allowed_lateness() {
+        let mut tracker = LatenessTracker::new(LateDataPolicy::Drop, Duration::from_millis(10));
+        let start = Instant::now();
+        tracker.on_watermark(&Timestamp::new(vec![1]), start);
+        tracker.on_watermark(&Timestamp::new(vec![2]), start + Duration::from_millis(5));
+        assert!(!tracker.is_late(&Timestamp::new(vec![1]), start));
+        // The allowed lateness of the first watermark elapsed.
+        let now = start + Duration::from_millis(12);
+        assert!(tracker.is_late(&Timestamp::new(vec![1]), now));
+        assert!(!tracker.is_late(&Timestamp::new(vec![2]), now));
+        let now = start + Duration::from_millis(15);
+        assert!(tracker.is_late(&Timestamp::new(vec![2]), now));
+        assert!(!tracker.is_late(&Timestamp::new(vec![3]), now));
+    }
+
+    #[test]
+    fn test_expire_watermarks_without_messages() {
+        let mut tracker = LatenessTracker::new(LateDataPolicy::Drop, Duration::from_millis(10));
+        let start = Instant::now();
+        for i in 0..100 {
+            tracker.on_watermark(&Timestamp::new(vec![i]), start + Duration::from_millis(i));
+        }
+        // Only the watermarks received in the last 10 ms are pending.
+        assert_eq!(tracker.pending_watermarks.len(), 10);
+        assert_eq!(tracker.expired_watermark, Some(Timestamp::new(vec![89])));
+    }
+}
@@ -2,7 +2,12 @@ use std::marker::PhantomData;
 
 use serde::Deserialize;
 
-use crate::dataflow::{graph::default_graph, Data};
+use crate::dataflow::{
+    graph::default_graph,
+    message::MAX_DIMENSIONS,
+    operators::iteration_operators::{EnterLoopOperator, FeedbackOperator, LeaveLoopOperator},
+    Data, OperatorConfig,
+};
 
 use super::{ReadStream, StreamId};
 
@@ -15,6 +20,23 @@ use super::{ReadStream, StreamId};
 /// // Makes sending on output_stream equivalent to sending on loop_stream.
 /// loop_stream.set(&output_stream);
 /// ```
+///
+/// # Iteration scopes
+/// Loops which use [`enter`](LoopStream::enter), [`set_feedback`](LoopStream::set_feedback), and
+/// [`leave`](LoopStream::leave) tell iterations apart by adding a coordinate to the timestamps of
+/// the messages in the loop, which increases with the iterations. The loop iterates over the
+/// messages received up to each watermark of its input stream in a round, and sends the watermark
+/// on the stream leaving the loop once an iteration of the round sends no messages back. The
+/// operators in the loop must flow watermarks, and send messages with the timestamps they receive.
+///
+/// ```ignore
+/// let loop_stream = LoopStream::new();
+/// let loop_input_stream = loop_stream.enter(&input_stream)?;
+/// let (feedback_stream, loop_output_stream) =
+///     erdos::connect_2_write!(MyOperator, OperatorConfig::new(), loop_input_stream);
+/// loop_stream.set_feedback(&feedback_stream);
+/// let output_stream = loop_stream.leave(&loop_output_stream);
+/// ```
 pub struct LoopStream<D: Data>
 where
     for<'a> D: Data + Deserialize<'a>,
@@ -59,3 +81,76 @@ where
         default_graph::add_stream_alias(self.id, stream.get_id()).unwrap();
     }
 }
+
+impl<D> LoopStream<D>
+where
+    for<'a> D: Data + Deserialize<'a>,
+{
+    /// Returns the stream which enters the iteration scope of the loop.
+    ///
+    /// The stream merges the messages of `input_stream` with the messages sent back using
+    /// [`set_feedback`](LoopStream::set_feedback), and adds an iteration coordinate to the
+    /// timestamps of the messages of `input_stream`.
+    ///
+    /// Fails if the timestamps in the loop would have more than [`MAX_DIMENSIONS`] dimensions,
+    /// assuming that the timestamps outside of all loops have one dimension.
+    pub fn enter(&self, input_stream: &ReadStream<D>) -> Result<ReadStream<D>, String> {
+        let depth = default_graph::get_iteration_depth(input_stream.get_id()) + 1;
+        if depth + 1 > MAX_DIMENSIONS {
+            return Err(format!(
+                "Loop {} is nested in {} iteration scopes, but timestamps have at most {} \
+                 dimensions",
+                self.name,
+                depth - 1,
+                MAX_DIMENSIONS
+            ));
+        }
+        let input_stream = input_stream.clone();
+        let feedback_stream: ReadStream<D> = self.into();
+        let loop_input_stream = crate::connect_1_write!(
+            EnterLoopOperator<D>,
+            self.make_config("EnterLoopOperator"),
+            input_stream,
+            feedback_stream
+        );
+        default_graph::set_iteration_depth(loop_input_stream.get_id(), depth);
+        Ok(loop_input_stream)
+    }
+
+    /// Sends the messages of `stream` back to the start of the loop, and increments their
+    /// iteration coordinate.
+    pub fn set_feedback(&self, stream: &ReadStream<D>) {
+        let stream = stream.clone();
+        let feedback_stream = crate::connect_1_write!(
+            FeedbackOperator<D>,
+            self.make_config("FeedbackOperator"),
+            stream
+        );
+        self.set(&feedback_stream);
+    }
+
+    /// Returns the stream which leaves the iteration scope of the loop.
+    ///
+    /// The stream removes the iteration coordinate from the timestamps of the messages of
+    /// `stream`, and receives the watermarks of the input stream of the loop once the loop
+    /// converges.
+    pub fn leave(&self, stream: &ReadStream<D>) -> ReadStream<D> {
+        let depth = default_graph::get_iteration_depth(stream.get_id());
+        let stream = stream.clone();
+        let output_stream = crate::connect_1_write!(
+            LeaveLoopOperator<D>,
+            self.make_config("LeaveLoopOperator"),
+            stream
+        );
+        default_graph::set_iteration_depth(output_stream.get_id(), depth.saturating_sub(1));
+        output_stream
+    }
+
+    /// Returns the configuration of an operator of the iteration scope, which handles watermarks
+    /// itself.
+    fn make_config(&self, operator_name: &str) -> OperatorConfig<()> {
+        OperatorConfig::new()
+            .name(&format!("{} {}", operator_name, self.name))
+            .flow_watermarks(false)
+    }
+}
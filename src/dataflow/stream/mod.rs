@@ -18,18 +18,25 @@
 use std::sync::Arc;
 
 use crate::{
-    dataflow::{Data, Message},
+    dataflow::{
+        checkpoint::{CheckpointError, Checkpointer},
+        Data, Message, Timestamp,
+    },
     node::operator_event::OperatorEvent,
 };
 
 // Private submodules
+mod capacity;
 mod extract_stream;
 mod ingest_stream;
 mod internal_read_stream;
 mod internal_stateful_read_stream;
+mod lateness;
 mod loop_stream;
 mod read_stream;
+mod side_output;
 mod stateful_read_stream;
+mod watermark_generator;
 mod write_stream;
 
 // Public submodules
@@ -39,17 +46,25 @@ pub mod errors;
 use errors::WriteStreamError;
 
 // Public exports
+pub use capacity::{OverflowPolicy, StreamCapacity};
 pub use extract_stream::ExtractStream;
 pub use ingest_stream::IngestStream;
 #[doc(hidden)]
 pub use internal_read_stream::InternalReadStream;
 #[doc(hidden)]
 pub use internal_stateful_read_stream::InternalStatefulReadStream;
+pub use lateness::LateDataPolicy;
 pub use loop_stream::LoopStream;
 pub use read_stream::ReadStream;
+#[doc(hidden)]
+pub use side_output::SideOutput;
+pub use side_output::{DeadLetter, DeadLetterReason};
 pub use stateful_read_stream::StatefulReadStream;
+pub use watermark_generator::ProcessingTimeWatermarks;
 pub use write_stream::WriteStream;
 
+pub(crate) use side_output::panic_message;
+
 pub type StreamId = crate::Uuid;
 
 pub(crate) trait EventMakerT {
@@ -60,6 +75,17 @@ pub(crate) trait EventMakerT {
 
     /// Returns the vector of events that a message receipt generates.
     fn make_events(&self, msg: Arc<Message<Self::EventDataType>>) -> Vec<OperatorEvent>;
+
+    /// Returns the vector of events that a deadline miss for a timestamp generates.
+    fn make_deadline_events(&self, _t: &Timestamp) -> Vec<OperatorEvent> {
+        Vec::new()
+    }
+
+    /// Sets up checkpointing of the stream's states, and restores the states if the operator
+    /// recovers from a checkpoint.
+    fn setup_checkpoints(&mut self, _checkpointer: &Checkpointer) -> Result<(), CheckpointError> {
+        Ok(())
+    }
 }
 
 /// Write stream trait which allows specialized implementations of
@@ -162,7 +188,7 @@ mod tests {
         let w1 = rt.block_on(rx.recv()).unwrap();
         match &*w1 {
             Message::Watermark(t) => {
-                assert_eq!(t.time[0], 1);
+                assert_eq!(t.time()[0], 1);
             }
             _ => {
                 panic!("Unexpected first watermark");
@@ -171,7 +197,7 @@ mod tests {
         let w2 = rt.block_on(rx.recv()).unwrap();
         match &*w2 {
             Message::Watermark(t) => {
-                assert_eq!(t.time[0], 2);
+                assert_eq!(t.time()[0], 2);
             }
             _ => {
                 panic!("Unexpected second watermark");
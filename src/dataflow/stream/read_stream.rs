@@ -148,6 +148,46 @@ impl<D: Data> ReadStream<D> {
             .add_watermark_callback(callback);
     }
 
+    /// Request a callback when the operator misses its
+    /// [deadline](crate::dataflow::OperatorConfig::deadline) for a timestamp.
+    ///
+    /// The handler runs before watermark callbacks for the timestamp, and can be used to send
+    /// a degraded or fallback result.
+    ///
+    /// # Arguments
+    /// * handler - The handler to be invoked when the deadline is missed.
+    pub fn add_deadline_miss_handler<F: 'static + Fn(&Timestamp)>(&self, handler: F) {
+        slog::debug!(
+            crate::TERMINAL_LOGGER,
+            "Registering a deadline miss handler on the ReadStream {} (ID: {})",
+            self.get_name(),
+            self.get_id()
+        );
+        self.internal_stream
+            .borrow_mut()
+            .add_deadline_miss_handler(handler);
+    }
+
+    /// Request a callback on the receipt of a late message, if the operator sets the
+    /// [`SideOutput`](super::LateDataPolicy::SideOutput)
+    /// [late data policy](crate::dataflow::OperatorConfig::late_data_policy).
+    ///
+    /// The message callbacks registered on the stream are not invoked for late messages.
+    ///
+    /// # Arguments
+    /// * callback - The callback to be invoked when a late message is received.
+    pub fn add_late_data_callback<F: 'static + Fn(&Timestamp, &D)>(&self, callback: F) {
+        slog::debug!(
+            crate::TERMINAL_LOGGER,
+            "Registering a late data callback on the ReadStream {} (ID: {})",
+            self.get_name(),
+            self.get_id()
+        );
+        self.internal_stream
+            .borrow_mut()
+            .add_late_data_callback(callback);
+    }
+
     /// Attaches state to the [`ReadStream`] and returns a [`StatefulReadStream`].
     ///
     /// In order to access the registered state in the callbacks, register callbacks on the
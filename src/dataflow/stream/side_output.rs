@@ -0,0 +1,135 @@
+use std::{
+    any::Any,
+    mem,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    communication::{CommunicationError, DecodeErrorHandler},
+    dataflow::{Data, Message, Timestamp},
+    node::operator_event::OperatorEvent,
+};
+
+use super::{StreamId, WriteStream, WriteStreamT};
+
+/// Why a message was sent to the side output of an operator.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeadLetterReason {
+    /// The message arrived after a watermark which covers it, and the operator uses the
+    /// [`SideOutput`](super::LateDataPolicy::SideOutput) late data policy.
+    Late,
+    /// The message could not be deserialized, with the error.
+    Deserialization(String),
+    /// A callback invoked on the message panicked, with the panic message.
+    Panic(String),
+}
+
+/// A message which an operator could not process, sent on its side output.
+///
+/// The side output is a [`WriteStream`] created with
+/// [`WriteStream::new_side_output`] and returned by the `connect` function of the operator.
+/// The timestamp of a dead letter is the timestamp of the message, or the bottom timestamp if the
+/// message could not be deserialized.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeadLetter {
+    /// Why the message could not be processed.
+    pub reason: DeadLetterReason,
+    /// The ID of the stream on which the operator received the message.
+    pub stream_id: StreamId,
+    /// The debug representation of the data of the message, or of the bytes which could not be
+    /// deserialized.
+    pub data: String,
+}
+
+/// Returns the message of a panic.
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "Box<dyn Any>".to_string()
+    }
+}
+
+/// Sends the messages which an operator could not process on its side output.
+///
+/// Note: the side output is set up by the `connect_x_write` macros.
+#[doc(hidden)]
+#[derive(Clone)]
+pub struct SideOutput {
+    write_stream: Arc<Mutex<WriteStream<DeadLetter>>>,
+}
+
+impl SideOutput {
+    pub(crate) fn new(write_stream: WriteStream<DeadLetter>) -> Self {
+        Self {
+            write_stream: Arc::new(Mutex::new(write_stream)),
+        }
+    }
+
+    pub(crate) fn send(&self, t: Timestamp, dead_letter: DeadLetter) {
+        let mut write_stream = self.write_stream.lock().unwrap();
+        slog::debug!(
+            crate::TERMINAL_LOGGER,
+            "Sending {:?} with timestamp {:?} on side output {} (ID: {})",
+            dead_letter,
+            t,
+            write_stream.get_name(),
+            write_stream.get_id()
+        );
+        if let Err(e) = write_stream.send(Message::new_message(t, dead_letter)) {
+            slog::error!(
+                crate::TERMINAL_LOGGER,
+                "Error sending on side output {} (ID: {}): {:?}",
+                write_stream.get_name(),
+                write_stream.get_id(),
+                e
+            );
+        }
+    }
+
+    /// Sends the message on the side output if the callback of `event` panics, and then resumes
+    /// the panic.
+    pub(crate) fn catch_panics<D: Data>(
+        &self,
+        event: &mut OperatorEvent,
+        stream_id: StreamId,
+        msg: Arc<Message<D>>,
+    ) {
+        let callback = mem::replace(&mut event.callback, Box::new(|| ()));
+        let side_output = self.clone();
+        event.callback = Box::new(move || {
+            if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(callback)) {
+                side_output.send(
+                    msg.timestamp().clone(),
+                    DeadLetter {
+                        reason: DeadLetterReason::Panic(panic_message(payload.as_ref())),
+                        stream_id,
+                        data: format!("{:?}", msg.data().unwrap()),
+                    },
+                );
+                panic::resume_unwind(payload);
+            }
+        });
+    }
+
+    /// Returns a handler which sends the messages received from other nodes on the stream
+    /// `stream_id` which fail to deserialize on the side output.
+    pub(crate) fn make_decode_error_handler(&self, stream_id: StreamId) -> DecodeErrorHandler {
+        let side_output = self.clone();
+        Box::new(move |bytes: &[u8], error: &CommunicationError| {
+            side_output.send(
+                Timestamp::bottom(),
+                DeadLetter {
+                    reason: DeadLetterReason::Deserialization(format!("{:?}", error)),
+                    stream_id,
+                    data: format!("{:?}", bytes),
+                },
+            )
+        })
+    }
+}
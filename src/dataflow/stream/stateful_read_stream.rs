@@ -3,6 +3,7 @@ use std::{cell::RefCell, rc::Rc, sync::Arc};
 use crate::{
     dataflow::{
         callback_builder::{OneReadOneWrite, TwoReadZeroWrite},
+        state::CheckpointableState,
         Data, State, Timestamp,
     },
     Uuid,
@@ -50,6 +51,31 @@ impl<D: Data, T: State> StatefulReadStream<D, T> {
             .add_watermark_callback_with_priority(callback, priority);
     }
 
+    /// Add a handler to be invoked when the operator misses its
+    /// [deadline](crate::dataflow::OperatorConfig::deadline) for a timestamp.
+    /// The handler receives the stream's state as an argument.
+    pub fn add_deadline_miss_handler<F: 'static + Fn(&Timestamp, &mut T)>(&self, handler: F) {
+        self.internal_stream
+            .borrow_mut()
+            .add_deadline_miss_handler(handler);
+    }
+
+    /// Checkpoints the state once the watermark callbacks for a timestamp complete, and restores
+    /// it when the node recovers from a checkpoint.
+    /// Checkpoints are written to the store set in the node's
+    /// [`Configuration`](crate::Configuration).
+    ///
+    /// Only [`TimeVersionedState`](crate::dataflow::state::TimeVersionedState) can be
+    /// checkpointed, because callbacks for later timestamps may modify the state before the
+    /// checkpoint is taken. It only checkpoints the states and messages for timestamps less than or
+    /// equal to the checkpointed timestamp.
+    pub fn enable_checkpointing(&self)
+    where
+        T: CheckpointableState,
+    {
+        self.internal_stream.borrow_mut().enable_checkpointing();
+    }
+
     /// Gets a reference to the stream state.
     pub fn get_state(&self) -> Arc<T> {
         self.internal_stream.borrow_mut().get_state()
@@ -0,0 +1,165 @@
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::dataflow::{Timestamp, TimestampT};
+
+/// Generates watermarks based on processing time for a stream whose sender stops sending
+/// messages, so that downstream operators keep making progress.
+///
+/// Once nothing was sent on the stream for `idle_timeout`, the stream sends a watermark with the
+/// timestamp returned by the clock, provided that it follows the last watermark sent.
+/// The generated watermarks are speculative: the sender may still send messages with earlier
+/// timestamps, which receivers handle according to the
+/// [`late_data_policy`](crate::dataflow::OperatorConfig::late_data_policy) of their operator.
+/// Watermarks sent by the sender which do not advance past a generated watermark are not
+/// forwarded.
+#[derive(Clone)]
+pub struct ProcessingTimeWatermarks {
+    /// The time without messages after which a watermark is generated.
+    pub idle_timeout: Duration,
+    /// Returns the timestamp of the generated watermarks.
+    clock: Arc<dyn Fn() -> Timestamp + Send + Sync>,
+}
+
+impl ProcessingTimeWatermarks {
+    /// Generates watermarks once nothing was sent for `idle_timeout`, with the timestamps returned
+    /// by `clock`. The clock maps processing time to the timestamps of the stream, e.g. to the
+    /// milliseconds elapsed since the Unix epoch for streams whose timestamps are event times in
+    /// milliseconds.
+    pub fn new<F: 'static + Fn() -> Timestamp + Send + Sync>(
+        idle_timeout: Duration,
+        clock: F,
+    ) -> Self {
+        assert!(
+            idle_timeout > Duration::from_secs(0),
+            "Idle timeout must be positive"
+        );
+        Self {
+            idle_timeout,
+            clock: Arc::new(clock),
+        }
+    }
+}
+
+impl fmt::Debug for ProcessingTimeWatermarks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ProcessingTimeWatermarks {{ idle_timeout: {:?} }}",
+            self.idle_timeout
+        )
+    }
+}
+
+/// The state of a stream which generates watermarks, shared by the copies of its
+/// [`WriteStream`](super::WriteStream).
+struct IdleState {
+    /// When the last message was sent on the stream.
+    last_sent: Instant,
+    /// The greatest watermark sent on the stream.
+    watermark: Option<Timestamp>,
+    /// Whether the top watermark was sent.
+    closed: bool,
+}
+
+/// Tracks the messages sent on a stream to generate
+/// [`ProcessingTimeWatermarks`].
+#[derive(Clone)]
+pub(crate) struct WatermarkGenerator {
+    config: ProcessingTimeWatermarks,
+    state: Arc<Mutex<IdleState>>,
+}
+
+impl WatermarkGenerator {
+    pub fn new(config: ProcessingTimeWatermarks) -> Self {
+        Self {
+            config,
+            state: Arc::new(Mutex::new(IdleState {
+                last_sent: Instant::now(),
+                watermark: None,
+                closed: false,
+            })),
+        }
+    }
+
+    pub fn get_config(&self) -> &ProcessingTimeWatermarks {
+        &self.config
+    }
+
+    /// Records a message sent on the stream, and returns whether it should be forwarded.
+    ///
+    /// Watermarks are forwarded if they advance past the watermarks previously sent in the partial
+    /// order of timestamps.
+    pub fn on_send(&self, watermark: Option<&Timestamp>) -> bool {
+        let mut state = self.state.lock().unwrap();
+        state.last_sent = Instant::now();
+        let watermark = match watermark {
+            Some(watermark) => watermark,
+            None => return true,
+        };
+        if watermark.is_top() {
+            state.closed = true;
+        }
+        match &state.watermark {
+            Some(previous) if !previous.less_than(watermark) => false,
+            _ => {
+                state.watermark = Some(watermark.clone());
+                true
+            }
+        }
+    }
+
+    /// Returns the watermark to send if the stream has been idle for the timeout, and records it
+    /// as sent.
+    pub fn generate(&self) -> Option<Timestamp> {
+        let mut state = self.state.lock().unwrap();
+        if state.closed || state.last_sent.elapsed() < self.config.idle_timeout {
+            return None;
+        }
+        let watermark = (self.config.clock)();
+        let advances = match &state.watermark {
+            Some(previous) => previous.less_than(&watermark),
+            None => true,
+        };
+        if watermark.is_top() || !advances {
+            return None;
+        }
+        state.last_sent = Instant::now();
+        state.watermark = Some(watermark.clone());
+        Some(watermark)
+    }
+
+    /// Whether the top watermark was sent on the stream.
+    pub fn is_closed(&self) -> bool {
+        self.state.lock().unwrap().closed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_when_idle() {
+        let generator = WatermarkGenerator::new(ProcessingTimeWatermarks::new(
+            Duration::from_millis(1),
+            || Timestamp::new(vec![5]),
+        ));
+        assert!(generator.on_send(None));
+        std::thread::sleep(Duration::from_millis(2));
+        assert_eq!(generator.generate(), Some(Timestamp::new(vec![5])));
+        // The clock did not advance.
+        std::thread::sleep(Duration::from_millis(2));
+        assert_eq!(generator.generate(), None);
+        // Watermarks which do not advance past the generated watermark are not forwarded.
+        assert!(!generator.on_send(Some(&Timestamp::new(vec![3]))));
+        assert!(generator.on_send(Some(&Timestamp::new(vec![6]))));
+        assert!(generator.on_send(Some(&Timestamp::top())));
+        std::thread::sleep(Duration::from_millis(2));
+        assert!(generator.is_closed());
+        assert_eq!(generator.generate(), None);
+    }
+}
@@ -4,10 +4,13 @@ use serde::Deserialize;
 
 use crate::{
     communication::{Pusher, SendEndpoint},
-    dataflow::{Data, Message, Timestamp},
+    dataflow::{add_timer_callback, Data, Message, OperatorConfig, Timestamp, TimestampT},
 };
 
-use super::{errors::WriteStreamError, StreamId, WriteStreamT};
+use super::{
+    errors::WriteStreamError, watermark_generator::WatermarkGenerator, DeadLetter, OverflowPolicy,
+    ProcessingTimeWatermarks, SideOutput, StreamCapacity, StreamId, WriteStreamT,
+};
 
 // TODO (Sukrit) :: This example needs to be fixed after we enable attaching WriteStreams to
 // callbacks for normal read streams.
@@ -73,6 +76,13 @@ pub struct WriteStream<D: Data> {
     low_watermark: Timestamp,
     /// Whether the stream is closed.
     stream_closed: bool,
+    /// Bounds the number of messages buffered for each receiver of the stream.
+    capacity: Option<StreamCapacity>,
+    /// Generates watermarks while nothing is sent on the stream.
+    watermark_generator: Option<WatermarkGenerator>,
+    /// Makes the side output of the operator if the stream is its side output stream, which
+    /// accepts late messages. Only set for streams of [`DeadLetter`]s.
+    side_output: Option<fn(&Self) -> SideOutput>,
 }
 
 impl<D: Data> WriteStream<D> {
@@ -110,11 +120,41 @@ impl<D: Data> WriteStream<D> {
             id,
             name,
             pusher: Some(Pusher::new()),
-            low_watermark: Timestamp::new(vec![0]),
+            low_watermark: Timestamp::bottom(),
             stream_closed: false,
+            capacity: None,
+            watermark_generator: None,
+            side_output: None,
         }
     }
 
+    /// Bounds the number of data messages buffered for each receiver of the stream.
+    ///
+    /// Once a receiver has `capacity` unread data messages, the `overflow_policy` determines
+    /// what happens to the data messages sent to it. Watermarks are never dropped or delayed.
+    /// The capacity overrides the
+    /// [`OperatorConfig::write_stream_capacity`](crate::dataflow::OperatorConfig::write_stream_capacity)
+    /// of the operator that writes on the stream.
+    ///
+    /// # Arguments
+    /// * `capacity` - The maximum number of buffered data messages.
+    /// * `overflow_policy` - The policy applied to data messages sent while a receiver is full.
+    pub fn with_capacity(mut self, capacity: usize, overflow_policy: OverflowPolicy) -> Self {
+        self.capacity = Some(StreamCapacity::new(capacity, overflow_policy));
+        self
+    }
+
+    /// Sends [`ProcessingTimeWatermarks`] on the stream once nothing was sent on it for their
+    /// idle timeout.
+    ///
+    /// The stream of an operator checks whether it is idle using a timer, which starts once
+    /// [`Operator::run`](crate::dataflow::Operator::run) completes and stops when the input
+    /// streams of the operator close.
+    pub fn with_processing_time_watermarks(mut self, watermarks: ProcessingTimeWatermarks) -> Self {
+        self.watermark_generator = Some(WatermarkGenerator::new(watermarks));
+        self
+    }
+
     pub fn from_endpoints(endpoints: Vec<SendEndpoint<Arc<Message<D>>>>, id: StreamId) -> Self {
         let mut stream = Self::new_with_id(id);
         for endpoint in endpoints {
@@ -123,6 +163,31 @@ impl<D: Data> WriteStream<D> {
         stream
     }
 
+    /// Creates the stream on which an operator sends, with the configuration of the stream
+    /// returned by the `connect` function of the operator.
+    ///
+    /// Note: this is an internal method called by the `connect_x_write` macros.
+    #[doc(hidden)]
+    pub fn from_template(template: &Self, endpoints: Vec<SendEndpoint<Arc<Message<D>>>>) -> Self {
+        let mut stream = Self::from_endpoints(endpoints, template.id);
+        stream.name = template.name.clone();
+        stream.capacity = template.capacity;
+        stream.watermark_generator = template
+            .get_processing_time_watermarks()
+            .map(WatermarkGenerator::new);
+        stream.side_output = template.side_output;
+        stream
+    }
+
+    /// Returns the side output of the operator if this is its side output stream.
+    ///
+    /// Note: this is an internal method called by the `connect_x_write` macros.
+    #[doc(hidden)]
+    pub fn make_side_output(&self) -> Option<SideOutput> {
+        self.side_output
+            .map(|make_side_output| make_side_output(self))
+    }
+
     /// Get the ID given to the stream by the constructor
     pub fn get_id(&self) -> StreamId {
         self.id
@@ -135,6 +200,18 @@ impl<D: Data> WriteStream<D> {
         &self.name[..]
     }
 
+    /// Returns the capacity of the stream, which is unbounded if `None`.
+    pub fn get_capacity(&self) -> Option<StreamCapacity> {
+        self.capacity
+    }
+
+    /// Returns the configuration of the watermarks generated on the stream, if any.
+    pub fn get_processing_time_watermarks(&self) -> Option<ProcessingTimeWatermarks> {
+        self.watermark_generator
+            .as_ref()
+            .map(|generator| generator.get_config().clone())
+    }
+
     /// Returns `true` if a top watermark message was received or the [`IngestStream`] failed to
     /// set up.
     pub fn is_closed(&self) -> bool {
@@ -162,17 +239,20 @@ impl<D: Data> WriteStream<D> {
 
     /// Updates the last watermark received on the stream.
     ///
+    /// Watermarks must advance in the partial order of timestamps. Messages are rejected if their
+    /// timestamp precedes the watermark in the partial order, unless the stream is a side output.
+    ///
     /// # Arguments
     /// * `msg` - The message to be sent on the stream.
     fn update_watermark(&mut self, msg: &Message<D>) -> Result<(), WriteStreamError> {
         match msg {
             Message::TimestampedData(td) => {
-                if td.timestamp < self.low_watermark {
+                if self.side_output.is_none() && td.timestamp.less_than(&self.low_watermark) {
                     return Err(WriteStreamError::TimestampError);
                 }
             }
             Message::Watermark(msg_watermark) => {
-                if msg_watermark < &self.low_watermark {
+                if !self.low_watermark.less_equal(msg_watermark) {
                     return Err(WriteStreamError::TimestampError);
                 }
                 slog::debug!(
@@ -190,6 +270,23 @@ impl<D: Data> WriteStream<D> {
     }
 }
 
+impl WriteStream<DeadLetter> {
+    /// Returns a new side output stream, on which ERDOS sends the messages that the operator
+    /// returning the stream from its `connect` function could not process.
+    ///
+    /// The side output receives the late messages of the operator if it uses the
+    /// [`SideOutput`](super::LateDataPolicy::SideOutput) late data policy, the messages from
+    /// other nodes which fail to deserialize, and the messages whose callbacks panic. A message
+    /// which fails to deserialize still fails the node if another operator on the node reads the
+    /// stream without a side output. Unlike other streams, the side output accepts messages with
+    /// timestamps preceding its low watermark. An operator should return at most one side output.
+    pub fn new_side_output() -> Self {
+        let mut stream = Self::new();
+        stream.side_output = Some(|stream| SideOutput::new(stream.clone()));
+        stream
+    }
+}
+
 impl<D: Data> Default for WriteStream<D> {
     fn default() -> Self {
         Self::new()
@@ -233,10 +330,32 @@ impl<'a, D: Data + Deserialize<'a>> WriteStreamT<D> for WriteStream<D> {
 
         // Update the watermark and send the message forward.
         self.update_watermark(&msg)?;
-        let msg_arc = Arc::new(msg);
+        let forward = match &self.watermark_generator {
+            Some(generator) => {
+                let watermark = match &msg {
+                    Message::Watermark(t) => Some(t),
+                    Message::TimestampedData(_) => None,
+                };
+                generator.on_send(watermark)
+            }
+            None => true,
+        };
+        if forward {
+            self.push(msg)?;
+        }
 
+        // If we received a top watermark, close the stream.
+        if close_stream {
+            self.close_stream();
+        }
+        Ok(())
+    }
+}
+
+impl<'a, D: Data + Deserialize<'a>> WriteStream<D> {
+    fn push(&mut self, msg: Message<D>) -> Result<(), WriteStreamError> {
         match self.pusher.as_mut() {
-            Some(pusher) => pusher.send(msg_arc).map_err(WriteStreamError::from)?,
+            Some(pusher) => pusher.send(Arc::new(msg)).map_err(WriteStreamError::from)?,
             None => {
                 slog::debug!(
                     crate::TERMINAL_LOGGER,
@@ -245,14 +364,64 @@ impl<'a, D: Data + Deserialize<'a>> WriteStreamT<D> for WriteStream<D> {
                     self.get_name(),
                     self.get_id()
                 );
-                ()
             }
         };
+        Ok(())
+    }
 
-        // If we received a top watermark, close the stream.
-        if close_stream {
-            self.close_stream();
+    /// Sends a processing-time watermark if nothing was sent on the stream for the idle timeout.
+    ///
+    /// The watermark does not update the low watermark of the stream, so messages with earlier
+    /// timestamps can still be sent.
+    pub(crate) fn send_generated_watermark(&mut self) -> Result<(), WriteStreamError> {
+        if self.stream_closed {
+            return Ok(());
         }
-        Ok(())
+        let watermark = match self
+            .watermark_generator
+            .as_ref()
+            .and_then(WatermarkGenerator::generate)
+        {
+            Some(watermark) => watermark,
+            None => return Ok(()),
+        };
+        slog::debug!(
+            crate::TERMINAL_LOGGER,
+            "Generating watermark {:?} on idle WriteStream {} (ID: {})",
+            watermark,
+            self.get_name(),
+            self.get_id()
+        );
+        self.push(Message::new_watermark(watermark))
+    }
+
+    /// Registers a timer which sends the processing-time watermarks of the stream.
+    ///
+    /// Note: this is an internal method called by the `connect_x_write` macros while the
+    /// operator writing on the stream is created.
+    #[doc(hidden)]
+    pub fn add_watermark_timer<T: Clone>(&self, config: &OperatorConfig<T>) {
+        let generator = match &self.watermark_generator {
+            Some(generator) => generator.clone(),
+            None => return,
+        };
+        add_timer_callback(
+            config,
+            generator.get_config().idle_timeout,
+            self.clone(),
+            move |_tick: u64, write_stream: &mut WriteStream<D>| {
+                if let Err(e) = write_stream.send_generated_watermark() {
+                    slog::error!(
+                        crate::TERMINAL_LOGGER,
+                        "Error generating watermark on WriteStream {} (ID: {}): {:?}",
+                        write_stream.get_name(),
+                        write_stream.get_id(),
+                        e
+                    );
+                }
+                // The stream may be closed by another copy of the stream.
+                !generator.is_closed()
+            },
+        );
     }
 }
@@ -0,0 +1,121 @@
+//! Timers which periodically invoke callbacks of an operator.
+
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use crate::{
+    dataflow::{OperatorConfig, State, Timestamp},
+    node::operator_event::OperatorEvent,
+    Uuid,
+};
+
+/// The timers which an operator registered while it was created, until they are taken by its
+/// executor. Shared by the copies of the operator's [`OperatorConfig`].
+#[derive(Clone, Default)]
+pub(crate) struct TimerRegistry(Arc<Mutex<Vec<Timer>>>);
+
+impl TimerRegistry {
+    fn register(&self, timer: Timer) {
+        self.0.lock().unwrap().push(timer);
+    }
+
+    /// Removes and returns the registered timers.
+    pub fn take(&self) -> Vec<Timer> {
+        self.0.lock().unwrap().drain(..).collect()
+    }
+}
+
+/// A callback registered using [`add_timer_callback`], which the
+/// [`OperatorExecutor`](crate::node::operator_executor::OperatorExecutor) of the operator
+/// invokes periodically.
+pub(crate) struct Timer {
+    /// The period with which the callback is invoked.
+    pub period: Duration,
+    /// Set once the callback stops the timer.
+    stopped: Arc<AtomicBool>,
+    /// Creates the event which invokes the callback for a tick.
+    make_event: Box<dyn Fn(u64) -> OperatorEvent + Send>,
+}
+
+impl Timer {
+    /// Whether the callback stopped the timer.
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+
+    /// Returns the event which invokes the callback for the `tick`-th time.
+    pub fn make_event(&self, tick: u64) -> OperatorEvent {
+        (self.make_event)(tick)
+    }
+}
+
+/// Adds a callback which is invoked every `period` once
+/// [`Operator::run`](crate::dataflow::Operator::run) completes, and stops when the input streams
+/// of the operator close or the operator is removed.
+///
+/// Must be called from the `new` method of an operator with the `config` the operator received,
+/// which attaches the timer to the operator. The callback receives the number of previous
+/// invocations and a mutable reference to `state`, which is shared across its invocations, and
+/// returns whether the timer should keep running. The state and the callback must be `Send` and
+/// `Sync` because the config moves to the thread that runs the operator. The first invocation
+/// happens as soon as the operator starts processing callbacks, and ticks which are missed because
+/// the operator is busy are skipped. Invocations are scheduled in the execution lattice of the
+/// operator along with its other callbacks, and precede the watermark callbacks which are pending
+/// when they are scheduled.
+///
+/// This function is experimental and may be changed in the future.
+pub fn add_timer_callback<T, S, F>(
+    config: &OperatorConfig<T>,
+    period: Duration,
+    state: S,
+    callback: F,
+) where
+    T: Clone,
+    S: State + Send + Sync,
+    F: 'static + Send + Sync + Fn(u64, &mut S) -> bool,
+{
+    assert!(
+        period > Duration::from_secs(0),
+        "Timer period must be positive"
+    );
+    let stopped = Arc::new(AtomicBool::new(false));
+    let state = Arc::new(state);
+    let callback = Arc::new(callback);
+    let mut write_ids = HashSet::with_capacity(1);
+    write_ids.insert(Uuid::new_deterministic());
+
+    let stopped_copy = Arc::clone(&stopped);
+    let make_event = move |tick: u64| {
+        let stopped = Arc::clone(&stopped_copy);
+        let mut state = Arc::clone(&state);
+        let callback = Arc::clone(&callback);
+        OperatorEvent::new(
+            Timestamp::bottom(),
+            false,
+            0,
+            HashSet::with_capacity(0),
+            write_ids.clone(),
+            move || {
+                // Ticks scheduled before the timer stopped are skipped.
+                if stopped.load(Ordering::SeqCst) {
+                    return;
+                }
+                let state = unsafe { Arc::get_mut_unchecked(&mut state) };
+                if !(callback)(tick, state) {
+                    stopped.store(true, Ordering::SeqCst);
+                }
+            },
+        )
+    };
+    config.timers.register(Timer {
+        period,
+        stopped,
+        make_event: Box::new(make_event),
+    });
+}
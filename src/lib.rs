@@ -121,7 +121,18 @@
 //! [`run`](crate::dataflow::Operator::run) of the
 //! [`Operator`](crate::dataflow::Operator) trait and pulling data from
 //! [`ReadStream`](crate::dataflow::stream::ReadStream)s.
-//! *Callbacks are not invoked while run executes.*
+//! *Callbacks are not invoked while run executes*, unless the operator sets
+//! [`run_concurrently`](crate::dataflow::OperatorConfig::run_concurrently)
+//! to run on an additional event runner while callbacks are invoked.
+//! Operators which act periodically should instead register timer callbacks
+//! using [`add_timer_callback`](crate::dataflow::add_timer_callback), which
+//! are invoked along with the other callbacks.
+//!
+//! A panic in a callback or in [`run`](crate::dataflow::Operator::run) is
+//! logged and reported to the node, which handles it according to the
+//! [`panic_policy`](crate::dataflow::OperatorConfig::panic_policy) of the
+//! operator: fail the node, which is the default, skip the callback, or
+//! restart the operator with fresh state.
 //!
 //! ## Performance
 //! ERDOS is designed for low latency. Self-driving car pipelines require
@@ -139,6 +150,16 @@
 //! maximum possible timestamp. Sending a top watermark closes the stream as
 //! there is no `t' > t_top`, so no more messages can be sent.
 //!
+//! Watermarks are logical and only advance when the sender of a stream sends
+//! them. Streams whose sender may stop sending can instead generate
+//! [processing-time watermarks](crate::dataflow::stream::ProcessingTimeWatermarks)
+//! once they are idle, in which case messages may arrive after a watermark
+//! which covers them. Operators handle such late messages according to their
+//! [`late_data_policy`](crate::dataflow::OperatorConfig::late_data_policy),
+//! which may send them on a
+//! [side output](crate::dataflow::WriteStream::new_side_output) along with
+//! the messages the operator fails to deserialize or process.
+//!
 //! ## Determinism
 //! ERDOS provides mechanisms to enable the building of deterministic
 //! applications.
@@ -161,7 +182,7 @@ pub use ::slog;
 pub use ::tokio;
 
 // Libraries used in this file.
-use std::{cell::RefCell, fmt};
+use std::{cell::RefCell, fmt, str::FromStr};
 
 use abomonation_derive::Abomonation;
 use clap::{self, App, Arg};
@@ -240,6 +261,14 @@ impl fmt::Display for Uuid {
     }
 }
 
+impl FromStr for Uuid {
+    type Err = uuid::parser::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(*uuid::Uuid::parse_str(s)?.as_bytes()))
+    }
+}
+
 /// Resets seed and creates a new dataflow graph.
 pub fn reset() {
     // All global variables should be reset here.
@@ -297,4 +326,75 @@ pub fn new_app(name: &str) -> clap::App {
                 .default_value("")
                 .help("Exports the dataflow graph as a DOT file to the provided filename"),
         )
+        .arg(
+            Arg::with_name("heartbeat-interval-ms")
+                .long("heartbeat-interval-ms")
+                .default_value("500")
+                .help("Interval in milliseconds at which heartbeats are sent to other nodes"),
+        )
+        .arg(
+            Arg::with_name("heartbeat-timeout-ms")
+                .long("heartbeat-timeout-ms")
+                .default_value("5000")
+                .help("Time in milliseconds without messages after which a node is considered down"),
+        )
+        .arg(
+            Arg::with_name("checkpoint-dir")
+                .long("checkpoint-dir")
+                .default_value("")
+                .help("Directory to which operators checkpoint their states"),
+        )
+        .arg(
+            Arg::with_name("recover")
+                .long("recover")
+                .help("Restores operators from the latest consistent checkpoint"),
+        )
+        .arg(
+            Arg::with_name("checkpoints-to-keep")
+                .long("checkpoints-to-keep")
+                .default_value("3")
+                .help("Number of latest checkpoints of each state kept in the checkpoint directory"),
+        )
+        .arg(
+            Arg::with_name("record-filename")
+                .long("record-filename")
+                .default_value("")
+                .help("Records the messages sent on streams to the provided filename"),
+        )
+        .arg(
+            Arg::with_name("record-streams")
+                .long("record-streams")
+                .default_value("")
+                .help("Comma separated list of IDs of the recorded streams (defaults to all)"),
+        )
+        .arg(
+            Arg::with_name("metrics-address")
+                .long("metrics-address")
+                .default_value("")
+                .help("Serves the node's metrics in the Prometheus format on the provided address"),
+        )
+        .arg(
+            Arg::with_name("stream-metrics")
+                .long("stream-metrics")
+                .help("Collects the number and size of the messages sent on streams"),
+        )
+        .arg(
+            Arg::with_name("trace-filename")
+                .long("trace-filename")
+                .default_value("")
+                .help("Writes Chrome trace events of operator callbacks to the provided filename"),
+        )
+        .arg(
+            Arg::with_name("scheduler")
+                .long("scheduler")
+                .default_value("none")
+                .possible_values(&["none", "round-robin", "min-communication"])
+                .help("Places operators which are not pinned to a node"),
+        )
+        .arg(
+            Arg::with_name("shared-memory-size")
+                .long("shared-memory-size")
+                .default_value("0")
+                .help("Size in bytes of the shared-memory rings between co-located nodes (0 disables them)"),
+        )
 }
@@ -26,5 +26,8 @@ fn main() {
             left_sum + right_sum
         }), s1, s2);
 
-    node.run();
+    // The node logs the reason of the failure.
+    if node.run().is_err() {
+        std::process::exit(1);
+    }
 }
@@ -0,0 +1,27 @@
+use std::{error::Error, fmt};
+
+use super::NodeId;
+
+/// Error raised when a [`Node`](super::Node) stops because of a failure.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NodeError {
+    /// Failed to send or receive messages from other nodes.
+    CommunicationError(String),
+    /// Failed to set up or run the dataflow on the node.
+    DataflowError(String),
+    /// Another node failed, which stopped the dataflow. Contains the id of the node and the
+    /// reason of its failure.
+    NodeFailed(NodeId, String),
+}
+
+impl fmt::Display for NodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NodeError::CommunicationError(e) => write!(f, "Communication error: {}", e),
+            NodeError::DataflowError(e) => write!(f, "Dataflow error: {}", e),
+            NodeError::NodeFailed(node_id, e) => write!(f, "Node {} failed: {}", node_id, e),
+        }
+    }
+}
+
+impl Error for NodeError {}
@@ -2,7 +2,10 @@ use std::{
     cmp::Ordering,
     collections::{BinaryHeap, HashSet},
     fmt,
-    sync::Arc,
+    sync::{
+        atomic::{self, AtomicUsize},
+        Arc,
+    },
 };
 
 use futures::lock::Mutex;
@@ -13,13 +16,17 @@ use petgraph::{
     Direction,
 };
 
-use crate::{dataflow::Timestamp, node::operator_event::OperatorEvent};
+use crate::{
+    dataflow::{Timestamp, TimestampT},
+    node::operator_event::OperatorEvent,
+};
 
 /// `RunnableEvent` is a data structure that is used to represent an event that is ready to be
 /// executed.
 ///
 /// A `RunnableEvent` is essentially an index into the lattice, with additional metadata to
-/// prioritize events that are ready to run.
+/// prioritize events that are ready to run. Runnable events are prioritized by the lexicographic
+/// order of their timestamps, which extends the partial order of the timestamps.
 #[derive(Clone)]
 pub struct RunnableEvent {
     /// The `node_index` is the index of the runnable event in the lattice.
@@ -168,6 +175,8 @@ pub struct ExecutionLattice {
     /// The `run_queue` is the queue that maintains the events to be executed next. Note that this
     /// is different from the `leaves` because a leaf is only removed once its marked as complete.
     run_queue: Arc<Mutex<BinaryHeap<RunnableEvent>>>,
+    /// The number of events which were added to the lattice and are not yet completed.
+    num_pending_events: AtomicUsize,
 }
 
 impl ExecutionLattice {
@@ -177,6 +186,7 @@ impl ExecutionLattice {
             forest: Arc::new(Mutex::new(StableGraph::new())),
             leaves: Arc::new(Mutex::new(Vec::new())),
             run_queue: Arc::new(Mutex::new(BinaryHeap::new())),
+            num_pending_events: AtomicUsize::new(0),
         }
     }
 
@@ -189,6 +199,8 @@ impl ExecutionLattice {
         let mut forest = self.forest.lock().await;
         let mut leaves = self.leaves.lock().await;
         let mut run_queue = self.run_queue.lock().await;
+        self.num_pending_events
+            .fetch_add(events.len(), atomic::Ordering::SeqCst);
 
         // If add_events becomes a bottleneck, look into changing the insertion algorithm to perform
         // only 1 DFS instead of 1 per event. This could lead to more complex code to deal with
@@ -403,6 +415,8 @@ impl ExecutionLattice {
 
         // Remove the node from the graph. This will also remove edges from the parents.
         forest.remove_node(node_idx);
+        self.num_pending_events
+            .fetch_sub(1, atomic::Ordering::SeqCst);
 
         // Promote parents to leaves if they have no dependencies, and add their corresponding
         // events to the run queue.
@@ -420,6 +434,49 @@ impl ExecutionLattice {
         }
     }
 
+    /// Removes all the events from the lattice, and returns the number of events removed.
+    ///
+    /// Must not be called while events retrieved with [`ExecutionLattice::get_event`] execute.
+    pub async fn clear(&self) -> usize {
+        // Take locks over everything.
+        let mut forest = self.forest.lock().await;
+        let mut leaves = self.leaves.lock().await;
+        let mut run_queue = self.run_queue.lock().await;
+
+        forest.clear();
+        leaves.clear();
+        run_queue.clear();
+        self.num_pending_events.swap(0, atomic::Ordering::SeqCst)
+    }
+
+    /// Returns `true` if an event whose timestamp precedes or equals `t` in the partial order was
+    /// added to the lattice and is not yet completed.
+    ///
+    /// Events with the bottom timestamp, such as the ticks of timers, are not tied to the
+    /// timestamps of the data, and are ignored.
+    pub async fn has_pending_events_up_to(&self, t: &Timestamp) -> bool {
+        // Take locks over everything.
+        let forest = self.forest.lock().await;
+        let leaves = self.leaves.lock().await;
+
+        // Events which are being executed were taken from the forest, but remain in the leaves.
+        let bottom = Timestamp::bottom();
+        let is_pending = |timestamp: &Timestamp| timestamp != &bottom && timestamp.less_equal(t);
+        forest
+            .node_indices()
+            .filter_map(|idx| forest[idx].as_ref())
+            .any(|event| is_pending(&event.timestamp))
+            || leaves
+                .iter()
+                .filter_map(|event| event.timestamp.as_ref())
+                .any(is_pending)
+    }
+
+    /// Returns the number of events which were added to the lattice and are not yet completed.
+    pub fn num_pending_events(&self) -> usize {
+        self.num_pending_events.load(atomic::Ordering::SeqCst)
+    }
+
     /// Convert graph to string in DOT format.
     #[allow(dead_code)]
     pub async fn to_dot(&self) -> String {
@@ -472,7 +529,8 @@ mod test {
         // Ensure that the correct event is returned by the lattice.
         let (event, _event_id) = block_on(lattice.get_event()).unwrap();
         assert_eq!(
-            event.timestamp.time[0], 1,
+            event.timestamp.time()[0],
+            1,
             "The wrong event was returned by the lattice."
         );
 
@@ -508,7 +566,8 @@ mod test {
         // Check the first event is returned correctly by the lattice.
         let (event, _event_id) = block_on(lattice.get_event()).unwrap();
         assert_eq!(
-            event.timestamp.time[0], 1,
+            event.timestamp.time()[0],
+            1,
             "The wrong event was returned by the lattice."
         );
 
@@ -516,7 +575,8 @@ mod test {
         // This shows that they can be executed concurrently.
         let (event_2, _event_id_2) = block_on(lattice.get_event()).unwrap();
         assert_eq!(
-            event_2.timestamp.time[0], 1,
+            event_2.timestamp.time()[0],
+            1,
             "The wrong event was returned by the lattice."
         );
     }
@@ -556,14 +616,14 @@ mod test {
         // Check that the first event is returned correctly by the lattice.
         let (event, event_id) = block_on(lattice.get_event()).unwrap();
         assert!(
-            event.timestamp.time[0] == 1 && !event.is_watermark_callback,
+            event.timestamp.time()[0] == 1 && !event.is_watermark_callback,
             "The wrong event was returned by the lattice."
         );
 
         // Check that the first event is returned correctly by the lattice.
         let (event_2, event_id_2) = block_on(lattice.get_event()).unwrap();
         assert!(
-            event_2.timestamp.time[0] == 1 && !event.is_watermark_callback,
+            event_2.timestamp.time()[0] == 1 && !event.is_watermark_callback,
             "The wrong event was returned by the lattice."
         );
         let no_event = block_on(lattice.get_event());
@@ -580,7 +640,7 @@ mod test {
 
         let (event_3, _event_id_3) = block_on(lattice.get_event()).unwrap();
         assert!(
-            event_3.timestamp.time[0] == 1 && event_3.is_watermark_callback,
+            event_3.timestamp.time()[0] == 1 && event_3.is_watermark_callback,
             "The wrong event was returned by the lattice."
         );
     }
@@ -620,7 +680,8 @@ mod test {
 
         let (event, event_id) = block_on(lattice.get_event()).unwrap();
         assert_eq!(
-            event.timestamp.time[0], 1,
+            event.timestamp.time()[0],
+            1,
             "The wrong event was returned by the lattice."
         );
         assert!(
@@ -630,7 +691,8 @@ mod test {
         block_on(lattice.mark_as_completed(event_id));
         let (event_2, event_id_2) = block_on(lattice.get_event()).unwrap();
         assert_eq!(
-            event_2.timestamp.time[0], 2,
+            event_2.timestamp.time()[0],
+            2,
             "The wrong event was returned by the lattice."
         );
         assert!(
@@ -640,7 +702,8 @@ mod test {
         block_on(lattice.mark_as_completed(event_id_2));
         let (event_3, _event_id_3) = block_on(lattice.get_event()).unwrap();
         assert_eq!(
-            event_3.timestamp.time[0], 3,
+            event_3.timestamp.time()[0],
+            3,
             "The wrong event was returned by the lattice."
         );
         assert!(
@@ -683,17 +746,20 @@ mod test {
 
         let (event, _event_id) = block_on(lattice.get_event()).unwrap();
         assert_eq!(
-            event.timestamp.time[0], 1,
+            event.timestamp.time()[0],
+            1,
             "The wrong event was returned by the lattice."
         );
         let (event_2, _event_id_2) = block_on(lattice.get_event()).unwrap();
         assert_eq!(
-            event_2.timestamp.time[0], 2,
+            event_2.timestamp.time()[0],
+            2,
             "The wrong event was returned by the lattice."
         );
         let (event_3, _event_id_3) = block_on(lattice.get_event()).unwrap();
         assert_eq!(
-            event_3.timestamp.time[0], 3,
+            event_3.timestamp.time()[0],
+            3,
             "The wrong event was returned by the lattice."
         );
     }
@@ -755,17 +821,17 @@ mod test {
         block_on(lattice.add_events(events));
         let (event, event_id) = block_on(lattice.get_event()).unwrap();
         assert!(
-            event.timestamp.time[0] == 1 && !event.is_watermark_callback,
+            event.timestamp.time()[0] == 1 && !event.is_watermark_callback,
             "The wrong event was returned by the lattice."
         );
         let (event_2, event_id_2) = block_on(lattice.get_event()).unwrap();
         assert!(
-            event_2.timestamp.time[0] == 2 && !event_2.is_watermark_callback,
+            event_2.timestamp.time()[0] == 2 && !event_2.is_watermark_callback,
             "The wrong event was returned by the lattice."
         );
         let (event_3, event_id_3) = block_on(lattice.get_event()).unwrap();
         assert!(
-            event_3.timestamp.time[0] == 3 && !event_3.is_watermark_callback,
+            event_3.timestamp.time()[0] == 3 && !event_3.is_watermark_callback,
             "The wrong event was returned by the lattice."
         );
         assert!(
@@ -775,7 +841,7 @@ mod test {
         block_on(lattice.mark_as_completed(event_id));
         let (event_4, event_id_4) = block_on(lattice.get_event()).unwrap();
         assert!(
-            event_4.timestamp.time[0] == 1 && event_4.is_watermark_callback,
+            event_4.timestamp.time()[0] == 1 && event_4.is_watermark_callback,
             "The wrong event was returned by the lattice."
         );
         assert!(
@@ -790,7 +856,7 @@ mod test {
         block_on(lattice.mark_as_completed(event_id_2));
         let (event_5, event_id_5) = block_on(lattice.get_event()).unwrap();
         assert!(
-            event_5.timestamp.time[0] == 2 && event_5.is_watermark_callback,
+            event_5.timestamp.time()[0] == 2 && event_5.is_watermark_callback,
             "The wrong event was returned by the lattice."
         );
         block_on(lattice.mark_as_completed(event_id_3));
@@ -801,7 +867,7 @@ mod test {
         block_on(lattice.mark_as_completed(event_id_5));
         let (event_6, event_id_6) = block_on(lattice.get_event()).unwrap();
         assert!(
-            event_6.timestamp.time[0] == 3 && event_6.is_watermark_callback,
+            event_6.timestamp.time()[0] == 3 && event_6.is_watermark_callback,
             "The wrong event was returned by the lattice."
         );
         block_on(lattice.mark_as_completed(event_id_6));
@@ -811,6 +877,63 @@ mod test {
         );
     }
 
+    /// Test that watermarks with incomparable multi-dimensional timestamps run concurrently, and
+    /// only wait for the messages whose timestamps precede them in the product order.
+    #[test]
+    fn test_product_order_watermarks() {
+        let lattice: ExecutionLattice = ExecutionLattice::new();
+        let events = vec![
+            OperatorEvent::new(
+                Timestamp::new(vec![1, 2]),
+                false,
+                0,
+                HashSet::new(),
+                HashSet::new(),
+                || (),
+            ),
+            OperatorEvent::new(
+                Timestamp::new(vec![1, 2]),
+                true,
+                0,
+                HashSet::new(),
+                HashSet::new(),
+                || (),
+            ),
+            OperatorEvent::new(
+                Timestamp::new(vec![2, 1]),
+                true,
+                0,
+                HashSet::new(),
+                HashSet::new(),
+                || (),
+            ),
+        ];
+        block_on(lattice.add_events(events));
+
+        // The message and the watermark [2, 1] run concurrently.
+        let (event, event_id) = block_on(lattice.get_event()).unwrap();
+        assert!(
+            event.timestamp.time()[..] == [1, 2] && !event.is_watermark_callback,
+            "The wrong event was returned by the lattice."
+        );
+        let (event_2, _event_id_2) = block_on(lattice.get_event()).unwrap();
+        assert!(
+            event_2.timestamp.time()[..] == [2, 1] && event_2.is_watermark_callback,
+            "The wrong event was returned by the lattice."
+        );
+        assert!(
+            block_on(lattice.get_event()).is_none(),
+            "The watermark [1, 2] should wait for the message [1, 2]."
+        );
+
+        block_on(lattice.mark_as_completed(event_id));
+        let (event_3, _event_id_3) = block_on(lattice.get_event()).unwrap();
+        assert!(
+            event_3.timestamp.time()[..] == [1, 2] && event_3.is_watermark_callback,
+            "The wrong event was returned by the lattice."
+        );
+    }
+
     /// Tests that duplicate events do not end up in the lattice's leaves or
     /// run queue. This can happen if duplicate edges exist in the dependency
     /// graph.
@@ -913,4 +1036,53 @@ mod test {
             "There should be no more events in the lattice."
         );
     }
+
+    /// Test that the lattice tracks the timestamps of the events which are not yet completed,
+    /// including the events which are being executed.
+    #[test]
+    fn test_pending_events() {
+        let lattice: ExecutionLattice = ExecutionLattice::new();
+        let events = vec![
+            OperatorEvent::new(
+                Timestamp::new(vec![1, 2]),
+                true,
+                0,
+                HashSet::new(),
+                HashSet::new(),
+                || (),
+            ),
+            OperatorEvent::new(
+                Timestamp::new(vec![1, 2]),
+                true,
+                1,
+                HashSet::new(),
+                HashSet::new(),
+                || (),
+            ),
+        ];
+        block_on(lattice.add_events(events));
+        let is_pending = |t: Timestamp| block_on(lattice.has_pending_events_up_to(&t));
+        assert!(is_pending(Timestamp::new(vec![1, 2])));
+        assert!(is_pending(Timestamp::new(vec![2, 2])));
+        assert!(!is_pending(Timestamp::new(vec![2, 1])));
+
+        // The event being executed is still pending.
+        let (_event, event_id) = block_on(lattice.get_event()).unwrap();
+        assert!(is_pending(Timestamp::new(vec![1, 2])));
+        block_on(lattice.mark_as_completed(event_id));
+        assert!(is_pending(Timestamp::new(vec![1, 2])));
+
+        // Events with the bottom timestamp are ignored.
+        let (_event_2, event_id_2) = block_on(lattice.get_event()).unwrap();
+        block_on(lattice.mark_as_completed(event_id_2));
+        block_on(lattice.add_events(vec![OperatorEvent::new(
+            Timestamp::bottom(),
+            false,
+            0,
+            HashSet::new(),
+            HashSet::new(),
+            || (),
+        )]));
+        assert!(!is_pending(Timestamp::top()));
+    }
 }
@@ -0,0 +1,592 @@
+//! Runtime metrics of the operators, streams, and channels of a node.
+//!
+//! A node collects metrics while it runs. They can be queried using
+//! [`NodeHandle::metrics`](crate::node::NodeHandle::metrics), or scraped in the Prometheus text
+//! format from the HTTP endpoint set in
+//! [`Configuration::metrics_address`](crate::Configuration::metrics_address). The metrics of
+//! streams are only collected if
+//! [`Configuration::stream_metrics`](crate::Configuration::stream_metrics) is set.
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write as FmtWrite,
+    io,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    time,
+};
+
+use crate::{
+    communication::{ChannelQueue, Recordable},
+    dataflow::{stream::StreamId, Timestamp, TimestampT},
+    node::{lattice::ExecutionLattice, NodeId},
+    OperatorId,
+};
+
+/// Time after which a connection to the metrics endpoint is closed if it did not send a request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Upper bounds, in seconds, of the buckets of the callback duration histograms.
+const CALLBACK_DURATION_BOUNDS: [f64; 12] = [
+    0.0001, 0.0005, 0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 5.0,
+];
+
+/// Snapshot of a histogram of durations.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HistogramSnapshot {
+    /// Upper bounds of the buckets, in seconds.
+    pub bounds: Vec<f64>,
+    /// Number of observations in each bucket. The last bucket counts the observations which
+    /// are larger than all bounds.
+    pub counts: Vec<u64>,
+    /// Sum of the observations, in seconds.
+    pub sum: f64,
+    /// Number of observations.
+    pub count: u64,
+}
+
+impl HistogramSnapshot {
+    fn new(bounds: &[f64]) -> Self {
+        Self {
+            bounds: bounds.to_vec(),
+            counts: vec![0; bounds.len() + 1],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, duration: Duration) {
+        let secs = duration.as_secs_f64();
+        let bucket = self
+            .bounds
+            .iter()
+            .position(|bound| secs <= *bound)
+            .unwrap_or(self.bounds.len());
+        self.counts[bucket] += 1;
+        self.sum += secs;
+        self.count += 1;
+    }
+}
+
+/// Metrics of an operator.
+pub struct OperatorMetrics {
+    id: OperatorId,
+    name: String,
+    callback_duration: Mutex<HistogramSnapshot>,
+    lattice: Arc<ExecutionLattice>,
+}
+
+impl OperatorMetrics {
+    /// Records the duration of a callback.
+    pub(crate) fn observe_callback(&self, duration: Duration) {
+        self.callback_duration.lock().unwrap().observe(duration);
+    }
+
+    fn snapshot(&self) -> OperatorMetricsSnapshot {
+        OperatorMetricsSnapshot {
+            id: self.id,
+            name: self.name.clone(),
+            callback_duration: self.callback_duration.lock().unwrap().clone(),
+            pending_events: self.lattice.num_pending_events(),
+        }
+    }
+}
+
+struct WatermarkState {
+    low_watermark: Option<Timestamp>,
+    /// Time at which the first data message was sent for each timestamp above the watermark.
+    pending: BTreeMap<Timestamp, Instant>,
+}
+
+/// Metrics of the messages sent on a stream.
+pub struct StreamMetrics {
+    id: StreamId,
+    messages_sent: AtomicU64,
+    bytes_sent: AtomicU64,
+    watermarks: Mutex<WatermarkState>,
+}
+
+impl StreamMetrics {
+    /// Updates the metrics with a message sent on the stream.
+    pub(crate) fn observe<D: Recordable>(&self, msg: &D) {
+        self.messages_sent.fetch_add(1, Ordering::Relaxed);
+        if let Ok(size) = msg.serialized_size() {
+            self.bytes_sent.fetch_add(size as u64, Ordering::Relaxed);
+        }
+        let mut watermarks = self.watermarks.lock().unwrap();
+        let t = msg.get_timestamp();
+        if msg.is_watermark() {
+            // Keep the timestamps which the watermark does not cover.
+            watermarks
+                .pending
+                .retain(|pending_t, _| !pending_t.less_equal(t));
+            watermarks.low_watermark = Some(t.clone());
+        } else {
+            let covered = match &watermarks.low_watermark {
+                Some(low_watermark) => t.less_equal(low_watermark),
+                None => false,
+            };
+            if !covered {
+                watermarks
+                    .pending
+                    .entry(t.clone())
+                    .or_insert_with(Instant::now);
+            }
+        }
+    }
+
+    fn snapshot(&self) -> StreamMetricsSnapshot {
+        let watermarks = self.watermarks.lock().unwrap();
+        StreamMetricsSnapshot {
+            id: self.id,
+            messages_sent: self.messages_sent.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            low_watermark: watermarks.low_watermark.clone(),
+            watermark_lag: watermarks
+                .pending
+                .values()
+                .min()
+                .map(Instant::elapsed)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+struct ChannelMetrics {
+    stream_id: StreamId,
+    sink: String,
+    queue: Arc<ChannelQueue>,
+}
+
+/// Stores the metrics of the operators, streams, and channels of a node.
+pub struct MetricsRegistry {
+    node_id: NodeId,
+    operators: Mutex<Vec<Arc<OperatorMetrics>>>,
+    streams: Mutex<Vec<Arc<StreamMetrics>>>,
+    channels: Mutex<Vec<ChannelMetrics>>,
+    down_nodes: Mutex<BTreeSet<NodeId>>,
+}
+
+impl MetricsRegistry {
+    pub(crate) fn new(node_id: NodeId) -> Self {
+        Self {
+            node_id,
+            operators: Mutex::new(Vec::new()),
+            streams: Mutex::new(Vec::new()),
+            channels: Mutex::new(Vec::new()),
+            down_nodes: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    /// Registers an operator whose pending events are stored in `lattice`.
+    pub(crate) fn register_operator(
+        &self,
+        id: OperatorId,
+        name: String,
+        lattice: Arc<ExecutionLattice>,
+    ) -> Arc<OperatorMetrics> {
+        let metrics = Arc::new(OperatorMetrics {
+            id,
+            name,
+            callback_duration: Mutex::new(HistogramSnapshot::new(&CALLBACK_DURATION_BOUNDS)),
+            lattice,
+        });
+        self.operators.lock().unwrap().push(Arc::clone(&metrics));
+        metrics
+    }
+
+    /// Registers a stream which originates on the node.
+    pub(crate) fn register_stream(&self, id: StreamId) -> Arc<StreamMetrics> {
+        let metrics = Arc::new(StreamMetrics {
+            id,
+            messages_sent: AtomicU64::new(0),
+            bytes_sent: AtomicU64::new(0),
+            watermarks: Mutex::new(WatermarkState {
+                low_watermark: None,
+                pending: BTreeMap::new(),
+            }),
+        });
+        self.streams.lock().unwrap().push(Arc::clone(&metrics));
+        metrics
+    }
+
+    /// Registers the queue of a channel of a stream to `sink`.
+    pub(crate) fn register_channel(
+        &self,
+        stream_id: StreamId,
+        sink: String,
+        queue: Arc<ChannelQueue>,
+    ) {
+        self.channels.lock().unwrap().push(ChannelMetrics {
+            stream_id,
+            sink,
+            queue,
+        });
+    }
+
+    /// Removes the metrics of an operator which was removed from the dataflow.
+    pub(crate) fn unregister_operator(&self, id: OperatorId) {
+        self.operators
+            .lock()
+            .unwrap()
+            .retain(|metrics| metrics.id != id);
+    }
+
+    /// Removes the metrics of a channel of a stream to `sink`.
+    pub(crate) fn unregister_channel(&self, stream_id: StreamId, sink: &str) {
+        let mut channels = self.channels.lock().unwrap();
+        if let Some(index) = channels
+            .iter()
+            .position(|metrics| metrics.stream_id == stream_id && metrics.sink == sink)
+        {
+            channels.remove(index);
+        }
+    }
+
+    /// Records whether another node is down.
+    pub(crate) fn set_node_down(&self, node_id: NodeId, down: bool) {
+        let mut down_nodes = self.down_nodes.lock().unwrap();
+        if down {
+            down_nodes.insert(node_id);
+        } else {
+            down_nodes.remove(&node_id);
+        }
+    }
+
+    /// Returns the current value of the metrics.
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            node_id: self.node_id,
+            operators: self
+                .operators
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|metrics| metrics.snapshot())
+                .collect(),
+            streams: self
+                .streams
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|metrics| metrics.snapshot())
+                .collect(),
+            channels: self
+                .channels
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|metrics| ChannelMetricsSnapshot {
+                    stream_id: metrics.stream_id,
+                    sink: metrics.sink.clone(),
+                    queue_depth: metrics.queue.len(),
+                })
+                .collect(),
+            down_nodes: self.down_nodes.lock().unwrap().iter().cloned().collect(),
+        }
+    }
+}
+
+/// Snapshot of the metrics of an operator.
+#[derive(Clone, Debug)]
+pub struct OperatorMetricsSnapshot {
+    pub id: OperatorId,
+    pub name: String,
+    /// Histogram of the durations of the operator's callbacks.
+    pub callback_duration: HistogramSnapshot,
+    /// Number of events in the operator's execution lattice which have not completed.
+    pub pending_events: usize,
+}
+
+/// Snapshot of the metrics of a stream which originates on the node.
+#[derive(Clone, Debug)]
+pub struct StreamMetricsSnapshot {
+    pub id: StreamId,
+    /// Number of messages and watermarks sent on the stream.
+    pub messages_sent: u64,
+    /// Serialized size of the messages and watermarks sent on the stream.
+    pub bytes_sent: u64,
+    /// The last watermark sent on the stream.
+    pub low_watermark: Option<Timestamp>,
+    /// Time since the oldest data message whose timestamp is not covered by a watermark was
+    /// sent. Zero if watermarks cover all data messages.
+    pub watermark_lag: Duration,
+}
+
+/// Snapshot of the metrics of a channel, which transports the messages of a stream to a sink.
+#[derive(Clone, Debug)]
+pub struct ChannelMetricsSnapshot {
+    pub stream_id: StreamId,
+    /// The operator, driver, or node which receives the messages.
+    pub sink: String,
+    /// Number of data messages queued on the channel.
+    pub queue_depth: usize,
+}
+
+/// Snapshot of the metrics of a node.
+#[derive(Clone, Debug)]
+pub struct MetricsSnapshot {
+    pub node_id: NodeId,
+    pub operators: Vec<OperatorMetricsSnapshot>,
+    pub streams: Vec<StreamMetricsSnapshot>,
+    pub channels: Vec<ChannelMetricsSnapshot>,
+    /// Other nodes which stopped sending heartbeats and did not reconnect yet.
+    pub down_nodes: Vec<NodeId>,
+}
+
+impl MetricsSnapshot {
+    /// Returns the metrics in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let node = self.node_id;
+
+        write_header(
+            &mut out,
+            "erdos_callback_duration_seconds",
+            "Duration of operator callbacks.",
+            "histogram",
+        );
+        for op in self.operators.iter() {
+            let labels = format!(
+                "node=\"{}\",operator=\"{}\",operator_id=\"{}\"",
+                node,
+                escape(&op.name),
+                op.id
+            );
+            let histogram = &op.callback_duration;
+            let mut cumulative_count = 0;
+            for (bound, count) in histogram.bounds.iter().zip(histogram.counts.iter()) {
+                cumulative_count += count;
+                writeln!(
+                    out,
+                    "erdos_callback_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels, bound, cumulative_count
+                )
+                .unwrap();
+            }
+            writeln!(
+                out,
+                "erdos_callback_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                labels, histogram.count
+            )
+            .unwrap();
+            writeln!(
+                out,
+                "erdos_callback_duration_seconds_sum{{{}}} {}",
+                labels, histogram.sum
+            )
+            .unwrap();
+            writeln!(
+                out,
+                "erdos_callback_duration_seconds_count{{{}}} {}",
+                labels, histogram.count
+            )
+            .unwrap();
+        }
+
+        write_header(
+            &mut out,
+            "erdos_pending_events",
+            "Number of events in the execution lattice of an operator which have not completed.",
+            "gauge",
+        );
+        for op in self.operators.iter() {
+            writeln!(
+                out,
+                "erdos_pending_events{{node=\"{}\",operator=\"{}\",operator_id=\"{}\"}} {}",
+                node,
+                escape(&op.name),
+                op.id,
+                op.pending_events
+            )
+            .unwrap();
+        }
+
+        write_header(
+            &mut out,
+            "erdos_stream_messages_total",
+            "Number of messages sent on a stream.",
+            "counter",
+        );
+        for stream in self.streams.iter() {
+            writeln!(
+                out,
+                "erdos_stream_messages_total{{node=\"{}\",stream=\"{}\"}} {}",
+                node, stream.id, stream.messages_sent
+            )
+            .unwrap();
+        }
+
+        write_header(
+            &mut out,
+            "erdos_stream_bytes_total",
+            "Serialized size of the messages sent on a stream.",
+            "counter",
+        );
+        for stream in self.streams.iter() {
+            writeln!(
+                out,
+                "erdos_stream_bytes_total{{node=\"{}\",stream=\"{}\"}} {}",
+                node, stream.id, stream.bytes_sent
+            )
+            .unwrap();
+        }
+
+        write_header(
+            &mut out,
+            "erdos_stream_watermark_lag_seconds",
+            "Time since the oldest data message not covered by a watermark was sent on a stream.",
+            "gauge",
+        );
+        for stream in self.streams.iter() {
+            writeln!(
+                out,
+                "erdos_stream_watermark_lag_seconds{{node=\"{}\",stream=\"{}\"}} {}",
+                node,
+                stream.id,
+                stream.watermark_lag.as_secs_f64()
+            )
+            .unwrap();
+        }
+
+        write_header(
+            &mut out,
+            "erdos_channel_queue_depth",
+            "Number of data messages queued on a channel.",
+            "gauge",
+        );
+        for channel in self.channels.iter() {
+            writeln!(
+                out,
+                "erdos_channel_queue_depth{{node=\"{}\",stream=\"{}\",sink=\"{}\"}} {}",
+                node,
+                channel.stream_id,
+                escape(&channel.sink),
+                channel.queue_depth
+            )
+            .unwrap();
+        }
+
+        write_header(
+            &mut out,
+            "erdos_down_nodes",
+            "Number of other nodes which stopped sending heartbeats and did not reconnect yet.",
+            "gauge",
+        );
+        writeln!(
+            out,
+            "erdos_down_nodes{{node=\"{}\"}} {}",
+            node,
+            self.down_nodes.len()
+        )
+        .unwrap();
+        out
+    }
+}
+
+fn write_header(out: &mut String, name: &str, help: &str, metric_type: &str) {
+    writeln!(out, "# HELP {} {}", name, help).unwrap();
+    writeln!(out, "# TYPE {} {}", name, metric_type).unwrap();
+}
+
+/// Escapes a Prometheus label value.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Serves the metrics in the Prometheus text format on `address` at the `/metrics` path.
+pub(crate) async fn serve(address: SocketAddr, registry: Arc<MetricsRegistry>) -> io::Result<()> {
+    let mut listener = TcpListener::bind(address).await?;
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let registry = Arc::clone(&registry);
+        tokio::spawn(async move {
+            if let Err(e) = respond(stream, &registry).await {
+                slog::warn!(
+                    crate::TERMINAL_LOGGER,
+                    "Error responding to metrics request: {}",
+                    e
+                );
+            }
+        });
+    }
+}
+
+/// Responds to an HTTP request for the metrics.
+async fn respond(mut stream: TcpStream, registry: &MetricsRegistry) -> io::Result<()> {
+    let request = time::timeout(REQUEST_TIMEOUT, read_request(&mut stream))
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "Timed out reading the request"))??;
+    let request = String::from_utf8_lossy(&request);
+    let path = request.split_whitespace().nth(1).unwrap_or("");
+    let (status, body) = if path == "/metrics" {
+        ("200 OK", registry.snapshot().to_prometheus())
+    } else {
+        ("404 Not Found", String::new())
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown(std::net::Shutdown::Write)?;
+    Ok(())
+}
+
+/// Reads the headers of an HTTP request.
+async fn read_request(stream: &mut TcpStream) -> io::Result<Vec<u8>> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < 8192 {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+    Ok(request)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataflow::Message;
+
+    #[test]
+    fn test_watermark_lag() {
+        let registry = MetricsRegistry::new(0);
+        let stream_metrics = registry.register_stream(StreamId::nil());
+        stream_metrics.observe(&Message::new_message(Timestamp::new(vec![1]), 1usize));
+        stream_metrics.observe(&Message::new_message(Timestamp::new(vec![2]), 2usize));
+        stream_metrics.observe(&Message::<usize>::new_watermark(Timestamp::new(vec![1])));
+        std::thread::sleep(Duration::from_millis(10));
+
+        let snapshot = registry.snapshot();
+        let stream_snapshot = &snapshot.streams[0];
+        assert_eq!(stream_snapshot.messages_sent, 3);
+        assert!(stream_snapshot.bytes_sent > 0);
+        assert_eq!(stream_snapshot.low_watermark, Some(Timestamp::new(vec![1])));
+        assert!(stream_snapshot.watermark_lag >= Duration::from_millis(10));
+
+        stream_metrics.observe(&Message::<usize>::new_watermark(Timestamp::new(vec![2])));
+        assert_eq!(registry.snapshot().streams[0].watermark_lag, Duration::ZERO);
+        assert!(registry
+            .snapshot()
+            .to_prometheus()
+            .contains("erdos_stream_messages_total{node=\"0\",stream=\"00000000-0000-0000-0000-000000000000\"} 4"));
+    }
+}
@@ -5,21 +5,25 @@
 //! The [`new_app`](crate::new_app) helper function may be useful in scaling
 //! from one node to many via command line arguments.
 //!
-//! Currently, operators are manually scheduled to a [`Node`] via the
-//! [`OperatorConfig`](crate::dataflow::OperatorConfig). By default, they are
-//! scheduled on node 0. We are looking into more elegant solutions for
-//! scheduling operators, and hope to provide a versatile solution.
+//! Operators may be pinned to a [`Node`] via the
+//! [`OperatorConfig`](crate::dataflow::OperatorConfig). The remaining operators
+//! are placed by the [`Scheduler`](crate::scheduler::Scheduler) set in the
+//! [`Configuration`](crate::Configuration), or on node 0 if no scheduler is set.
 
 // Private submodules
+mod errors;
 mod lattice;
 mod node;
 
 // Crate-wide visible submodules
 pub(crate) mod operator_event;
+pub(crate) mod trace;
 
 // Public submodules
+pub mod metrics;
 #[doc(hidden)]
 pub mod operator_executor;
 
 // Public exports
+pub use errors::NodeError;
 pub use node::{Node, NodeHandle, NodeId};
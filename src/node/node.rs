@@ -2,38 +2,55 @@ use std::{
     collections::{HashMap, HashSet},
     sync::Arc,
     thread,
+    time::Duration,
 };
 
 use futures::future;
-use futures_util::stream::StreamExt;
+use serde::Deserialize;
 use slog;
 use tokio::{
     net::TcpStream,
     runtime::Builder,
     sync::{
-        mpsc::{self, Receiver, Sender, UnboundedReceiver},
+        mpsc::{self, Receiver, Sender, UnboundedReceiver, UnboundedSender},
         Mutex,
     },
+    task::JoinHandle,
+    time,
 };
-use tokio_util::codec::Framed;
 
 use crate::communication::{
     self,
     receivers::{self, ControlReceiver, DataReceiver},
     senders::{self, ControlSender, DataSender},
-    ControlMessage, ControlMessageCodec, ControlMessageHandler, MessageCodec,
+    ControlMessage, ControlMessageCodec, ControlMessageHandler, InterProcessMessage, MessageCodec,
+    MessageRecorder, Reconnector,
+};
+use crate::dataflow::{
+    checkpoint::{self, CheckpointStore},
+    graph::{default_graph, Graph, OperatorMetadata},
+    stream::{panic_message, ExtractStream, IngestStream},
+    Data, PanicPolicy, Timestamp,
+};
+use crate::node::{
+    metrics::{self, MetricsRegistry, MetricsSnapshot},
+    trace::TraceWriter,
+    NodeError,
 };
-use crate::dataflow::graph::default_graph;
 use crate::scheduler::{
     self,
     channel_manager::ChannelManager,
     endpoints_manager::{ChannelsToReceivers, ChannelsToSenders},
 };
-use crate::Configuration;
+use crate::{Configuration, OperatorId};
 
 /// Unique index for a [`Node`].
 pub type NodeId = usize;
 
+/// A dataflow graph to which a running [`Node`] is reconfigured, and a channel on which the
+/// result of the reconfiguration is returned.
+type ReconfigureRequest = (Graph, std::sync::mpsc::Sender<Result<(), String>>);
+
 /// Structure which executes a portion of an ERDOS application.
 ///
 /// The [`Node`] contains a runtime which executes operators and manages
@@ -55,6 +72,17 @@ pub struct Node {
     /// Channel used to shut down the node.
     shutdown_tx: Sender<()>,
     shutdown_rx: Option<Receiver<()>>,
+    /// Channel used to shut down the node once the dataflow is drained, or once the timeout sent
+    /// on the channel elapses.
+    drain_tx: UnboundedSender<Duration>,
+    drain_rx: Option<UnboundedReceiver<Duration>>,
+    /// Channel used to reconfigure the dataflow while the node runs.
+    reconfigure_tx: UnboundedSender<ReconfigureRequest>,
+    reconfigure_rx: Option<UnboundedReceiver<ReconfigureRequest>>,
+    /// Runtime metrics of the operators, streams, and channels on the node.
+    metrics: Arc<MetricsRegistry>,
+    /// Records the messages sent on streams originating on the node, if recording is enabled.
+    recorder: Option<Arc<MessageRecorder>>,
 }
 
 impl Node {
@@ -63,6 +91,8 @@ impl Node {
         let id = config.index;
         let logger = config.logger.clone();
         let (shutdown_tx, shutdown_rx) = mpsc::channel(1);
+        let (drain_tx, drain_rx) = mpsc::unbounded_channel();
+        let (reconfigure_tx, reconfigure_rx) = mpsc::unbounded_channel();
         Self {
             config,
             id,
@@ -72,13 +102,20 @@ impl Node {
             initialized: Arc::new((std::sync::Mutex::new(false), std::sync::Condvar::new())),
             shutdown_tx,
             shutdown_rx: Some(shutdown_rx),
+            drain_tx,
+            drain_rx: Some(drain_rx),
+            reconfigure_tx,
+            reconfigure_rx: Some(reconfigure_rx),
+            metrics: Arc::new(MetricsRegistry::new(id)),
+            recorder: None,
         }
     }
 
     /// Runs an ERDOS node.
     ///
-    /// The method never returns.
-    pub fn run(&mut self) {
+    /// The method returns once the node shuts down. Returns an error if the node or another node
+    /// of the dataflow failed.
+    pub fn run(&mut self) -> Result<(), NodeError> {
         slog::debug!(self.config.logger, "Node {}: running", self.id);
         // Build a runtime with n threads.
         let mut runtime = Builder::new()
@@ -88,8 +125,9 @@ impl Node {
             .enable_all()
             .build()
             .unwrap();
-        runtime.block_on(self.async_run());
+        let result = runtime.block_on(self.async_run());
         slog::debug!(self.config.logger, "Node {}: finished running", self.id);
+        result
     }
 
     /// Runs an ERDOS node in a seperate OS thread.
@@ -98,12 +136,18 @@ impl Node {
     pub fn run_async(mut self) -> NodeHandle {
         // Clone to avoid move to other thread.
         let shutdown_tx = self.shutdown_tx.clone();
+        let drain_tx = self.drain_tx.clone();
+        let reconfigure_tx = self.reconfigure_tx.clone();
         // Copy dataflow graph to the other thread
         let graph = default_graph::clone();
         let initialized = self.initialized.clone();
+        let metrics = Arc::clone(&self.metrics);
         let thread_handle = thread::spawn(move || {
             default_graph::set(graph);
-            self.run();
+            let result = self.run();
+            // Unblock the driver if the node failed before it finished initializing.
+            self.set_node_initialized();
+            result
         });
         // Wait for ERDOS to start up.
         let (lock, cvar) = &*initialized;
@@ -115,6 +159,9 @@ impl Node {
         NodeHandle {
             thread_handle,
             shutdown_tx,
+            drain_tx,
+            reconfigure_tx,
+            metrics,
         }
     }
 
@@ -128,16 +175,30 @@ impl Node {
     }
 
     /// Splits a vector of TCPStreams into `DataSender`s and `DataReceiver`s.
+    ///
+    /// Also returns a `Reconnector` for each TCPStream which re-establishes the connection if it
+    /// fails.
     async fn split_data_streams(
         &mut self,
         mut streams: Vec<(NodeId, TcpStream)>,
-    ) -> (Vec<DataSender>, Vec<DataReceiver>) {
+    ) -> (
+        Vec<DataSender>,
+        Vec<DataReceiver>,
+        Vec<Reconnector<MessageCodec, InterProcessMessage>>,
+    ) {
         let mut sink_halves = Vec::new();
         let mut stream_halves = Vec::new();
+        let mut reconnectors = Vec::new();
         while let Some((node_id, stream)) = streams.pop() {
             // Use the message codec to divide the TCP stream data into messages.
-            let framed = Framed::new(stream, MessageCodec::new());
-            let (split_sink, split_stream) = framed.split();
+            let (reconnector, split_sink, split_stream) = Reconnector::new(
+                self.id,
+                node_id,
+                self.config.data_addresses[node_id],
+                stream,
+                self.config.logger.clone(),
+            );
+            reconnectors.push(reconnector);
             // Create an ERDOS receiver for the stream half.
             stream_halves.push(
                 DataReceiver::new(
@@ -160,36 +221,53 @@ impl Node {
                 .await,
             );
         }
-        (sink_halves, stream_halves)
+        (sink_halves, stream_halves, reconnectors)
     }
 
-    /// Splits a vector of TCPStreams into `ControlMessageHandler`, `ControlSender`s and `ControlReceiver`s.
+    /// Splits a vector of TCPStreams into `ControlSender`s and `ControlReceiver`s.
+    ///
+    /// Also returns a `Reconnector` for each TCPStream which re-establishes the connection if it
+    /// fails.
     async fn split_control_streams(
         &mut self,
         streams: Vec<(NodeId, TcpStream)>,
-    ) -> (Vec<ControlSender>, Vec<ControlReceiver>) {
+    ) -> (
+        Vec<ControlSender>,
+        Vec<ControlReceiver>,
+        Vec<Reconnector<ControlMessageCodec, ControlMessage>>,
+    ) {
         let mut control_receivers = Vec::new();
         let mut control_senders = Vec::new();
+        let mut reconnectors = Vec::new();
 
         for (node_id, stream) in streams {
             // Use the message codec to divide the TCP stream data into messages.
-            let framed = Framed::new(stream, ControlMessageCodec::new());
-            let (split_sink, split_stream) = framed.split();
+            let (reconnector, split_sink, split_stream) = Reconnector::new(
+                self.id,
+                node_id,
+                self.config.control_addresses[node_id],
+                stream,
+                self.config.logger.clone(),
+            );
+            reconnectors.push(reconnector);
             // Create an control receiver for the stream half.
             control_receivers.push(ControlReceiver::new(
                 node_id,
+                self.config.heartbeat_timeout,
                 split_stream,
                 &mut self.control_handler,
             ));
             // Create an control sender for the sink half.
             control_senders.push(ControlSender::new(
                 node_id,
+                self.id,
+                self.config.heartbeat_interval,
                 split_sink,
                 &mut self.control_handler,
             ));
         }
 
-        (control_senders, control_receivers)
+        (control_senders, control_receivers, reconnectors)
     }
 
     async fn wait_for_communication_layer_initialized(&mut self) -> Result<(), String> {
@@ -235,16 +313,23 @@ impl Node {
 
     async fn wait_for_local_operators_initialized(
         &mut self,
-        mut rx_from_operators: UnboundedReceiver<ControlMessage>,
+        rx_from_operators: &mut UnboundedReceiver<ControlMessage>,
         num_local_operators: usize,
-    ) {
+    ) -> Result<(), NodeError> {
         let mut initialized_operators = HashSet::new();
         while initialized_operators.len() < num_local_operators {
-            if let Some(ControlMessage::OperatorInitialized(op_id)) = rx_from_operators.recv().await
-            {
-                initialized_operators.insert(op_id);
+            match rx_from_operators.recv().await {
+                Some(ControlMessage::OperatorInitialized(op_id)) => {
+                    initialized_operators.insert(op_id);
+                }
+                Some(msg @ ControlMessage::OperatorFailed(_, _))
+                | Some(msg @ ControlMessage::OperatorPanicked(_, _, _)) => {
+                    self.handle_control_message(msg)?;
+                }
+                _ => (),
             }
         }
+        Ok(())
     }
 
     async fn broadcast_local_operators_initialized(&mut self) -> Result<(), String> {
@@ -258,7 +343,7 @@ impl Node {
             .map_err(|e| format!("Error broadcasting control message: {:?}", e))
     }
 
-    async fn wait_for_all_operators_initialized(&mut self) -> Result<(), String> {
+    async fn wait_for_all_operators_initialized(&mut self) -> Result<(), NodeError> {
         let num_nodes = self.config.data_addresses.len();
         let mut initialized_nodes = HashSet::new();
         initialized_nodes.insert(self.id);
@@ -268,32 +353,69 @@ impl Node {
                 .read_all_operators_initialized_on_node_msg()
                 .await
             {
-                Ok(node_id) => {
+                Ok(ControlMessage::AllOperatorsInitializedOnNode(node_id)) => {
                     initialized_nodes.insert(node_id);
                 }
+                Ok(msg) => self.handle_control_message(msg)?,
                 Err(e) => {
-                    return Err(format!("Error waiting for other nodes to set up: {:?}", e));
+                    return Err(NodeError::CommunicationError(format!(
+                        "Error waiting for other nodes to set up: {:?}",
+                        e
+                    )));
                 }
             }
         }
         Ok(())
     }
 
-    async fn run_operators(&mut self) -> Result<(), String> {
-        self.wait_for_communication_layer_initialized().await?;
+    async fn run_operators(&mut self) -> Result<(), NodeError> {
+        self.wait_for_communication_layer_initialized()
+            .await
+            .map_err(NodeError::CommunicationError)?;
 
-        let graph = scheduler::schedule(&default_graph::clone());
+        let graph = scheduler::schedule(
+            &default_graph::clone(),
+            self.config.scheduler.as_deref(),
+            &self.config.data_addresses,
+            self.config.shared_memory_size.is_some(),
+        )
+        .map_err(NodeError::DataflowError)?;
         if let Some(filename) = &self.config.graph_filename {
-            graph.to_dot(filename.as_str()).map_err(|e| e.to_string())?;
+            graph
+                .to_dot(filename.as_str())
+                .map_err(|e| NodeError::DataflowError(e.to_string()))?;
         }
 
+        let recorder = match &self.config.record_filename {
+            Some(filename) => Some(
+                MessageRecorder::new(filename, self.config.recorded_streams.clone()).map_err(
+                    |e| {
+                        NodeError::DataflowError(format!(
+                            "Error creating message log {}: {:?}",
+                            filename, e
+                        ))
+                    },
+                )?,
+            ),
+            None => None,
+        };
+        self.recorder = recorder.clone();
+        let trace_writer = match &self.config.trace_filename {
+            Some(filename) => Some(TraceWriter::new(filename).map_err(|e| {
+                NodeError::DataflowError(format!("Error creating trace file {}: {}", filename, e))
+            })?),
+            None => None,
+        };
         let channel_manager = ChannelManager::new(
             &graph,
-            self.id,
+            &self.config,
             Arc::clone(&self.channels_to_receivers),
             Arc::clone(&self.channels_to_senders),
+            recorder,
+            &self.metrics,
         )
-        .await;
+        .await
+        .map_err(NodeError::DataflowError)?;
         // Execute operators scheduled on the current node.
         let channel_manager = Arc::new(std::sync::Mutex::new(channel_manager));
         let local_operators: Vec<_> = graph
@@ -302,149 +424,697 @@ impl Node {
             .filter(|op| op.node_id == self.id)
             .collect();
 
-        let (operator_tx, rx_from_operators) = mpsc::unbounded_channel();
+        // Restore operators from the latest timestamp checkpointed by all states.
+        let recovery_time = match &self.config.checkpoint_store {
+            Some(store) if self.config.recover_from_checkpoint => {
+                let operator_ids: Vec<_> = graph.get_operators().iter().map(|op| op.id).collect();
+                let recovery_time =
+                    checkpoint::latest_consistent_checkpoint(store.as_ref(), &operator_ids)
+                        .map_err(|e| {
+                            NodeError::DataflowError(format!("Error reading checkpoints: {:?}", e))
+                        })?;
+                slog::info!(
+                    self.config.logger,
+                    "Node {}: recovering from checkpoint {:?}",
+                    self.id,
+                    recovery_time
+                );
+                recovery_time
+            }
+            _ => None,
+        };
+
+        let (operator_tx, mut rx_from_operators) = mpsc::unbounded_channel();
+        let mut spawner = OperatorSpawner {
+            channel_manager: Arc::clone(&channel_manager),
+            operator_handles: Vec::new(),
+            operator_tx,
+            checkpoint_store: self.config.checkpoint_store.clone(),
+            checkpoints_to_keep: self.config.checkpoints_to_keep,
+            metrics: Arc::clone(&self.metrics),
+            trace_writer,
+            logger: self.config.logger.clone(),
+        };
         let mut channels_to_operators = HashMap::new();
 
         let num_local_operators = local_operators.len();
-
-        let mut join_handles = Vec::with_capacity(num_local_operators);
         for operator_info in local_operators {
-            let name = operator_info
-                .name
-                .clone()
-                .unwrap_or_else(|| format!("{}", operator_info.id));
-            slog::debug!(
-                self.config.logger,
-                "Node {}: starting operator {}",
-                self.id,
-                name
-            );
-            let channel_manager_copy = Arc::clone(&channel_manager);
-            let operator_tx_copy = operator_tx.clone();
-            let (tx, rx) = mpsc::unbounded_channel();
+            let tx = spawner.spawn(operator_info.clone(), recovery_time.clone());
             channels_to_operators.insert(operator_info.id, tx);
-            // Launch the operator as a separate async task.
-            let join_handle = tokio::spawn(async move {
-                let mut operator_executor =
-                    (operator_info.runner)(channel_manager_copy, operator_tx_copy, rx);
-                operator_executor.execute().await;
-            });
-            join_handles.push(join_handle);
         }
 
         // Wait for all operators to finish setting up.
-        self.wait_for_local_operators_initialized(rx_from_operators, num_local_operators)
-            .await;
+        self.wait_for_local_operators_initialized(&mut rx_from_operators, num_local_operators)
+            .await?;
         // Setup driver on the current node.
         if let Some(driver) = graph.get_driver(self.id) {
-            for setup_hook in driver.setup_hooks {
+            for setup_hook in driver.setup_hooks() {
                 (setup_hook)(Arc::clone(&channel_manager));
             }
         }
         // Broadcast all operators initialized on current node.
-        self.broadcast_local_operators_initialized().await?;
+        self.broadcast_local_operators_initialized()
+            .await
+            .map_err(NodeError::CommunicationError)?;
         // Wait for all other nodes to finish setting up.
         self.wait_for_all_operators_initialized().await?;
         // Tell driver to run.
         self.set_node_initialized();
         // Tell all operators to run.
-        for (op_id, tx) in channels_to_operators {
+        for (op_id, tx) in channels_to_operators.iter() {
+            tx.send(ControlMessage::RunOperator(*op_id)).map_err(|e| {
+                NodeError::DataflowError(format!("Error telling operator to run: {}", e))
+            })?;
+        }
+        // Handle control messages and reconfigure the dataflow until the node shuts down.
+        let mut graph = graph;
+        let mut reconfigure_rx = self.reconfigure_rx.take().unwrap();
+        let mut drain_rx = self.drain_rx.take().unwrap();
+        let mut drain_timeout = None;
+        loop {
+            tokio::select! {
+                msg = self.control_handler.read() => {
+                    let msg = msg.map_err(|e| {
+                        NodeError::CommunicationError(format!(
+                            "Error receiving control message: {:?}",
+                            e
+                        ))
+                    })?;
+                    if let ControlMessage::Shutdown(node_id) = msg {
+                        slog::debug!(
+                            self.config.logger,
+                            "Node {}: node {} requested to shut down",
+                            self.id,
+                            node_id
+                        );
+                        break;
+                    }
+                    self.handle_control_message(msg)?;
+                }
+                Some(msg) = rx_from_operators.recv() => self.handle_control_message(msg)?,
+                Some(timeout) = drain_rx.recv() => {
+                    // Shut down the other nodes as well.
+                    self.control_handler
+                        .broadcast_to_nodes(ControlMessage::Shutdown(self.id))
+                        .map_err(|e| {
+                            NodeError::CommunicationError(format!(
+                                "Error broadcasting control message: {:?}",
+                                e
+                            ))
+                        })?;
+                    drain_timeout = Some(timeout);
+                    break;
+                }
+                Some((new_graph, result_tx)) = reconfigure_rx.recv() => {
+                    let result = self
+                        .reconfigure(
+                            &mut graph,
+                            &new_graph,
+                            &mut spawner,
+                            &mut rx_from_operators,
+                            &mut channels_to_operators,
+                        )
+                        .await;
+                    if let Err(e) = &result {
+                        slog::error!(
+                            self.config.logger,
+                            "Node {}: failed to reconfigure the dataflow: {}",
+                            self.id,
+                            e
+                        );
+                    }
+                    result_tx.send(result).ok();
+                }
+            }
+        }
+        let drain_fut = self.drain(
+            &graph,
+            &mut spawner,
+            &mut rx_from_operators,
+            &channels_to_operators,
+        );
+        match drain_timeout {
+            Some(timeout) => match time::timeout(timeout, drain_fut).await {
+                Ok(result) => result,
+                Err(_) => {
+                    // Stop the node as if it were shut down immediately.
+                    slog::warn!(
+                        self.config.logger,
+                        "Node {}: the dataflow did not drain within {:?}, shutting down",
+                        self.id,
+                        timeout
+                    );
+                    Ok(())
+                }
+            },
+            None => drain_fut.await,
+        }
+    }
+
+    /// Drains the dataflow before the node shuts down.
+    ///
+    /// Closes the ingest streams of the driver, waits for the operators on the node to process
+    /// their input streams and to be destroyed, and sends the messages queued for other nodes.
+    async fn drain(
+        &mut self,
+        graph: &Graph,
+        spawner: &mut OperatorSpawner,
+        rx_from_operators: &mut UnboundedReceiver<ControlMessage>,
+        channels_to_operators: &HashMap<OperatorId, UnboundedSender<ControlMessage>>,
+    ) -> Result<(), NodeError> {
+        slog::debug!(
+            self.config.logger,
+            "Node {}: draining the dataflow",
+            self.id
+        );
+        if let Some(driver) = graph.get_driver(self.id) {
+            for close_hook in driver.ingest_close_hooks.iter() {
+                (close_hook)();
+            }
+        }
+        // Operators which already finished running dropped their channels.
+        for tx in channels_to_operators.values() {
+            tx.send(ControlMessage::Shutdown(self.id)).ok();
+        }
+        // Keep handling control messages in case another node or an operator fails.
+        let join_fut = spawner.join_operators();
+        tokio::pin!(join_fut);
+        loop {
+            tokio::select! {
+                _ = &mut join_fut => break,
+                msg = self.control_handler.read() => {
+                    let msg = msg.map_err(|e| {
+                        NodeError::CommunicationError(format!(
+                            "Error receiving control message: {:?}",
+                            e
+                        ))
+                    })?;
+                    self.handle_control_message(msg)?;
+                }
+                Some(msg) = rx_from_operators.recv() => self.handle_control_message(msg)?,
+            }
+        }
+
+        // Wait for the data senders to nodes which are up to send their queued messages.
+        let num_nodes = self.config.data_addresses.len();
+        let mut flushed_nodes: HashSet<NodeId> = (0..num_nodes)
+            .filter(|&node_id| node_id == self.id || self.control_handler.is_node_down(node_id))
+            .collect();
+        self.control_handler
+            .broadcast_to_data_senders(ControlMessage::Shutdown(self.id))
+            .map_err(|e| {
+                NodeError::CommunicationError(format!(
+                    "Error broadcasting control message: {:?}",
+                    e
+                ))
+            })?;
+        while flushed_nodes.len() < num_nodes {
+            let msg = self.control_handler.read().await.map_err(|e| {
+                NodeError::CommunicationError(format!("Error receiving control message: {:?}", e))
+            })?;
+            match msg {
+                ControlMessage::DataSenderFlushed(node_id) => {
+                    flushed_nodes.insert(node_id);
+                }
+                ControlMessage::NodeDown(node_id) => {
+                    self.handle_control_message(msg)?;
+                    flushed_nodes.insert(node_id);
+                }
+                msg => self.handle_control_message(msg)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Reconfigures the running dataflow from `graph` to `new_graph`.
+    ///
+    /// Operators which remain in the dataflow keep running on their nodes. Operators removed from
+    /// the dataflow are stopped, and new operators are scheduled, set up, and run. Channels are
+    /// added to and removed from the running streams.
+    async fn reconfigure(
+        &mut self,
+        graph: &mut Graph,
+        new_graph: &Graph,
+        spawner: &mut OperatorSpawner,
+        rx_from_operators: &mut UnboundedReceiver<ControlMessage>,
+        channels_to_operators: &mut HashMap<OperatorId, UnboundedSender<ControlMessage>>,
+    ) -> Result<(), String> {
+        // Keep the running operators on their nodes.
+        let mut unscheduled_graph = new_graph.clone();
+        for operator in graph.get_operators() {
+            if new_graph.get_operator(operator.id).is_some() {
+                unscheduled_graph.pin_operator(operator.id, operator.node_id);
+            }
+        }
+        let new_graph = scheduler::schedule(
+            &unscheduled_graph,
+            self.config.scheduler.as_deref(),
+            &self.config.data_addresses,
+            self.config.shared_memory_size.is_some(),
+        )?;
+        if let Some(filename) = &self.config.graph_filename {
+            new_graph
+                .to_dot(filename.as_str())
+                .map_err(|e| e.to_string())?;
+        }
+
+        let mut channels_to_receivers = self.channels_to_receivers.lock().await;
+        let channels_to_senders = self.channels_to_senders.lock().await;
+        spawner.channel_manager.lock().unwrap().reconfigure(
+            &new_graph,
+            &mut channels_to_receivers,
+            &channels_to_senders,
+            &self.metrics,
+        )?;
+        drop(channels_to_receivers);
+        drop(channels_to_senders);
+
+        // Stop the operators removed from the dataflow.
+        for operator in graph.get_operators() {
+            if operator.node_id == self.id && new_graph.get_operator(operator.id).is_none() {
+                slog::debug!(
+                    self.config.logger,
+                    "Node {}: removing operator {}",
+                    self.id,
+                    operator.id
+                );
+                if let Some(tx) = channels_to_operators.remove(&operator.id) {
+                    tx.send(ControlMessage::RemoveOperator(operator.id)).ok();
+                }
+                self.metrics.unregister_operator(operator.id);
+            }
+        }
+
+        // Start the operators added to the dataflow.
+        let new_operators: Vec<_> = new_graph
+            .get_operators()
+            .into_iter()
+            .filter(|op| op.node_id == self.id && graph.get_operator(op.id).is_none())
+            .collect();
+        let mut new_channels_to_operators = Vec::with_capacity(new_operators.len());
+        for operator_info in new_operators.iter() {
+            let tx = spawner.spawn(operator_info.clone(), None);
+            new_channels_to_operators.push((operator_info.id, tx));
+        }
+        self.wait_for_local_operators_initialized(rx_from_operators, new_operators.len())
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+        // Set up the streams added to the driver.
+        if let Some(driver) = new_graph.get_driver(self.id) {
+            let old_stream_ids: HashSet<_> = graph
+                .get_driver(self.id)
+                .map(|old_driver| {
+                    old_driver
+                        .ingest_stream_ids
+                        .into_iter()
+                        .chain(old_driver.extract_stream_ids)
+                        .collect()
+                })
+                .unwrap_or_default();
+            let stream_ids = driver
+                .ingest_stream_ids
+                .iter()
+                .chain(driver.extract_stream_ids.iter());
+            for (stream_id, setup_hook) in stream_ids.zip(driver.setup_hooks()) {
+                if !old_stream_ids.contains(stream_id) {
+                    (setup_hook)(Arc::clone(&spawner.channel_manager));
+                }
+            }
+        }
+        for (op_id, tx) in new_channels_to_operators {
             tx.send(ControlMessage::RunOperator(op_id))
                 .map_err(|e| format!("Error telling operator to run: {}", e))?;
+            channels_to_operators.insert(op_id, tx);
         }
-        // Wait for all operators to finish running.
-        future::join_all(join_handles).await;
+
+        *graph = new_graph;
         Ok(())
     }
 
-    async fn async_run(&mut self) {
+    /// Handles a control message received while the node runs.
+    ///
+    /// Returns an error if another node failed, or if an operator on the node panicked with the
+    /// [`PanicPolicy::FailNode`] policy.
+    fn handle_control_message(&mut self, msg: ControlMessage) -> Result<(), NodeError> {
+        match msg {
+            ControlMessage::NodeDown(node_id) => {
+                let newly_down = self
+                    .control_handler
+                    .handle_node_down(node_id)
+                    .map_err(|e| {
+                        NodeError::CommunicationError(format!(
+                            "Error broadcasting control message: {:?}",
+                            e
+                        ))
+                    })?;
+                self.metrics.set_node_down(node_id, true);
+                if newly_down {
+                    slog::error!(
+                        self.config.logger,
+                        "Node {}: node {} is down",
+                        self.id,
+                        node_id
+                    );
+                }
+            }
+            ControlMessage::NodeReconnected(node_id) => {
+                self.metrics.set_node_down(node_id, false);
+                if self.control_handler.handle_node_reconnected(node_id) {
+                    slog::info!(
+                        self.config.logger,
+                        "Node {}: node {} reconnected",
+                        self.id,
+                        node_id
+                    );
+                }
+            }
+            // The node is already shutting down.
+            ControlMessage::Shutdown(_) => (),
+            ControlMessage::NodeFailed(node_id, reason) => {
+                slog::error!(
+                    self.config.logger,
+                    "Node {}: stopping because node {} failed: {}",
+                    self.id,
+                    node_id,
+                    reason
+                );
+                return Err(NodeError::NodeFailed(node_id, reason));
+            }
+            ControlMessage::OperatorPanicked(op_id, msg, PanicPolicy::FailNode) => {
+                return Err(NodeError::DataflowError(format!(
+                    "Operator {} panicked: {}",
+                    op_id, msg
+                )));
+            }
+            // The operator logged the panic and handles it.
+            ControlMessage::OperatorPanicked(_, _, _) => (),
+            ControlMessage::OperatorFailed(op_id, msg) => {
+                return Err(NodeError::DataflowError(format!(
+                    "Operator {} failed: {}",
+                    op_id, msg
+                )));
+            }
+            msg => slog::warn!(
+                self.config.logger,
+                "Node {}: received unexpected control message {:?}",
+                self.id,
+                msg
+            ),
+        }
+        Ok(())
+    }
+
+    async fn async_run(&mut self) -> Result<(), NodeError> {
         // Assign values used later to avoid lifetime errors.
         let num_nodes = self.config.data_addresses.len();
         let logger = self.config.logger.clone();
         // Create TCPStreams between all node pairs.
-        let control_streams = communication::create_tcp_streams(
+        let (control_streams, control_listener) = communication::create_tcp_streams(
             self.config.control_addresses.clone(),
             self.id,
             &self.config.logger,
         )
         .await;
-        let data_streams = communication::create_tcp_streams(
+        let (data_streams, data_listener) = communication::create_tcp_streams(
             self.config.data_addresses.clone(),
             self.id,
             &self.config.logger,
         )
         .await;
-        let (control_senders, control_receivers) =
+        let (control_senders, control_receivers, control_reconnectors) =
             self.split_control_streams(control_streams).await;
-        let (senders, receivers) = self.split_data_streams(data_streams).await;
+        let (senders, receivers, data_reconnectors) = self.split_data_streams(data_streams).await;
+        // Serve the metrics over HTTP.
+        if let Some(address) = self.config.metrics_address {
+            let metrics_logger = logger.clone();
+            let serve_fut = metrics::serve(address, Arc::clone(&self.metrics));
+            tokio::spawn(async move {
+                if let Err(e) = serve_fut.await {
+                    slog::error!(
+                        metrics_logger,
+                        "Failed to serve metrics on {}: {}",
+                        address,
+                        e
+                    );
+                }
+            });
+        }
         // Listen for shutdown message.
         let mut shutdown_rx = self.shutdown_rx.take().unwrap();
         let shutdown_fut = shutdown_rx.recv();
         // Execute threads that send data to other nodes.
         let control_senders_fut = senders::run_control_senders(control_senders);
+        tokio::pin!(control_senders_fut);
         let senders_fut = senders::run_senders(senders);
         // Execute threads that receive data from other nodes.
         let control_recvs_fut = receivers::run_control_receivers(control_receivers);
         let recvs_fut = receivers::run_receivers(receivers);
+        // Re-establish failed connections to other nodes.
+        let control_reconnectors_fut =
+            communication::run_reconnectors(control_reconnectors, control_listener, logger.clone());
+        let data_reconnectors_fut =
+            communication::run_reconnectors(data_reconnectors, data_listener, logger.clone());
         // Execute operators.
         let ops_fut = self.run_operators();
+        let mut control_senders_failed = false;
         // These threads only complete when a failure happens.
-        if num_nodes <= 1 {
+        let result = if num_nodes <= 1 {
             // Senders and Receivers should return if there's only 1 node.
-            if let Err(e) = tokio::try_join!(
+            match tokio::try_join!(
                 senders_fut,
                 recvs_fut,
-                control_senders_fut,
+                &mut control_senders_fut,
                 control_recvs_fut
             ) {
-                slog::error!(
-                    logger,
-                    "Non-fatal network communication error; this should not happen! {:?}",
-                    e
-                );
-            }
-            tokio::select! {
-                Err(e) = ops_fut => slog::error!(
-                    logger,
-                    "Error running operators on node {:?}: {:?}", self.id, e
-                ),
-                _ = shutdown_fut => slog::debug!(logger, "Node {}: shutting down", self.id),
+                Ok(_) => tokio::select! {
+                    result = ops_fut => result,
+                    _ = shutdown_fut => Ok(()),
+                },
+                Err(e) => {
+                    drop(ops_fut);
+                    Err(NodeError::CommunicationError(format!(
+                        "Error with network communication: {:?}",
+                        e
+                    )))
+                }
             }
         } else {
             tokio::select! {
-                Err(e) = senders_fut => slog::error!(logger, "Error with data senders: {:?}", e),
-                Err(e) = recvs_fut => slog::error!(logger, "Error with data receivers: {:?}", e),
-                Err(e) = control_senders_fut => slog::error!(logger, "Error with control senders: {:?}", e),
-                Err(e) = control_recvs_fut => slog::error!(
-                    self.config.logger,
-                    "Error with control receivers: {:?}", e
-                ),
-                Err(e) = ops_fut => slog::error!(
-                    logger,
-                    "Error running operators on node {:?}: {:?}", self.id, e
-                ),
-                _ = shutdown_fut => slog::debug!(logger, "Node {}: shutting down", self.id),
+                Err(e) = senders_fut => Err(NodeError::CommunicationError(
+                    format!("Error with data senders: {:?}", e)
+                )),
+                Err(e) = recvs_fut => Err(NodeError::CommunicationError(
+                    format!("Error with data receivers: {:?}", e)
+                )),
+                Err(e) = &mut control_senders_fut => {
+                    control_senders_failed = true;
+                    Err(NodeError::CommunicationError(
+                        format!("Error with control senders: {:?}", e)
+                    ))
+                },
+                Err(e) = control_recvs_fut => Err(NodeError::CommunicationError(
+                    format!("Error with control receivers: {:?}", e)
+                )),
+                Err(e) = control_reconnectors_fut => Err(NodeError::CommunicationError(
+                    format!("Error re-establishing control connections: {:?}", e)
+                )),
+                Err(e) = data_reconnectors_fut => Err(NodeError::CommunicationError(
+                    format!("Error re-establishing data connections: {:?}", e)
+                )),
+                result = ops_fut => result,
+                _ = shutdown_fut => Ok(()),
             }
+        };
+        if let Some(recorder) = &self.recorder {
+            recorder.flush();
         }
+        match &result {
+            Ok(()) => slog::debug!(logger, "Node {}: shutting down", self.id),
+            // The node which failed notified the other nodes.
+            Err(NodeError::NodeFailed(_, _)) => (),
+            Err(e) => {
+                slog::error!(logger, "Node {}: failed: {:?}", self.id, e);
+                if num_nodes > 1 && !control_senders_failed {
+                    // Stop the other nodes, and wait for the control senders to notify them.
+                    let msg = ControlMessage::NodeFailed(self.id, format!("{:?}", e));
+                    if self.control_handler.broadcast_to_nodes(msg).is_ok() {
+                        time::timeout(self.config.heartbeat_timeout, control_senders_fut)
+                            .await
+                            .ok();
+                    }
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Spawns the operators which run on a node.
+struct OperatorSpawner {
+    channel_manager: Arc<std::sync::Mutex<ChannelManager>>,
+    /// Completes once the spawned operators finish executing.
+    operator_handles: Vec<JoinHandle<()>>,
+    /// Used by the operators to notify the node that they are initialized.
+    operator_tx: UnboundedSender<ControlMessage>,
+    checkpoint_store: Option<Arc<dyn CheckpointStore>>,
+    checkpoints_to_keep: usize,
+    metrics: Arc<MetricsRegistry>,
+    trace_writer: Option<Arc<TraceWriter>>,
+    logger: slog::Logger,
+}
+
+impl OperatorSpawner {
+    /// Launches an operator as a separate async task, which restores its state from
+    /// `recovery_time` if set. Returns the channel used to send control messages to the operator.
+    fn spawn(
+        &mut self,
+        operator_info: OperatorMetadata,
+        recovery_time: Option<Timestamp>,
+    ) -> UnboundedSender<ControlMessage> {
+        let name = operator_info
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("{}", operator_info.id));
+        slog::debug!(
+            self.logger,
+            "Node {}: starting operator {}",
+            operator_info.node_id,
+            name
+        );
+        let channel_manager_copy = Arc::clone(&self.channel_manager);
+        let operator_tx_copy = self.operator_tx.clone();
+        let initialized_tx = self.operator_tx.clone();
+        let (tx, rx) = mpsc::unbounded_channel();
+        let checkpoint_store = self.checkpoint_store.clone();
+        let checkpoints_to_keep = self.checkpoints_to_keep;
+        let metrics = Arc::clone(&self.metrics);
+        let trace_writer = self.trace_writer.clone();
+        let handle = tokio::spawn(async move {
+            let mut operator_executor =
+                (operator_info.runner)(channel_manager_copy, operator_tx_copy, rx);
+            operator_executor.register_metrics(&metrics);
+            if let Some(writer) = trace_writer {
+                operator_executor.set_trace_writer(writer);
+            }
+            if let Some(store) = checkpoint_store {
+                if let Err(e) =
+                    operator_executor.setup_checkpoints(store, recovery_time, checkpoints_to_keep)
+                {
+                    let msg = format!("Failed to set up checkpoints for {}: {:?}", name, e);
+                    initialized_tx
+                        .send(ControlMessage::OperatorFailed(operator_info.id, msg))
+                        .ok();
+                    return;
+                }
+            }
+            // Notify the node that the operator is done setting up.
+            initialized_tx
+                .send(ControlMessage::OperatorInitialized(operator_info.id))
+                .ok();
+            operator_executor.execute().await;
+        });
+        self.operator_handles.push(handle);
+        tx
+    }
+
+    /// Waits for the spawned operators to finish executing.
+    async fn join_operators(&mut self) {
+        future::join_all(self.operator_handles.drain(..)).await;
     }
 }
 
 /// Handle to a [`Node`] running asynchronously.
 pub struct NodeHandle {
-    thread_handle: thread::JoinHandle<()>,
+    thread_handle: thread::JoinHandle<Result<(), NodeError>>,
     shutdown_tx: Sender<()>,
+    drain_tx: UnboundedSender<Duration>,
+    reconfigure_tx: UnboundedSender<ReconfigureRequest>,
+    metrics: Arc<MetricsRegistry>,
 }
 
 // TODO: distinguish between shutting down the dataflow and shutting down the node.
 impl NodeHandle {
     /// Waits for the associated [`Node`] to finish.
-    pub fn join(self) -> Result<(), String> {
-        self.thread_handle.join().map_err(|e| format!("{:?}", e))
+    ///
+    /// Returns an error if the node or another node of the dataflow failed.
+    pub fn join(self) -> Result<(), NodeError> {
+        self.thread_handle.join().map_err(|payload| {
+            NodeError::DataflowError(format!(
+                "The node panicked: {}",
+                panic_message(payload.as_ref())
+            ))
+        })?
+    }
+    /// Returns the current runtime metrics of the [`Node`].
+    pub fn metrics(&self) -> MetricsSnapshot {
+        self.metrics.snapshot()
+    }
+    /// Reconfigures the running dataflow to match the dataflow graph of the driver.
+    ///
+    /// Operators, ingest streams, and extract streams which were connected since the [`Node`]
+    /// started are scheduled and run, and the ones which were removed are stopped. Blocks until
+    /// the new operators are set up and running.
+    ///
+    /// Must be called from the driver's thread. In a dataflow which runs on several nodes, every
+    /// driver must make the same changes and reconfigure its node. The nodes do not coordinate, so
+    /// messages which a node receives on a stream before it reconfigured are kept until it does.
+    pub fn reconfigure(&self) -> Result<(), String> {
+        let (result_tx, result_rx) = std::sync::mpsc::channel();
+        self.reconfigure_tx
+            .send((default_graph::clone(), result_tx))
+            .map_err(|_| "The node is not running".to_string())?;
+        result_rx
+            .recv()
+            .map_err(|_| "The node stopped while reconfiguring".to_string())?
+    }
+    /// Stops the operator and removes it from the running dataflow.
+    ///
+    /// Fails if other operators or extract streams still read the operator's write streams.
+    pub fn remove_operator(&self, operator_id: OperatorId) -> Result<(), String> {
+        default_graph::remove_operator(operator_id)?;
+        self.reconfigure()
+    }
+    /// Removes the ingest stream from the running dataflow.
+    ///
+    /// Fails if operators or extract streams still read from the ingest stream.
+    pub fn remove_ingest_stream<D>(&self, ingest_stream: &IngestStream<D>) -> Result<(), String>
+    where
+        for<'a> D: Data + Deserialize<'a>,
+    {
+        default_graph::remove_ingest_stream(ingest_stream)?;
+        self.reconfigure()
+    }
+    /// Removes the extract stream from the running dataflow.
+    pub fn remove_extract_stream<D>(&self, extract_stream: &ExtractStream<D>) -> Result<(), String>
+    where
+        for<'a> D: Data + Deserialize<'a>,
+    {
+        default_graph::remove_extract_stream(extract_stream)?;
+        self.reconfigure()
     }
     /// Blocks until the [`Node`] shuts down.
-    pub fn shutdown(mut self) -> Result<(), String> {
+    ///
+    /// The node stops immediately, so messages which are being processed or sent may be lost.
+    pub fn shutdown(mut self) -> Result<(), NodeError> {
         // Error indicates node is already shutting down.
         self.shutdown_tx.try_send(()).ok();
-        self.thread_handle.join().map_err(|e| format!("{:?}", e))
+        self.join()
+    }
+    /// Drains the dataflow, and blocks until the [`Node`] shuts down.
+    ///
+    /// Sends top watermarks on the ingest streams of the driver, and waits for the operators on
+    /// the node to process their input streams and call
+    /// [`Operator::destroy`](crate::dataflow::Operator::destroy). The messages queued for other
+    /// nodes are sent before the node shuts down. The other nodes are asked to drain their part of
+    /// the dataflow and shut down as well.
+    ///
+    /// Timers stop once the node starts shutting down, but operators which never close their write
+    /// streams prevent the dataflow from draining. If the node does not finish draining within
+    /// `timeout`, it stops as with [`NodeHandle::shutdown`]. Messages sent on the ingest streams
+    /// afterwards are rejected.
+    pub fn shutdown_gracefully(self, timeout: Duration) -> Result<(), NodeError> {
+        // Error indicates node is already shutting down.
+        self.drain_tx.send(timeout).ok();
+        self.join()
     }
 }
@@ -1,6 +1,9 @@
 use std::{cmp::Ordering, collections::HashSet, fmt};
 
-use crate::{dataflow::Timestamp, Uuid};
+use crate::{
+    dataflow::{Timestamp, TimestampT},
+    Uuid,
+};
 
 /// `OperatorEvent` is a structure that encapsulates a particular invocation of the
 /// callback in response to a message or watermark. These events are processed according to the
@@ -89,6 +92,13 @@ impl PartialEq for OperatorEvent {
     }
 }
 
+/// Returns `true` if either event writes to an item the other event accesses.
+fn has_conflicts(x: &OperatorEvent, y: &OperatorEvent) -> bool {
+    !x.write_ids.is_disjoint(&y.write_ids)
+        || !x.read_ids.is_disjoint(&y.write_ids)
+        || !x.write_ids.is_disjoint(&y.read_ids)
+}
+
 // TODO: we can allow appends to occur in parallel.
 /// `x < y` implies `x` *precedes* `y`.
 fn resolve_access_conflicts(x: &OperatorEvent, y: &OperatorEvent) -> Ordering {
@@ -119,6 +129,9 @@ fn resolve_access_conflicts(x: &OperatorEvent, y: &OperatorEvent) -> Ordering {
 }
 
 /// Ordering used in the lattice where `self < other` implies `self` *precedes* other.
+///
+/// Timestamps are compared using the partial order defined by [`TimestampT`], so events with
+/// incomparable timestamps are only ordered by their access conflicts.
 impl Ord for OperatorEvent {
     fn cmp(&self, other: &OperatorEvent) -> Ordering {
         match (self.is_watermark_callback, other.is_watermark_callback) {
@@ -127,39 +140,46 @@ impl Ord for OperatorEvent {
                 // should run first. Ties are broken by first by dependencies to ensure
                 // that writes to state occur before reads, and then by priority where a smaller
                 // number is higher priority.
-                match self.timestamp.cmp(&other.timestamp) {
-                    Ordering::Equal => match resolve_access_conflicts(&self, other) {
+                if self.timestamp == other.timestamp {
+                    match resolve_access_conflicts(self, other) {
                         // Prioritize other.
                         Ordering::Equal => Ordering::Greater,
                         ord => ord,
-                    },
-                    ord => ord,
+                    }
+                } else if self.timestamp.less_equal(&other.timestamp) {
+                    Ordering::Less
+                } else if other.timestamp.less_equal(&self.timestamp) {
+                    Ordering::Greater
+                } else {
+                    // The timestamps are incomparable, so the watermarks may run concurrently
+                    // unless they access the same state. Conflicting watermarks which are not
+                    // ordered by their accesses run in the lexicographic order of timestamps.
+                    match resolve_access_conflicts(self, other) {
+                        Ordering::Equal if has_conflicts(self, other) => {
+                            self.timestamp.cmp(&other.timestamp)
+                        }
+                        ord => ord,
+                    }
                 }
             }
             (true, false) => {
                 // `self` is a watermark, and `other` is a normal message callback.
                 // TODO: can compare dependencies to increase parallelism and order on a more
                 // fine-grained level.
-                match self.timestamp.cmp(&other.timestamp) {
-                    Ordering::Greater => {
-                        // `self` timestamp is greater than `other`, execute `other` first.
-                        Ordering::Greater
-                    }
-                    Ordering::Equal => {
-                        // `self` timestamp is equal to `other`, execute `other` first.
-                        Ordering::Greater
-                    }
-                    Ordering::Less => {
-                        // `self` timestamp is less than `other`, run them in any order.
-                        // Assume state is time-versioned, so dependency issues should not arise.
-                        Ordering::Equal
-                    }
+                if other.timestamp.less_equal(&self.timestamp) {
+                    // `other` timestamp is less than or equal to `self`, execute `other` first.
+                    Ordering::Greater
+                } else {
+                    // `other` timestamp is greater than or incomparable to `self`, run them in
+                    // any order. Assume state is time-versioned, so dependency issues should not
+                    // arise.
+                    Ordering::Equal
                 }
             }
             (false, true) => other.cmp(self).reverse(),
             // Neither of the events are watermark callbacks.
             // If they have no WW, RW, or WR conflicts, they can run concurrently.
-            (false, false) => resolve_access_conflicts(&self, other),
+            (false, false) => resolve_access_conflicts(self, other),
         }
     }
 }
@@ -397,6 +417,62 @@ mod test {
         }
     }
 
+    /// This test ensures that events with multi-dimensional timestamps are ordered by the product
+    /// order, and that events with incomparable timestamps can run concurrently.
+    #[test]
+    fn test_product_order_event_orderings() {
+        let event = |time: Vec<u64>, is_watermark_callback: bool| {
+            OperatorEvent::new(
+                Timestamp::new(time),
+                is_watermark_callback,
+                0,
+                HashSet::new(),
+                HashSet::new(),
+                || (),
+            )
+        };
+        assert!(
+            event(vec![1, 1], true) < event(vec![1, 2], true),
+            "Watermark [1, 1] should precede Watermark [1, 2]."
+        );
+        assert!(
+            event(vec![1, 2], true) == event(vec![2, 1], true),
+            "Watermarks [1, 2] and [2, 1] can run concurrently."
+        );
+        assert!(
+            event(vec![1, 2], false) < event(vec![2, 2], true),
+            "Message [1, 2] should precede Watermark [2, 2]."
+        );
+        assert!(
+            event(vec![1, 3], false) == event(vec![2, 2], true),
+            "Message [1, 3] and Watermark [2, 2] can run concurrently."
+        );
+
+        // Incomparable watermarks which write to the same state run in lexicographic order.
+        let mut write_ids = HashSet::new();
+        write_ids.insert(Uuid::new_deterministic());
+        let watermark_event_a = OperatorEvent::new(
+            Timestamp::new(vec![1, 2]),
+            true,
+            0,
+            HashSet::new(),
+            write_ids.clone(),
+            || (),
+        );
+        let watermark_event_b = OperatorEvent::new(
+            Timestamp::new(vec![2, 1]),
+            true,
+            0,
+            HashSet::new(),
+            write_ids,
+            || (),
+        );
+        assert!(
+            watermark_event_a < watermark_event_b,
+            "Watermark [1, 2] should precede Watermark [2, 1] because both write to the same state."
+        );
+    }
+
     #[test]
     fn test_resolve_access_conflicts() {
         let mut write_ids = HashSet::new();
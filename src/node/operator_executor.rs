@@ -1,6 +1,7 @@
 use std::{
     cell::RefCell,
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
+    panic::{self, AssertUnwindSafe},
     pin::Pin,
     rc::Rc,
     sync::{
@@ -8,24 +9,32 @@ use std::{
         Arc,
     },
     task::{Context, Poll},
+    time::Duration,
 };
 
-use futures::future;
+use futures::{future, stream};
 use tokio::{
     self,
     stream::{Stream, StreamExt},
-    sync::{mpsc, watch},
+    sync::{mpsc, oneshot, watch},
+    time::{self, Instant},
 };
 
 use crate::{
     communication::{ControlMessage, RecvEndpoint},
     dataflow::{
-        operator::{Operator, OperatorConfig},
-        stream::{InternalReadStream, StreamId},
-        Data, EventMakerT, Message, ReadStream,
+        checkpoint::{CheckpointError, CheckpointStore, Checkpointer},
+        operator::{Operator, OperatorConfig, PanicPolicy},
+        stream::{panic_message, InternalReadStream, StreamId},
+        timer::Timer,
+        Data, EventMakerT, Message, ReadStream, Timestamp, TimestampT,
     },
     node::lattice::ExecutionLattice,
     node::operator_event::OperatorEvent,
+    node::{
+        metrics::{MetricsRegistry, OperatorMetrics},
+        trace::{CallbackTracer, TraceWriter},
+    },
 };
 
 #[derive(Clone, Debug, PartialEq)]
@@ -34,9 +43,24 @@ enum EventRunnerMessage {
     DestroyOperator,
 }
 
+/// Creates the events generated by a deadline miss for a timestamp.
+pub type DeadlineEventMaker = Box<dyn Fn(&Timestamp) -> Vec<OperatorEvent>>;
+
+/// Sets up checkpointing of the states of a stream.
+pub type CheckpointSetup = Box<dyn Fn(&Checkpointer) -> Result<(), CheckpointError>>;
+
+/// Removes the callbacks and states an operator registered on a stream.
+pub type CallbackClear = Box<dyn Fn()>;
+
+/// Creates a new instance of an operator, which registers its callbacks on the input streams.
+pub type OperatorFactory = Box<dyn Fn() -> Box<dyn Operator>>;
+
 pub trait OperatorExecutorStreamT: Send + Stream<Item = Vec<OperatorEvent>> {
     fn get_id(&self) -> StreamId;
     fn get_closed_ref(&self) -> Arc<AtomicBool>;
+    fn get_deadline_event_maker(&self) -> DeadlineEventMaker;
+    fn get_checkpoint_setup(&self) -> CheckpointSetup;
+    fn get_callback_clear(&self) -> CallbackClear;
     fn to_pinned_stream(self: Box<Self>) -> Pin<Box<dyn Send + Stream<Item = Vec<OperatorEvent>>>>;
 }
 
@@ -55,6 +79,25 @@ impl<D: Data> OperatorExecutorStreamT for OperatorExecutorStream<D> {
         self.closed.clone()
     }
 
+    fn get_deadline_event_maker(&self) -> DeadlineEventMaker {
+        let stream = Rc::clone(&self.stream);
+        Box::new(move |t: &Timestamp| stream.borrow().make_deadline_events(t))
+    }
+
+    fn get_checkpoint_setup(&self) -> CheckpointSetup {
+        let stream = Rc::clone(&self.stream);
+        Box::new(move |checkpointer: &Checkpointer| {
+            let mut stream = stream.borrow_mut();
+            let checkpointer = checkpointer.nested(&stream.get_id().to_string());
+            stream.setup_checkpoints(&checkpointer)
+        })
+    }
+
+    fn get_callback_clear(&self) -> CallbackClear {
+        let stream = Rc::clone(&self.stream);
+        Box::new(move || stream.borrow_mut().clear_callbacks())
+    }
+
     fn to_pinned_stream(self: Box<Self>) -> Pin<Box<dyn Send + Stream<Item = Vec<OperatorEvent>>>> {
         Box::into_pin(self as Box<dyn Send + Stream<Item = Vec<OperatorEvent>>>)
     }
@@ -77,7 +120,7 @@ impl<D: Data> Stream for OperatorExecutorStream<D> {
             mut_self.recv_endpoint = endpoint;
         }
         match mut_self.recv_endpoint.as_mut() {
-            Some(RecvEndpoint::InterThread(rx)) => match rx.poll_recv(cx) {
+            Some(recv_endpoint) => match recv_endpoint.poll_recv(cx) {
                 Poll::Ready(Some(msg)) => {
                     if msg.is_top_watermark() {
                         self.closed.store(true, Ordering::SeqCst);
@@ -119,6 +162,130 @@ impl<D: Data> OperatorExecutorStream<D> {
     }
 }
 
+/// Tracks the relative deadline of each timestamp an operator processes.
+///
+/// A timestamp's deadline starts when its first event is added to the lattice, and is met once
+/// the lattice has no pending events with an equal or smaller timestamp. Once this happens after a
+/// watermark callback completes, no later events for the timestamp start a new deadline.
+struct DeadlineTracker {
+    /// The relative deadline.
+    deadline: Duration,
+    /// Absolute deadlines of timestamps which have not yet completed.
+    pending: BTreeMap<Timestamp, Instant>,
+    /// The latest timestamp in the partial order for which a watermark callback completed.
+    completed: Option<Timestamp>,
+}
+
+impl DeadlineTracker {
+    fn new(deadline: Duration) -> Self {
+        Self {
+            deadline,
+            pending: BTreeMap::new(),
+            completed: None,
+        }
+    }
+
+    /// Starts the deadline for a timestamp if it is not already running.
+    fn start(&mut self, t: &Timestamp, now: Instant) {
+        let completed = match &self.completed {
+            Some(completed) => t.less_equal(completed),
+            None => false,
+        };
+        if t.is_top() || completed {
+            return;
+        }
+        let deadline = self.deadline;
+        self.pending.entry(t.clone()).or_insert(now + deadline);
+    }
+
+    /// Returns the timestamps whose deadlines are running.
+    fn pending_timestamps(&self) -> Vec<Timestamp> {
+        self.pending.keys().cloned().collect()
+    }
+
+    /// Marks the deadline of `t` as met.
+    fn meet(&mut self, t: &Timestamp) {
+        self.pending.remove(t);
+    }
+
+    /// Marks all timestamps up to and including `t` as completed.
+    fn complete(&mut self, t: Timestamp) {
+        self.pending.retain(|p, _| !p.less_equal(&t));
+        let is_later = match &self.completed {
+            Some(completed) => completed.less_equal(&t),
+            None => true,
+        };
+        if is_later {
+            self.completed = Some(t);
+        }
+    }
+
+    /// Returns the earliest pending deadline.
+    fn next_deadline(&self) -> Option<Instant> {
+        self.pending.values().min().cloned()
+    }
+
+    /// Removes and returns timestamps whose deadlines expired.
+    fn take_expired(&mut self, now: Instant) -> Vec<Timestamp> {
+        let expired: Vec<Timestamp> = self
+            .pending
+            .iter()
+            .filter(|(_, deadline)| **deadline <= now)
+            .map(|(t, _)| t.clone())
+            .collect();
+        for t in expired.iter() {
+            self.pending.remove(t);
+        }
+        expired
+    }
+}
+
+/// Schedules the ticks of the timers registered by an operator.
+struct TimerScheduler {
+    /// The timers, with the number of ticks scheduled so far and the instant of the next tick.
+    timers: Vec<(Timer, u64, Instant)>,
+}
+
+impl TimerScheduler {
+    /// Schedules the first tick of each timer at `now`.
+    fn new(timers: Vec<Timer>, now: Instant) -> Self {
+        Self {
+            timers: timers.into_iter().map(|timer| (timer, 0, now)).collect(),
+        }
+    }
+
+    /// Whether any timer has not been stopped by its callback.
+    fn is_running(&self) -> bool {
+        self.timers.iter().any(|(timer, _, _)| !timer.is_stopped())
+    }
+
+    /// Returns the earliest tick of the timers which are running.
+    fn next_tick(&self) -> Option<Instant> {
+        self.timers
+            .iter()
+            .filter(|(timer, _, _)| !timer.is_stopped())
+            .map(|(_, _, next_tick)| *next_tick)
+            .min()
+    }
+
+    /// Returns the events of the ticks which are due, and schedules the next ticks. Ticks which
+    /// were missed are skipped.
+    fn take_events(&mut self, now: Instant) -> Vec<OperatorEvent> {
+        let mut events = Vec::new();
+        for (timer, num_ticks, next_tick) in self.timers.iter_mut() {
+            if timer.is_stopped() || *next_tick > now {
+                continue;
+            }
+            events.push(timer.make_event(*num_ticks));
+            *num_ticks += 1;
+            while *next_tick <= now {
+                *next_tick += timer.period;
+            }
+        }
+        events
+    }
+}
+
 /// `OperatorExecutor` is a structure that is in charge of executing callbacks associated with
 /// messages and watermarks arriving on input streams at an `Operator`. The callbacks are invoked
 /// according to the partial order defined in [`OperatorEvent`].
@@ -129,7 +296,11 @@ impl<D: Data> OperatorExecutorStream<D> {
 /// events.
 pub struct OperatorExecutor {
     /// The instance of the operator that needs to be executed.
-    operator: Box<dyn Operator>,
+    /// Taken while [`Operator::run`] executes as an event of the lattice.
+    operator: Option<Box<dyn Operator>>,
+    /// Receives the instance of the operator once [`Operator::run`] completes, while it executes
+    /// as an event of the lattice.
+    running_operator: Option<oneshot::Receiver<Box<dyn Operator>>>,
     /// The configuration with which the operator was instantiated, without the argument.
     config: OperatorConfig<()>,
     /// A merged stream of all the input streams of the operator. This is used to retrieve events
@@ -139,22 +310,60 @@ pub struct OperatorExecutor {
     streams_closed: HashMap<StreamId, Arc<AtomicBool>>,
     /// A lattice that keeps a partial order of the events that need to be processed.
     lattice: Arc<ExecutionLattice>,
+    /// Reports the panics of the operator to the node.
+    control_tx: mpsc::UnboundedSender<ControlMessage>,
     /// Receives control messages regarding the operator.
     control_rx: mpsc::UnboundedReceiver<ControlMessage>,
+    /// Create events for deadline miss handlers registered on the input streams.
+    deadline_event_makers: Vec<DeadlineEventMaker>,
+    /// Set up checkpointing of the states registered on the input streams.
+    checkpoint_setups: Vec<CheckpointSetup>,
+    /// Remove the callbacks and states registered on the input streams when the operator
+    /// restarts.
+    callback_clears: Vec<CallbackClear>,
+    /// Creates the instances of the operator restarted by the [`PanicPolicy::Restart`] policy.
+    operator_factory: Option<OperatorFactory>,
+    /// The store of the checkpoints of the operator's states, if checkpointing is set up.
+    checkpoint_store: Option<Arc<dyn CheckpointStore>>,
+    /// The number of latest checkpoints of each state which are kept in the store.
+    checkpoints_to_keep: usize,
+    /// Metrics of the operator, if they are registered.
+    metrics: Option<Arc<OperatorMetrics>>,
+    /// Traces the callbacks of the operator, if tracing is enabled.
+    tracer: Option<CallbackTracer>,
+    /// Timers registered by the operator using
+    /// [`add_timer_callback`](crate::dataflow::add_timer_callback).
+    timers: Vec<Timer>,
 }
 
 impl OperatorExecutor {
     /// Creates a new OperatorEvent.
+    ///
+    /// Takes the timers which the operator registered in `config` while it was created.
     pub fn new<T: 'static + Operator, U: Clone>(
         operator: T,
         config: OperatorConfig<U>,
         mut operator_streams: Vec<Box<dyn OperatorExecutorStreamT>>,
+        control_tx: mpsc::UnboundedSender<ControlMessage>,
         control_rx: mpsc::UnboundedReceiver<ControlMessage>,
     ) -> Self {
         let streams_closed: HashMap<_, _> = operator_streams
             .iter()
             .map(|s| (s.get_id(), s.get_closed_ref()))
             .collect();
+        let deadline_event_makers = operator_streams
+            .iter()
+            .map(|s| s.get_deadline_event_maker())
+            .collect();
+        let checkpoint_setups = operator_streams
+            .iter()
+            .map(|s| s.get_checkpoint_setup())
+            .collect();
+        let callback_clears = operator_streams
+            .iter()
+            .map(|s| s.get_callback_clear())
+            .collect();
+        let timers = config.timers.take();
         let event_stream = operator_streams.pop().map(|first| {
             operator_streams
                 .into_iter()
@@ -163,15 +372,79 @@ impl OperatorExecutor {
                 })
         });
         Self {
-            operator: Box::new(operator),
+            operator: Some(Box::new(operator)),
+            running_operator: None,
             config: config.drop_arg(),
             event_stream,
             streams_closed,
             lattice: Arc::new(ExecutionLattice::new()),
+            control_tx,
             control_rx,
+            deadline_event_makers,
+            checkpoint_setups,
+            callback_clears,
+            operator_factory: None,
+            checkpoint_store: None,
+            checkpoints_to_keep: 0,
+            metrics: None,
+            tracer: None,
+            timers,
         }
     }
 
+    /// Sets the factory which creates the new instances of the operator restarted by the
+    /// [`PanicPolicy::Restart`] policy.
+    pub fn set_operator_factory(&mut self, operator_factory: OperatorFactory) {
+        self.operator_factory = Some(operator_factory);
+    }
+
+    /// Registers the metrics of the operator, which are updated while it executes.
+    pub fn register_metrics(&mut self, registry: &MetricsRegistry) {
+        self.metrics = Some(registry.register_operator(
+            self.config.id,
+            self.get_name(),
+            Arc::clone(&self.lattice),
+        ));
+    }
+
+    /// Traces the begin and end of the operator's callbacks to `writer`.
+    pub(crate) fn set_trace_writer(&mut self, writer: Arc<TraceWriter>) {
+        self.tracer = Some(CallbackTracer::new(writer, self.get_name()));
+    }
+
+    /// Returns the name of the operator, or its ID if it has no name.
+    fn get_name(&self) -> String {
+        self.config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("{}", self.config.id))
+    }
+
+    /// Checkpoints the states of the operator to `store`, keeping the `checkpoints_to_keep` latest
+    /// checkpoints of each state, and restores the states from the checkpoints taken at
+    /// `recovery_time` if provided.
+    ///
+    /// Must be called before [`OperatorExecutor::execute`].
+    pub fn setup_checkpoints(
+        &mut self,
+        store: Arc<dyn CheckpointStore>,
+        recovery_time: Option<Timestamp>,
+        checkpoints_to_keep: usize,
+    ) -> Result<(), CheckpointError> {
+        self.checkpoint_store = Some(Arc::clone(&store));
+        self.checkpoints_to_keep = checkpoints_to_keep;
+        let checkpointer = Checkpointer::new(
+            store,
+            self.config.id.to_string(),
+            recovery_time,
+            checkpoints_to_keep,
+        );
+        for setup in self.checkpoint_setups.iter() {
+            (setup)(&checkpointer)?;
+        }
+        Ok(())
+    }
+
     /// Whether all input streams have been closed.
     ///
     /// Returns true if there are no input streams.
@@ -186,20 +459,22 @@ impl OperatorExecutor {
     /// Once [`Operator::run`] completes, the function runs callbacks by retrieving events from the
     /// input streams, adding them to the lattice maintained by the executor and notifying the
     /// `event_runner` invocations to process the received events.
+    /// Upon a [`ControlMessage::RemoveOperator`] message, the function stops processing events and
+    /// destroys the operator. Upon a [`ControlMessage::Shutdown`] message, the function stops the
+    /// timers of the operator, and destroys it once its input streams close.
+    /// Panics in [`Operator::run`] and in callbacks are handled according to the [`PanicPolicy`]
+    /// of the operator.
     pub async fn execute(&mut self) {
         loop {
-            if let Some(ControlMessage::RunOperator(id)) = self.control_rx.recv().await {
-                if id == self.config.id {
-                    break;
-                }
+            match self.control_rx.recv().await {
+                Some(ControlMessage::RunOperator(id)) if id == self.config.id => break,
+                // The operator was removed from the dataflow before it started running.
+                Some(ControlMessage::RemoveOperator(id)) if id == self.config.id => return,
+                _ => (),
             }
         }
 
-        let name = self
-            .config
-            .name
-            .clone()
-            .unwrap_or_else(|| format!("{}", self.config.id));
+        let name = self.get_name();
         slog::debug!(
             crate::TERMINAL_LOGGER,
             "Node {}: running operator {}",
@@ -207,45 +482,312 @@ impl OperatorExecutor {
             name
         );
 
-        // Callbacks are not invoked while the operator is running.
-        tokio::task::block_in_place(|| self.operator.run());
-
-        if let Some(mut event_stream) = self.event_stream.take() {
-            // Launch consumers
-            // TODO: use CondVar instead of watch.
-            // TODO: adjust number of event runners. based on size of event lattice.
-            let (notifier_tx, notifier_rx) = watch::channel(EventRunnerMessage::AddedEvents);
-            let mut event_runner_handles = Vec::new();
-            for _ in 0..self.config.num_event_runners {
-                let event_runner_fut =
-                    Self::event_runner(Arc::clone(&self.lattice), notifier_rx.clone());
-                event_runner_handles.push(tokio::spawn(event_runner_fut));
+        // Receives the messages of the panics in `run` and in the callbacks.
+        let (panic_tx, mut panic_rx) = mpsc::unbounded_channel();
+        let panic_policy = self.get_panic_policy();
+        let mut shutting_down = false;
+        // Each iteration runs an instance of the operator, which is replaced upon a restart.
+        loop {
+            let has_input_streams = self.event_stream.is_some();
+            let invokes_callbacks = has_input_streams || !self.timers.is_empty();
+            let run_concurrently = self.config.run_concurrently && invokes_callbacks;
+            let mut run_panicked = false;
+            if run_concurrently {
+                // `run` executes on its own event runner, and its event precedes only the
+                // callbacks of the top watermark. Thus, the other callbacks are invoked while the
+                // operator runs.
+                let mut operator = self.operator.take().unwrap();
+                let (operator_tx, operator_rx) = oneshot::channel();
+                let panic_tx = panic_tx.clone();
+                let run_event = OperatorEvent::new(
+                    Timestamp::top(),
+                    false,
+                    0,
+                    HashSet::new(),
+                    HashSet::new(),
+                    move || {
+                        // `run` blocks this event runner until it returns. The event holds
+                        // `Timestamp::top()`, so the callbacks of the top watermark wait for it.
+                        let result = tokio::task::block_in_place(|| {
+                            panic::catch_unwind(AssertUnwindSafe(|| operator.run()))
+                        });
+                        if let Err(payload) = result {
+                            panic_tx.send(panic_message(payload.as_ref())).ok();
+                        }
+                        operator_tx.send(operator).ok();
+                    },
+                );
+                self.running_operator = Some(operator_rx);
+                self.lattice.add_events(vec![run_event]).await;
+            } else {
+                // Callbacks are not invoked while the operator is running.
+                let operator = self.operator.as_mut().unwrap();
+                let result = tokio::task::block_in_place(|| {
+                    panic::catch_unwind(AssertUnwindSafe(|| operator.run()))
+                });
+                if let Err(payload) = result {
+                    panic_tx.send(panic_message(payload.as_ref())).ok();
+                    run_panicked = panic_policy != PanicPolicy::Skip;
+                }
             }
-            while let Some(events) = event_stream.next().await {
-                {
-                    // Add all the received events to the lattice.
-                    self.lattice.add_events(events).await;
-                    // Notify receivers that new events were added.
-                    notifier_tx
-                        .broadcast(EventRunnerMessage::AddedEvents)
-                        .unwrap();
+
+            let mut removed = false;
+            let mut restart = false;
+            let mut failed = false;
+            if invokes_callbacks && !run_panicked {
+                // Operators without input streams only process the events of their timers.
+                let mut event_stream = self
+                    .event_stream
+                    .take()
+                    .unwrap_or_else(|| Box::pin(stream::empty()));
+                let mut timer_scheduler =
+                    TimerScheduler::new(self.timers.drain(..).collect(), Instant::now());
+                // Launch consumers
+                // TODO: use CondVar instead of watch.
+                // TODO: adjust number of event runners. based on size of event lattice.
+                let (notifier_tx, notifier_rx) = watch::channel(EventRunnerMessage::AddedEvents);
+                // Event runners report completed events to track deadlines.
+                let (completed_tx, mut completed_rx) = mpsc::unbounded_channel();
+                let mut deadline_tracker = self.config.deadline.map(DeadlineTracker::new);
+                let mut event_runner_handles = Vec::new();
+                // `run` occupies an additional event runner while it executes.
+                let num_event_runners = self.config.num_event_runners + run_concurrently as usize;
+                for event_runner_id in 0..num_event_runners {
+                    let event_runner_fut = Self::event_runner(
+                        Arc::clone(&self.lattice),
+                        notifier_rx.clone(),
+                        deadline_tracker.as_ref().map(|_| completed_tx.clone()),
+                        self.metrics.clone(),
+                        self.tracer
+                            .as_ref()
+                            .map(|tracer| tracer.for_event_runner(event_runner_id)),
+                        panic_tx.clone(),
+                        panic_policy,
+                    );
+                    event_runner_handles.push(tokio::spawn(event_runner_fut));
+                }
+                // Drop the original sender so the channel closes with the event runners.
+                drop(completed_tx);
+                let mut input_closed = !has_input_streams;
+                loop {
+                    let next_deadline = deadline_tracker
+                        .as_ref()
+                        .and_then(DeadlineTracker::next_deadline);
+                    // Timers stop once the input streams close or the node shuts down.
+                    let timers_running = timer_scheduler.is_running()
+                        && !(has_input_streams && input_closed)
+                        && !shutting_down;
+                    let next_tick = if timers_running {
+                        timer_scheduler.next_tick()
+                    } else {
+                        None
+                    };
+                    // Keep tracking deadlines of events which are still processed after the input
+                    // streams close.
+                    if removed
+                        || restart
+                        || failed
+                        || (input_closed && next_deadline.is_none() && !timers_running)
+                    {
+                        break;
+                    }
+                    tokio::select! {
+                        events_option = event_stream.next(), if !input_closed => match events_option {
+                            Some(events) => {
+                                if let Some(tracker) = deadline_tracker.as_mut() {
+                                    let now = Instant::now();
+                                    for event in events.iter() {
+                                        tracker.start(&event.timestamp, now);
+                                    }
+                                }
+                                // Add all the received events to the lattice.
+                                self.lattice.add_events(events).await;
+                                // Notify receivers that new events were added.
+                                notifier_tx
+                                    .broadcast(EventRunnerMessage::AddedEvents)
+                                    .unwrap();
+                            }
+                            None => input_closed = true,
+                        },
+                        Some(control_msg) = self.control_rx.recv() => match control_msg {
+                            // Stop processing events once the operator is removed from the dataflow.
+                            ControlMessage::RemoveOperator(id) => removed = id == self.config.id,
+                            // Keep processing the input streams until they close.
+                            ControlMessage::Shutdown(_) => shutting_down = true,
+                            _ => (),
+                        },
+                        Some(msg) = panic_rx.recv() => {
+                            let policy = self.on_panic(msg);
+                            restart = policy == PanicPolicy::Restart;
+                            failed = policy == PanicPolicy::FailNode;
+                        },
+                        Some((t, is_watermark_callback)) = completed_rx.recv() => {
+                            if let Some(tracker) = deadline_tracker.as_mut() {
+                                // Other events up to a timestamp may still run, such as the
+                                // other watermark callbacks for the timestamp.
+                                for pending_t in tracker.pending_timestamps() {
+                                    if !self.lattice.has_pending_events_up_to(&pending_t).await {
+                                        tracker.meet(&pending_t);
+                                    }
+                                }
+                                if is_watermark_callback
+                                    && !self.lattice.has_pending_events_up_to(&t).await
+                                {
+                                    tracker.complete(t);
+                                }
+                            }
+                        },
+                        _ = time::delay_until(next_tick.unwrap_or_else(Instant::now)),
+                            if next_tick.is_some() => {
+                            let events = timer_scheduler.take_events(Instant::now());
+                            if !events.is_empty() {
+                                self.lattice.add_events(events).await;
+                                notifier_tx
+                                    .broadcast(EventRunnerMessage::AddedEvents)
+                                    .unwrap();
+                            }
+                        },
+                        _ = time::delay_until(next_deadline.unwrap_or_else(Instant::now)),
+                            if next_deadline.is_some() => {
+                            let expired = deadline_tracker
+                                .as_mut()
+                                .map(|tracker| tracker.take_expired(Instant::now()))
+                                .unwrap_or_default();
+                            let mut events = Vec::new();
+                            for t in expired {
+                                slog::warn!(
+                                    crate::TERMINAL_LOGGER,
+                                    "Node {}: operator {} missed deadline for timestamp {:?}",
+                                    self.config.node_id,
+                                    name,
+                                    t
+                                );
+                                for make_events in self.deadline_event_makers.iter() {
+                                    events.append(&mut (make_events)(&t));
+                                }
+                            }
+                            if !events.is_empty() {
+                                self.lattice.add_events(events).await;
+                                notifier_tx
+                                    .broadcast(EventRunnerMessage::AddedEvents)
+                                    .unwrap();
+                            }
+                        },
+                    }
                 }
+                // Wait for event runners to finish.
+                notifier_tx
+                    .broadcast(EventRunnerMessage::DestroyOperator)
+                    .unwrap();
+                // Handle errors?
+                future::join_all(event_runner_handles).await;
+                // A restarted operator keeps receiving from the input streams.
+                if has_input_streams {
+                    self.event_stream = Some(event_stream);
+                }
+            }
+            // The operator is destroyed once it finishes running. `run` did not execute if the
+            // event runners stopped because of panics, in which case the operator is dropped
+            // with the events of the lattice.
+            if let Some(mut operator_rx) = self.running_operator.take() {
+                self.operator = operator_rx.try_recv().ok();
             }
-            // Wait for event runners to finish.
-            notifier_tx
-                .broadcast(EventRunnerMessage::DestroyOperator)
-                .unwrap();
-            // Handle errors?
-            future::join_all(event_runner_handles).await;
-
-            if self.all_streams_closed() {
+            // Handle the panics which happened while the event runners finished.
+            while let Ok(msg) = panic_rx.try_recv() {
+                match self.on_panic(msg) {
+                    PanicPolicy::Skip => (),
+                    PanicPolicy::Restart => restart = true,
+                    PanicPolicy::FailNode => failed = true,
+                }
+            }
+
+            if failed {
+                return;
+            }
+            if restart && !removed {
+                self.restart().await;
+                continue;
+            }
+            if invokes_callbacks && (removed || self.all_streams_closed()) {
                 slog::debug!(
                     crate::TERMINAL_LOGGER,
                     "Node {}: destroying operator {}",
                     self.config.node_id,
                     name,
                 );
-                self.operator.destroy();
+                if let Some(operator) = self.operator.as_mut() {
+                    operator.destroy();
+                }
+            }
+            return;
+        }
+    }
+
+    /// Returns the policy applied to the panics of the operator. Operators which cannot be
+    /// recreated fail the node instead of restarting.
+    fn get_panic_policy(&self) -> PanicPolicy {
+        match self.config.panic_policy {
+            PanicPolicy::Restart if self.operator_factory.is_none() => PanicPolicy::FailNode,
+            panic_policy => panic_policy,
+        }
+    }
+
+    /// Logs a panic of the operator with message `msg`, and reports it to the node.
+    /// Returns the policy applied to the panic.
+    fn on_panic(&self, msg: String) -> PanicPolicy {
+        let panic_policy = self.get_panic_policy();
+        slog::error!(
+            crate::TERMINAL_LOGGER,
+            "Node {}: operator {} panicked: {}; applying panic policy {:?}",
+            self.config.node_id,
+            self.get_name(),
+            msg,
+            panic_policy
+        );
+        let report = ControlMessage::OperatorPanicked(self.config.id, msg, panic_policy);
+        if let Err(e) = self.control_tx.send(report) {
+            slog::error!(
+                crate::TERMINAL_LOGGER,
+                "Node {}: error reporting the panic of operator {}: {:?}",
+                self.config.node_id,
+                self.get_name(),
+                e
+            );
+        }
+        panic_policy
+    }
+
+    /// Replaces the operator with a new instance created by the operator factory, and discards
+    /// the events which were not processed.
+    ///
+    /// Must be called once the event runners and [`Operator::run`] completed.
+    async fn restart(&mut self) {
+        let num_discarded_events = self.lattice.clear().await;
+        slog::warn!(
+            crate::TERMINAL_LOGGER,
+            "Node {}: restarting operator {} without destroying the previous instance, and \
+            discarding {} events the previous instance did not process",
+            self.config.node_id,
+            self.get_name(),
+            num_discarded_events
+        );
+        // The new instance registers its own callbacks and states on the input streams.
+        self.operator = None;
+        for clear_callbacks in self.callback_clears.iter() {
+            (clear_callbacks)();
+        }
+        let operator_factory = self.operator_factory.as_ref().unwrap();
+        self.operator = Some((operator_factory)());
+        self.timers = self.config.timers.take();
+        if let Some(store) = self.checkpoint_store.clone() {
+            if let Err(e) = self.setup_checkpoints(store, None, self.checkpoints_to_keep) {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "Node {}: failed to set up checkpoints for restarted operator {}: {:?}",
+                    self.config.node_id,
+                    self.get_name(),
+                    e
+                );
             }
         }
     }
@@ -253,15 +795,64 @@ impl OperatorExecutor {
     /// An `event_runner` invocation is in charge of executing callbacks associated with an event.
     /// Upon receipt of an `AddedEvents` notification, it queries the lattice for events that are
     /// ready to run, executes them, and notifies the lattice of their completion.
+    /// If a `completed_tx` channel is provided, the timestamps of completed events, and whether
+    /// they are watermark callbacks, are sent on it, and callbacks run in `block_in_place` so
+    /// that the executor detects missed deadlines while a callback blocks.
+    /// If `metrics` are provided, the durations of the callbacks are recorded.
+    /// If a `tracer` is provided, the begin and end of each callback are traced.
+    /// The messages of the callbacks which panic are sent on `panic_tx`. Unless the
+    /// `panic_policy` is [`PanicPolicy::Skip`], the invocation stops after a panic.
     async fn event_runner(
         lattice: Arc<ExecutionLattice>,
         mut notifier_rx: watch::Receiver<EventRunnerMessage>,
+        completed_tx: Option<mpsc::UnboundedSender<(Timestamp, bool)>>,
+        metrics: Option<Arc<OperatorMetrics>>,
+        mut tracer: Option<CallbackTracer>,
+        panic_tx: mpsc::UnboundedSender<String>,
+        panic_policy: PanicPolicy,
     ) {
         // Wait for notification for events added.
         while let Some(control_msg) = notifier_rx.recv().await {
             while let Some((event, event_id)) = lattice.get_event().await {
-                (event.callback)();
+                let completed_event = (event.timestamp.clone(), event.is_watermark_callback);
+                if let Some(tracer) = tracer.as_mut() {
+                    tracer.begin(&event);
+                }
+                let start = Instant::now();
+                let callback = event.callback;
+                let run_callback = || panic::catch_unwind(AssertUnwindSafe(callback));
+                let result = if completed_tx.is_some() {
+                    // Let the runtime move the executor off this thread while the callback runs.
+                    tokio::task::block_in_place(run_callback)
+                } else {
+                    run_callback()
+                };
+                if let Some(metrics) = metrics.as_ref() {
+                    metrics.observe_callback(start.elapsed());
+                }
+                if let Some(tracer) = tracer.as_mut() {
+                    tracer.end(
+                        &event.timestamp,
+                        event.is_watermark_callback,
+                        event.priority,
+                    );
+                }
                 lattice.mark_as_completed(event_id).await;
+                match result {
+                    Ok(()) => {
+                        if let Some(tx) = completed_tx.as_ref() {
+                            // The executor may have stopped tracking deadlines.
+                            tx.send(completed_event).ok();
+                        }
+                    }
+                    Err(payload) => {
+                        // The executor may have stopped handling panics.
+                        panic_tx.send(panic_message(payload.as_ref())).ok();
+                        if panic_policy != PanicPolicy::Skip {
+                            return;
+                        }
+                    }
+                }
             }
             if EventRunnerMessage::DestroyOperator == control_msg {
                 break;
@@ -271,3 +862,23 @@ impl OperatorExecutor {
 }
 
 unsafe impl Send for OperatorExecutor {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deadline_tracker_product_order() {
+        let now = Instant::now();
+        let mut tracker = DeadlineTracker::new(Duration::from_millis(10));
+        tracker.start(&Timestamp::new(vec![0, 5]), now);
+        tracker.start(&Timestamp::new(vec![1, 0]), now);
+        // [0, 5] and [1, 0] are incomparable, so completing one keeps the deadline of the other.
+        tracker.complete(Timestamp::new(vec![1, 0]));
+        tracker.start(&Timestamp::new(vec![0, 1]), now);
+        assert_eq!(
+            tracker.take_expired(now + Duration::from_millis(10)),
+            vec![Timestamp::new(vec![0, 1]), Timestamp::new(vec![0, 5])]
+        );
+    }
+}
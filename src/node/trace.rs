@@ -0,0 +1,183 @@
+//! Traces of the callbacks executed by operators, in the Chrome trace event format.
+//!
+//! Tracing is enabled by setting
+//! [`Configuration::trace_filename`](crate::Configuration::trace_filename). The resulting file
+//! can be opened in `chrome://tracing`, and uses the same layout as the traces written by the
+//! Python `erdos.profile.Profile` helper: each operator is shown as a process, and each of its
+//! event runners as a thread.
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{dataflow::Timestamp, node::operator_event::OperatorEvent};
+
+/// Appends trace events to a file in the Chrome trace event format.
+///
+/// Events are buffered, and flushed to the file when the writer is dropped once the node stops.
+pub(crate) struct TraceWriter {
+    writer: Mutex<BufWriter<File>>,
+}
+
+impl TraceWriter {
+    /// Creates a writer which overwrites the trace file at `path`.
+    pub(crate) fn new<P: AsRef<Path>>(path: P) -> io::Result<Arc<Self>> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        // The closing bracket of the JSON array is optional in the trace event format, which
+        // allows appending events until the node stops.
+        writer.write_all(b"[\n")?;
+        writer.flush()?;
+        Ok(Arc::new(Self {
+            writer: Mutex::new(writer),
+        }))
+    }
+
+    /// Appends a batch of events to the trace.
+    ///
+    /// Failures are logged instead of returned so that tracing does not affect the dataflow.
+    fn write_events(&self, events: &str) {
+        let mut writer = self.writer.lock().unwrap();
+        if let Err(e) = writer.write_all(events.as_bytes()) {
+            slog::error!(
+                crate::TERMINAL_LOGGER,
+                "Failed to write trace events: {}",
+                e
+            );
+        }
+    }
+}
+
+impl Drop for TraceWriter {
+    fn drop(&mut self) {
+        if let Ok(writer) = self.writer.get_mut() {
+            if let Err(e) = writer.flush() {
+                slog::error!(crate::TERMINAL_LOGGER, "Failed to flush trace: {}", e);
+            }
+        }
+    }
+}
+
+/// Traces the callbacks executed by an event runner of an operator.
+///
+/// Each event runner buffers its events, and appends them to the trace in batches to avoid
+/// contending with the other event runners for the [`TraceWriter`].
+pub(crate) struct CallbackTracer {
+    writer: Arc<TraceWriter>,
+    operator_name: String,
+    event_runner_id: usize,
+    buffer: String,
+}
+
+impl CallbackTracer {
+    /// Size of the buffered events above which they are appended to the trace.
+    const BUFFER_SIZE: usize = 64 * 1024;
+
+    pub(crate) fn new(writer: Arc<TraceWriter>, operator_name: String) -> Self {
+        Self {
+            writer,
+            operator_name,
+            event_runner_id: 0,
+            buffer: String::new(),
+        }
+    }
+
+    /// Returns a tracer for the event runner with ID `event_runner_id`.
+    pub(crate) fn for_event_runner(&self, event_runner_id: usize) -> Self {
+        Self {
+            writer: Arc::clone(&self.writer),
+            operator_name: self.operator_name.clone(),
+            event_runner_id,
+            buffer: String::new(),
+        }
+    }
+
+    /// Records the beginning of the callback of `event`.
+    pub(crate) fn begin(&mut self, event: &OperatorEvent) {
+        self.trace(
+            "B",
+            &event.timestamp,
+            event.is_watermark_callback,
+            event.priority,
+        );
+    }
+
+    /// Records the end of a callback. Takes the fields of the event because its callback was
+    /// moved out to run it.
+    pub(crate) fn end(&mut self, timestamp: &Timestamp, is_watermark_callback: bool, priority: i8) {
+        self.trace("E", timestamp, is_watermark_callback, priority);
+    }
+
+    fn trace(
+        &mut self,
+        phase: &str,
+        timestamp: &Timestamp,
+        is_watermark_callback: bool,
+        priority: i8,
+    ) {
+        let name = if is_watermark_callback {
+            "watermark_callback"
+        } else {
+            "callback"
+        };
+        // Microseconds since the Unix epoch, like the traces of the Python operators.
+        let ts = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros();
+        self.buffer.push_str(&format!(
+            "{{\"name\":\"{}\",\"cat\":\"erdos\",\"ph\":\"{}\",\"pid\":\"{}\",\"tid\":{},\"ts\":{},\
+             \"args\":{{\"timestamp\":\"{}\",\"priority\":{}}}}}",
+            name,
+            phase,
+            escape(&self.operator_name),
+            self.event_runner_id,
+            ts,
+            format_timestamp(timestamp),
+            priority
+        ));
+        self.buffer.push_str(",\n");
+        if self.buffer.len() >= Self::BUFFER_SIZE {
+            self.flush();
+        }
+    }
+
+    /// Appends the buffered events to the trace.
+    fn flush(&mut self) {
+        self.writer.write_events(&self.buffer);
+        self.buffer.clear();
+    }
+}
+
+impl Drop for CallbackTracer {
+    fn drop(&mut self) {
+        if !self.buffer.is_empty() {
+            self.flush();
+        }
+    }
+}
+
+fn format_timestamp(t: &Timestamp) -> String {
+    if t.is_top() {
+        "top".to_string()
+    } else {
+        format!("{:?}", t.time())
+    }
+}
+
+/// Escapes a JSON string.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
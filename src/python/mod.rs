@@ -7,7 +7,7 @@ use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
 use crate::{
     communication::ControlMessage,
     dataflow::{
-        graph::default_graph,
+        graph::{default_graph, Vertex},
         stream::{InternalReadStream, WriteStreamT},
         Message, Operator, OperatorConfig, ReadStream, WriteStream,
     },
@@ -132,7 +132,7 @@ fn internal(_py: Python, m: &PyModule) -> PyResult<()> {
                         let recv_endpoint = channel_manager
                             .lock()
                             .unwrap()
-                            .take_recv_endpoint(id)
+                            .take_recv_endpoint(id, &Vertex::Operator(op_id))
                             .unwrap();
                         PyReadStream::from(ReadStream::from(InternalReadStream::from_endpoint(
                             recv_endpoint,
@@ -278,15 +278,6 @@ operator.__init__(*read_streams, *write_streams, *args, **kwargs)
                 if let Err(e) = py_result {
                     e.print(py)
                 }
-                // Notify node that operator is done setting up
-                if let Err(e) = control_sender.send(ControlMessage::OperatorInitialized(op_id)) {
-                    slog::error!(
-                        crate::TERMINAL_LOGGER,
-                        "Error sending OperatorInitialized message to control handler: {:?}",
-                        e
-                    );
-                }
-
                 let operator_obj = py
                     .eval("operator", None, Some(&locals))
                     .unwrap()
@@ -298,20 +289,24 @@ operator.__init__(*read_streams, *write_streams, *args, **kwargs)
                 config.id = op_id;
                 config.flow_watermarks = flow_watermarks;
                 config.node_id = node_id;
+                config.pinned = true;
                 OperatorExecutor::new(
                     PyOperator {
                         operator: operator_arc,
                     },
                     config,
                     op_ex_streams,
+                    control_sender,
                     control_receiver,
                 )
             };
 
+        // Python operators run in separate processes, so they are pinned to their nodes.
         default_graph::add_operator(
             op_id,
             name,
             node_id,
+            true,
             read_stream_ids,
             write_stream_ids,
             operator_runner,
@@ -323,7 +318,7 @@ operator.__init__(*read_streams, *write_streams, *args, **kwargs)
             .collect();
 
         for py_write_stream in connect_write_streams.iter() {
-            default_graph::add_operator_stream(op_id, &py_write_stream.write_stream);
+            default_graph::add_operator_stream(op_id, &py_write_stream.write_stream, None);
         }
 
         Ok(result)
@@ -359,9 +354,9 @@ operator.__init__(*read_streams, *write_streams, *args, **kwargs)
                 graph_filename,
             );
             let mut node = Node::new(config);
-            node.run();
-        });
-        Ok(())
+            node.run()
+        })
+        .map_err(|e| exceptions::Exception::py_err(e.to_string()))
     }
 
     #[pyfn(m, "run_async")]
@@ -487,7 +482,7 @@ impl PyNodeHandle {
         py.allow_threads(|| match self.node_handle.take() {
             Some(node_handle) => node_handle
                 .shutdown()
-                .map_err(exceptions::Exception::py_err),
+                .map_err(|e| exceptions::Exception::py_err(e.to_string())),
             None => Err(exceptions::Exception::py_err(
                 "Unable to shut down; no Rust node handle available",
             )),
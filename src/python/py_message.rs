@@ -25,10 +25,13 @@ impl PyMessage {
             Message::new_watermark(Timestamp::top())
         } else {
             match (timestamp_coordinates, data) {
-                (Some(t), Some(d)) => {
-                    Message::new_message(Timestamp::new(t), Vec::from(d.as_bytes()))
-                }
-                (Some(t), None) => Message::new_watermark(Timestamp::new(t)),
+                (Some(t), Some(d)) => Message::new_message(
+                    Timestamp::try_new(t).map_err(exceptions::ValueError::py_err)?,
+                    Vec::from(d.as_bytes()),
+                ),
+                (Some(t), None) => Message::new_watermark(
+                    Timestamp::try_new(t).map_err(exceptions::ValueError::py_err)?,
+                ),
                 (_, _) => unreachable!(),
             }
         };
@@ -47,8 +50,8 @@ impl PyMessage {
     #[getter(timestamp)]
     fn timestamp(&self) -> Option<Vec<u64>> {
         match &self.msg {
-            Message::TimestampedData(d) => Some(d.timestamp.time.clone()),
-            Message::Watermark(t) => Some(t.time.clone()),
+            Message::TimestampedData(d) => Some(d.timestamp.time().to_vec()),
+            Message::Watermark(t) => Some(t.time().to_vec()),
         }
     }
 
@@ -12,6 +12,7 @@ create_exception!(WriteStreamError, TimestampError, exceptions::Exception);
 create_exception!(WriteStreamError, ClosedError, exceptions::Exception);
 create_exception!(WriteStreamError, IOError, exceptions::Exception);
 create_exception!(WriteStreamError, SerializationError, exceptions::Exception);
+create_exception!(WriteStreamError, NoCapacityError, exceptions::Exception);
 
 #[pyclass]
 pub struct PyWriteStream {
@@ -39,6 +40,7 @@ impl PyWriteStream {
                 WriteStreamError::Closed => ClosedError::py_err(error_str),
                 WriteStreamError::IOError => IOError::py_err(error_str),
                 WriteStreamError::SerializationError => SerializationError::py_err(error_str),
+                WriteStreamError::NoCapacity => NoCapacityError::py_err(error_str),
             }
         })
     }
@@ -1,42 +1,79 @@
-use async_trait::async_trait;
 use serde::Deserialize;
-use std::{any::Any, collections::HashMap, sync::Arc};
+use std::{
+    any::Any,
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    path::PathBuf,
+    sync::Arc,
+};
 use tokio::sync::{mpsc, Mutex};
 
 use crate::{
-    communication::{Pusher, PusherT, RecvEndpoint, SendEndpoint},
+    communication::{
+        self, ChannelEndpoints, ChannelQueue, DecodeErrorHandler, DecodeErrorHandlers,
+        MessageRecorder, Pusher, PusherT, RecvEndpoint, SendEndpoint, SharedMemoryReceiver,
+        SharedMemorySender,
+    },
     dataflow::{
         graph::{Channel, Graph, Vertex},
-        stream::StreamId,
+        stream::{SideOutput, StreamCapacity, StreamId},
         Data, Message,
     },
-    node::NodeId,
+    node::{
+        metrics::{MetricsRegistry, StreamMetrics},
+        NodeId,
+    },
     scheduler::endpoints_manager::{ChannelsToReceivers, ChannelsToSenders},
+    Configuration,
 };
 
-#[async_trait]
 pub trait StreamEndpointsT: Send {
     fn as_any(&mut self) -> &mut dyn Any;
 
     /// Creates a new inter-thread channel for the stream.
     ///
     /// It creates a `mpsc::Channel` and adds the sender and receiver to the
-    /// corresponding endpoints.
-    fn add_inter_thread_channel(&mut self);
+    /// corresponding endpoints. The queue of the channel to `sink` is registered in `metrics`.
+    fn add_inter_thread_channel(&mut self, sink: String, metrics: &MetricsRegistry);
+
+    /// Adds a `SendEndpoint` which records the messages sent on the stream.
+    fn add_record_endpoint(&mut self, recorder: Arc<MessageRecorder>);
+
+    /// Adds a `SendEndpoint` which updates the metrics of the stream.
+    fn add_metrics_endpoint(&mut self, metrics: Arc<StreamMetrics>);
 
     /// Adds a `SendEndpoint` to the other node.
     ///
     /// Assumes that `channels_to_senders` already stores a `mpsc::Sender` to the
     /// network sender to the other node.
-    async fn add_inter_node_send_endpoint(
+    fn add_inter_node_send_endpoint(
         &mut self,
         other_node_id: NodeId,
-        channels_to_senders: Arc<Mutex<ChannelsToSenders>>,
-    ) -> Result<(), String>;
-    fn add_inter_node_recv_endpoint(
-        &mut self,
-        receiver_pushers: &mut HashMap<StreamId, Box<dyn PusherT>>,
+        channels_to_senders: &ChannelsToSenders,
+        metrics: &MetricsRegistry,
     ) -> Result<(), String>;
+
+    /// Adds a `SendEndpoint` to the other node which writes messages to the shared-memory ring at
+    /// `path`.
+    ///
+    /// The ring is created by the receiving node, and provides backpressure instead of the
+    /// capacity of the stream.
+    fn add_shared_memory_send_endpoint(&mut self, other_node_id: NodeId, path: PathBuf);
+
+    /// Adds a channel to `sink` on which the messages received from another node are sent.
+    fn add_inter_node_recv_endpoint(&mut self, sink: String, metrics: &MetricsRegistry);
+
+    /// Removes a channel to `sink`, or the `SendEndpoint` to another node if `sink` describes a
+    /// node.
+    fn remove_channel(&mut self, sink: &str, metrics: &MetricsRegistry);
+
+    /// Adds a handler of the messages received from other nodes which fail to deserialize, for
+    /// the reader of the channel to `sink`.
+    fn add_decode_error_handler(&mut self, sink: String, handler: DecodeErrorHandler);
+
+    /// Returns a pusher which sends the messages received from other nodes on the channels of the
+    /// stream, including the channels added later.
+    fn make_pusher(&self) -> Box<dyn PusherT>;
 }
 
 pub struct StreamEndpoints<D>
@@ -45,28 +82,46 @@ where
 {
     /// The id of the stream.
     stream_id: StreamId,
-    /// The receive endopoints of the stream.
-    recv_endpoints: Vec<RecvEndpoint<Arc<Message<D>>>>,
-    /// The send endpoints of the stream.
+    /// Bounds the channels of the stream, which are unbounded if `None`.
+    capacity: Option<StreamCapacity>,
+    /// The receive endopoints of the stream, and the sinks to which they belong.
+    recv_endpoints: Vec<(String, RecvEndpoint<Arc<Message<D>>>)>,
+    /// The send endpoints of the stream which do not belong to a channel.
     send_endpoints: Vec<SendEndpoint<Arc<Message<D>>>>,
+    /// The send endpoints of the stream's channels. They are shared with the stream's
+    /// `WriteStream` or pushers so that channels can be added and removed while the node runs.
+    channels: ChannelEndpoints<Arc<Message<D>>>,
+    /// Handlers of the messages received from other nodes which fail to deserialize, shared with
+    /// the stream's pushers.
+    decode_error_handlers: DecodeErrorHandlers,
 }
 
 impl<D> StreamEndpoints<D>
 where
     for<'a> D: Data + Deserialize<'a>,
 {
-    pub fn new(stream_id: StreamId) -> Self {
+    pub fn new(stream_id: StreamId, capacity: Option<StreamCapacity>) -> Self {
         Self {
             stream_id,
+            capacity,
             recv_endpoints: Vec::new(),
             send_endpoints: Vec::new(),
+            channels: Arc::new(std::sync::Mutex::new(Vec::new())),
+            decode_error_handlers: Arc::new(std::sync::Mutex::new(Vec::new())),
         }
     }
 
-    /// Takes a `RecvEndpoint` out of the stream.
-    fn take_recv_endpoint(&mut self) -> Result<RecvEndpoint<Arc<Message<D>>>, &'static str> {
-        match self.recv_endpoints.pop() {
-            Some(recv_endpoint) => Ok(recv_endpoint),
+    /// Takes the `RecvEndpoint` of a channel to `sink` out of the stream.
+    fn take_recv_endpoint(
+        &mut self,
+        sink: &str,
+    ) -> Result<RecvEndpoint<Arc<Message<D>>>, &'static str> {
+        match self
+            .recv_endpoints
+            .iter()
+            .position(|(name, _)| name == sink)
+        {
+            Some(index) => Ok(self.recv_endpoints.remove(index).1),
             None => Err("No more recv endpoints available"),
         }
     }
@@ -75,6 +130,7 @@ where
     fn get_send_endpoints(&mut self) -> Result<Vec<SendEndpoint<Arc<Message<D>>>>, &'static str> {
         let mut result: Vec<SendEndpoint<Arc<Message<D>>>> = Vec::new();
         result.append(&mut self.send_endpoints);
+        result.push(SendEndpoint::Channels(Arc::clone(&self.channels)));
         Ok(result)
     }
 
@@ -82,12 +138,15 @@ where
         self.send_endpoints.push(endpoint);
     }
 
-    fn add_recv_endpoint(&mut self, endpoint: RecvEndpoint<Arc<Message<D>>>) {
-        self.recv_endpoints.push(endpoint);
+    fn add_channel_endpoint(&mut self, sink: String, endpoint: SendEndpoint<Arc<Message<D>>>) {
+        self.channels.lock().unwrap().push((sink, endpoint));
+    }
+
+    fn add_recv_endpoint(&mut self, sink: String, endpoint: RecvEndpoint<Arc<Message<D>>>) {
+        self.recv_endpoints.push((sink, endpoint));
     }
 }
 
-#[async_trait]
 impl<D> StreamEndpointsT for StreamEndpoints<D>
 where
     for<'a> D: Data + Deserialize<'a>,
@@ -96,44 +155,96 @@ where
         self
     }
 
-    fn add_inter_thread_channel(&mut self) {
+    fn add_inter_thread_channel(&mut self, sink: String, metrics: &MetricsRegistry) {
         let (tx, rx) = mpsc::unbounded_channel();
-        self.add_send_endpoint(SendEndpoint::InterThread(tx));
-        self.add_recv_endpoint(RecvEndpoint::InterThread(rx));
+        let queue = ChannelQueue::new(self.capacity);
+        metrics.register_channel(self.stream_id, sink.clone(), Arc::clone(&queue));
+        self.add_channel_endpoint(
+            sink.clone(),
+            SendEndpoint::TrackedInterThread(tx, Arc::clone(&queue)),
+        );
+        self.add_recv_endpoint(sink, RecvEndpoint::TrackedInterThread(rx, queue));
+    }
+
+    fn add_record_endpoint(&mut self, recorder: Arc<MessageRecorder>) {
+        self.add_send_endpoint(SendEndpoint::Record(self.stream_id, recorder));
+    }
+
+    fn add_metrics_endpoint(&mut self, metrics: Arc<StreamMetrics>) {
+        self.add_send_endpoint(SendEndpoint::Metrics(metrics));
     }
 
-    async fn add_inter_node_send_endpoint(
+    fn add_inter_node_send_endpoint(
         &mut self,
         other_node_id: NodeId,
-        channels_to_senders: Arc<Mutex<ChannelsToSenders>>,
+        channels_to_senders: &ChannelsToSenders,
+        metrics: &MetricsRegistry,
     ) -> Result<(), String> {
-        let channels_to_senders = channels_to_senders.lock().await;
         if let Some(tx) = channels_to_senders.clone_channel(other_node_id) {
-            self.add_send_endpoint(SendEndpoint::InterProcess(self.stream_id, tx));
+            // The queue tracks the messages queued for the network sender.
+            let queue = ChannelQueue::new(self.capacity);
+            let sink = node_sink_name(other_node_id);
+            metrics.register_channel(self.stream_id, sink.clone(), Arc::clone(&queue));
+            self.add_channel_endpoint(
+                sink,
+                SendEndpoint::TrackedInterProcess(self.stream_id, tx, queue),
+            );
             Ok(())
         } else {
             Err(format!("Unable to clone channel to node {}", other_node_id))
         }
     }
 
-    fn add_inter_node_recv_endpoint(
-        &mut self,
-        receiver_pushers: &mut HashMap<StreamId, Box<dyn PusherT>>,
-    ) -> Result<(), String> {
-        let pusher: &mut Box<dyn PusherT> = receiver_pushers
-            .entry(self.stream_id)
-            .or_insert_with(|| Box::new(Pusher::<Arc<Message<D>>>::new()));
-        if let Some(pusher) = pusher.as_any().downcast_mut::<Pusher<Arc<Message<D>>>>() {
-            let (tx, rx) = mpsc::unbounded_channel();
-            pusher.add_endpoint(SendEndpoint::InterThread(tx));
-            self.add_recv_endpoint(RecvEndpoint::InterThread(rx));
-            Ok(())
-        } else {
-            Err(format!(
-                "Error casting pusher when adding inter node recv endpoint for stream {}",
-                self.stream_id
-            ))
+    fn add_shared_memory_send_endpoint(&mut self, other_node_id: NodeId, path: PathBuf) {
+        self.add_channel_endpoint(
+            node_sink_name(other_node_id),
+            SendEndpoint::SharedMemory(Arc::new(SharedMemorySender::new(path))),
+        );
+    }
+
+    fn add_inter_node_recv_endpoint(&mut self, sink: String, metrics: &MetricsRegistry) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let queue = ChannelQueue::new(None);
+        metrics.register_channel(self.stream_id, sink.clone(), Arc::clone(&queue));
+        self.add_channel_endpoint(
+            sink.clone(),
+            SendEndpoint::TrackedInterThread(tx, Arc::clone(&queue)),
+        );
+        self.add_recv_endpoint(sink, RecvEndpoint::TrackedInterThread(rx, queue));
+    }
+
+    fn remove_channel(&mut self, sink: &str, metrics: &MetricsRegistry) {
+        let mut channels = self.channels.lock().unwrap();
+        if let Some(index) = channels.iter().position(|(name, _)| name == sink) {
+            channels.remove(index);
+        }
+        // The receiver of the channel may not have been taken yet.
+        if let Some(index) = self
+            .recv_endpoints
+            .iter()
+            .position(|(name, _)| name == sink)
+        {
+            self.recv_endpoints.remove(index);
         }
+        self.decode_error_handlers
+            .lock()
+            .unwrap()
+            .retain(|(name, _)| name != sink);
+        metrics.unregister_channel(self.stream_id, sink);
+    }
+
+    fn add_decode_error_handler(&mut self, sink: String, handler: DecodeErrorHandler) {
+        self.decode_error_handlers
+            .lock()
+            .unwrap()
+            .push((sink, handler));
+    }
+
+    fn make_pusher(&self) -> Box<dyn PusherT> {
+        let mut pusher = Pusher::<Arc<Message<D>>>::new();
+        pusher.add_endpoint(SendEndpoint::Channels(Arc::clone(&self.channels)));
+        pusher.set_decode_error_handlers(Arc::clone(&self.decode_error_handlers));
+        Box::new(pusher)
     }
 }
 
@@ -142,6 +253,16 @@ where
 pub struct ChannelManager {
     /// The node to which the [`ChannelManager`] belongs.
     node_id: NodeId,
+    /// The data addresses of the nodes, which identify the shared-memory rings to the nodes.
+    data_addresses: Vec<SocketAddr>,
+    /// Size of the shared-memory rings created by the node, which are disabled if `None`.
+    shared_memory_size: Option<usize>,
+    /// Logs errors of the shared-memory receivers.
+    logger: slog::Logger,
+    /// Records the messages sent on the streams which originate on the node.
+    recorder: Option<Arc<MessageRecorder>>,
+    /// Whether to collect the metrics of the streams which originate on the node.
+    stream_metrics: bool,
     /// The dataflow graph.
     graph: Graph,
     /// Stores a `StreamEndpoints` for each stream id.
@@ -152,84 +273,232 @@ impl ChannelManager {
     /// Creates transport channels between connected operators on this node, transport channels
     /// for operators with streams containing dataflow channels to other nodes, and transport
     /// channels from TCP receivers to operators that are connected to streams originating on
-    /// other nodes.
+    /// other nodes. Messages from nodes on the same host are read from shared-memory rings, which
+    /// are created here before any node sends messages.
+    /// If a `recorder` is provided, it records the messages sent on the streams which originate
+    /// on this node. The metrics of the channels, and of the streams if enabled in the
+    /// configuration, are registered in `metrics`.
     pub async fn new(
         graph: &Graph,
-        node_id: NodeId,
+        config: &Configuration,
         channels_to_receivers: Arc<Mutex<ChannelsToReceivers>>,
         channels_to_senders: Arc<Mutex<ChannelsToSenders>>,
-    ) -> Self {
+        recorder: Option<Arc<MessageRecorder>>,
+        metrics: &MetricsRegistry,
+    ) -> Result<Self, String> {
         let mut channel_manager = Self {
-            node_id,
-            graph: graph.clone(),
+            node_id: config.index,
+            data_addresses: config.data_addresses.clone(),
+            shared_memory_size: config.shared_memory_size,
+            logger: config.logger.clone(),
+            recorder,
+            stream_metrics: config.stream_metrics,
+            graph: Graph::new(),
             stream_entries: HashMap::new(),
         };
+        channel_manager.reconfigure(
+            graph,
+            &mut *channels_to_receivers.lock().await,
+            &*channels_to_senders.lock().await,
+            metrics,
+        )?;
+        Ok(channel_manager)
+    }
 
-        let mut receiver_pushers: HashMap<StreamId, Box<dyn PusherT>> = HashMap::new();
+    /// Updates the transport channels to match the scheduled `graph`, which may add and remove
+    /// operators, drivers, and streams.
+    ///
+    /// Channels are added to and removed from the streams in place, so that running operators
+    /// send on the new channels. Pushers for the streams newly received from other nodes are
+    /// sent to the receivers via `channels_to_receivers`.
+    pub(crate) fn reconfigure(
+        &mut self,
+        graph: &Graph,
+        channels_to_receivers: &mut ChannelsToReceivers,
+        channels_to_senders: &ChannelsToSenders,
+        metrics: &MetricsRegistry,
+    ) -> Result<(), String> {
+        let mut node_vertices: HashSet<Vertex> =
+            graph.get_vertices_on(self.node_id).into_iter().collect();
+        node_vertices.extend(self.graph.get_vertices_on(self.node_id));
 
-        let node_vertices = graph.get_vertices_on(node_id);
-        for stream_metadata in graph.get_streams() {
+        // Streams which were removed from the graph have no channels left.
+        let mut streams: Vec<_> = graph
+            .get_streams()
+            .into_iter()
+            .map(|stream| {
+                let channels = stream.get_channels();
+                (stream, channels)
+            })
+            .collect();
+        for stream in self.graph.get_streams() {
+            if graph.get_stream(stream.get_id()).is_none() {
+                streams.push((stream, Vec::new()));
+            }
+        }
+
+        for (stream_metadata, new_channels) in streams {
+            let stream_id = stream_metadata.get_id();
+            let old_channels = self
+                .graph
+                .get_stream(stream_id)
+                .map(|stream| stream.get_channels())
+                .unwrap_or_default();
             if node_vertices.contains(&stream_metadata.get_source()) {
-                let stream_endpoint_t = channel_manager
+                let is_new = !self.stream_entries.contains_key(&stream_id);
+                let stream_endpoint_t = self
                     .stream_entries
-                    .entry(stream_metadata.get_id())
+                    .entry(stream_id)
                     .or_insert_with(|| stream_metadata.to_stream_endpoints_t());
-                for channel in stream_metadata.get_channels() {
-                    match channel {
-                        Channel::InterNode(channel_metadata) => {
-                            let other_node_id = match channel_metadata.sink {
-                                Vertex::Driver(id) => id,
-                                Vertex::Operator(op_id) => {
-                                    graph.get_operator(op_id).unwrap().node_id
-                                }
-                            };
-                            stream_endpoint_t
-                                .add_inter_node_send_endpoint(
-                                    other_node_id,
-                                    Arc::clone(&channels_to_senders),
-                                )
-                                .await
-                                .unwrap();
+                if is_new {
+                    if let Some(recorder) = &self.recorder {
+                        if recorder.is_recorded(stream_id) {
+                            stream_endpoint_t.add_record_endpoint(Arc::clone(recorder));
                         }
-                        Channel::InterThread(_) => {
-                            stream_endpoint_t.add_inter_thread_channel();
+                    }
+                    if self.stream_metrics {
+                        stream_endpoint_t.add_metrics_endpoint(metrics.register_stream(stream_id));
+                    }
+                }
+                for channel in difference(&old_channels, &new_channels) {
+                    if let Channel::InterThread(channel_metadata) = channel {
+                        stream_endpoint_t
+                            .remove_channel(&sink_name(&channel_metadata.sink), metrics);
+                    }
+                }
+                for channel in difference(&new_channels, &old_channels) {
+                    match channel {
+                        Channel::InterThread(channel_metadata) => {
+                            stream_endpoint_t.add_inter_thread_channel(
+                                sink_name(&channel_metadata.sink),
+                                metrics,
+                            );
                         }
                         Channel::Unscheduled(cm) => eprintln!("Unscheduled channel: {:?}", cm),
+                        // Channels to other nodes are added below.
+                        _ => (),
+                    }
+                }
+                // A single endpoint sends the messages of the stream to each other node.
+                let old_nodes = sink_nodes(&self.graph, &old_channels);
+                let new_nodes = sink_nodes(graph, &new_channels);
+                for other_node_id in old_nodes.keys() {
+                    if !new_nodes.contains_key(other_node_id) {
+                        stream_endpoint_t.remove_channel(&node_sink_name(*other_node_id), metrics);
+                    }
+                }
+                for (&other_node_id, &shared_memory) in new_nodes.iter() {
+                    if old_nodes.contains_key(&other_node_id) {
+                        continue;
+                    }
+                    if shared_memory {
+                        stream_endpoint_t.add_shared_memory_send_endpoint(
+                            other_node_id,
+                            communication::ring_path(self.data_addresses[other_node_id], stream_id),
+                        );
+                    } else {
+                        stream_endpoint_t.add_inter_node_send_endpoint(
+                            other_node_id,
+                            channels_to_senders,
+                            metrics,
+                        )?;
                     }
                 }
             } else {
-                for channel in stream_metadata.get_channels() {
-                    if let Channel::InterNode(channel_metadata) = channel {
-                        if node_vertices.contains(&channel_metadata.sink) {
-                            let stream_endpoint_t = channel_manager
-                                .stream_entries
-                                .entry(stream_metadata.get_id())
-                                .or_insert_with(|| stream_metadata.to_stream_endpoints_t());
-                            stream_endpoint_t
-                                .add_inter_node_recv_endpoint(&mut receiver_pushers)
-                                .unwrap();
-                        }
+                // Channels from other nodes to the operators and driver on this node.
+                let is_local = |channel: &Channel| match channel {
+                    Channel::InterNode(cm) | Channel::SharedMemory(cm) => {
+                        node_vertices.contains(&cm.sink)
                     }
+                    _ => false,
+                };
+                let added: Vec<_> = difference(&new_channels, &old_channels)
+                    .into_iter()
+                    .filter(is_local)
+                    .collect();
+                let removed: Vec<_> = difference(&old_channels, &new_channels)
+                    .into_iter()
+                    .filter(is_local)
+                    .collect();
+                if added.is_empty() && removed.is_empty() {
+                    continue;
+                }
+                let is_new = !self.stream_entries.contains_key(&stream_id);
+                let stream_endpoint_t = self
+                    .stream_entries
+                    .entry(stream_id)
+                    .or_insert_with(|| stream_metadata.to_stream_endpoints_t());
+                for channel in removed {
+                    stream_endpoint_t.remove_channel(&sink_name(&channel.metadata().sink), metrics);
+                }
+                for channel in added.iter() {
+                    stream_endpoint_t
+                        .add_inter_node_recv_endpoint(sink_name(&channel.metadata().sink), metrics);
+                }
+                if graph.get_stream(stream_id).is_none() {
+                    // Drop the messages which other nodes still send on the removed stream.
+                    channels_to_receivers.remove(stream_id);
+                    continue;
+                }
+                if !is_new {
+                    continue;
+                }
+                // Publish the messages received from the other node on the stream's channels.
+                let pusher = stream_endpoint_t.make_pusher();
+                match added.first() {
+                    Some(Channel::SharedMemory(_)) => {
+                        self.spawn_shared_memory_receiver(stream_id, pusher)
+                    }
+                    // Send the pusher to the DataReceivers which publish received messages from
+                    // TCP on the proper transport channel.
+                    _ => channels_to_receivers.send(stream_id, pusher),
                 }
             }
         }
+        self.graph = graph.clone();
+        Ok(())
+    }
 
-        // Send pushers to the DataReceiver which publishes received messages from TCP
-        // on the proper transport channel.
-        for (k, v) in receiver_pushers.into_iter() {
-            channels_to_receivers.lock().await.send(k, v);
+    /// Creates the ring of a stream received over shared memory, and forwards the messages
+    /// written to it with the `pusher`.
+    fn spawn_shared_memory_receiver(&self, stream_id: StreamId, pusher: Box<dyn PusherT>) {
+        let size = match self.shared_memory_size {
+            Some(size) => size,
+            None => return,
+        };
+        let path = communication::ring_path(self.data_addresses[self.node_id], stream_id);
+        match SharedMemoryReceiver::new(path.clone(), size, pusher) {
+            Ok(receiver) => {
+                let logger = self.logger.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = receiver.run().await {
+                        slog::error!(
+                            logger,
+                            "Error receiving messages on stream {} over shared memory: {:?}",
+                            stream_id,
+                            e
+                        );
+                    }
+                });
+            }
+            Err(e) => slog::error!(
+                self.logger,
+                "Unable to create shared-memory ring {}: {}",
+                path.display(),
+                e
+            ),
         }
-        channel_manager
     }
 
     pub fn node_id(&self) -> NodeId {
         self.node_id
     }
 
-    /// Takes a `RecvEnvpoint` from a given stream.
+    /// Takes the `RecvEnvpoint` of the channel from a given stream to `sink`.
     pub fn take_recv_endpoint<D>(
         &mut self,
         stream_id: StreamId,
+        sink: &Vertex,
     ) -> Result<RecvEndpoint<Arc<Message<D>>>, String>
     where
         for<'a> D: Data + Deserialize<'a>,
@@ -239,7 +508,7 @@ impl ChannelManager {
         if let Some(stream_entry_t) = self.stream_entries.get_mut(&stream_id) {
             if let Some(stream_entry) = stream_entry_t.as_any().downcast_mut::<StreamEndpoints<D>>()
             {
-                match stream_entry.take_recv_endpoint() {
+                match stream_entry.take_recv_endpoint(&sink_name(sink)) {
                     Ok(recv_endpoint) => Ok(recv_endpoint),
                     Err(msg) => Err(format!(
                         "Could not get recv endpoint with id {}: {}",
@@ -257,6 +526,23 @@ impl ChannelManager {
         }
     }
 
+    /// Sends the messages received from other nodes on a stream which fail to deserialize on the
+    /// side output of `sink`, an operator which reads the stream.
+    pub fn add_side_output(
+        &mut self,
+        stream_id: StreamId,
+        sink: &Vertex,
+        side_output: &SideOutput,
+    ) {
+        let stream_id = self.graph.resolve_stream_id(stream_id);
+        if let Some(stream_entry_t) = self.stream_entries.get_mut(&stream_id) {
+            stream_entry_t.add_decode_error_handler(
+                sink_name(sink),
+                side_output.make_decode_error_handler(stream_id),
+            );
+        }
+    }
+
     /// Returns a cloned vector of the `SendEndpoint`s for a given stream.
     pub fn get_send_endpoints<D>(
         &mut self,
@@ -286,3 +572,53 @@ impl ChannelManager {
         }
     }
 }
+
+/// Returns the channels in `channels` which are not in `other`, counting duplicate channels.
+fn difference(channels: &[Channel], other: &[Channel]) -> Vec<Channel> {
+    let mut other = other.to_vec();
+    let mut result = Vec::new();
+    for channel in channels {
+        match other.iter().position(|c| c == channel) {
+            Some(index) => {
+                other.swap_remove(index);
+            }
+            None => result.push(channel.clone()),
+        }
+    }
+    result
+}
+
+/// Returns the other nodes to which `channels` send messages, and whether they use shared
+/// memory.
+fn sink_nodes(graph: &Graph, channels: &[Channel]) -> HashMap<NodeId, bool> {
+    let mut nodes = HashMap::new();
+    for channel in channels {
+        match channel {
+            Channel::InterNode(cm) => nodes.insert(sink_node_id(graph, &cm.sink), false),
+            Channel::SharedMemory(cm) => nodes.insert(sink_node_id(graph, &cm.sink), true),
+            _ => None,
+        };
+    }
+    nodes
+}
+
+/// Returns the node on which the sink of a channel runs.
+fn sink_node_id(graph: &Graph, sink: &Vertex) -> NodeId {
+    match sink {
+        Vertex::Driver(node_id) => *node_id,
+        Vertex::Operator(op_id) => graph.get_operator(*op_id).unwrap().node_id,
+    }
+}
+
+/// Describes the sink of a channel in the metrics.
+fn sink_name(sink: &Vertex) -> String {
+    match sink {
+        Vertex::Driver(node_id) => format!("driver {}", node_id),
+        Vertex::Operator(op_id) => format!("operator {}", op_id),
+    }
+}
+
+/// Describes the other node to which a stream is sent in the metrics.
+fn node_sink_name(node_id: NodeId) -> String {
+    format!("node {}", node_id)
+}
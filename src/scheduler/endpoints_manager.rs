@@ -7,6 +7,10 @@ use crate::{
     node::NodeId,
 };
 
+/// Update of the pusher of a stream sent to the receivers. The stream was removed from the
+/// dataflow if the pusher is `None`.
+pub type PusherUpdate = (StreamId, Option<Box<dyn PusherT>>);
+
 /// Wrapper used to update pushers in the TCP receiving.
 ///
 /// Stores `mpsc::Sender`s to receivers on which `PusherT` can be sent to inform
@@ -15,7 +19,7 @@ pub struct ChannelsToReceivers {
     // We do not use a tokio::mpsc::UnboundedSender because that only provides a blocking API.
     // It does not allow us to just check if the channel has a new message. We need this API in
     // the receivers, which regularly check if there are new pushers available.
-    senders: Vec<UnboundedSender<(StreamId, Box<dyn PusherT>)>>,
+    senders: Vec<UnboundedSender<PusherUpdate>>,
 }
 
 impl ChannelsToReceivers {
@@ -26,7 +30,7 @@ impl ChannelsToReceivers {
     }
 
     /// Adds a `mpsc::Sender` to a new receiver thread.
-    pub fn add_sender(&mut self, sender: UnboundedSender<(StreamId, Box<dyn PusherT>)>) {
+    pub fn add_sender(&mut self, sender: UnboundedSender<PusherUpdate>) {
         self.senders.push(sender);
     }
 
@@ -35,10 +39,18 @@ impl ChannelsToReceivers {
     /// It sends a `PusherT` to message on all receiving threads.
     pub fn send(&mut self, stream_id: StreamId, pusher: Box<dyn PusherT>) {
         for sender in self.senders.iter_mut() {
-            let msg = (stream_id.clone(), pusher.clone());
+            let msg = (stream_id.clone(), Some(pusher.clone()));
             sender.send(msg).unwrap();
         }
     }
+
+    /// Informs the receivers that the stream was removed from the dataflow, so that they drop the
+    /// messages received on it.
+    pub fn remove(&mut self, stream_id: StreamId) {
+        for sender in self.senders.iter_mut() {
+            sender.send((stream_id, None)).unwrap();
+        }
+    }
 }
 
 /// Wrapper used to store mappings between node ids and `mpsc::UnboundedSender` to sender threads.
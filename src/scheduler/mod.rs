@@ -1,15 +1,54 @@
+use std::net::SocketAddr;
+
 use crate::dataflow::graph::{Channel, Graph, Vertex};
 
+// Private submodules
+mod placement;
+
 // Crate-wide visible submodules
 pub(crate) mod endpoints_manager;
 
-// Public exports
+// Public submodules
 pub mod channel_manager;
 
-/// Schedules a dataflow graph. Assigns operators to nodes and updates channels.
+// Public exports
+pub use placement::{MinCommunicationScheduler, PlacementGraph, RoundRobinScheduler, Scheduler};
+
+/// Schedules a dataflow graph on the nodes with the given `data_addresses`. Assigns operators to
+/// nodes and updates channels. Operators which are not pinned are placed by the `scheduler`, or
+/// otherwise remain on the node set in their configuration.
+/// Channels between nodes with the same IP address use shared memory if `shared_memory` is set.
 /// After running this method, there should be no unscheduled channels remaining.
-pub(crate) fn schedule(graph: &Graph) -> Graph {
+pub(crate) fn schedule(
+    graph: &Graph,
+    scheduler: Option<&dyn Scheduler>,
+    data_addresses: &[SocketAddr],
+    shared_memory: bool,
+) -> Result<Graph, String> {
+    let num_nodes = data_addresses.len();
     let mut scheduled_graph = graph.clone();
+    if let Some(scheduler) = scheduler {
+        if num_nodes == 0 {
+            return Err("Cannot schedule operators without any nodes".to_string());
+        }
+        let placement_graph = PlacementGraph::new(graph);
+        let placement = scheduler.place(&placement_graph, num_nodes);
+        for op_id in placement_graph.unpinned_operators() {
+            match placement.get(&op_id) {
+                Some(&node_id) if node_id < num_nodes => {
+                    scheduled_graph.set_operator_node(op_id, node_id)
+                }
+                Some(&node_id) => {
+                    return Err(format!(
+                        "Scheduler placed operator {} on node {}, but there are {} nodes",
+                        op_id, node_id, num_nodes
+                    ))
+                }
+                None => return Err(format!("Scheduler did not place operator {}", op_id)),
+            }
+        }
+    }
+    let graph = scheduled_graph.clone();
     for stream in scheduled_graph.get_streams_ref_mut() {
         let source_node_id = match stream.get_source() {
             Vertex::Driver(node_id) => node_id,
@@ -28,6 +67,10 @@ pub(crate) fn schedule(graph: &Graph) -> Graph {
                     };
                     if source_node_id == sink_node_id {
                         Channel::InterThread(cm)
+                    } else if shared_memory
+                        && data_addresses[source_node_id].ip() == data_addresses[sink_node_id].ip()
+                    {
+                        Channel::SharedMemory(cm)
                     } else {
                         Channel::InterNode(cm)
                     }
@@ -37,5 +80,5 @@ pub(crate) fn schedule(graph: &Graph) -> Graph {
         }
         stream.set_channels(channels);
     }
-    scheduled_graph
+    Ok(scheduled_graph)
 }
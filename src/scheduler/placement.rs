@@ -0,0 +1,316 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::{
+    dataflow::graph::{Channel, Graph, Vertex},
+    node::NodeId,
+    OperatorId,
+};
+
+/// Describes the operators to place on nodes, and the channels between them.
+#[derive(Clone, Debug)]
+pub struct PlacementGraph {
+    /// The operators sorted by ID, and the node to which each operator is pinned.
+    pub operators: Vec<(OperatorId, Option<NodeId>)>,
+    /// The source and sink of each channel. Drivers are fixed on their nodes.
+    pub channels: Vec<(Vertex, Vertex)>,
+}
+
+impl PlacementGraph {
+    pub(crate) fn new(graph: &Graph) -> Self {
+        let mut operators: Vec<_> = graph
+            .get_operators()
+            .into_iter()
+            .map(|op| (op.id, if op.pinned { Some(op.node_id) } else { None }))
+            .collect();
+        operators.sort();
+        let mut channels: Vec<_> = graph
+            .get_streams()
+            .into_iter()
+            .flat_map(|stream| stream.get_channels())
+            .map(|channel| match channel {
+                Channel::InterNode(cm)
+                | Channel::InterThread(cm)
+                | Channel::SharedMemory(cm)
+                | Channel::Unscheduled(cm) => (cm.source, cm.sink),
+            })
+            .collect();
+        // Sort the channels so that each node computes the same placement.
+        channels.sort_by_key(|(source, sink)| (vertex_key(source), vertex_key(sink)));
+        Self {
+            operators,
+            channels,
+        }
+    }
+
+    /// Returns the IDs of the operators which are not pinned.
+    pub fn unpinned_operators(&self) -> Vec<OperatorId> {
+        self.operators
+            .iter()
+            .filter(|(_, pinned_node)| pinned_node.is_none())
+            .map(|(id, _)| *id)
+            .collect()
+    }
+}
+
+fn vertex_key(vertex: &Vertex) -> (u8, NodeId, OperatorId) {
+    match vertex {
+        Vertex::Driver(node_id) => (0, *node_id, OperatorId::nil()),
+        Vertex::Operator(op_id) => (1, 0, *op_id),
+    }
+}
+
+/// Places operators which are not pinned on nodes.
+///
+/// Every node schedules the dataflow graph independently, so placements must be deterministic.
+pub trait Scheduler: Send + Sync {
+    /// Returns the node on which each operator in
+    /// [`unpinned_operators`](PlacementGraph::unpinned_operators) runs, out of `num_nodes` nodes.
+    /// Places no operators if `num_nodes` is 0.
+    fn place(&self, graph: &PlacementGraph, num_nodes: usize) -> HashMap<OperatorId, NodeId>;
+}
+
+/// Places the operators which are not pinned on the nodes in turn, in the order of their IDs.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RoundRobinScheduler {}
+
+impl RoundRobinScheduler {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Scheduler for RoundRobinScheduler {
+    fn place(&self, graph: &PlacementGraph, num_nodes: usize) -> HashMap<OperatorId, NodeId> {
+        if num_nodes == 0 {
+            return HashMap::new();
+        }
+        graph
+            .unpinned_operators()
+            .into_iter()
+            .enumerate()
+            .map(|(i, op_id)| (op_id, i % num_nodes))
+            .collect()
+    }
+}
+
+/// Places the operators which are not pinned so as to minimize the number of channels between
+/// nodes, while balancing the number of operators on each node.
+///
+/// Operators are first placed greedily in breadth-first order from the drivers and pinned
+/// operators, on the node to which they have the most channels. Operators are then moved between
+/// nodes while this reduces the number of channels between nodes. Each node runs at most
+/// `ceil(number of operators / num_nodes)` operators, unless more operators are pinned to it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MinCommunicationScheduler {}
+
+impl MinCommunicationScheduler {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Scheduler for MinCommunicationScheduler {
+    fn place(&self, graph: &PlacementGraph, num_nodes: usize) -> HashMap<OperatorId, NodeId> {
+        if num_nodes == 0 {
+            return HashMap::new();
+        }
+        let capacity = (graph.operators.len() + num_nodes - 1) / num_nodes;
+        // Undirected neighbors of each vertex, with one entry per channel.
+        let mut neighbors: HashMap<Vertex, Vec<Vertex>> = HashMap::new();
+        for (source, sink) in graph.channels.iter() {
+            if source != sink {
+                neighbors
+                    .entry(source.clone())
+                    .or_default()
+                    .push(sink.clone());
+                neighbors
+                    .entry(sink.clone())
+                    .or_default()
+                    .push(source.clone());
+            }
+        }
+
+        let mut placement: HashMap<Vertex, NodeId> = HashMap::new();
+        let mut load = vec![0; num_nodes];
+        let mut queue = VecDeque::new();
+        for (source, sink) in graph.channels.iter() {
+            for vertex in [source, sink].iter() {
+                if let Vertex::Driver(node_id) = vertex {
+                    if placement.insert((*vertex).clone(), *node_id).is_none() {
+                        queue.push_back((*vertex).clone());
+                    }
+                }
+            }
+        }
+        for (op_id, pinned_node) in graph.operators.iter() {
+            if let Some(node_id) = pinned_node {
+                placement.insert(Vertex::Operator(*op_id), *node_id);
+                if *node_id < num_nodes {
+                    load[*node_id] += 1;
+                }
+                queue.push_back(Vertex::Operator(*op_id));
+            }
+        }
+
+        // Number of channels between a vertex and the vertices placed on each node.
+        let node_channels = |vertex: &Vertex, placement: &HashMap<Vertex, NodeId>| {
+            let mut counts = vec![0usize; num_nodes];
+            for neighbor in neighbors.get(vertex).into_iter().flatten() {
+                if let Some(&node_id) = placement.get(neighbor) {
+                    if node_id < num_nodes {
+                        counts[node_id] += 1;
+                    }
+                }
+            }
+            counts
+        };
+
+        // Greedily place operators reachable from placed vertices, then the remaining operators.
+        let unpinned = graph.unpinned_operators();
+        let mut visited: HashSet<Vertex> = queue.iter().cloned().collect();
+        let mut order = Vec::with_capacity(unpinned.len());
+        let unpinned_set: HashSet<OperatorId> = unpinned.iter().cloned().collect();
+        let mut remaining = unpinned.iter();
+        loop {
+            while let Some(vertex) = queue.pop_front() {
+                if let Vertex::Operator(op_id) = vertex {
+                    if unpinned_set.contains(&op_id) {
+                        order.push(op_id);
+                    }
+                }
+                for neighbor in neighbors.get(&vertex).into_iter().flatten() {
+                    if visited.insert(neighbor.clone()) {
+                        queue.push_back(neighbor.clone());
+                    }
+                }
+            }
+            match remaining.find(|op_id| !visited.contains(&Vertex::Operator(**op_id))) {
+                Some(op_id) => {
+                    visited.insert(Vertex::Operator(*op_id));
+                    queue.push_back(Vertex::Operator(*op_id));
+                }
+                None => break,
+            }
+        }
+        for op_id in order.iter() {
+            let vertex = Vertex::Operator(*op_id);
+            let counts = node_channels(&vertex, &placement);
+            // Prefer the node with the most channels, then the least loaded node.
+            let node_id = (0..num_nodes)
+                .filter(|&node_id| load[node_id] < capacity)
+                .max_by_key(|&node_id| (counts[node_id], std::cmp::Reverse(load[node_id])))
+                .unwrap_or(0);
+            load[node_id] += 1;
+            placement.insert(vertex, node_id);
+        }
+
+        // Move operators to the nodes to which they have more channels.
+        let mut improved = true;
+        while improved {
+            improved = false;
+            for op_id in order.iter() {
+                let vertex = Vertex::Operator(*op_id);
+                let current = placement[&vertex];
+                let counts = node_channels(&vertex, &placement);
+                let best = (0..num_nodes)
+                    .filter(|&node_id| node_id != current && load[node_id] < capacity)
+                    .max_by_key(|&node_id| counts[node_id]);
+                if let Some(node_id) = best {
+                    if counts[node_id] > counts[current] {
+                        load[current] -= 1;
+                        load[node_id] += 1;
+                        placement.insert(vertex, node_id);
+                        improved = true;
+                    }
+                }
+            }
+        }
+
+        unpinned
+            .into_iter()
+            .map(|op_id| (op_id, placement[&Vertex::Operator(op_id)]))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn op(i: u8) -> OperatorId {
+        crate::Uuid([i; 16])
+    }
+
+    #[test]
+    fn test_round_robin_skips_pinned() {
+        let graph = PlacementGraph {
+            operators: vec![
+                (op(1), None),
+                (op(2), Some(0)),
+                (op(3), None),
+                (op(4), None),
+            ],
+            channels: Vec::new(),
+        };
+        let placement = RoundRobinScheduler::new().place(&graph, 2);
+        assert_eq!(placement.len(), 3);
+        assert_eq!(placement[&op(1)], 0);
+        assert_eq!(placement[&op(3)], 1);
+        assert_eq!(placement[&op(4)], 0);
+    }
+
+    #[test]
+    fn test_min_communication_places_pipelines_together() {
+        // Two pipelines, each starting at an operator pinned to a different node.
+        let graph = PlacementGraph {
+            operators: vec![
+                (op(1), Some(0)),
+                (op(2), None),
+                (op(3), None),
+                (op(4), Some(1)),
+                (op(5), None),
+                (op(6), None),
+            ],
+            channels: vec![
+                (Vertex::Operator(op(1)), Vertex::Operator(op(5))),
+                (Vertex::Operator(op(5)), Vertex::Operator(op(3))),
+                (Vertex::Operator(op(4)), Vertex::Operator(op(2))),
+                (Vertex::Operator(op(2)), Vertex::Operator(op(6))),
+            ],
+        };
+        let placement = MinCommunicationScheduler::new().place(&graph, 2);
+        assert_eq!(placement[&op(5)], 0);
+        assert_eq!(placement[&op(3)], 0);
+        assert_eq!(placement[&op(2)], 1);
+        assert_eq!(placement[&op(6)], 1);
+    }
+
+    #[test]
+    fn test_min_communication_balances_load() {
+        // A pipeline from the driver on node 0 which does not fit on a single node.
+        let graph = PlacementGraph {
+            operators: vec![(op(1), None), (op(2), None), (op(3), None), (op(4), None)],
+            channels: vec![
+                (Vertex::Driver(0), Vertex::Operator(op(1))),
+                (Vertex::Operator(op(1)), Vertex::Operator(op(2))),
+                (Vertex::Operator(op(2)), Vertex::Operator(op(3))),
+                (Vertex::Operator(op(3)), Vertex::Operator(op(4))),
+            ],
+        };
+        let placement = MinCommunicationScheduler::new().place(&graph, 2);
+        assert_eq!(placement[&op(1)], 0);
+        assert_eq!(placement[&op(2)], 0);
+        assert_eq!(placement[&op(3)], 1);
+        assert_eq!(placement[&op(4)], 1);
+    }
+
+    #[test]
+    fn test_place_on_no_nodes() {
+        let graph = PlacementGraph {
+            operators: vec![(op(1), None), (op(2), None)],
+            channels: vec![(Vertex::Operator(op(1)), Vertex::Operator(op(2)))],
+        };
+        assert!(RoundRobinScheduler::new().place(&graph, 0).is_empty());
+        assert!(MinCommunicationScheduler::new().place(&graph, 0).is_empty());
+    }
+}
@@ -0,0 +1,165 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use erdos::{
+    dataflow::{
+        message::*,
+        stream::{errors::WriteStreamError, ExtractStream, OverflowPolicy, WriteStreamT},
+        Operator, OperatorConfig, ReadStream, WriteStream,
+    },
+    node::Node,
+    *,
+};
+
+mod utils;
+
+/// Synchronizes the sender and the receiver of a bounded stream.
+#[derive(Default)]
+pub struct SyncState {
+    /// Whether the sender sent all messages.
+    sent: AtomicBool,
+    /// Number of messages the sender failed to send because the stream had no capacity.
+    num_no_capacity: AtomicUsize,
+}
+
+/// Sends 4 messages before the receiver reads any of them.
+pub struct SendOperator {
+    sync_state: Arc<SyncState>,
+    write_stream: WriteStream<usize>,
+}
+
+impl SendOperator {
+    pub fn new(config: OperatorConfig<Arc<SyncState>>, write_stream: WriteStream<usize>) -> Self {
+        Self {
+            sync_state: config.arg.unwrap(),
+            write_stream,
+        }
+    }
+
+    pub fn connect() -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for SendOperator {
+    fn run(&mut self) {
+        for count in 1..=4 {
+            let timestamp = Timestamp::new(vec![count as u64]);
+            match self
+                .write_stream
+                .send(Message::new_message(timestamp.clone(), count))
+            {
+                Ok(()) => (),
+                Err(WriteStreamError::NoCapacity) => {
+                    self.sync_state
+                        .num_no_capacity
+                        .fetch_add(1, Ordering::SeqCst);
+                }
+                Err(e) => panic!("Unexpected error {:?}", e),
+            }
+            // Watermarks do not count towards the capacity.
+            self.write_stream
+                .send(Message::new_watermark(timestamp))
+                .unwrap();
+        }
+        self.sync_state.sent.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Forwards the received data messages once the sender sent all messages.
+pub struct ForwardOperator {
+    sync_state: Arc<SyncState>,
+}
+
+impl ForwardOperator {
+    pub fn new(
+        config: OperatorConfig<Arc<SyncState>>,
+        read_stream: ReadStream<usize>,
+        write_stream: WriteStream<usize>,
+    ) -> Self {
+        read_stream.add_state(write_stream).add_callback(
+            |t: &Timestamp, data: &usize, write_stream: &mut WriteStream<usize>| {
+                write_stream
+                    .send(Message::new_message(t.clone(), *data))
+                    .unwrap();
+            },
+        );
+        Self {
+            sync_state: config.arg.unwrap(),
+        }
+    }
+
+    pub fn connect(_read_stream: &ReadStream<usize>) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for ForwardOperator {
+    fn run(&mut self) {
+        // Messages are not read from the streams until run completes.
+        while !self.sync_state.sent.load(Ordering::SeqCst) {
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+/// Runs the sender with a capacity of 2, and returns the forwarded data and the number of
+/// messages which the sender failed to send.
+fn run_bounded(overflow_policy: OverflowPolicy) -> (Vec<usize>, usize) {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+    let sync_state = Arc::new(SyncState::default());
+
+    let s1 = connect_1_write!(
+        SendOperator,
+        OperatorConfig::new()
+            .arg(Arc::clone(&sync_state))
+            .write_stream_capacity(2, overflow_policy)
+    );
+    let s2 = connect_1_write!(
+        ForwardOperator,
+        OperatorConfig::new().arg(Arc::clone(&sync_state)),
+        s1
+    );
+    let mut extract_stream = ExtractStream::new(0, &s2);
+    node.run_async();
+
+    // The forwarder flows the watermark for timestamp 4 after forwarding all received data.
+    let mut received = Vec::new();
+    loop {
+        let msg = extract_stream.read().unwrap();
+        match msg.data() {
+            Some(data) => received.push(*data),
+            None if msg.timestamp() == &Timestamp::new(vec![4]) => break,
+            None => (),
+        }
+    }
+    (received, sync_state.num_no_capacity.load(Ordering::SeqCst))
+}
+
+#[test]
+fn test_drop_newest() {
+    let (received, num_no_capacity) = run_bounded(OverflowPolicy::DropNewest);
+    assert_eq!(received, vec![1, 2]);
+    assert_eq!(num_no_capacity, 0);
+}
+
+#[test]
+fn test_drop_oldest() {
+    let (received, num_no_capacity) = run_bounded(OverflowPolicy::DropOldest);
+    assert_eq!(received, vec![3, 4]);
+    assert_eq!(num_no_capacity, 0);
+}
+
+#[test]
+fn test_no_capacity_error() {
+    let (received, num_no_capacity) = run_bounded(OverflowPolicy::Error);
+    assert_eq!(received, vec![1, 2]);
+    assert_eq!(num_no_capacity, 2);
+}
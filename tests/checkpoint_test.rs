@@ -0,0 +1,260 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    io,
+    path::PathBuf,
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+use erdos::{
+    dataflow::{
+        checkpoint::{self, CheckpointError, CheckpointStore, FileCheckpointStore},
+        graph::default_graph,
+        message::*,
+        state::TimeVersionedState,
+        stream::{ExtractStream, IngestStream, WriteStreamT},
+        Operator, OperatorConfig, ReadStream, WriteStream,
+    },
+    node::{Node, NodeError, NodeHandle},
+    *,
+};
+
+mod utils;
+
+/// Sends the running sum of the received messages upon receiving a watermark.
+pub struct SumOperator {}
+
+impl SumOperator {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        read_stream: ReadStream<usize>,
+        write_stream: WriteStream<usize>,
+    ) -> Self {
+        let mut state = TimeVersionedState::<usize, usize>::new_with_history_size(1);
+        state.set_initial_state(0).unwrap();
+        let stateful_read_stream = read_stream.add_state(state);
+        stateful_read_stream.enable_checkpointing();
+        stateful_read_stream.add_callback(Self::callback);
+        stateful_read_stream.add_watermark_callback(Self::watermark_callback);
+        stateful_read_stream
+            .add_write_stream(&write_stream)
+            .borrow_mut()
+            .add_watermark_callback(
+                |t: &Timestamp,
+                 state: &TimeVersionedState<usize, usize>,
+                 write_stream: &mut WriteStream<usize>| {
+                    let sum = *state.get_current_state().unwrap();
+                    write_stream
+                        .send(Message::new_message(t.clone(), sum))
+                        .unwrap();
+                },
+            );
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<usize>) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+
+    fn callback(_t: &Timestamp, data: &usize, state: &mut TimeVersionedState<usize, usize>) {
+        state.append(*data).unwrap();
+    }
+
+    fn watermark_callback(t: &Timestamp, state: &mut TimeVersionedState<usize, usize>) {
+        let sum: usize = state.get_current_messages().unwrap().iter().sum();
+        let previous_sum = *state.iter_states().unwrap().nth(1).unwrap().1;
+        *state.get_current_state_mut().unwrap() = previous_sum + sum;
+        state.close_time(t).unwrap();
+    }
+}
+
+impl Operator for SumOperator {}
+
+/// Runs a node with a `SumOperator` which checkpoints to `store`.
+fn run_sum_operator(
+    store: &Arc<dyn CheckpointStore>,
+    recover_from_checkpoint: bool,
+) -> (IngestStream<usize>, ExtractStream<usize>, NodeHandle) {
+    erdos::reset();
+    let mut config = utils::make_default_config();
+    config.checkpoint_store = Some(Arc::clone(store));
+    config.recover_from_checkpoint = recover_from_checkpoint;
+    let node = Node::new(config);
+    let ingest_stream = IngestStream::new(0);
+    let s = connect_1_write!(SumOperator, OperatorConfig::new(), ingest_stream);
+    let extract_stream = ExtractStream::new(0, &s);
+    (ingest_stream, extract_stream, node.run_async())
+}
+
+/// Sends messages and watermarks for timestamps 1 to `max_time`, with the timestamp as data.
+fn send_messages(ingest_stream: &mut IngestStream<usize>, max_time: u64) {
+    for t in 1..=max_time {
+        let timestamp = Timestamp::new(vec![t]);
+        ingest_stream
+            .send(Message::new_message(timestamp.clone(), t as usize))
+            .unwrap();
+        ingest_stream
+            .send(Message::new_watermark(timestamp))
+            .unwrap();
+    }
+}
+
+/// Reads messages until receiving a data message.
+fn read_data(extract_stream: &mut ExtractStream<usize>) -> Message<usize> {
+    loop {
+        let msg = extract_stream.read().unwrap();
+        if msg.data().is_some() {
+            return msg;
+        }
+    }
+}
+
+/// Waits until all operators of the dataflow have a checkpoint for `t`.
+fn wait_for_checkpoint(store: &dyn CheckpointStore, t: Timestamp) {
+    let operator_ids: Vec<_> = default_graph::clone()
+        .get_operators()
+        .iter()
+        .map(|op| op.id)
+        .collect();
+    while checkpoint::latest_consistent_checkpoint(store, &operator_ids).unwrap() != Some(t.clone())
+    {
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn make_checkpoint_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("erdos-{}-{}", name, std::process::id()))
+}
+
+#[test]
+fn test_recover_from_checkpoint() {
+    let dir = make_checkpoint_dir("recovery-test");
+    let store: Arc<dyn CheckpointStore> = Arc::new(FileCheckpointStore::new(&dir));
+
+    // Checkpoint the sum for timestamps 1 to 3.
+    let (mut ingest_stream, mut extract_stream, node_handle) = run_sum_operator(&store, false);
+    send_messages(&mut ingest_stream, 3);
+    assert_eq!(
+        read_data(&mut extract_stream),
+        Message::new_message(Timestamp::new(vec![1]), 1)
+    );
+    assert_eq!(
+        read_data(&mut extract_stream),
+        Message::new_message(Timestamp::new(vec![2]), 3)
+    );
+    assert_eq!(
+        read_data(&mut extract_stream),
+        Message::new_message(Timestamp::new(vec![3]), 6)
+    );
+    // The checkpoint is written after the watermark callback computes the sum.
+    wait_for_checkpoint(store.as_ref(), Timestamp::new(vec![3]));
+    node_handle.shutdown().unwrap();
+
+    // Recreate the same dataflow, and recover the sum from the checkpoint for timestamp 3.
+    let (mut ingest_stream, mut extract_stream, _node_handle) = run_sum_operator(&store, true);
+    // Replayed messages up to timestamp 3 are skipped.
+    send_messages(&mut ingest_stream, 4);
+    assert_eq!(
+        read_data(&mut extract_stream),
+        Message::new_message(Timestamp::new(vec![4]), 10)
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_checkpoint_excludes_later_messages() {
+    let dir = make_checkpoint_dir("later-messages-test");
+    let store: Arc<dyn CheckpointStore> = Arc::new(FileCheckpointStore::new(&dir));
+
+    // The message for timestamp 2 arrives before the watermark for timestamp 1.
+    let (mut ingest_stream, mut extract_stream, node_handle) = run_sum_operator(&store, false);
+    for t in 1..=2 {
+        ingest_stream
+            .send(Message::new_message(Timestamp::new(vec![t]), t as usize))
+            .unwrap();
+    }
+    ingest_stream
+        .send(Message::new_watermark(Timestamp::new(vec![1])))
+        .unwrap();
+    assert_eq!(
+        read_data(&mut extract_stream),
+        Message::new_message(Timestamp::new(vec![1]), 1)
+    );
+    wait_for_checkpoint(store.as_ref(), Timestamp::new(vec![1]));
+    node_handle.shutdown().unwrap();
+
+    // The replayed message for timestamp 2 is only summed once.
+    let (mut ingest_stream, mut extract_stream, _node_handle) = run_sum_operator(&store, true);
+    send_messages(&mut ingest_stream, 2);
+    assert_eq!(
+        read_data(&mut extract_stream),
+        Message::new_message(Timestamp::new(vec![2]), 3)
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Lists a checkpoint for timestamp 1 for each operator, but fails to read checkpoints.
+struct UnreadableCheckpointStore {
+    operator_ids: Vec<OperatorId>,
+}
+
+impl CheckpointStore for UnreadableCheckpointStore {
+    fn write(&self, _key: &str, _t: &Timestamp, _bytes: &[u8]) -> Result<(), CheckpointError> {
+        Ok(())
+    }
+
+    fn read(&self, _key: &str, _t: &Timestamp) -> Result<Option<Vec<u8>>, CheckpointError> {
+        Err(CheckpointError::IoError(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "unreadable",
+        )))
+    }
+
+    fn remove(&self, _key: &str, _t: &Timestamp) -> Result<(), CheckpointError> {
+        Ok(())
+    }
+
+    fn list_key(&self, _key: &str) -> Result<BTreeSet<Timestamp>, CheckpointError> {
+        Ok(BTreeSet::new())
+    }
+
+    fn list(&self) -> Result<HashMap<String, BTreeSet<Timestamp>>, CheckpointError> {
+        Ok(self
+            .operator_ids
+            .iter()
+            .map(|id| {
+                (
+                    id.to_string(),
+                    vec![Timestamp::new(vec![1])].into_iter().collect(),
+                )
+            })
+            .collect())
+    }
+}
+
+#[test]
+fn test_recovery_failure() {
+    let ingest_stream: IngestStream<usize> = IngestStream::new(0);
+    let _s = connect_1_write!(SumOperator, OperatorConfig::new(), ingest_stream);
+    let operator_ids = default_graph::clone()
+        .get_operators()
+        .iter()
+        .map(|op| op.id)
+        .collect();
+
+    let mut config = utils::make_default_config();
+    config.checkpoint_store = Some(Arc::new(UnreadableCheckpointStore { operator_ids }));
+    config.recover_from_checkpoint = true;
+    let node_handle = Node::new(config).run_async();
+
+    // The node fails instead of waiting for the operator to be set up.
+    let error = node_handle.join().unwrap_err();
+    assert!(
+        matches!(&error, NodeError::DataflowError(e) if e.contains("Failed to set up checkpoints")),
+        "{}",
+        error
+    );
+}
@@ -0,0 +1,217 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Duration,
+};
+
+use erdos::{
+    dataflow::{
+        add_timer_callback,
+        message::*,
+        stream::{ExtractStream, IngestStream, WriteStreamT},
+        Operator, OperatorConfig, ReadStream, WriteStream,
+    },
+    node::Node,
+    *,
+};
+
+mod utils;
+
+/// Sends a message and a watermark for timestamp 1.
+pub struct SendOperator {
+    write_stream: WriteStream<usize>,
+}
+
+impl SendOperator {
+    pub fn new(_config: OperatorConfig<()>, write_stream: WriteStream<usize>) -> Self {
+        Self { write_stream }
+    }
+
+    pub fn connect() -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for SendOperator {
+    fn run(&mut self) {
+        self.write_stream
+            .send(Message::new_message(Timestamp::new(vec![1]), 1))
+            .unwrap();
+        self.write_stream
+            .send(Message::new_watermark(Timestamp::new(vec![1])))
+            .unwrap();
+    }
+}
+
+/// Takes too long to process messages, and sends a fallback value upon missing the deadline.
+pub struct SlowOperator {}
+
+impl SlowOperator {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        read_stream: ReadStream<usize>,
+        write_stream: WriteStream<usize>,
+    ) -> Self {
+        read_stream.add_callback(|_t: &Timestamp, _data: &usize| {
+            thread::sleep(Duration::from_millis(1000));
+        });
+        read_stream
+            .add_state(write_stream)
+            .add_deadline_miss_handler(|t: &Timestamp, write_stream: &mut WriteStream<usize>| {
+                write_stream
+                    .send(Message::new_message(t.clone(), 0))
+                    .unwrap();
+            });
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<usize>) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for SlowOperator {}
+
+/// Has a fast and a slow watermark callback, and sends a fallback value upon missing the deadline.
+pub struct SlowWatermarkOperator {}
+
+impl SlowWatermarkOperator {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        read_stream: ReadStream<usize>,
+        write_stream: WriteStream<usize>,
+    ) -> Self {
+        read_stream.add_watermark_callback(|_t: &Timestamp| {});
+        read_stream.add_watermark_callback(|_t: &Timestamp| {
+            thread::sleep(Duration::from_millis(1000));
+        });
+        read_stream
+            .add_state(write_stream)
+            .add_deadline_miss_handler(|t: &Timestamp, write_stream: &mut WriteStream<usize>| {
+                write_stream
+                    .send(Message::new_message(t.clone(), 0))
+                    .unwrap();
+            });
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<usize>) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for SlowWatermarkOperator {}
+
+/// Set once the [`TimerOperator`] misses a deadline.
+static TIMER_OPERATOR_MISSED_DEADLINE: AtomicBool = AtomicBool::new(false);
+
+/// Has a slow timer which always has a tick pending, and records deadline misses.
+pub struct TimerOperator {}
+
+impl TimerOperator {
+    pub fn new(config: OperatorConfig<()>, read_stream: ReadStream<usize>) -> Self {
+        add_timer_callback(&config, Duration::from_millis(100), (), |_tick, _state| {
+            thread::sleep(Duration::from_millis(150));
+            true
+        });
+        read_stream.add_callback(|_t: &Timestamp, _data: &usize| {});
+        read_stream
+            .add_state(())
+            .add_deadline_miss_handler(|_t: &Timestamp, _state: &mut ()| {
+                TIMER_OPERATOR_MISSED_DEADLINE.store(true, Ordering::SeqCst);
+            });
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<usize>) {}
+}
+
+impl Operator for TimerOperator {}
+
+#[test]
+fn test_deadline_miss_handler() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let s1 = connect_1_write!(SendOperator, OperatorConfig::new().name("SendOperator"));
+    let s2 = connect_1_write!(
+        SlowOperator,
+        OperatorConfig::new()
+            .name("SlowOperator")
+            .deadline(Duration::from_millis(100))
+            .num_event_runners(2),
+        s1
+    );
+    let mut extract_stream = ExtractStream::new(0, &s2);
+
+    node.run_async();
+
+    assert_eq!(
+        extract_stream.read(),
+        Ok(Message::new_message(Timestamp::new(vec![1]), 0))
+    );
+    assert_eq!(
+        extract_stream.read(),
+        Ok(Message::new_watermark(Timestamp::new(vec![1])))
+    );
+}
+
+#[test]
+fn test_deadline_slow_watermark_callback() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let s1 = connect_1_write!(SendOperator, OperatorConfig::new().name("SendOperator"));
+    // The deadline is only met once both watermark callbacks complete.
+    let s2 = connect_1_write!(
+        SlowWatermarkOperator,
+        OperatorConfig::new()
+            .name("SlowWatermarkOperator")
+            .deadline(Duration::from_millis(100))
+            .num_event_runners(2),
+        s1
+    );
+    let mut extract_stream = ExtractStream::new(0, &s2);
+
+    node.run_async();
+
+    assert_eq!(
+        extract_stream.read(),
+        Ok(Message::new_message(Timestamp::new(vec![1]), 0))
+    );
+    assert_eq!(
+        extract_stream.read(),
+        Ok(Message::new_watermark(Timestamp::new(vec![1])))
+    );
+}
+
+#[test]
+fn test_deadline_with_timer() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let mut ingest_stream = IngestStream::new(0);
+    // The pending ticks of the timer do not delay the deadline of timestamp 1.
+    connect_0_write!(
+        TimerOperator,
+        OperatorConfig::new()
+            .name("TimerOperator")
+            .deadline(Duration::from_millis(200))
+            .num_event_runners(3),
+        ingest_stream
+    );
+
+    node.run_async();
+
+    // Send the data while a tick of the timer executes.
+    thread::sleep(Duration::from_millis(120));
+    let timestamp = Timestamp::new(vec![1]);
+    ingest_stream
+        .send(Message::new_message(timestamp.clone(), 1))
+        .unwrap();
+    ingest_stream
+        .send(Message::new_watermark(timestamp))
+        .unwrap();
+    thread::sleep(Duration::from_millis(500));
+    assert!(!TIMER_OPERATOR_MISSED_DEADLINE.load(Ordering::SeqCst));
+}
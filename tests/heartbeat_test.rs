@@ -0,0 +1,114 @@
+use std::{
+    io::{Read, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use erdos::{dataflow::stream::IngestStream, node::NodeHandle};
+
+mod utils;
+
+/// Forwards the TCP connections accepted on `listen_addr` to `target_addr`.
+///
+/// While `partitioned` is set, the proxy drops new connections and discards the data sent on
+/// existing ones, which stay broken after the partition heals.
+fn run_proxy(listen_addr: SocketAddr, target_addr: SocketAddr, partitioned: Arc<AtomicBool>) {
+    let listener = TcpListener::bind(listen_addr).unwrap();
+    thread::spawn(move || {
+        for client in listener.incoming() {
+            let client = match client {
+                Ok(client) => client,
+                Err(_) => continue,
+            };
+            if partitioned.load(Ordering::SeqCst) {
+                continue;
+            }
+            let server = match TcpStream::connect(target_addr) {
+                Ok(server) => server,
+                Err(_) => continue,
+            };
+            forward(
+                client.try_clone().unwrap(),
+                server.try_clone().unwrap(),
+                Arc::clone(&partitioned),
+            );
+            forward(server, client, Arc::clone(&partitioned));
+        }
+    });
+}
+
+fn forward(mut from: TcpStream, mut to: TcpStream, partitioned: Arc<AtomicBool>) {
+    thread::spawn(move || {
+        let mut buf = [0u8; 4096];
+        let mut broken = false;
+        loop {
+            let n = match from.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => n,
+            };
+            broken |= partitioned.load(Ordering::SeqCst);
+            if !broken && to.write_all(&buf[..n]).is_err() {
+                break;
+            }
+        }
+        to.shutdown(Shutdown::Both).ok();
+    });
+}
+
+/// Waits until the nodes which `node_handle` considers down are `down_nodes`.
+fn wait_for_down_nodes(node_handle: &NodeHandle, down_nodes: Vec<usize>) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while node_handle.metrics().down_nodes != down_nodes {
+        assert!(
+            Instant::now() < deadline,
+            "Node {} did not consider nodes {:?} down",
+            node_handle.metrics().node_id,
+            down_nodes
+        );
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn test_partition() {
+    let _ingest_stream: IngestStream<usize> = IngestStream::new(0);
+
+    let (mut config_0, mut config_1) = utils::make_two_node_configs();
+    for config in [&mut config_0, &mut config_1].iter_mut() {
+        config.heartbeat_interval = Duration::from_millis(50);
+        config.heartbeat_timeout = Duration::from_millis(500);
+    }
+    // Node 1 connects to node 0 through the proxy.
+    let proxy_address: SocketAddr = format!("127.0.0.1:{}", utils::get_unique_port())
+        .parse()
+        .unwrap();
+    let partitioned = Arc::new(AtomicBool::new(false));
+    run_proxy(
+        proxy_address,
+        config_0.control_addresses[0],
+        Arc::clone(&partitioned),
+    );
+    config_1.control_addresses[0] = proxy_address;
+
+    let (node_handle_0, node_handle_1) = utils::run_two_nodes(config_0, config_1);
+    assert!(node_handle_0.metrics().down_nodes.is_empty());
+    assert!(node_handle_1.metrics().down_nodes.is_empty());
+
+    // The nodes stop receiving each other's heartbeats.
+    partitioned.store(true, Ordering::SeqCst);
+    wait_for_down_nodes(&node_handle_0, vec![1]);
+    wait_for_down_nodes(&node_handle_1, vec![0]);
+
+    // Node 1 re-establishes the connection once the partition heals.
+    partitioned.store(false, Ordering::SeqCst);
+    wait_for_down_nodes(&node_handle_0, vec![]);
+    wait_for_down_nodes(&node_handle_1, vec![]);
+
+    node_handle_1.shutdown().unwrap();
+    node_handle_0.shutdown().unwrap();
+}
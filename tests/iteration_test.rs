@@ -0,0 +1,93 @@
+use erdos::{
+    dataflow::{
+        message::*,
+        operators::{MapOperator, SplitOperator},
+        stream::{ExtractStream, IngestStream},
+        LoopStream, OperatorConfig, Timestamp,
+    },
+    node::Node,
+    *,
+};
+
+mod utils;
+
+#[test]
+fn test_iteration_scope() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let mut ingest_stream = IngestStream::new(0);
+    let loop_stream = LoopStream::new();
+    let loop_input_stream = loop_stream.enter(&(&ingest_stream).into()).unwrap();
+    // Increment the data until it reaches 10.
+    let incremented_stream = connect_1_write!(
+        MapOperator<usize, usize>,
+        OperatorConfig::new()
+            .name("MapOperator")
+            .arg(|data: &usize| -> usize { data + 1 }),
+        loop_input_stream
+    );
+    let (feedback_stream, loop_output_stream) = connect_2_write!(
+        SplitOperator<usize, 2>,
+        OperatorConfig::new()
+            .name("SplitOperator")
+            .arg(|data: &usize| -> usize { (*data >= 10) as usize }),
+        incremented_stream
+    );
+    loop_stream.set_feedback(&feedback_stream);
+    let output_stream = loop_stream.leave(&loop_output_stream);
+    let mut extract_stream = ExtractStream::new(0, &output_stream);
+
+    node.run_async();
+
+    for (time, data) in &[(0, vec![0, 5]), (1, vec![8])] {
+        for d in data {
+            ingest_stream
+                .send(Message::new_message(Timestamp::new(vec![*time]), *d))
+                .unwrap();
+        }
+        ingest_stream
+            .send(Message::new_watermark(Timestamp::new(vec![*time])))
+            .unwrap();
+    }
+    ingest_stream
+        .send(Message::new_watermark(Timestamp::top()))
+        .unwrap();
+
+    // The watermark of a timestamp leaves the loop after all of its messages.
+    let mut messages = Vec::new();
+    loop {
+        match extract_stream.read().unwrap() {
+            Message::TimestampedData(msg) => {
+                assert_eq!(msg.data, 10);
+                messages.push(msg.timestamp.time()[0]);
+            }
+            Message::Watermark(t) if t.is_top() => break,
+            Message::Watermark(t) => {
+                let num_messages = messages.iter().filter(|time| **time <= t.time()[0]).count();
+                assert_eq!(num_messages, t.time()[0] as usize + 2);
+            }
+        }
+    }
+    messages.sort_unstable();
+    assert_eq!(messages, vec![0, 0, 1]);
+}
+
+#[test]
+fn test_nested_iteration_scopes() {
+    let ingest_stream: IngestStream<usize> = IngestStream::new(0);
+    let mut stream = (&ingest_stream).into();
+    // Timestamps with one dimension have room for 3 iteration coordinates.
+    for _ in 0..3 {
+        let loop_stream = LoopStream::new();
+        let loop_input_stream = loop_stream.enter(&stream).unwrap();
+        let map_stream = connect_1_write!(
+            MapOperator<usize, usize>,
+            OperatorConfig::new().arg(|data: &usize| -> usize { *data }),
+            loop_input_stream
+        );
+        loop_stream.set_feedback(&map_stream);
+        stream = map_stream;
+    }
+    assert!(LoopStream::new().enter(&stream).is_err());
+}
@@ -0,0 +1,160 @@
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use erdos::{
+    dataflow::{
+        message::*,
+        stream::{
+            ExtractStream, IngestStream, LateDataPolicy, ProcessingTimeWatermarks, WriteStreamT,
+        },
+        Operator, OperatorConfig, ReadStream, WriteStream,
+    },
+    node::Node,
+    *,
+};
+
+mod utils;
+
+type Received = Arc<Mutex<Vec<u64>>>;
+
+/// Records the timestamps of the messages it receives on time and of the late messages.
+pub struct LateDataOperator {}
+
+impl LateDataOperator {
+    pub fn new(
+        config: OperatorConfig<(Received, Received)>,
+        read_stream: ReadStream<usize>,
+        _write_stream: WriteStream<usize>,
+    ) -> Self {
+        let (on_time, late) = config.arg.unwrap();
+        read_stream.add_callback(move |t: &Timestamp, _msg: &usize| {
+            on_time.lock().unwrap().push(t.time()[0]);
+        });
+        read_stream.add_late_data_callback(move |t: &Timestamp, _msg: &usize| {
+            late.lock().unwrap().push(t.time()[0]);
+        });
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<usize>) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for LateDataOperator {}
+
+/// Sends a message, waits for its stream to generate a watermark, and then sends a late message.
+pub struct IdleSourceOperator {
+    write_stream: WriteStream<usize>,
+}
+
+impl IdleSourceOperator {
+    pub fn new(_config: OperatorConfig<()>, write_stream: WriteStream<usize>) -> Self {
+        Self { write_stream }
+    }
+
+    pub fn connect() -> WriteStream<usize> {
+        WriteStream::new().with_processing_time_watermarks(ProcessingTimeWatermarks::new(
+            Duration::from_millis(100),
+            || Timestamp::new(vec![10]),
+        ))
+    }
+}
+
+impl Operator for IdleSourceOperator {
+    fn run(&mut self) {
+        for time in &[1, 5, 11] {
+            self.write_stream
+                .send(Message::new_message(Timestamp::new(vec![*time]), 0))
+                .unwrap();
+            if *time == 1 {
+                thread::sleep(Duration::from_millis(500));
+            }
+        }
+        self.write_stream
+            .send(Message::new_watermark(Timestamp::top()))
+            .unwrap();
+    }
+}
+
+#[test]
+fn test_ingest_stream_watermarks_side_output() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let on_time = Arc::new(Mutex::new(Vec::new()));
+    let late = Arc::new(Mutex::new(Vec::new()));
+    let mut ingest_stream = IngestStream::new(0).with_processing_time_watermarks(
+        ProcessingTimeWatermarks::new(Duration::from_millis(500), || Timestamp::new(vec![10])),
+    );
+    let output_stream = connect_1_write!(
+        LateDataOperator,
+        OperatorConfig::new()
+            .name("LateDataOperator")
+            .arg((Arc::clone(&on_time), Arc::clone(&late)))
+            .late_data_policy(LateDataPolicy::SideOutput, Duration::from_secs(0)),
+        ingest_stream
+    );
+    let mut extract_stream = ExtractStream::new(0, &output_stream);
+
+    node.run_async();
+
+    ingest_stream
+        .send(Message::new_message(Timestamp::new(vec![1]), 0))
+        .unwrap();
+    // The ingest stream generates a watermark once the driver stops sending messages.
+    assert_eq!(
+        extract_stream.read().unwrap(),
+        Message::new_watermark(Timestamp::new(vec![10]))
+    );
+    for time in &[5, 11] {
+        ingest_stream
+            .send(Message::new_message(Timestamp::new(vec![*time]), 0))
+            .unwrap();
+    }
+    ingest_stream
+        .send(Message::new_watermark(Timestamp::top()))
+        .unwrap();
+    while !extract_stream.read().unwrap().is_top_watermark() {}
+
+    assert_eq!(*on_time.lock().unwrap(), vec![1, 11]);
+    assert_eq!(*late.lock().unwrap(), vec![5]);
+}
+
+#[test]
+fn test_operator_watermarks_drop() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let on_time = Arc::new(Mutex::new(Vec::new()));
+    let late = Arc::new(Mutex::new(Vec::new()));
+    let source_stream = connect_1_write!(
+        IdleSourceOperator,
+        OperatorConfig::new()
+            .name("IdleSourceOperator")
+            .run_concurrently(true)
+    );
+    let output_stream = connect_1_write!(
+        LateDataOperator,
+        OperatorConfig::new()
+            .name("LateDataOperator")
+            .arg((Arc::clone(&on_time), Arc::clone(&late)))
+            .late_data_policy(LateDataPolicy::Drop, Duration::from_secs(0)),
+        source_stream
+    );
+    let mut extract_stream = ExtractStream::new(0, &output_stream);
+
+    node.run_async();
+
+    assert_eq!(
+        extract_stream.read().unwrap(),
+        Message::new_watermark(Timestamp::new(vec![10]))
+    );
+    while !extract_stream.read().unwrap().is_top_watermark() {}
+
+    assert_eq!(*on_time.lock().unwrap(), vec![1, 11]);
+    assert!(late.lock().unwrap().is_empty());
+}
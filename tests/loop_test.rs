@@ -49,7 +49,7 @@ impl Operator for LoopOperator {
             if let Ok(Message::TimestampedData(mut timestamped_data)) = self.read_stream.read() {
                 println!("LoopOp: received {:?}", timestamped_data);
                 timestamped_data.data += 1;
-                timestamped_data.timestamp.time[0] += 1;
+                timestamped_data.timestamp.time_mut()[0] += 1;
                 println!("LoopOp: sending {:?}", timestamped_data);
                 self.write_stream
                     .send(Message::new_message(
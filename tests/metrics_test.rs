@@ -0,0 +1,122 @@
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    time::Duration,
+};
+
+use erdos::{
+    dataflow::{
+        message::*,
+        stream::{ExtractStream, IngestStream, WriteStreamT},
+        Operator, OperatorConfig, ReadStream, WriteStream,
+    },
+    node::Node,
+    *,
+};
+
+mod utils;
+
+pub struct SquareOperator {}
+
+impl SquareOperator {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        read_stream: ReadStream<usize>,
+        write_stream: WriteStream<usize>,
+    ) -> Self {
+        read_stream.add_state(write_stream).add_callback(
+            |t: &Timestamp, data: &usize, write_stream: &mut WriteStream<usize>| {
+                write_stream
+                    .send(Message::new_message(t.clone(), data * data))
+                    .unwrap();
+            },
+        );
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<usize>) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for SquareOperator {}
+
+#[test]
+fn test_metrics() {
+    let mut config = utils::make_default_config();
+    let metrics_address = format!("127.0.0.1:{}", utils::get_unique_port())
+        .parse()
+        .unwrap();
+    config.metrics_address = Some(metrics_address);
+    config.stream_metrics = true;
+    let node = Node::new(config);
+    let mut ingest_stream = IngestStream::new(0);
+    let s = connect_1_write!(
+        SquareOperator,
+        OperatorConfig::new().name("SquareOperator"),
+        ingest_stream
+    );
+    let mut extract_stream = ExtractStream::new(0, &s);
+    let node_handle = node.run_async();
+
+    for t in 1..=3 {
+        let timestamp = Timestamp::new(vec![t]);
+        ingest_stream
+            .send(Message::new_message(timestamp.clone(), t as usize))
+            .unwrap();
+        ingest_stream
+            .send(Message::new_watermark(timestamp))
+            .unwrap();
+    }
+    // Read until the watermark for timestamp 3.
+    loop {
+        let msg = extract_stream.read().unwrap();
+        if msg.data().is_none() && msg.timestamp() == &Timestamp::new(vec![3]) {
+            break;
+        }
+    }
+
+    let metrics = node_handle.metrics();
+    assert_eq!(metrics.node_id, 0);
+    let square_metrics = metrics
+        .operators
+        .iter()
+        .find(|op| op.name == "SquareOperator")
+        .unwrap();
+    assert!(square_metrics.callback_duration.count >= 3);
+
+    let ingest_metrics = metrics
+        .streams
+        .iter()
+        .find(|stream| stream.id == ingest_stream.get_id())
+        .unwrap();
+    assert_eq!(ingest_metrics.messages_sent, 6);
+    assert!(ingest_metrics.bytes_sent > 0);
+    assert_eq!(ingest_metrics.low_watermark, Some(Timestamp::new(vec![3])));
+    assert_eq!(ingest_metrics.watermark_lag, Duration::from_secs(0));
+
+    let extract_channel = metrics
+        .channels
+        .iter()
+        .find(|channel| channel.stream_id == s.get_id())
+        .unwrap();
+    assert_eq!(extract_channel.sink, "driver 0");
+    assert_eq!(extract_channel.queue_depth, 0);
+
+    // Scrape the metrics over HTTP.
+    let mut stream = TcpStream::connect(metrics_address).unwrap();
+    stream
+        .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+    assert!(response.contains(&format!(
+        "erdos_stream_messages_total{{node=\"0\",stream=\"{}\"}} 6",
+        ingest_stream.get_id()
+    )));
+    assert!(response
+        .contains("erdos_callback_duration_seconds_count{node=\"0\",operator=\"SquareOperator\""));
+
+    node_handle.shutdown().unwrap();
+}
@@ -1,12 +1,24 @@
 extern crate erdos;
 use erdos::dataflow::{
+    operators::FilterOperator,
+    operators::FlatMapOperator,
     operators::JoinOperator,
+    operators::LatestOperator,
     operators::MapOperator,
+    operators::PeriodicSourceOperator,
+    operators::SplitOperator,
+    operators::{ApproximateTime, ApproximateTimeSyncOperator},
+    operators::{FoldOperator, KeyedFoldOperator, KeyedReduceOperator, ReduceOperator},
+    operators::{JoinType, KeyedJoinOperator, NWayJoinOperator},
+    operators::{SampleOperator, ThrottleOperator},
+    operators::{Window, WindowOperator},
     stream::{ExtractStream, WriteStreamT},
-    Message, Operator, OperatorConfig, Timestamp, WriteStream,
+    Data, Message, Operator, OperatorConfig, PanicPolicy, ReadStream, Timestamp, WriteStream,
 };
-use erdos::node::Node;
+use erdos::node::{Node, NodeError};
 use erdos::*;
+use serde::Deserialize;
+use std::{thread, time::Duration};
 
 mod utils;
 
@@ -70,6 +82,131 @@ fn test_input_receiver_map() {
     }
 }
 
+#[test]
+fn test_input_receiver_filter() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let s1 = connect_1_write!(InputGenOp, OperatorConfig::new().name("InputOperator"));
+    let s2 = connect_1_write!(
+        FilterOperator<u32>,
+        OperatorConfig::new()
+            .name("FilterOperator")
+            .arg(|data: &u32| -> bool { data & 1 == 0 }),
+        s1
+    );
+    let mut extract_stream = ExtractStream::new(0, &s2);
+
+    node.run_async();
+
+    for i in 0..5 {
+        // Read until the next message, skipping the watermarks of the dropped messages.
+        loop {
+            if let Message::TimestampedData(data) = extract_stream.read().unwrap() {
+                assert_eq!(data.timestamp, Timestamp::new(vec![i * 2]));
+                assert_eq!(data.data, i as u32 * 2);
+                break;
+            }
+        }
+    }
+}
+
+#[test]
+fn test_input_receiver_flat_map() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let s1 = connect_1_write!(InputGenOp, OperatorConfig::new().name("InputOperator"));
+    let s2 = connect_1_write!(
+        FlatMapOperator<u32, u32>,
+        OperatorConfig::new()
+            .name("FlatMapOperator")
+            .arg(|data: &u32| -> Vec<u32> { vec![*data; *data as usize] }),
+        s1
+    );
+    let mut extract_stream = ExtractStream::new(0, &s2);
+
+    node.run_async();
+
+    // Each message i is expanded into i copies.
+    let mut received = Vec::new();
+    while received.len() < 45 {
+        if let Message::TimestampedData(data) = extract_stream.read().unwrap() {
+            assert_eq!(data.timestamp, Timestamp::new(vec![data.data as u64]));
+            received.push(data.data);
+        }
+    }
+    let expected: Vec<u32> = (0..10).flat_map(|i| vec![i; i as usize]).collect();
+    assert_eq!(received, expected);
+}
+
+#[test]
+fn test_input_receiver_split() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let s1 = connect_1_write!(InputGenOp, OperatorConfig::new().name("InputOperator"));
+    // Send multiples of 3 on s2, other even messages on s3, and the remaining messages on s4.
+    let (s2, s3, s4) = connect_3_write!(
+        SplitOperator<u32, 3>,
+        OperatorConfig::new()
+            .name("SplitOperator")
+            .arg(|data: &u32| -> usize {
+                match (data % 3, data % 2) {
+                    (0, _) => 0,
+                    (_, 0) => 1,
+                    _ => 2,
+                }
+            }),
+        s1
+    );
+    let mut extract_stream_2 = ExtractStream::new(0, &s2);
+    let mut extract_stream_3 = ExtractStream::new(0, &s3);
+    let mut extract_stream_4 = ExtractStream::new(0, &s4);
+
+    node.run_async();
+
+    // Read the messages until the watermark for the last timestamp.
+    let read_all = |extract_stream: &mut ExtractStream<u32>| {
+        let mut received = Vec::new();
+        loop {
+            match extract_stream.read().unwrap() {
+                Message::TimestampedData(data) => received.push(data.data),
+                Message::Watermark(t) if t == Timestamp::new(vec![9]) => break,
+                Message::Watermark(_) => (),
+            }
+        }
+        received
+    };
+    assert_eq!(read_all(&mut extract_stream_2), vec![0, 3, 6, 9]);
+    assert_eq!(read_all(&mut extract_stream_3), vec![2, 4, 8]);
+    assert_eq!(read_all(&mut extract_stream_4), vec![1, 5, 7]);
+}
+
+#[test]
+fn test_split_invalid_index() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let s1 = connect_1_write!(InputGenOp, OperatorConfig::new().name("InputOperator"));
+    let (_s2, _s3) = connect_2_write!(
+        SplitOperator<u32, 2>,
+        OperatorConfig::new()
+            .name("SplitOperator")
+            .arg(|data: &u32| -> usize { *data as usize })
+            .panic_policy(PanicPolicy::FailNode),
+        s1
+    );
+
+    // The node fails once the operator receives the message with index 2.
+    let error = node.run_async().join().unwrap_err();
+    assert!(
+        matches!(&error, NodeError::DataflowError(e) if e.contains("only has 2 output streams")),
+        "{}",
+        error
+    );
+}
+
 // Join Operator Tests.
 #[test]
 fn test_input_receiver_join() {
@@ -110,3 +247,577 @@ fn test_input_receiver_join() {
         }
     }
 }
+
+// Window Operator Tests.
+/// Returns the first `num_windows` windows computed from the output of the InputGenOp.
+fn run_window_operator(window: Window, num_windows: usize) -> Vec<(Timestamp, Vec<u32>)> {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let s1 = connect_1_write!(InputGenOp, OperatorConfig::new().name("InputOperator"));
+    let s2 = connect_1_write!(
+        WindowOperator<u32>,
+        OperatorConfig::new().name("WindowOperator").arg(window),
+        s1
+    );
+    let mut extract_stream = ExtractStream::new(0, &s2);
+
+    node.run_async();
+
+    let mut windows = Vec::new();
+    while windows.len() < num_windows {
+        if let Message::TimestampedData(data) = extract_stream.read().unwrap() {
+            windows.push((data.timestamp, data.data));
+        }
+    }
+    windows
+}
+
+#[test]
+fn test_count_tumbling_window() {
+    let windows = run_window_operator(Window::count_tumbling(3), 3);
+    assert_eq!(
+        windows,
+        vec![
+            (Timestamp::new(vec![2]), vec![0, 1, 2]),
+            (Timestamp::new(vec![5]), vec![3, 4, 5]),
+            (Timestamp::new(vec![8]), vec![6, 7, 8]),
+        ]
+    );
+}
+
+#[test]
+fn test_count_sliding_window() {
+    let windows = run_window_operator(Window::count_sliding(3, 2), 4);
+    assert_eq!(
+        windows,
+        vec![
+            (Timestamp::new(vec![2]), vec![0, 1, 2]),
+            (Timestamp::new(vec![4]), vec![2, 3, 4]),
+            (Timestamp::new(vec![6]), vec![4, 5, 6]),
+            (Timestamp::new(vec![8]), vec![6, 7, 8]),
+        ]
+    );
+}
+
+#[test]
+fn test_time_tumbling_window() {
+    let windows = run_window_operator(Window::time_tumbling(4), 2);
+    assert_eq!(
+        windows,
+        vec![
+            (Timestamp::new(vec![3]), vec![0, 1, 2, 3]),
+            (Timestamp::new(vec![7]), vec![4, 5, 6, 7]),
+        ]
+    );
+}
+
+#[test]
+fn test_time_sliding_window() {
+    let windows = run_window_operator(Window::time_sliding(4, 3), 3);
+    assert_eq!(
+        windows,
+        vec![
+            (Timestamp::new(vec![3]), vec![0, 1, 2, 3]),
+            (Timestamp::new(vec![6]), vec![3, 4, 5, 6]),
+            (Timestamp::new(vec![9]), vec![6, 7, 8, 9]),
+        ]
+    );
+}
+
+#[test]
+fn test_watermark_window() {
+    let windows = run_window_operator(Window::watermark(), 10);
+    for (i, (timestamp, data)) in windows.into_iter().enumerate() {
+        assert_eq!(timestamp, Timestamp::new(vec![i as u64]));
+        assert_eq!(data, vec![i as u32]);
+    }
+}
+
+// Fold and Reduce Operator Tests.
+/// Returns the first `num_messages` data messages read from the extract stream.
+fn read_data<D>(extract_stream: &mut ExtractStream<D>, num_messages: usize) -> Vec<(u64, D)>
+where
+    for<'a> D: Data + Deserialize<'a>,
+{
+    let mut messages = Vec::new();
+    while messages.len() < num_messages {
+        if let Message::TimestampedData(data) = extract_stream.read().unwrap() {
+            messages.push((data.timestamp.time()[0], data.data));
+        }
+    }
+    messages
+}
+
+#[test]
+fn test_fold() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let s1 = connect_1_write!(InputGenOp, OperatorConfig::new().name("InputOperator"));
+    // Expands each message i into the messages 0 to i - 1 with timestamp i.
+    let s2 = connect_1_write!(
+        FlatMapOperator<u32, u32>,
+        OperatorConfig::new()
+            .name("FlatMapOperator")
+            .arg(|data: &u32| -> Vec<u32> { (0..*data).collect() }),
+        s1
+    );
+    let s3 = connect_1_write!(
+        FoldOperator<u32, u64>,
+        OperatorConfig::new()
+            .name("FoldOperator")
+            .arg((0, |sum: u64, data: &u32| -> u64 { sum + *data as u64 })),
+        s2
+    );
+    let mut extract_stream = ExtractStream::new(0, &s3);
+
+    node.run_async();
+
+    // No sum is sent for timestamp 0, which has no messages.
+    for (t, sum) in read_data(&mut extract_stream, 9) {
+        assert_eq!(sum, (0..t).sum());
+    }
+}
+
+#[test]
+fn test_reduce() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let s1 = connect_1_write!(InputGenOp, OperatorConfig::new().name("InputOperator"));
+    let s2 = connect_1_write!(
+        FlatMapOperator<u32, u32>,
+        OperatorConfig::new()
+            .name("FlatMapOperator")
+            .arg(|data: &u32| -> Vec<u32> { (0..=*data).rev().collect() }),
+        s1
+    );
+    let s3 = connect_1_write!(
+        ReduceOperator<u32>,
+        OperatorConfig::new()
+            .name("ReduceOperator")
+            .arg(|max: u32, data: &u32| -> u32 { max.max(*data) }),
+        s2
+    );
+    let mut extract_stream = ExtractStream::new(0, &s3);
+
+    node.run_async();
+
+    let messages = read_data(&mut extract_stream, 10);
+    assert_eq!(messages, (0..10).map(|t| (t, t as u32)).collect::<Vec<_>>());
+}
+
+/// Expands each message i into the messages 0 to i - 1 with timestamp i, keyed by their parity.
+fn make_keyed_stream(s: ReadStream<u32>) -> ReadStream<(u32, u32)> {
+    connect_1_write!(
+        FlatMapOperator<u32, (u32, u32)>,
+        OperatorConfig::new()
+            .name("FlatMapOperator")
+            .arg(|data: &u32| -> Vec<(u32, u32)> { (0..*data).map(|x| (x % 2, x)).collect() }),
+        s
+    )
+}
+
+#[test]
+fn test_keyed_fold() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let s1 = connect_1_write!(InputGenOp, OperatorConfig::new().name("InputOperator"));
+    let s2 = make_keyed_stream(s1);
+    let s3 = connect_1_write!(
+        KeyedFoldOperator<u32, u32, usize>,
+        OperatorConfig::new()
+            .name("KeyedFoldOperator")
+            .arg((0, |count: usize, _data: &u32| -> usize { count + 1 })),
+        s2
+    );
+    let mut extract_stream = ExtractStream::new(0, &s3);
+
+    node.run_async();
+
+    // Timestamp 1 only has a message with key 0, and the later timestamps have both keys.
+    let messages = read_data(&mut extract_stream, 17);
+    let mut expected = vec![(1, (0, 1))];
+    for t in 2..10 {
+        expected.push((t, (0, t as usize - t as usize / 2)));
+        expected.push((t, (1, t as usize / 2)));
+    }
+    assert_eq!(messages, expected);
+}
+
+#[test]
+fn test_keyed_reduce() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let s1 = connect_1_write!(InputGenOp, OperatorConfig::new().name("InputOperator"));
+    let s2 = make_keyed_stream(s1);
+    let s3 = connect_1_write!(
+        KeyedReduceOperator<u32, u32>,
+        OperatorConfig::new()
+            .name("KeyedReduceOperator")
+            .arg(|sum: u32, data: &u32| -> u32 { sum + data }),
+        s2
+    );
+    let mut extract_stream = ExtractStream::new(0, &s3);
+
+    node.run_async();
+
+    for (t, (key, sum)) in read_data(&mut extract_stream, 17) {
+        assert_eq!(sum, (0..t as u32).filter(|x| x % 2 == key).sum());
+    }
+}
+
+// Keyed and N-Way Join Operator Tests.
+type KeyedJoinMessage = (u32, Option<u32>, Option<u32>);
+
+/// Joins the output of the InputGenOp with a stream which has the even messages i and i + 100
+/// for each timestamp i, and returns the first `num_messages` joined messages.
+fn run_keyed_join_operator(
+    join_type: JoinType,
+    num_messages: usize,
+) -> Vec<(u64, KeyedJoinMessage)> {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let s1 = connect_1_write!(InputGenOp, OperatorConfig::new().name("InputOperator"));
+    let s2 = connect_1_write!(
+        FlatMapOperator<u32, u32>,
+        OperatorConfig::new()
+            .name("FlatMapOperator")
+            .arg(|data: &u32| -> Vec<u32> {
+                if data & 1 == 0 {
+                    vec![*data, data + 100]
+                } else {
+                    vec![]
+                }
+            }),
+        s1
+    );
+    let s3 = connect_1_write!(
+        KeyedJoinOperator<u32, u32, u32>,
+        OperatorConfig::new().name("KeyedJoinOperator").arg((
+            join_type,
+            |left: &u32| -> u32 { *left },
+            |right: &u32| -> u32 { *right }
+        )),
+        s1,
+        s2
+    );
+    let mut extract_stream = ExtractStream::new(0, &s3);
+
+    node.run_async();
+
+    read_data(&mut extract_stream, num_messages)
+}
+
+#[test]
+fn test_keyed_inner_join() {
+    let messages = run_keyed_join_operator(JoinType::Inner, 5);
+    let expected: Vec<_> = (0..5)
+        .map(|i| {
+            (
+                i * 2,
+                (i as u32 * 2, Some(i as u32 * 2), Some(i as u32 * 2)),
+            )
+        })
+        .collect();
+    assert_eq!(messages, expected);
+}
+
+#[test]
+fn test_keyed_left_join() {
+    let messages = run_keyed_join_operator(JoinType::Left, 10);
+    let expected: Vec<_> = (0..10)
+        .map(|t| {
+            let right = if t % 2 == 1 { None } else { Some(t as u32) };
+            (t, (t as u32, Some(t as u32), right))
+        })
+        .collect();
+    assert_eq!(messages, expected);
+}
+
+#[test]
+fn test_keyed_outer_join() {
+    let messages = run_keyed_join_operator(JoinType::Outer, 15);
+    let mut expected = Vec::new();
+    for t in 0..10 {
+        let data = t as u32;
+        if t % 2 == 1 {
+            expected.push((t, (data, Some(data), None)));
+        } else {
+            expected.push((t, (data, Some(data), Some(data))));
+            expected.push((t, (data + 100, None, Some(data + 100))));
+        }
+    }
+    assert_eq!(messages, expected);
+}
+
+#[test]
+fn test_n_way_join() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let s1 = connect_1_write!(InputGenOp, OperatorConfig::new().name("InputOperator"));
+    let s2 = connect_1_write!(
+        MapOperator<u32, u32>,
+        OperatorConfig::new()
+            .name("MapOperator")
+            .arg(|data: &u32| -> u32 { data * 10 }),
+        s1
+    );
+    let s3 = connect_1_write!(
+        FilterOperator<u32>,
+        OperatorConfig::new()
+            .name("FilterOperator")
+            .arg(|data: &u32| -> bool { data & 1 == 0 }),
+        s1
+    );
+    let s4 = connect_1_write!(
+        MapOperator<u32, u32>,
+        OperatorConfig::new()
+            .name("MapOperator2")
+            .arg(|data: &u32| -> u32 { data * 100 }),
+        s1
+    );
+    let s5 = connect_1_write!(
+        MapOperator<u32, u32>,
+        OperatorConfig::new()
+            .name("MapOperator3")
+            .arg(|data: &u32| -> u32 { data * 1000 }),
+        s1
+    );
+    let s6 = connect_vec_1_write!(
+        NWayJoinOperator<u32, Vec<Vec<u32>>>,
+        OperatorConfig::new()
+            .name("NWayJoinOperator")
+            .arg(|data: Vec<Vec<u32>>| -> Vec<Vec<u32>> { data }),
+        vec![s1, s2, s3, s4, s5]
+    );
+    let mut extract_stream = ExtractStream::new(0, &s6);
+
+    node.run_async();
+
+    for (t, data) in read_data(&mut extract_stream, 10) {
+        let t = t as u32;
+        let filtered = if t % 2 == 1 { vec![] } else { vec![t] };
+        assert_eq!(
+            data,
+            vec![
+                vec![t],
+                vec![t * 10],
+                filtered,
+                vec![t * 100],
+                vec![t * 1000]
+            ]
+        );
+    }
+}
+
+// Approximate Time Sync Operator Tests.
+/// Sends the messages 10, 40 and 70 with timestamps 1, 4 and 7, and a watermark after each
+/// message and at timestamp 9.
+pub struct SparseInputGenOp {
+    output_stream: WriteStream<u64>,
+}
+
+impl SparseInputGenOp {
+    pub fn new(_config: OperatorConfig<()>, output_stream: WriteStream<u64>) -> Self {
+        Self { output_stream }
+    }
+
+    pub fn connect() -> WriteStream<u64> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for SparseInputGenOp {
+    fn run(&mut self) {
+        for t in [1, 4, 7] {
+            self.output_stream
+                .send(Message::new_message(Timestamp::new(vec![t]), t * 10))
+                .unwrap();
+            self.output_stream
+                .send(Message::new_watermark(Timestamp::new(vec![t])))
+                .unwrap();
+        }
+        self.output_stream
+            .send(Message::new_watermark(Timestamp::new(vec![9])))
+            .unwrap();
+    }
+}
+
+#[test]
+fn test_approximate_time_sync() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let s1 = connect_1_write!(InputGenOp, OperatorConfig::new().name("InputOperator"));
+    let s2 = connect_1_write!(
+        FilterOperator<u32>,
+        OperatorConfig::new()
+            .name("FilterOperator")
+            .arg(|data: &u32| -> bool { data & 1 == 0 }),
+        s1
+    );
+    let s3 = connect_1_write!(
+        SparseInputGenOp,
+        OperatorConfig::new().name("SparseInputOperator")
+    );
+    let s4 = connect_1_write!(
+        ApproximateTimeSyncOperator<(u32, u64)>,
+        OperatorConfig::new()
+            .name("ApproximateTimeSyncOperator")
+            .arg(ApproximateTime::new(1, 10)),
+        s2,
+        s3
+    );
+    let mut extract_stream = ExtractStream::new(0, &s4);
+
+    node.run_async();
+
+    // The message with timestamp 2 is dropped because the message with timestamp 4 is closer to
+    // the message with timestamp 4 of the sparse stream.
+    let t = |t: u64| Timestamp::new(vec![t]);
+    let messages = read_data(&mut extract_stream, 3);
+    assert_eq!(
+        messages
+            .iter()
+            .map(|(_, set)| set.clone())
+            .collect::<Vec<_>>(),
+        vec![
+            ((t(0), 0), (t(1), 10)),
+            ((t(4), 4), (t(4), 40)),
+            ((t(6), 6), (t(7), 70)),
+        ]
+    );
+    // Each set is sent once all messages within the slop of the set were received.
+    for (time, (_, (t2, _))) in messages {
+        assert!(time > t2.time()[0]);
+    }
+}
+
+// Sampling, Throttle and Latest Operator Tests.
+/// Returns the data messages read from the extract stream until the watermark with timestamp
+/// `time` is received.
+fn read_data_until_watermark<D>(extract_stream: &mut ExtractStream<D>, time: u64) -> Vec<(u64, D)>
+where
+    for<'a> D: Data + Deserialize<'a>,
+{
+    let mut messages = Vec::new();
+    loop {
+        match extract_stream.read().unwrap() {
+            Message::TimestampedData(data) => messages.push((data.timestamp.time()[0], data.data)),
+            Message::Watermark(t) if t == Timestamp::new(vec![time]) => return messages,
+            Message::Watermark(_) => (),
+        }
+    }
+}
+
+#[test]
+fn test_sample() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let s1 = connect_1_write!(InputGenOp, OperatorConfig::new().name("InputOperator"));
+    let s2 = connect_1_write!(
+        SampleOperator<u32>,
+        OperatorConfig::new().name("SampleOperator").arg(3),
+        s1
+    );
+    let mut extract_stream = ExtractStream::new(0, &s2);
+
+    node.run_async();
+
+    assert_eq!(
+        read_data_until_watermark(&mut extract_stream, 9),
+        vec![(0, 0), (3, 3), (6, 6), (9, 9)]
+    );
+}
+
+#[test]
+fn test_throttle() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let s1 = connect_1_write!(InputGenOp, OperatorConfig::new().name("InputOperator"));
+    let s2 = connect_1_write!(
+        ThrottleOperator<u32>,
+        OperatorConfig::new()
+            .name("ThrottleOperator")
+            .arg(Duration::from_secs(3600)),
+        s1
+    );
+    let mut extract_stream = ExtractStream::new(0, &s2);
+
+    node.run_async();
+
+    // Only the first timestamp is forwarded within the period, but all watermarks flow.
+    assert_eq!(
+        read_data_until_watermark(&mut extract_stream, 9),
+        vec![(0, 0)]
+    );
+}
+
+#[test]
+fn test_latest() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let s1 = connect_1_write!(InputGenOp, OperatorConfig::new().name("InputOperator"));
+    let s2 = connect_1_write!(
+        LatestOperator<u32>,
+        OperatorConfig::new().name("LatestOperator"),
+        s1
+    );
+    let mut extract_stream = ExtractStream::new(0, &s2);
+
+    node.run_async();
+    // Keep the receiver busy until all messages are forwarded.
+    thread::sleep(Duration::from_secs(1));
+
+    assert_eq!(
+        read_data_until_watermark(&mut extract_stream, 9),
+        vec![(9, 9)]
+    );
+}
+
+#[test]
+fn test_periodic_source() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let period = Duration::from_millis(20);
+    let s1 = connect_1_write!(
+        PeriodicSourceOperator<u64>,
+        OperatorConfig::new()
+            .name("PeriodicSourceOperator")
+            .arg((period, |t: &Timestamp| -> Option<u64> {
+                Some(t.time()[0] * 2).filter(|data| *data < 10)
+            }))
+    );
+    let mut extract_stream = ExtractStream::new(0, &s1);
+
+    node.run_async();
+
+    // Each message is followed by a watermark, and the stream closes after the 5th message.
+    let mut messages = Vec::new();
+    let mut ticks = Vec::new();
+    loop {
+        let msg = extract_stream.read().unwrap();
+        if msg.is_top_watermark() {
+            break;
+        }
+        if msg.data().is_none() {
+            ticks.push(std::time::Instant::now());
+        }
+        messages.push((msg.timestamp().time()[0], msg.data().cloned()));
+    }
+    let expected: Vec<(u64, Option<u64>)> = (0..5)
+        .flat_map(|t| vec![(t, Some(t * 2)), (t, None)])
+        .collect();
+    assert_eq!(messages, expected);
+    assert!(ticks[4].duration_since(ticks[0]) >= period * 3);
+}
@@ -0,0 +1,221 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use erdos::{
+    dataflow::{
+        message::*,
+        stream::{ExtractStream, IngestStream, WriteStreamT},
+        Operator, OperatorConfig, PanicPolicy, ReadStream, WriteStream,
+    },
+    node::{Node, NodeError},
+    *,
+};
+
+mod utils;
+
+type Processed = Arc<Mutex<Vec<(usize, usize)>>>;
+
+/// Records each message along with the number of messages the instance of the operator processed,
+/// and panics upon receiving the value 13.
+pub struct UnluckyOperator {}
+
+impl UnluckyOperator {
+    pub fn new(
+        config: OperatorConfig<(Arc<AtomicUsize>, Processed)>,
+        read_stream: ReadStream<usize>,
+        _write_stream: WriteStream<usize>,
+    ) -> Self {
+        let (num_instances, processed) = config.arg.unwrap();
+        num_instances.fetch_add(1, Ordering::SeqCst);
+        let num_messages = AtomicUsize::new(0);
+        read_stream.add_callback(move |_t: &Timestamp, msg: &usize| {
+            let count = num_messages.fetch_add(1, Ordering::SeqCst) + 1;
+            processed.lock().unwrap().push((*msg, count));
+            if *msg == 13 {
+                panic!("Unlucky number");
+            }
+        });
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<usize>) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for UnluckyOperator {}
+
+/// Panics in `run` the first time it runs, and sends a message the second time.
+pub struct FlakySourceOperator {
+    num_instances: Arc<AtomicUsize>,
+    write_stream: WriteStream<usize>,
+}
+
+impl FlakySourceOperator {
+    pub fn new(config: OperatorConfig<Arc<AtomicUsize>>, write_stream: WriteStream<usize>) -> Self {
+        let num_instances = config.arg.unwrap();
+        num_instances.fetch_add(1, Ordering::SeqCst);
+        Self {
+            num_instances,
+            write_stream,
+        }
+    }
+
+    pub fn connect() -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for FlakySourceOperator {
+    fn run(&mut self) {
+        if self.num_instances.load(Ordering::SeqCst) == 1 {
+            panic!("Failed to start");
+        }
+        self.write_stream
+            .send(Message::new_message(Timestamp::new(vec![1]), 1))
+            .unwrap();
+        self.write_stream
+            .send(Message::new_watermark(Timestamp::top()))
+            .unwrap();
+    }
+}
+
+/// Sends the value with the timestamp, followed by a watermark.
+fn send(ingest_stream: &mut IngestStream<usize>, time: u64, value: usize) {
+    ingest_stream
+        .send(Message::new_message(Timestamp::new(vec![time]), value))
+        .unwrap();
+    ingest_stream
+        .send(Message::new_watermark(Timestamp::new(vec![time])))
+        .unwrap();
+}
+
+#[test]
+fn test_skip() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let num_instances = Arc::new(AtomicUsize::new(0));
+    let processed = Arc::new(Mutex::new(Vec::new()));
+    let mut ingest_stream = IngestStream::new(0);
+    let output_stream = connect_1_write!(
+        UnluckyOperator,
+        OperatorConfig::new()
+            .name("UnluckyOperator")
+            .arg((Arc::clone(&num_instances), Arc::clone(&processed)))
+            .panic_policy(PanicPolicy::Skip),
+        ingest_stream
+    );
+    let mut extract_stream = ExtractStream::new(0, &output_stream);
+
+    node.run_async();
+
+    for (time, value) in &[(1, 1), (2, 13), (3, 2)] {
+        send(&mut ingest_stream, *time, *value);
+    }
+    // Watermarks keep flowing after the panic.
+    for time in 1..4 {
+        assert_eq!(
+            extract_stream.read().unwrap(),
+            Message::new_watermark(Timestamp::new(vec![time]))
+        );
+    }
+    assert_eq!(num_instances.load(Ordering::SeqCst), 1);
+    assert_eq!(*processed.lock().unwrap(), vec![(1, 1), (13, 2), (2, 3)]);
+}
+
+#[test]
+fn test_restart() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let num_instances = Arc::new(AtomicUsize::new(0));
+    let processed = Arc::new(Mutex::new(Vec::new()));
+    let mut ingest_stream = IngestStream::new(0);
+    let output_stream = connect_1_write!(
+        UnluckyOperator,
+        OperatorConfig::new()
+            .name("UnluckyOperator")
+            .arg((Arc::clone(&num_instances), Arc::clone(&processed)))
+            .panic_policy(PanicPolicy::Restart),
+        ingest_stream
+    );
+    let mut extract_stream = ExtractStream::new(0, &output_stream);
+
+    node.run_async();
+
+    send(&mut ingest_stream, 1, 1);
+    assert_eq!(
+        extract_stream.read().unwrap(),
+        Message::new_watermark(Timestamp::new(vec![1]))
+    );
+    send(&mut ingest_stream, 2, 13);
+    // Wait for the operator to restart, which discards the events it did not process.
+    while num_instances.load(Ordering::SeqCst) < 2 {
+        thread::sleep(Duration::from_millis(10));
+    }
+    send(&mut ingest_stream, 3, 2);
+    // The restarted operator may receive the watermark which followed the panic.
+    while extract_stream.read().unwrap() != Message::new_watermark(Timestamp::new(vec![3])) {}
+    // The restarted operator starts with fresh state.
+    assert_eq!(*processed.lock().unwrap(), vec![(1, 1), (13, 2), (2, 1)]);
+}
+
+#[test]
+fn test_restart_run() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let num_instances = Arc::new(AtomicUsize::new(0));
+    let source_stream = connect_1_write!(
+        FlakySourceOperator,
+        OperatorConfig::new()
+            .name("FlakySourceOperator")
+            .arg(Arc::clone(&num_instances))
+            .panic_policy(PanicPolicy::Restart)
+    );
+    let mut extract_stream = ExtractStream::new(0, &source_stream);
+
+    node.run_async();
+
+    assert_eq!(
+        extract_stream.read().unwrap(),
+        Message::new_message(Timestamp::new(vec![1]), 1)
+    );
+    assert!(extract_stream.read().unwrap().is_top_watermark());
+    assert_eq!(num_instances.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_fail_node() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let num_instances = Arc::new(AtomicUsize::new(0));
+    let processed = Arc::new(Mutex::new(Vec::new()));
+    let mut ingest_stream = IngestStream::new(0);
+    let _output_stream = connect_1_write!(
+        UnluckyOperator,
+        OperatorConfig::new()
+            .name("UnluckyOperator")
+            .arg((Arc::clone(&num_instances), Arc::clone(&processed)))
+            .panic_policy(PanicPolicy::FailNode),
+        ingest_stream
+    );
+
+    let node_handle = node.run_async();
+    send(&mut ingest_stream, 1, 13);
+
+    let error = node_handle.join().unwrap_err();
+    assert!(
+        matches!(&error, NodeError::DataflowError(e) if e.contains("panicked: Unlucky number")),
+        "{}",
+        error
+    );
+}
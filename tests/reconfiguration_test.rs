@@ -0,0 +1,196 @@
+use std::{sync::mpsc, thread};
+
+use erdos::{
+    dataflow::{
+        graph::{default_graph, Graph},
+        message::*,
+        stream::{ExtractStream, IngestStream, WriteStreamT},
+        Operator, OperatorConfig, ReadStream, WriteStream,
+    },
+    node::{Node, NodeHandle},
+    *,
+};
+
+mod utils;
+
+pub struct SquareOperator {}
+
+impl SquareOperator {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        read_stream: ReadStream<usize>,
+        write_stream: WriteStream<usize>,
+    ) -> Self {
+        read_stream.add_state(write_stream).add_callback(
+            |t: &Timestamp, data: &usize, write_stream: &mut WriteStream<usize>| {
+                write_stream
+                    .send(Message::new_message(t.clone(), data * data))
+                    .unwrap();
+            },
+        );
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<usize>) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for SquareOperator {}
+
+/// Sends a message and a watermark with timestamp `t` on the ingest stream.
+fn send(ingest_stream: &mut IngestStream<usize>, t: u64) {
+    let timestamp = Timestamp::new(vec![t]);
+    ingest_stream
+        .send(Message::new_message(timestamp.clone(), t as usize))
+        .unwrap();
+    ingest_stream
+        .send(Message::new_watermark(timestamp))
+        .unwrap();
+}
+
+/// Reads the message and the watermark with timestamp `t` from the extract stream.
+fn read(extract_stream: &mut ExtractStream<usize>, t: u64) -> usize {
+    let msg = extract_stream.read().unwrap();
+    assert_eq!(msg.timestamp(), &Timestamp::new(vec![t]));
+    let data = *msg.data().unwrap();
+    let msg = extract_stream.read().unwrap();
+    assert!(msg.data().is_none());
+    data
+}
+
+fn operator_id(name: &str) -> OperatorId {
+    default_graph::clone()
+        .get_operators()
+        .into_iter()
+        .find(|op| op.name.as_deref() == Some(name))
+        .unwrap()
+        .id
+}
+
+#[test]
+fn test_reconfiguration() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+    let mut ingest_stream = IngestStream::new(0);
+    let s = connect_1_write!(
+        SquareOperator,
+        OperatorConfig::new().name("SquareOperator"),
+        ingest_stream
+    );
+    let mut extract_stream = ExtractStream::new(0, &s);
+    let node_handle = node.run_async();
+
+    send(&mut ingest_stream, 1);
+    assert_eq!(read(&mut extract_stream, 1), 1);
+
+    // Add an operator which reads the output of the running operator.
+    let s2 = connect_1_write!(
+        SquareOperator,
+        OperatorConfig::new().name("SquareOperator2"),
+        s
+    );
+    let mut extract_stream_2 = ExtractStream::new(0, &s2);
+    node_handle.reconfigure().unwrap();
+
+    send(&mut ingest_stream, 2);
+    assert_eq!(read(&mut extract_stream, 2), 4);
+    assert_eq!(read(&mut extract_stream_2, 2), 16);
+
+    // The first operator cannot be removed while the second operator reads from it.
+    assert!(node_handle
+        .remove_operator(operator_id("SquareOperator"))
+        .is_err());
+
+    // Remove the second operator, and check that the first operator keeps running.
+    node_handle
+        .remove_extract_stream(&extract_stream_2)
+        .unwrap();
+    node_handle
+        .remove_operator(operator_id("SquareOperator2"))
+        .unwrap();
+    send(&mut ingest_stream, 3);
+    assert_eq!(read(&mut extract_stream, 3), 9);
+
+    node_handle.shutdown().unwrap();
+}
+
+/// Runs node 0 with `config` on its own driver thread, which reconfigures the node to each graph
+/// it receives and shuts the node down once the returned sender is dropped.
+fn run_node_0(
+    config: Configuration,
+) -> (
+    mpsc::Sender<Graph>,
+    mpsc::Receiver<Result<(), String>>,
+    thread::JoinHandle<()>,
+) {
+    let (graph_tx, graph_rx) = mpsc::channel::<Graph>();
+    let (result_tx, result_rx) = mpsc::channel();
+    let graph = default_graph::clone();
+    let driver = thread::spawn(move || {
+        default_graph::set(graph);
+        let node_handle = Node::new(config).run_async();
+        result_tx.send(Ok(())).unwrap();
+        for graph in graph_rx {
+            default_graph::set(graph);
+            result_tx.send(node_handle.reconfigure()).unwrap();
+        }
+        node_handle.shutdown().unwrap();
+    });
+    (graph_tx, result_rx, driver)
+}
+
+/// Makes the same change on the drivers of both nodes, which reconfigure without coordinating.
+fn reconfigure(
+    node_handle_1: &NodeHandle,
+    graph_tx: &mpsc::Sender<Graph>,
+    result_rx: &mpsc::Receiver<Result<(), String>>,
+) {
+    graph_tx.send(default_graph::clone()).unwrap();
+    node_handle_1.reconfigure().unwrap();
+    result_rx.recv().unwrap().unwrap();
+}
+
+#[test]
+fn test_multi_node_reconfiguration() {
+    let mut ingest_stream = IngestStream::new(0);
+    let s = connect_1_write!(
+        SquareOperator,
+        OperatorConfig::new().name("SquareOperator").node(0),
+        ingest_stream
+    );
+    let mut extract_stream = ExtractStream::new(1, &s);
+
+    // Node 0 runs on a driver thread, which also reconfigures it.
+    let (config_0, config_1) = utils::make_two_node_configs();
+    let (graph_tx, result_rx, driver_0) = run_node_0(config_0);
+    let node_handle_1 = Node::new(config_1).run_async();
+    result_rx.recv().unwrap().unwrap();
+
+    send(&mut ingest_stream, 1);
+    assert_eq!(read(&mut extract_stream, 1), 1);
+
+    // Add an operator on node 1 which reads the output of the operator on node 0.
+    let s2 = connect_1_write!(
+        SquareOperator,
+        OperatorConfig::new().name("SquareOperator2").node(1),
+        s
+    );
+    let mut extract_stream_2 = ExtractStream::new(1, &s2);
+    reconfigure(&node_handle_1, &graph_tx, &result_rx);
+
+    send(&mut ingest_stream, 2);
+    assert_eq!(read(&mut extract_stream, 2), 4);
+    assert_eq!(read(&mut extract_stream_2, 2), 16);
+
+    // Remove the operator on node 1, and check that the operator on node 0 keeps running.
+    default_graph::remove_extract_stream(&extract_stream_2).unwrap();
+    default_graph::remove_operator(operator_id("SquareOperator2")).unwrap();
+    reconfigure(&node_handle_1, &graph_tx, &result_rx);
+    send(&mut ingest_stream, 3);
+    assert_eq!(read(&mut extract_stream, 3), 9);
+
+    drop(graph_tx);
+    driver_0.join().unwrap();
+    node_handle_1.shutdown().unwrap();
+}
@@ -0,0 +1,120 @@
+use erdos::{
+    dataflow::{
+        message::*,
+        record::{self, Replayer},
+        stream::{ExtractStream, IngestStream, WriteStreamT},
+        Operator, OperatorConfig, ReadStream, WriteStream,
+    },
+    node::Node,
+    *,
+};
+
+mod utils;
+
+pub struct SquareOperator {}
+
+impl SquareOperator {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        read_stream: ReadStream<usize>,
+        write_stream: WriteStream<usize>,
+    ) -> Self {
+        read_stream.add_state(write_stream).add_callback(
+            |t: &Timestamp, data: &usize, write_stream: &mut WriteStream<usize>| {
+                write_stream
+                    .send(Message::new_message(t.clone(), data * data))
+                    .unwrap();
+            },
+        );
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<usize>) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for SquareOperator {}
+
+/// Reads messages until the watermark for timestamp 3, and returns the received data.
+fn read_until_watermark(extract_stream: &mut ExtractStream<usize>) -> Vec<usize> {
+    let mut received = Vec::new();
+    loop {
+        let msg = extract_stream.read().unwrap();
+        match msg.data() {
+            Some(data) => received.push(*data),
+            None if msg.timestamp() == &Timestamp::new(vec![3]) => return received,
+            None => (),
+        }
+    }
+}
+
+#[test]
+fn test_record_and_replay() {
+    let path = std::env::temp_dir().join(format!("erdos-record-test-{}.log", std::process::id()));
+
+    // Record the messages sent on all streams.
+    erdos::reset();
+    let mut config = utils::make_default_config();
+    config.record_filename = Some(path.to_string_lossy().to_string());
+    let node = Node::new(config);
+    let mut ingest_stream = IngestStream::new(0);
+    let s = connect_1_write!(SquareOperator, OperatorConfig::new(), ingest_stream);
+    let mut extract_stream = ExtractStream::new(0, &s);
+    let node_handle = node.run_async();
+
+    for t in 1..=3 {
+        let timestamp = Timestamp::new(vec![t]);
+        ingest_stream
+            .send(Message::new_message(timestamp.clone(), t as usize))
+            .unwrap();
+        ingest_stream
+            .send(Message::new_watermark(timestamp))
+            .unwrap();
+    }
+    assert_eq!(read_until_watermark(&mut extract_stream), vec![1, 4, 9]);
+    // The log is flushed upon each watermark, before the node stops.
+    let recorded_before_shutdown = record::read_log(&path)
+        .unwrap()
+        .iter()
+        .filter(|recorded_msg| recorded_msg.stream_id == ingest_stream.get_id())
+        .count();
+    assert_eq!(recorded_before_shutdown, 6);
+    node_handle.shutdown().unwrap();
+
+    let log = record::read_log(&path).unwrap();
+    let recorded_ingest: Vec<_> = log
+        .iter()
+        .filter(|recorded_msg| recorded_msg.stream_id == ingest_stream.get_id())
+        .collect();
+    assert_eq!(recorded_ingest.len(), 6);
+    assert!(recorded_ingest[1].is_watermark);
+    assert_eq!(recorded_ingest[2].timestamp, Timestamp::new(vec![2]));
+    assert_eq!(
+        recorded_ingest[2].decode::<usize>().unwrap(),
+        Message::new_message(Timestamp::new(vec![2]), 2)
+    );
+    let recorded_square: Vec<usize> = log
+        .iter()
+        .filter(|recorded_msg| recorded_msg.stream_id == s.get_id())
+        .filter_map(|recorded_msg| recorded_msg.decode::<usize>().unwrap().data().cloned())
+        .collect();
+    assert_eq!(recorded_square, vec![1, 4, 9]);
+
+    // Recreate the same dataflow, and replay the messages sent on the ingest stream.
+    erdos::reset();
+    let node = Node::new(utils::make_default_config());
+    let mut ingest_stream = IngestStream::new(0);
+    let s = connect_1_write!(SquareOperator, OperatorConfig::new(), ingest_stream);
+    let mut extract_stream = ExtractStream::new(0, &s);
+    node.run_async();
+
+    let recorded_stream_id = ingest_stream.get_id();
+    Replayer::new(log)
+        .add_stream(recorded_stream_id, &mut ingest_stream)
+        .replay()
+        .unwrap();
+    assert_eq!(read_until_watermark(&mut extract_stream), vec![1, 4, 9]);
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,109 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use erdos::{
+    dataflow::{
+        message::*,
+        stream::{ExtractStream, IngestStream, WriteStreamT},
+        Operator, OperatorConfig, ReadStream, WriteStream,
+    },
+    node::Node,
+    *,
+};
+
+mod utils;
+
+/// Polls the number of messages processed by its callback in `run`, and sends it once 2
+/// messages are processed.
+pub struct PollOperator {
+    num_received: Arc<AtomicUsize>,
+    write_stream: WriteStream<usize>,
+}
+
+impl PollOperator {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        read_stream: ReadStream<usize>,
+        write_stream: WriteStream<usize>,
+    ) -> Self {
+        let num_received = Arc::new(AtomicUsize::new(0));
+        let num_received_copy = Arc::clone(&num_received);
+        read_stream.add_callback(move |_t: &Timestamp, _data: &usize| {
+            num_received_copy.fetch_add(1, Ordering::SeqCst);
+        });
+        Self {
+            num_received,
+            write_stream,
+        }
+    }
+
+    pub fn connect(_read_stream: &ReadStream<usize>) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for PollOperator {
+    fn run(&mut self) {
+        while self.num_received.load(Ordering::SeqCst) < 2 {
+            thread::sleep(Duration::from_millis(10));
+        }
+        self.write_stream
+            .send(Message::new_message(Timestamp::new(vec![3]), 2))
+            .unwrap();
+    }
+}
+
+#[test]
+fn test_run_concurrently() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let mut ingest_stream = IngestStream::new(0);
+    let s1 = connect_1_write!(
+        PollOperator,
+        OperatorConfig::new()
+            .name("PollOperator")
+            .run_concurrently(true),
+        ingest_stream
+    );
+    let mut extract_stream = ExtractStream::new(0, &s1);
+
+    let node_handle = node.run_async();
+
+    // Watermarks flow while the operator runs.
+    let timestamp = Timestamp::new(vec![1]);
+    ingest_stream
+        .send(Message::new_message(timestamp.clone(), 1))
+        .unwrap();
+    ingest_stream
+        .send(Message::new_watermark(timestamp.clone()))
+        .unwrap();
+    assert_eq!(
+        extract_stream.read().unwrap(),
+        Message::new_watermark(timestamp)
+    );
+    // The operator receives the messages while it runs.
+    let timestamp = Timestamp::new(vec![2]);
+    ingest_stream
+        .send(Message::new_message(timestamp.clone(), 2))
+        .unwrap();
+    assert_eq!(
+        extract_stream.read().unwrap(),
+        Message::new_message(Timestamp::new(vec![3]), 2)
+    );
+    ingest_stream
+        .send(Message::new_watermark(timestamp.clone()))
+        .unwrap();
+    assert_eq!(
+        extract_stream.read().unwrap(),
+        Message::new_watermark(timestamp)
+    );
+
+    node_handle.shutdown().unwrap();
+}
@@ -0,0 +1,53 @@
+use std::path::Path;
+
+use erdos::dataflow::{
+    message::*,
+    stream::{ExtractStream, IngestStream},
+    ReadStream,
+};
+
+mod utils;
+
+/// Size of the frames sent between the nodes, which wrap around the rings.
+const FRAME_SIZE: usize = 1024 * 1024;
+
+#[test]
+fn test_shared_memory() {
+    let mut ingest_stream = IngestStream::new(0);
+    let mut extract_stream = ExtractStream::new(1, &ReadStream::from(&ingest_stream));
+
+    let (mut config_0, mut config_1) = utils::make_two_node_configs();
+    config_0.shared_memory_size = Some(4 * FRAME_SIZE);
+    config_1.shared_memory_size = Some(4 * FRAME_SIZE);
+    let sink_port = config_1.data_addresses[1].port();
+    let (node_handle_0, node_handle_1) = utils::run_two_nodes(config_0, config_1);
+
+    // The receiving node created a ring instead of receiving the stream over TCP.
+    if Path::new("/dev/shm").is_dir() {
+        let ring_path = format!("/dev/shm/erdos-{}-{}", sink_port, ingest_stream.get_id());
+        assert!(Path::new(&ring_path).exists());
+    }
+
+    for t in 0..10 {
+        let timestamp = Timestamp::new(vec![t]);
+        ingest_stream
+            .send(Message::new_message(
+                timestamp.clone(),
+                vec![t as u8; FRAME_SIZE],
+            ))
+            .unwrap();
+        ingest_stream
+            .send(Message::new_watermark(timestamp))
+            .unwrap();
+    }
+    for t in 0..10 {
+        let msg = extract_stream.read().unwrap();
+        assert_eq!(msg.timestamp(), &Timestamp::new(vec![t]));
+        assert_eq!(msg.data().unwrap(), &vec![t as u8; FRAME_SIZE]);
+        let msg = extract_stream.read().unwrap();
+        assert!(msg.data().is_none());
+    }
+
+    node_handle_0.shutdown().unwrap();
+    node_handle_1.shutdown().unwrap();
+}
@@ -0,0 +1,137 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use erdos::{
+    dataflow::{
+        message::*, operators::PeriodicSourceOperator, stream::IngestStream, LoopStream, Operator,
+        OperatorConfig, ReadStream, Timestamp, WriteStream,
+    },
+    node::{Node, NodeError},
+    *,
+};
+
+mod utils;
+
+/// Counts the messages received on its input stream, and records whether it was destroyed.
+pub struct CountOperator {
+    destroyed: Arc<AtomicBool>,
+}
+
+impl CountOperator {
+    pub fn new(
+        config: OperatorConfig<(Arc<AtomicUsize>, Arc<AtomicBool>)>,
+        read_stream: ReadStream<usize>,
+        _write_stream: WriteStream<usize>,
+    ) -> Self {
+        let (num_messages, destroyed) = config.arg.unwrap();
+        read_stream.add_callback(move |_t: &Timestamp, _msg: &usize| {
+            num_messages.fetch_add(1, Ordering::SeqCst);
+        });
+        Self { destroyed }
+    }
+
+    pub fn connect(_read_stream: &ReadStream<usize>) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for CountOperator {
+    fn destroy(&mut self) {
+        self.destroyed.store(true, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn test_shutdown_gracefully() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let num_messages = Arc::new(AtomicUsize::new(0));
+    let destroyed = Arc::new(AtomicBool::new(false));
+    let mut ingest_stream = IngestStream::new(0);
+    let _count_stream = connect_1_write!(
+        CountOperator,
+        OperatorConfig::new()
+            .name("CountOperator")
+            .arg((Arc::clone(&num_messages), Arc::clone(&destroyed))),
+        ingest_stream
+    );
+    // The timers of a source operator stop when the node shuts down.
+    let period = Duration::from_millis(10);
+    let _source_stream = connect_1_write!(
+        PeriodicSourceOperator<u64>,
+        OperatorConfig::new()
+            .name("PeriodicSourceOperator")
+            .arg((period, |t: &Timestamp| -> Option<u64> { Some(t.time()[0]) }))
+    );
+
+    let node_handle = node.run_async();
+    for i in 0..100 {
+        ingest_stream
+            .send(Message::new_message(Timestamp::new(vec![i as u64]), i))
+            .unwrap();
+    }
+    node_handle
+        .shutdown_gracefully(Duration::from_secs(10))
+        .unwrap();
+
+    // The messages in flight are processed before the operator is destroyed.
+    assert_eq!(num_messages.load(Ordering::SeqCst), 100);
+    assert!(destroyed.load(Ordering::SeqCst));
+    assert!(ingest_stream.is_closed());
+}
+
+#[test]
+fn test_shutdown_gracefully_timeout() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    // The input stream of the operator only closes once its output stream closes, so the
+    // dataflow never drains.
+    let destroyed = Arc::new(AtomicBool::new(false));
+    let loop_stream = LoopStream::new();
+    let count_stream = connect_1_write!(
+        CountOperator,
+        OperatorConfig::new()
+            .name("CountOperator")
+            .arg((Arc::new(AtomicUsize::new(0)), Arc::clone(&destroyed))),
+        loop_stream
+    );
+    loop_stream.set(&count_stream);
+
+    let node_handle = node.run_async();
+    node_handle
+        .shutdown_gracefully(Duration::from_millis(100))
+        .unwrap();
+
+    assert!(!destroyed.load(Ordering::SeqCst));
+}
+
+#[test]
+fn test_node_failure() {
+    let _ingest_stream: IngestStream<usize> = IngestStream::new(0);
+
+    // Node 1 fails because it cannot write the dataflow graph.
+    let (config_0, mut config_1) = utils::make_two_node_configs();
+    config_1.graph_filename = Some("/nonexistent/graph.dot".to_string());
+    let (node_handle_0, node_handle_1) = utils::run_two_nodes(config_0, config_1);
+
+    let error_1 = node_handle_1.join().unwrap_err();
+    assert!(
+        matches!(error_1, NodeError::DataflowError(_)),
+        "{}",
+        error_1
+    );
+    // Node 1 notifies node 0, which stops as well.
+    let error_0 = node_handle_0.join().unwrap_err();
+    assert!(
+        matches!(error_0, NodeError::NodeFailed(1, _)),
+        "{}",
+        error_0
+    );
+}
@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+use erdos::{
+    dataflow::{
+        message::*,
+        stream::{
+            DeadLetter, DeadLetterReason, ExtractStream, IngestStream, LateDataPolicy,
+            ProcessingTimeWatermarks,
+        },
+        Operator, OperatorConfig, PanicPolicy, ReadStream, WriteStream,
+    },
+    node::Node,
+    *,
+};
+
+mod utils;
+
+/// Panics upon receiving the value 13, and sends the messages it cannot process on its side
+/// output.
+pub struct FragileOperator {}
+
+impl FragileOperator {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        read_stream: ReadStream<usize>,
+        _write_stream: WriteStream<usize>,
+        _side_output: WriteStream<DeadLetter>,
+    ) -> Self {
+        read_stream.add_callback(|_t: &Timestamp, msg: &usize| {
+            if *msg == 13 {
+                panic!("Unlucky number");
+            }
+        });
+        Self {}
+    }
+
+    pub fn connect(
+        _read_stream: &ReadStream<usize>,
+    ) -> (WriteStream<usize>, WriteStream<DeadLetter>) {
+        (WriteStream::new(), WriteStream::new_side_output())
+    }
+}
+
+impl Operator for FragileOperator {}
+
+#[test]
+fn test_side_output() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let mut ingest_stream = IngestStream::new(0).with_processing_time_watermarks(
+        ProcessingTimeWatermarks::new(Duration::from_millis(500), || Timestamp::new(vec![10])),
+    );
+    let ingest_stream_id = ingest_stream.get_id();
+    let (output_stream, side_output) = connect_2_write!(
+        FragileOperator,
+        OperatorConfig::new()
+            .name("FragileOperator")
+            .panic_policy(PanicPolicy::Skip)
+            .late_data_policy(LateDataPolicy::SideOutput, Duration::from_secs(0)),
+        ingest_stream
+    );
+    let mut extract_stream = ExtractStream::new(0, &output_stream);
+    let mut side_output_extract_stream = ExtractStream::new(0, &side_output);
+
+    node.run_async();
+
+    ingest_stream
+        .send(Message::new_message(Timestamp::new(vec![1]), 1))
+        .unwrap();
+    // Wait for the generated watermark to flow through the operator.
+    assert_eq!(
+        extract_stream.read().unwrap(),
+        Message::new_watermark(Timestamp::new(vec![10]))
+    );
+    assert_eq!(
+        side_output_extract_stream.read().unwrap(),
+        Message::new_watermark(Timestamp::new(vec![10]))
+    );
+    for (time, data) in &[(5, 5), (11, 13)] {
+        ingest_stream
+            .send(Message::new_message(Timestamp::new(vec![*time]), *data))
+            .unwrap();
+    }
+
+    // The side output accepts messages preceding its watermark.
+    assert_eq!(
+        side_output_extract_stream.read().unwrap(),
+        Message::new_message(
+            Timestamp::new(vec![5]),
+            DeadLetter {
+                reason: DeadLetterReason::Late,
+                stream_id: ingest_stream_id,
+                data: "5".to_string(),
+            }
+        )
+    );
+    assert_eq!(
+        side_output_extract_stream.read().unwrap(),
+        Message::new_message(
+            Timestamp::new(vec![11]),
+            DeadLetter {
+                reason: DeadLetterReason::Panic("Unlucky number".to_string()),
+                stream_id: ingest_stream_id,
+                data: "13".to_string(),
+            }
+        )
+    );
+}
@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use erdos::{
+    dataflow::{
+        add_timer_callback,
+        message::*,
+        stream::{ExtractStream, IngestStream, WriteStreamT},
+        Operator, OperatorConfig, ReadStream, WriteStream,
+    },
+    node::Node,
+    *,
+};
+
+mod utils;
+
+/// Sends the number of each tick of a timer, and closes its stream once its input stream closes.
+pub struct TickOperator {
+    write_stream: WriteStream<u64>,
+}
+
+impl TickOperator {
+    pub fn new(
+        config: OperatorConfig<()>,
+        _read_stream: ReadStream<usize>,
+        write_stream: WriteStream<u64>,
+    ) -> Self {
+        add_timer_callback(
+            &config,
+            Duration::from_millis(10),
+            write_stream.clone(),
+            |tick: u64, write_stream: &mut WriteStream<u64>| {
+                write_stream
+                    .send(Message::new_message(Timestamp::new(vec![tick]), tick))
+                    .unwrap();
+                true
+            },
+        );
+        Self { write_stream }
+    }
+
+    pub fn connect(_read_stream: &ReadStream<usize>) -> WriteStream<u64> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for TickOperator {
+    fn destroy(&mut self) {
+        self.write_stream
+            .send(Message::new_watermark(Timestamp::top()))
+            .unwrap();
+    }
+}
+
+#[test]
+fn test_timer_callback() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let mut ingest_stream = IngestStream::new(0);
+    let s1 = connect_1_write!(
+        TickOperator,
+        OperatorConfig::new()
+            .name("TickOperator")
+            .flow_watermarks(false),
+        ingest_stream
+    );
+    let mut extract_stream = ExtractStream::new(0, &s1);
+
+    node.run_async();
+
+    // The timer ticks while the input stream is open.
+    for tick in 0..3 {
+        let msg = extract_stream.read().unwrap();
+        assert_eq!(msg.data(), Some(&tick));
+    }
+
+    // The timer stops once the input stream closes, and the operator is destroyed.
+    ingest_stream
+        .send(Message::new_watermark(Timestamp::top()))
+        .unwrap();
+    let mut tick = 3;
+    loop {
+        let msg = extract_stream.read().unwrap();
+        if msg.is_top_watermark() {
+            break;
+        }
+        assert_eq!(msg.data(), Some(&tick));
+        tick += 1;
+    }
+}
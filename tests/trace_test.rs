@@ -0,0 +1,79 @@
+use erdos::{
+    dataflow::{
+        message::*,
+        stream::{ExtractStream, IngestStream, WriteStreamT},
+        Operator, OperatorConfig, ReadStream, WriteStream,
+    },
+    node::Node,
+    *,
+};
+
+mod utils;
+
+pub struct SquareOperator {}
+
+impl SquareOperator {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        read_stream: ReadStream<usize>,
+        write_stream: WriteStream<usize>,
+    ) -> Self {
+        read_stream.add_state(write_stream).add_callback(
+            |t: &Timestamp, data: &usize, write_stream: &mut WriteStream<usize>| {
+                write_stream
+                    .send(Message::new_message(t.clone(), data * data))
+                    .unwrap();
+            },
+        );
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<usize>) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for SquareOperator {}
+
+#[test]
+fn test_chrome_trace() {
+    let path = std::env::temp_dir().join(format!("erdos-trace-test-{}.json", std::process::id()));
+    let mut config = utils::make_default_config();
+    config.trace_filename = Some(path.to_string_lossy().to_string());
+    let node = Node::new(config);
+    let mut ingest_stream = IngestStream::new(0);
+    let s = connect_1_write!(
+        SquareOperator,
+        OperatorConfig::new().name("SquareOperator"),
+        ingest_stream
+    );
+    let mut extract_stream = ExtractStream::new(0, &s);
+    let node_handle = node.run_async();
+
+    let timestamp = Timestamp::new(vec![1]);
+    ingest_stream
+        .send(Message::new_message(timestamp.clone(), 2))
+        .unwrap();
+    ingest_stream
+        .send(Message::new_watermark(timestamp))
+        .unwrap();
+    assert_eq!(extract_stream.read().unwrap().data(), Some(&4));
+    assert!(extract_stream.read().unwrap().data().is_none());
+    node_handle.shutdown().unwrap();
+
+    let trace = std::fs::read_to_string(&path).unwrap();
+    assert!(trace.starts_with('['));
+    let events: Vec<&str> = trace
+        .lines()
+        .filter(|line| line.contains("\"name\":\"callback\""))
+        .collect();
+    assert_eq!(events.len(), 2);
+    assert!(events[0].contains("\"ph\":\"B\""));
+    assert!(events[1].contains("\"ph\":\"E\""));
+    for event in events {
+        assert!(event.contains("\"pid\":\"SquareOperator\""));
+        assert!(event.contains("\"args\":{\"timestamp\":\"[1]\",\"priority\":0}"));
+    }
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -1,12 +1,19 @@
-use erdos::Configuration;
+use std::{net::SocketAddr, thread};
+
+use erdos::{
+    dataflow::graph::default_graph,
+    node::{Node, NodeHandle},
+    Configuration,
+};
 
 /// Returns a unique port for each test to avoid race conditions.
-fn get_unique_port() -> usize {
+pub fn get_unique_port() -> usize {
     use std::sync::atomic::{AtomicUsize, Ordering};
     static PORT: AtomicUsize = AtomicUsize::new(9000);
     PORT.fetch_add(1, Ordering::SeqCst)
 }
 
+#[allow(dead_code)]
 pub fn make_default_config() -> Configuration {
     let data_addresses = vec![format!("127.0.0.1:{}", get_unique_port())
         .parse()
@@ -16,3 +23,42 @@ pub fn make_default_config() -> Configuration {
         .expect("Unable to parse socket address")];
     Configuration::new(0, data_addresses, control_addresses, 4, None)
 }
+
+/// Returns the configurations of nodes 0 and 1 of a dataflow which runs on two local nodes.
+#[allow(dead_code)]
+pub fn make_two_node_configs() -> (Configuration, Configuration) {
+    let make_addresses = || -> Vec<SocketAddr> {
+        (0..2)
+            .map(|_| {
+                format!("127.0.0.1:{}", get_unique_port())
+                    .parse()
+                    .expect("Unable to parse socket address")
+            })
+            .collect()
+    };
+    let data_addresses = make_addresses();
+    let control_addresses = make_addresses();
+    (
+        Configuration::new(
+            0,
+            data_addresses.clone(),
+            control_addresses.clone(),
+            4,
+            None,
+        ),
+        Configuration::new(1, data_addresses, control_addresses, 4, None),
+    )
+}
+
+/// Runs the default graph on two nodes, and returns the handles of nodes 0 and 1.
+#[allow(dead_code)]
+pub fn run_two_nodes(config_0: Configuration, config_1: Configuration) -> (NodeHandle, NodeHandle) {
+    // Nodes only finish starting once all nodes are up, so start node 0 from another thread.
+    let graph = default_graph::clone();
+    let node_0 = thread::spawn(move || {
+        default_graph::set(graph);
+        Node::new(config_0).run_async()
+    });
+    let node_handle_1 = Node::new(config_1).run_async();
+    (node_0.join().unwrap(), node_handle_1)
+}